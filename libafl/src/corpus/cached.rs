@@ -6,6 +6,8 @@ use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use crate::corpus::ondisk::OnDiskCompression;
 use crate::{
     corpus::{
         inmemory_ondisk::InMemoryOnDiskCorpus, ondisk::OnDiskMetadataFormat, Corpus, CorpusId,
@@ -293,6 +295,22 @@ where
         })
     }
 
+    /// Transparently compress all inputs this corpus stores to disk, using the given [`OnDiskCompression`].
+    /// See [`InMemoryOnDiskCorpus::with_compression`].
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[must_use]
+    pub fn with_compression(mut self, compression: OnDiskCompression) -> Self {
+        self.inner = self.inner.with_compression(compression);
+        self
+    }
+
+    /// Makes this corpus read-only, see [`InMemoryOnDiskCorpus::read_only`].
+    #[must_use]
+    pub fn read_only(mut self) -> Self {
+        self.inner = self.inner.read_only();
+        self
+    }
+
     /// Fetch the inner corpus
     pub fn inner(&self) -> &InMemoryOnDiskCorpus<I> {
         &self.inner
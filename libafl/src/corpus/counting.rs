@@ -0,0 +1,191 @@
+//! The counting corpus only counts the [`Testcase`]s added to it, without storing them.
+use core::{cell::RefCell, marker::PhantomData};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    inputs::{Input, UsesInput},
+    Error,
+};
+
+/// A corpus which counts the [`Testcase`]s added to it, but drops them instead of storing them.
+///
+/// Use it for the solutions when only their number matters, such as in throughput experiments or
+/// CI gates, to avoid writing each one to disk. Its count is reported as the objectives by the
+/// monitors, as usual. To count unique solutions, deduplicate them in the objective, e.g., by stack
+/// hash with a [`crate::feedbacks::NewHashFeedback`], and wrap the part detecting them in an
+/// [`crate::feedbacks::ObjectiveCountFeedback`] to also report the total number.
+///
+/// The added testcases cannot be retrieved, the corpus iterates no ids.
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "I: serde::de::DeserializeOwned")]
+pub struct CountingCorpus<I> {
+    count: usize,
+    current: Option<CorpusId>,
+    phantom: PhantomData<I>,
+}
+
+impl<I> UsesInput for CountingCorpus<I>
+where
+    I: Input,
+{
+    type Input = I;
+}
+
+impl<I> Corpus for CountingCorpus<I>
+where
+    I: Input,
+{
+    /// Returns the number of all testcases added so far
+    #[inline]
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the number of all disabled entries
+    fn count_disabled(&self) -> usize {
+        0
+    }
+
+    /// Returns the number of all testcases added so far
+    #[inline]
+    fn count_all(&self) -> usize {
+        self.count
+    }
+
+    /// Counts and drops an enabled testcase, returning the index it would have
+    #[inline]
+    fn add(&mut self, _testcase: Testcase<I>) -> Result<CorpusId, Error> {
+        let idx = CorpusId::from(self.count);
+        self.count += 1;
+        Ok(idx)
+    }
+
+    /// Add a disabled testcase to the corpus and return its index
+    #[inline]
+    fn add_disabled(&mut self, _testcase: Testcase<I>) -> Result<CorpusId, Error> {
+        Err(Error::unsupported("Unsupported by CountingCorpus"))
+    }
+
+    /// Replaces the testcase at the given idx
+    #[inline]
+    fn replace(&mut self, _idx: CorpusId, _testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        Err(Error::unsupported("Unsupported by CountingCorpus"))
+    }
+
+    /// Removes an entry from the corpus, returning it if it was present.
+    #[inline]
+    fn remove(&mut self, _idx: CorpusId) -> Result<Testcase<I>, Error> {
+        Err(Error::unsupported("Unsupported by CountingCorpus"))
+    }
+
+    /// Get by id; considers only enabled testcases
+    #[inline]
+    fn get(&self, _idx: CorpusId) -> Result<&RefCell<Testcase<I>>, Error> {
+        Err(Error::unsupported("Unsupported by CountingCorpus"))
+    }
+
+    /// Get by id; considers both enabled and disabled testcases
+    #[inline]
+    fn get_from_all(&self, _idx: CorpusId) -> Result<&RefCell<Testcase<I>>, Error> {
+        Err(Error::unsupported("Unsupported by CountingCorpus"))
+    }
+
+    /// Current testcase scheduled
+    #[inline]
+    fn current(&self) -> &Option<CorpusId> {
+        &self.current
+    }
+
+    /// Peek the next free corpus id
+    #[inline]
+    fn peek_free_id(&self) -> CorpusId {
+        CorpusId::from(self.count)
+    }
+
+    /// Current testcase scheduled (mutable)
+    #[inline]
+    fn current_mut(&mut self) -> &mut Option<CorpusId> {
+        &mut self.current
+    }
+
+    #[inline]
+    fn next(&self, _idx: CorpusId) -> Option<CorpusId> {
+        None
+    }
+
+    #[inline]
+    fn prev(&self, _idx: CorpusId) -> Option<CorpusId> {
+        None
+    }
+
+    #[inline]
+    fn first(&self) -> Option<CorpusId> {
+        None
+    }
+
+    #[inline]
+    fn last(&self) -> Option<CorpusId> {
+        None
+    }
+
+    /// Get the nth corpus id; considers only enabled testcases
+    #[inline]
+    fn nth(&self, nth: usize) -> CorpusId {
+        CorpusId::from(nth)
+    }
+
+    /// Get the nth corpus id; considers both enabled and disabled testcases
+    #[inline]
+    fn nth_from_all(&self, nth: usize) -> CorpusId {
+        CorpusId::from(nth)
+    }
+
+    #[inline]
+    fn load_input_into(&self, _testcase: &mut Testcase<Self::Input>) -> Result<(), Error> {
+        Err(Error::unsupported("Unsupported by CountingCorpus"))
+    }
+
+    #[inline]
+    fn store_input_from(&self, _testcase: &Testcase<Self::Input>) -> Result<(), Error> {
+        Err(Error::unsupported("Unsupported by CountingCorpus"))
+    }
+}
+
+impl<I> CountingCorpus<I>
+where
+    I: Input,
+{
+    /// Creates a new, empty [`CountingCorpus`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            current: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        corpus::{Corpus, CountingCorpus, Testcase},
+        inputs::BytesInput,
+    };
+
+    #[test]
+    fn test_counting_corpus() {
+        let mut corpus = CountingCorpus::<BytesInput>::new();
+        for _ in 0..3 {
+            corpus
+                .add(Testcase::new(BytesInput::new(vec![1, 2])))
+                .unwrap();
+        }
+        assert_eq!(corpus.count(), 3);
+        assert_eq!(corpus.peek_free_id(), corpus.nth(3));
+        assert!(corpus.get(corpus.nth(0)).is_err());
+        assert_eq!(corpus.ids().count(), 0);
+    }
+}
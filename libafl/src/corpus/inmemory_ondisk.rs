@@ -14,9 +14,11 @@ use std::{
 
 #[cfg(feature = "gzip")]
 use libafl_bolts::compress::GzipCompressor;
-use libafl_bolts::serdeany::SerdeAnyMap;
+use libafl_bolts::{fs::write_file_atomic, serdeany::SerdeAnyMap};
 use serde::{Deserialize, Serialize};
 
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use super::ondisk::OnDiskCompression;
 use super::{
     ondisk::{OnDiskMetadata, OnDiskMetadataFormat},
     HasTestcase,
@@ -51,6 +53,11 @@ where
     meta_format: Option<OnDiskMetadataFormat>,
     prefix: Option<String>,
     locking: bool,
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[serde(default)]
+    compression: Option<OnDiskCompression>,
+    #[serde(default)]
+    read_only: bool,
 }
 
 impl<I> UsesInput for InMemoryOnDiskCorpus<I>
@@ -85,6 +92,9 @@ where
     #[inline]
     fn add(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error> {
         let idx = self.inner.add(testcase)?;
+        if self.read_only {
+            return Ok(idx);
+        }
         let testcase = &mut self.get(idx).unwrap().borrow_mut();
         self.save_testcase(testcase, idx)?;
         *testcase.input_mut() = None;
@@ -95,6 +105,9 @@ where
     #[inline]
     fn add_disabled(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error> {
         let idx = self.inner.add_disabled(testcase)?;
+        if self.read_only {
+            return Ok(idx);
+        }
         let testcase = &mut self.get_from_all(idx).unwrap().borrow_mut();
         self.save_testcase(testcase, idx)?;
         *testcase.input_mut() = None;
@@ -104,6 +117,7 @@ where
     /// Replaces the testcase at the given idx
     #[inline]
     fn replace(&mut self, idx: CorpusId, testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        self.check_writable()?;
         let entry = self.inner.replace(idx, testcase)?;
         self.remove_testcase(&entry)?;
         let testcase = &mut self.get(idx).unwrap().borrow_mut();
@@ -115,6 +129,7 @@ where
     /// Removes an entry from the corpus, returning it if it was present.
     #[inline]
     fn remove(&mut self, idx: CorpusId) -> Result<Testcase<I>, Error> {
+        self.check_writable()?;
         let entry = self.inner.remove(idx)?;
         self.remove_testcase(&entry)?;
         Ok(entry)
@@ -188,13 +203,14 @@ where
                     "No file path set for testcase. Could not load inputs.",
                 ));
             };
-            let input = I::from_file(file_path)?;
+            let input = self.load_input_from_file(file_path)?;
             testcase.set_input(input);
         }
         Ok(())
     }
 
     fn store_input_from(&self, testcase: &Testcase<Self::Input>) -> Result<(), Error> {
+        self.check_writable()?;
         // Store the input to disk
         let Some(file_path) = testcase.file_path() else {
             return Err(Error::illegal_argument(
@@ -206,6 +222,10 @@ where
                 "No input available for testcase. Could not store anything.",
             ));
         };
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        if let Some(compression) = self.compression {
+            return write_file_atomic(file_path, &compression.compress(&input.to_file_bytes()?)?);
+        }
         input.to_file(file_path)
     }
 }
@@ -314,9 +334,69 @@ where
             meta_format,
             prefix,
             locking,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression: None,
+            read_only: false,
         })
     }
 
+    /// Transparently compress all inputs this corpus stores to disk, using the given [`OnDiskCompression`].
+    ///
+    /// Filenames are unchanged, compressed files are detected by their header when loading,
+    /// so the corpus directory may contain a mix of compressed and uncompressed inputs.
+    /// Without compression, inputs are loaded as is, without looking for a header.
+    /// Metadata files are not affected, use [`OnDiskMetadataFormat::JsonGzip`] for these.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[must_use]
+    pub fn with_compression(mut self, compression: OnDiskCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Makes this corpus read-only, protecting a shared corpus on disk during experiments.
+    /// New testcases are still added, but only kept in memory, with their inputs.
+    /// Every attempt to replace, remove, rename or store a testcase returns an [`Error`] without touching the disk.
+    /// Testcases can still be loaded, and their in-memory metadata used for scheduling.
+    #[must_use]
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// If this corpus is read-only, see [`InMemoryOnDiskCorpus::read_only`]
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Errors if this corpus is read-only
+    fn check_writable(&self) -> Result<(), Error> {
+        if self.read_only {
+            return Err(Error::illegal_state(format!(
+                "The corpus in {} is read-only",
+                self.dir_path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Load an input from disk, decompressing it first, if needed.
+    /// Inputs are only checked for a compression header if compression is enabled.
+    /// An input that merely starts like a compressed one, but fails to decompress, is loaded as is.
+    fn load_input_from_file(&self, file_path: &Path) -> Result<I, Error> {
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        if self.compression.is_some() {
+            let bytes = fs::read(file_path)?;
+            if OnDiskCompression::is_compressed(&bytes) {
+                if let Ok(decompressed) = OnDiskCompression::decompress(&bytes) {
+                    return I::from_file_bytes(&decompressed);
+                }
+            }
+            return I::from_file_bytes(&bytes);
+        }
+        I::from_file(file_path)
+    }
+
     /// Sets the filename for a [`Testcase`].
     /// If an error gets returned from the corpus (i.e., file exists), we'll have to retry with a different filename.
     #[inline]
@@ -325,6 +405,7 @@ where
         testcase: &mut Testcase<I>,
         filename: String,
     ) -> Result<(), Error> {
+        self.check_writable()?;
         if testcase.filename().is_some() {
             // We are renaming!
 
@@ -475,3 +556,89 @@ where
         &self.dir_path
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        corpus::{Corpus, InMemoryOnDiskCorpus, Testcase},
+        inputs::BytesInput,
+    };
+
+    #[test]
+    fn test_read_only() {
+        let dir = PathBuf::from("target/.test/inmemory_ondisk/read_only");
+        drop(fs::remove_dir_all(&dir));
+        let mut corpus = InMemoryOnDiskCorpus::<BytesInput>::no_meta(&dir)
+            .unwrap()
+            .read_only();
+
+        let id = corpus
+            .add(Testcase::new(BytesInput::new(vec![1, 2, 3])))
+            .unwrap();
+        let disabled = corpus
+            .add_disabled(Testcase::new(BytesInput::new(vec![4])))
+            .unwrap();
+        assert_eq!(corpus.count(), 1);
+        assert_eq!(corpus.count_disabled(), 1);
+        assert_eq!(
+            corpus.get(id).unwrap().borrow().input(),
+            &Some(BytesInput::new(vec![1, 2, 3]))
+        );
+        assert!(corpus
+            .get_from_all(disabled)
+            .unwrap()
+            .borrow()
+            .input()
+            .is_some());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        assert!(corpus.remove(id).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn test_compression_roundtrip() {
+        use crate::corpus::ondisk::OnDiskCompression;
+
+        let dir = PathBuf::from("target/.test/inmemory_ondisk/compressed");
+        drop(fs::remove_dir_all(&dir));
+        let mut corpus = InMemoryOnDiskCorpus::<BytesInput>::no_meta(&dir)
+            .unwrap()
+            .with_compression(OnDiskCompression::Gzip);
+
+        let bytes = b"a compressible input, a compressible input".to_vec();
+        let id = corpus
+            .add(Testcase::new(BytesInput::new(bytes.clone())))
+            .unwrap();
+        let file_path = corpus
+            .get(id)
+            .unwrap()
+            .borrow()
+            .file_path()
+            .clone()
+            .unwrap();
+        let on_disk = fs::read(&file_path).unwrap();
+        assert!(OnDiskCompression::is_compressed(&on_disk));
+        assert_eq!(OnDiskCompression::decompress(&on_disk).unwrap(), bytes);
+        assert_eq!(
+            corpus.load_input_from_file(&file_path).unwrap(),
+            BytesInput::new(bytes)
+        );
+        // Decompressing happens in memory, nothing else shows up in the corpus directory
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A raw input that merely starts with the header is loaded as is
+        let raw = b"LAcz\x01 not actually compressed".to_vec();
+        let raw_path = dir.join("raw");
+        fs::write(&raw_path, &raw).unwrap();
+        assert_eq!(
+            corpus.load_input_from_file(&raw_path).unwrap(),
+            BytesInput::new(raw)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
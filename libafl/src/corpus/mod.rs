@@ -13,6 +13,8 @@ pub use inmemory_ondisk::InMemoryOnDiskCorpus;
 
 #[cfg(feature = "std")]
 pub mod ondisk;
+#[cfg(all(feature = "std", any(feature = "gzip", feature = "zstd")))]
+pub use ondisk::OnDiskCompression;
 #[cfg(feature = "std")]
 pub use ondisk::OnDiskCorpus;
 
@@ -25,6 +27,9 @@ pub use cached::CachedOnDiskCorpus;
 pub mod minimizer;
 use core::{cell::RefCell, fmt};
 
+pub mod counting;
+pub use counting::CountingCorpus;
+
 pub mod nop;
 #[cfg(feature = "cmin")]
 pub use minimizer::*;
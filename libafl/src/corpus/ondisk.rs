@@ -4,10 +4,12 @@
 //! For any other occasions, consider using [`crate::corpus::CachedOnDiskCorpus`]
 //! which stores a certain number of testcases in memory and removes additional ones in a FIFO manner.
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use core::{cell::RefCell, time::Duration};
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "gzip")]
+use libafl_bolts::compress::GzipCompressor;
 use libafl_bolts::serdeany::SerdeAnyMap;
 use serde::{Deserialize, Serialize};
 
@@ -34,6 +36,76 @@ pub enum OnDiskMetadataFormat {
     JsonGzip,
 }
 
+/// Header prepended to compressed inputs, followed by one byte identifying the [`OnDiskCompression`]
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+const COMPRESSED_INPUT_MAGIC: &[u8; 4] = b"LAcz";
+
+/// Options for the transparent compression of inputs stored to disk,
+/// available if the `gzip` or `zstd` feature is enabled
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnDiskCompression {
+    /// Compress using the [`libafl_bolts::compress::GzipCompressor`]
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Compress using zstandard
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl OnDiskCompression {
+    /// The id written to the header of compressed inputs
+    fn id(self) -> u8 {
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => 1,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 2,
+        }
+    }
+
+    /// Compress the given bytes and prepend the header used to detect compressed inputs on load
+    pub fn compress(self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut compressed = COMPRESSED_INPUT_MAGIC.to_vec();
+        compressed.push(self.id());
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => compressed.extend(GzipCompressor::new().compress(bytes)),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => compressed.extend(zstd::encode_all(bytes, 0)?),
+        }
+        Ok(compressed)
+    }
+
+    /// Returns `true` if the given header (at least the first 5 bytes of a file) belongs to a compressed input
+    #[must_use]
+    pub fn is_compressed(header: &[u8]) -> bool {
+        header.len() > COMPRESSED_INPUT_MAGIC.len() && header.starts_with(COMPRESSED_INPUT_MAGIC)
+    }
+
+    /// Decompress bytes previously returned by [`OnDiskCompression::compress`].
+    /// The compression algorithm is detected from the header.
+    pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        if !Self::is_compressed(bytes) {
+            return Err(Error::illegal_argument(
+                "Tried to decompress an input without compression header",
+            ));
+        }
+        let id = bytes[COMPRESSED_INPUT_MAGIC.len()];
+        let payload = &bytes[COMPRESSED_INPUT_MAGIC.len() + 1..];
+        match id {
+            #[cfg(feature = "gzip")]
+            1 => GzipCompressor::new().decompress(payload),
+            #[cfg(feature = "zstd")]
+            2 => Ok(zstd::decode_all(payload)?),
+            _ => Err(Error::unsupported(format!(
+                "Input compressed with unknown or disabled compression (id {id})"
+            ))),
+        }
+    }
+}
+
 /// The [`Testcase`] metadata that'll be stored to disk
 #[derive(Debug, Serialize)]
 pub struct OnDiskMetadata<'a> {
@@ -290,6 +362,32 @@ where
         })
     }
 
+    /// Transparently compress all inputs this corpus stores to disk, using the given [`OnDiskCompression`].
+    ///
+    /// Filenames are unchanged, compressed files are detected by their header when loading.
+    /// Usually, you want to keep the solutions corpus uncompressed, for easy manual inspection,
+    /// so only call this for the corpus you want compressed.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[must_use]
+    pub fn with_compression(mut self, compression: OnDiskCompression) -> Self {
+        self.inner = self.inner.with_compression(compression);
+        self
+    }
+
+    /// Makes this corpus read-only: any attempt to write to the disk returns an [`Error`] instead.
+    /// See [`crate::corpus::InMemoryOnDiskCorpus::read_only`].
+    #[must_use]
+    pub fn read_only(mut self) -> Self {
+        self.inner = self.inner.read_only();
+        self
+    }
+
+    /// If this corpus is read-only
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.inner.inner().is_read_only()
+    }
+
     /// Path to the corpus directory associated with this corpus
     pub fn dir_path(&self) -> &PathBuf {
         &self.dir_path
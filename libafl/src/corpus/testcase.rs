@@ -1,9 +1,9 @@
 //! The [`Testcase`] is a struct embedded in each [`Corpus`].
 //! It will contain a respective input, and metadata.
 
-use alloc::string::String;
 #[cfg(feature = "track_hit_feedbacks")]
-use alloc::{borrow::Cow, vec::Vec};
+use alloc::borrow::Cow;
+use alloc::{string::String, vec::Vec};
 use core::{
     cell::{Ref, RefMut},
     time::Duration,
@@ -11,6 +11,8 @@ use core::{
 #[cfg(feature = "std")]
 use std::path::PathBuf;
 
+#[cfg(feature = "std")]
+use libafl_bolts::serdeany::Wrap;
 use libafl_bolts::{serdeany::SerdeAnyMap, HasLen};
 use serde::{Deserialize, Serialize};
 
@@ -52,6 +54,9 @@ where
     file_path: Option<PathBuf>,
     /// Map of metadata associated with this [`Testcase`]
     metadata: SerdeAnyMap,
+    /// If the metadata changed since it was last flushed to disk
+    #[serde(default)]
+    metadata_dirty: bool,
     /// Complete path to the metadata [`SerdeAnyMap`] on disk, if this [`Testcase`] is backed by a file in the filesystem
     #[cfg(feature = "std")]
     metadata_path: Option<PathBuf>,
@@ -87,9 +92,11 @@ where
         &self.metadata
     }
 
-    /// Get all the metadata into an [`hashbrown::HashMap`] (mutable)
+    /// Get all the metadata into an [`hashbrown::HashMap`] (mutable).
+    /// Marks the metadata as dirty, see [`Testcase::metadata_dirty`].
     #[inline]
     fn metadata_map_mut(&mut self) -> &mut SerdeAnyMap {
+        self.metadata_dirty = true;
         &mut self.metadata
     }
 }
@@ -165,6 +172,45 @@ where
         &mut self.metadata_path
     }
 
+    /// If the metadata may have changed since it was last flushed to disk.
+    /// Set by every mutable access to the metadata.
+    #[inline]
+    pub fn metadata_dirty(&self) -> bool {
+        self.metadata_dirty
+    }
+
+    /// Marks the metadata as flushed to disk, or as dirty again
+    #[inline]
+    pub fn set_metadata_dirty(&mut self, dirty: bool) {
+        self.metadata_dirty = dirty;
+    }
+
+    /// The type names of all metadata on this [`Testcase`], sorted, for debugging
+    #[must_use]
+    pub fn metadata_type_names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self
+            .metadata
+            .values()
+            .map(|meta| meta.type_name())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Dumps all metadata on this [`Testcase`] as a json object, keyed by the type names of the metadata.
+    /// Useful to triage why an entry is favored, without knowing the concrete metadata types.
+    #[cfg(feature = "std")]
+    pub fn metadata_debug_dump(&self) -> Result<serde_json::Value, Error> {
+        let mut dump = serde_json::Map::new();
+        for meta in self.metadata.values() {
+            dump.insert(
+                String::from(meta.type_name()),
+                serde_json::to_value(Wrap(meta))?,
+            );
+        }
+        Ok(serde_json::Value::Object(dump))
+    }
+
     /// Get the execution time of the testcase
     #[inline]
     pub fn exec_time(&self) -> &Option<Duration> {
@@ -257,6 +303,7 @@ where
             #[cfg(feature = "std")]
             file_path: None,
             metadata: SerdeAnyMap::default(),
+            metadata_dirty: true,
             #[cfg(feature = "std")]
             metadata_path: None,
             exec_time: None,
@@ -283,6 +330,7 @@ where
             #[cfg(feature = "std")]
             file_path: None,
             metadata: SerdeAnyMap::default(),
+            metadata_dirty: true,
             #[cfg(feature = "std")]
             metadata_path: None,
             exec_time: None,
@@ -309,6 +357,7 @@ where
             #[cfg(feature = "std")]
             file_path: None,
             metadata: SerdeAnyMap::default(),
+            metadata_dirty: true,
             #[cfg(feature = "std")]
             metadata_path: None,
             exec_time: None,
@@ -335,6 +384,7 @@ where
             #[cfg(feature = "std")]
             file_path: None,
             metadata: SerdeAnyMap::default(),
+            metadata_dirty: true,
             #[cfg(feature = "std")]
             metadata_path: None,
             exec_time: None,
@@ -389,6 +439,7 @@ where
             input: None,
             filename: None,
             metadata: SerdeAnyMap::new(),
+            metadata_dirty: true,
             exec_time: None,
             cached_len: None,
             scheduled_count: 0,
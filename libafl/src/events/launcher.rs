@@ -32,6 +32,8 @@ use std::{fs::File, os::unix::io::AsRawFd};
 use libafl_bolts::os::dup2;
 #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
 use libafl_bolts::os::startable_self;
+#[cfg(feature = "std")]
+use libafl_bolts::rands::derive_seed;
 #[cfg(feature = "adaptive_serialization")]
 use libafl_bolts::tuples::{Handle, Handled};
 #[cfg(all(unix, feature = "std", feature = "fork"))]
@@ -66,6 +68,32 @@ use crate::{
 /// The (internal) `env` that indicates we're running as client.
 const _AFL_LAUNCHER_CLIENT: &str = "AFL_LAUNCHER_CLIENT";
 
+/// The env variable the [`Launcher`] passes the rng seed of each client in, see [`client_rng_seed`]
+#[cfg(feature = "std")]
+pub const LIBAFL_CLIENT_RNG_SEED: &str = "LIBAFL_CLIENT_RNG_SEED";
+
+/// The rng seed the [`Launcher`] derived for this client from its `rng_seed` and the core the client runs on,
+/// or `None` if no base seed was set. Seed the rand of the client's state with it, e.g.,
+/// `StdRand::with_seed(client_rng_seed().unwrap_or_else(current_nanos))`,
+/// so that the clients get distinct but reproducible random streams.
+#[cfg(feature = "std")]
+#[must_use]
+pub fn client_rng_seed() -> Option<u64> {
+    std::env::var(LIBAFL_CLIENT_RNG_SEED)
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+}
+
+/// Passes the rng seed of the client on `core_id` to it, if a base seed is set
+#[cfg(feature = "std")]
+fn set_client_rng_seed(base_seed: Option<u64>, core_id: CoreId) {
+    if let Some(base_seed) = base_seed {
+        let seed = derive_seed(base_seed, core_id.0 as u64);
+        log::info!("Seeding the client on core {} with {seed}", core_id.0);
+        std::env::set_var(LIBAFL_CLIENT_RNG_SEED, seed.to_string());
+    }
+}
+
 /// The env variable to set in order to enable child output
 #[cfg(all(feature = "fork", unix))]
 const LIBAFL_DEBUG_OUTPUT: &str = "LIBAFL_DEBUG_OUTPUT";
@@ -138,6 +166,9 @@ where
     /// Tell the manager to serialize or not the state on restart
     #[builder(default = LlmpShouldSaveState::OnRestart)]
     serialize_state: LlmpShouldSaveState,
+    /// The base seed each client's rng seed is derived from, together with its core, see [`client_rng_seed`]
+    #[builder(default = None)]
+    rng_seed: Option<u64>,
     #[builder(setter(skip), default = PhantomData)]
     phantom_data: PhantomData<(&'a S, &'a SP, EMH)>,
 }
@@ -157,7 +188,8 @@ where
             .field("broker_port", &self.broker_port)
             .field("core", &self.cores)
             .field("spawn_broker", &self.spawn_broker)
-            .field("remote_broker_addr", &self.remote_broker_addr);
+            .field("remote_broker_addr", &self.remote_broker_addr)
+            .field("rng_seed", &self.rng_seed);
         #[cfg(all(unix, feature = "std"))]
         {
             dbg_struct
@@ -282,6 +314,7 @@ where
                         let builder = builder.time_ref(self.time_ref.clone());
                         let (state, mgr) = builder.build().launch()?;
 
+                        set_client_rng_seed(self.rng_seed, *bind_to);
                         return (self.run_client.take().unwrap())(state, mgr, *bind_to);
                     }
                 };
@@ -357,6 +390,7 @@ where
                     .build()
                     .launch()?;
 
+                set_client_rng_seed(self.rng_seed, CoreId(core_id));
                 return (self.run_client.take().unwrap())(state, mgr, CoreId(core_id));
             }
             Err(std::env::VarError::NotPresent) => {
@@ -3,7 +3,7 @@
 
 #[cfg(feature = "std")]
 use alloc::string::ToString;
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{borrow::Cow, boxed::Box, vec::Vec};
 use core::{marker::PhantomData, time::Duration};
 #[cfg(feature = "std")]
 use std::net::TcpStream;
@@ -45,6 +45,7 @@ use crate::{
     executors::{Executor, HasObservers},
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
     inputs::{NopInput, UsesInput},
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
     observers::ObserversTuple,
     state::{HasExecutions, HasLastReportTime, NopState, State, UsesState},
     Error, HasMetadata,
@@ -61,6 +62,14 @@ where
     pub(crate) throttle: Option<Duration>,
     /// We sent last message at `last_sent`
     last_sent: Duration,
+    /// We pause sending new testcases for `saturation_backoff` once the broker is saturated
+    saturation_backoff: Option<Duration>,
+    /// We do not send new testcases until `backoff_until`
+    backoff_until: Duration,
+    /// The new testcases [`EventFirer::fire`] dropped while backing off
+    dropped_events: u64,
+    /// The dropped testcases we already reported to the broker
+    reported_dropped_events: u64,
     hooks: EMH,
     /// The LLMP client for inter process communication
     llmp: LlmpClient<SP>,
@@ -97,6 +106,7 @@ impl LlmpEventManager<(), NopState<NopInput>, NopShMemProvider> {
 #[derive(Debug, Copy, Clone)]
 pub struct LlmpEventManagerBuilder<EMH> {
     throttle: Option<Duration>,
+    saturation_backoff: Option<Duration>,
     hooks: EMH,
 }
 
@@ -112,6 +122,7 @@ impl LlmpEventManagerBuilder<()> {
     pub fn new() -> Self {
         Self {
             throttle: None,
+            saturation_backoff: None,
             hooks: (),
         }
     }
@@ -120,6 +131,7 @@ impl LlmpEventManagerBuilder<()> {
     pub fn hooks<EMH>(self, hooks: EMH) -> LlmpEventManagerBuilder<EMH> {
         LlmpEventManagerBuilder {
             throttle: self.throttle,
+            saturation_backoff: self.saturation_backoff,
             hooks,
         }
     }
@@ -133,6 +145,15 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         self
     }
 
+    /// Stop sending new testcases for `backoff` whenever the broker does not keep up with our messages,
+    /// instead of eventually giving up on the broker. The dropped testcases are counted, see
+    /// [`LlmpEventManager::dropped_events`], and reported to the broker as `dropped_events` user stats.
+    #[must_use]
+    pub fn saturation_backoff(mut self, backoff: Duration) -> Self {
+        self.saturation_backoff = Some(backoff);
+        self
+    }
+
     /// Create a manager from a raw LLMP client
     #[cfg(feature = "adaptive_serialization")]
     pub fn build_from_client<S, SP>(
@@ -148,6 +169,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -177,6 +202,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -207,6 +236,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -241,6 +274,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -269,6 +306,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -301,6 +342,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -328,6 +373,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -359,6 +408,10 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
         Ok(LlmpEventManager {
             throttle: self.throttle,
             last_sent: Duration::from_secs(0),
+            saturation_backoff: self.saturation_backoff,
+            backoff_until: Duration::ZERO,
+            dropped_events: 0,
+            reported_dropped_events: 0,
             hooks: self.hooks,
             llmp,
             #[cfg(feature = "llmp_compression")]
@@ -579,36 +632,34 @@ impl<EMH, S: State, SP: ShMemProvider> LlmpEventManager<EMH, S, SP> {
     pub fn send_exiting(&mut self) -> Result<(), Error> {
         self.llmp.sender_mut().send_exiting()
     }
-}
 
-impl<EMH, S, SP> UsesState for LlmpEventManager<EMH, S, SP>
-where
-    S: State,
-    SP: ShMemProvider,
-{
-    type State = S;
-}
+    /// The amount of new testcases dropped because the broker was saturated,
+    /// see [`LlmpEventManagerBuilder::saturation_backoff`]
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events
+    }
 
-impl<EMH, S, SP> EventFirer for LlmpEventManager<EMH, S, SP>
-where
-    S: State,
-    SP: ShMemProvider,
-{
-    fn should_send(&self) -> bool {
-        if let Some(throttle) = self.throttle {
-            current_time() - self.last_sent > throttle
-        } else {
-            true
+    /// Checks if we have to back off from sending new testcases, and starts a new backoff if the broker is saturated
+    fn should_back_off(&mut self) -> bool {
+        let Some(backoff) = self.saturation_backoff else {
+            return false;
+        };
+        let cur = current_time();
+        if cur < self.backoff_until {
+            return true;
         }
+        if self.llmp.sender().is_saturated() {
+            log::info!("The broker is saturated, not sending new testcases for {backoff:?}");
+            self.backoff_until = cur + backoff;
+            return true;
+        }
+        false
     }
 
     #[cfg(feature = "llmp_compression")]
-    fn fire(
-        &mut self,
-        _state: &mut Self::State,
-        event: Event<<Self::State as UsesInput>::Input>,
-    ) -> Result<(), Error> {
-        let serialized = postcard::to_allocvec(&event)?;
+    fn send_event(&mut self, event: &Event<S::Input>) -> Result<(), Error> {
+        let serialized = postcard::to_allocvec(event)?;
         let flags = LLMP_FLAG_INITIALIZED;
 
         match self.compressor.maybe_compress(&serialized) {
@@ -629,14 +680,53 @@ where
     }
 
     #[cfg(not(feature = "llmp_compression"))]
+    fn send_event(&mut self, event: &Event<S::Input>) -> Result<(), Error> {
+        let serialized = postcard::to_allocvec(event)?;
+        self.llmp.send_buf(LLMP_TAG_EVENT_TO_BOTH, &serialized)?;
+        Ok(())
+    }
+}
+
+impl<EMH, S, SP> UsesState for LlmpEventManager<EMH, S, SP>
+where
+    S: State,
+    SP: ShMemProvider,
+{
+    type State = S;
+}
+
+impl<EMH, S, SP> EventFirer for LlmpEventManager<EMH, S, SP>
+where
+    S: State,
+    SP: ShMemProvider,
+{
+    fn should_send(&self) -> bool {
+        if let Some(throttle) = self.throttle {
+            current_time() - self.last_sent > throttle
+        } else {
+            true
+        }
+    }
+
     fn fire(
         &mut self,
         _state: &mut Self::State,
         event: Event<<Self::State as UsesInput>::Input>,
     ) -> Result<(), Error> {
-        let serialized = postcard::to_allocvec(&event)?;
-        self.llmp.send_buf(LLMP_TAG_EVENT_TO_BOTH, &serialized)?;
-        Ok(())
+        if matches!(event, Event::NewTestcase { .. }) && self.should_back_off() {
+            self.dropped_events += 1;
+            return Ok(());
+        }
+        let dropped_events = self.dropped_events;
+        if dropped_events > self.reported_dropped_events {
+            self.reported_dropped_events = dropped_events;
+            self.send_event(&Event::UpdateUserStats {
+                name: Cow::Borrowed("dropped_events"),
+                value: UserStats::new(UserStatsValue::Number(dropped_events), AggregatorOps::Sum),
+                phantom: PhantomData,
+            })?;
+        }
+        self.send_event(&event)
     }
 
     #[cfg(not(feature = "adaptive_serialization"))]
@@ -766,3 +856,130 @@ where
         EventManagerId(self.llmp.sender().id().0 as usize)
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use alloc::vec::Vec;
+    use core::{marker::PhantomData, time::Duration};
+
+    #[cfg(feature = "adaptive_serialization")]
+    use libafl_bolts::tuples::Handled;
+    use libafl_bolts::{
+        llmp::{LlmpClient, LlmpReceiver, LlmpSharedMap, Tag},
+        rands::StdRand,
+        shmem::{ShMemProvider, StdShMemProvider},
+        ClientId,
+    };
+    use serial_test::serial;
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::{
+            llmp::{LlmpEventManager, LLMP_TAG_EVENT_TO_BOTH},
+            Event, EventConfig, EventFirer, LogSeverity,
+        },
+        executors::ExitKind,
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        monitors::UserStatsValue,
+        state::StdState,
+    };
+
+    #[test]
+    #[serial]
+    #[cfg_attr(miri, ignore)]
+    fn test_saturation_backoff_counts_drops() {
+        const ENV_NAME: &str = "_TEST_LLMP_SATURATION_BACKOFF";
+
+        #[cfg(feature = "adaptive_serialization")]
+        let time_ref = crate::observers::TimeObserver::new("time").handle();
+
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let mut llmp_client = LlmpClient::new(
+            shmem_provider.clone(),
+            LlmpSharedMap::new(ClientId(0), shmem_provider.new_shmem(1024).unwrap()),
+            ClientId(0),
+        )
+        .unwrap();
+
+        // Plays the broker, which does not read anything until the end of the test
+        llmp_client.sender().to_env(ENV_NAME).unwrap();
+        let mut receiver =
+            LlmpReceiver::on_existing_from_env(shmem_provider.clone(), ENV_NAME).unwrap();
+
+        let filler = vec![0; 1 << 16];
+        while !llmp_client.is_saturated() {
+            llmp_client.send_buf(Tag(0x7E57), &filler).unwrap();
+        }
+
+        let builder = LlmpEventManager::builder().saturation_backoff(Duration::from_secs(60));
+        #[cfg(not(feature = "adaptive_serialization"))]
+        let mut llmp_mgr = builder
+            .build_from_client(llmp_client, "fuzzer".into())
+            .unwrap();
+        #[cfg(feature = "adaptive_serialization")]
+        let mut llmp_mgr = builder
+            .build_from_client(llmp_client, "fuzzer".into(), time_ref)
+            .unwrap();
+
+        for _ in 0..2 {
+            // Asking does not drop anything, firing does
+            assert!(llmp_mgr.should_send());
+            assert_eq!(llmp_mgr.dropped_events(), 0);
+            llmp_mgr
+                .fire(
+                    &mut state,
+                    Event::NewTestcase {
+                        input: BytesInput::new(vec![1]),
+                        observers_buf: None,
+                        exit_kind: ExitKind::Ok,
+                        corpus_size: 1,
+                        client_config: EventConfig::AlwaysUnique,
+                        time: Duration::ZERO,
+                        executions: 0,
+                        forward_id: None,
+                    },
+                )
+                .unwrap();
+        }
+        assert_eq!(llmp_mgr.dropped_events(), 2);
+
+        // The drops get reported with the next event sent
+        llmp_mgr
+            .fire(
+                &mut state,
+                Event::Log {
+                    severity_level: LogSeverity::Info,
+                    message: "test".into(),
+                    phantom: PhantomData,
+                },
+            )
+            .unwrap();
+
+        let mut events = Vec::new();
+        while let Some((_, tag, buf)) = receiver.recv_buf().unwrap() {
+            if tag == LLMP_TAG_EVENT_TO_BOTH {
+                events.push(postcard::from_bytes::<Event<BytesInput>>(buf).unwrap());
+            }
+        }
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            Event::UpdateUserStats { name, value, .. }
+                if name == "dropped_events" && matches!(value.value(), UserStatsValue::Number(2))
+        ));
+        assert!(matches!(events[1], Event::Log { .. }));
+    }
+}
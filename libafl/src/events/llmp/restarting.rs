@@ -3,19 +3,25 @@
 //! When the target crashes, a watch process (the parent) will
 //! restart/refork it.
 
-use alloc::vec::Vec;
+#[cfg(all(unix, feature = "std", feature = "fork"))]
+use alloc::sync::Arc;
+use alloc::{boxed::Box, vec::Vec};
 #[cfg(all(unix, not(miri), feature = "std"))]
 use core::ptr::addr_of_mut;
 #[cfg(feature = "std")]
 use core::sync::atomic::{compiler_fence, Ordering};
-#[cfg(all(feature = "std", feature = "adaptive_serialization"))]
+#[cfg(feature = "std")]
 use core::time::Duration;
 use core::{marker::PhantomData, num::NonZeroUsize};
+#[cfg(all(unix, feature = "std", feature = "fork"))]
+use core::{mem::size_of, sync::atomic::AtomicBool};
 #[cfg(feature = "std")]
 use std::net::SocketAddr;
 
 #[cfg(feature = "std")]
 use libafl_bolts::core_affinity::CoreId;
+#[cfg(feature = "std")]
+use libafl_bolts::current_time;
 #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
 use libafl_bolts::os::startable_self;
 #[cfg(all(unix, feature = "std", not(miri)))]
@@ -28,7 +34,10 @@ use libafl_bolts::tuples::{Handle, Handled};
 use libafl_bolts::{
     llmp::LlmpConnection, os::CTRL_C_EXIT, shmem::StdShMemProvider, staterestore::StateRestorer,
 };
-use libafl_bolts::{shmem::ShMemProvider, tuples::tuple_list};
+use libafl_bolts::{
+    shmem::{ShMem, ShMemProvider},
+    tuples::tuple_list,
+};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
 use typed_builder::TypedBuilder;
@@ -43,9 +52,10 @@ use crate::events::EVENTMGR_SIGHANDLER_STATE;
 use crate::observers::TimeObserver;
 use crate::{
     events::{
-        hooks::EventManagerHooksTuple, Event, EventConfig, EventFirer, EventManager,
-        EventManagerId, EventProcessor, EventRestarter, HasEventManagerId, LlmpEventBroker,
-        LlmpEventManager, LlmpShouldSaveState, ProgressReporter,
+        hooks::EventManagerHooksTuple, CustomBufEventResult, Event, EventConfig, EventFirer,
+        EventManager, EventManagerId, EventProcessor, EventRestarter, HasCustomBufHandlers,
+        HasEventManagerId, LlmpEventBroker, LlmpEventManager, LlmpShouldSaveState,
+        ProgressReporter,
     },
     executors::{Executor, HasObservers},
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
@@ -71,6 +81,8 @@ where
     staterestorer: StateRestorer<SP>,
     /// Decide if the state restorer must save the serialized state
     save_state: LlmpShouldSaveState,
+    /// The executions of this client, watched by the restarter, if it runs a client watchdog
+    watchdog_heartbeat: Option<SP::ShMem>,
 }
 
 #[cfg(all(feature = "std", feature = "adaptive_serialization"))]
@@ -133,6 +145,27 @@ where
     S: State + HasExecutions + HasMetadata + HasLastReportTime,
     SP: ShMemProvider,
 {
+    fn maybe_report_progress(
+        &mut self,
+        state: &mut Self::State,
+        monitor_timeout: Duration,
+    ) -> Result<(), Error> {
+        // Called on every iteration of the fuzz loop, before the next corpus entry is fuzzed
+        self.heartbeat(*state.executions());
+
+        let Some(last_report_time) = state.last_report_time() else {
+            // this is the first time we execute, no need to report progress just yet.
+            *state.last_report_time_mut() = Some(current_time());
+            return Ok(());
+        };
+        let cur = current_time();
+        // default to 0 here to avoid crashes on clock skew
+        if cur.checked_sub(*last_report_time).unwrap_or_default() > monitor_timeout {
+            // report_progress sets a new `last_report_time` internally.
+            self.report_progress(state)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "std")]
@@ -211,6 +244,20 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<EMH, S, SP> HasCustomBufHandlers for LlmpRestartingEventManager<EMH, S, SP>
+where
+    S: State,
+    SP: ShMemProvider,
+{
+    fn add_custom_buf_handler(
+        &mut self,
+        handler: Box<dyn FnMut(&mut S, &str, &[u8]) -> Result<CustomBufEventResult, Error>>,
+    ) {
+        self.llmp_mgr.add_custom_buf_handler(handler);
+    }
+}
+
 #[cfg(feature = "std")]
 impl<E, EMH, S, SP, Z> EventProcessor<E, Z> for LlmpRestartingEventManager<EMH, S, SP>
 where
@@ -224,6 +271,7 @@ where
     fn process(&mut self, fuzzer: &mut Z, state: &mut S, executor: &mut E) -> Result<usize, Error> {
         let res = self.llmp_mgr.process(fuzzer, state, executor)?;
         self.intermediate_save()?;
+        self.heartbeat(*state.executions());
         Ok(res)
     }
 }
@@ -270,6 +318,7 @@ where
             llmp_mgr,
             staterestorer,
             save_state: LlmpShouldSaveState::OnRestart,
+            watchdog_heartbeat: None,
         }
     }
 
@@ -283,6 +332,7 @@ where
             llmp_mgr,
             staterestorer,
             save_state,
+            watchdog_heartbeat: None,
         }
     }
 
@@ -306,6 +356,84 @@ where
         }
         Ok(())
     }
+
+    /// Tells the client watchdog of the restarter, if any, that this client is still making progress
+    fn heartbeat(&mut self, executions: u64) {
+        if let Some(heartbeat) = self
+            .watchdog_heartbeat
+            .as_mut()
+            .and_then(|heartbeat| heartbeat.as_mut_ptr_of::<u64>())
+        {
+            unsafe { heartbeat.write_volatile(executions) };
+        }
+    }
+}
+
+/// Watches the heartbeat of a forked client, killing the client once its executions stalled for too long
+#[cfg(all(unix, feature = "std", feature = "fork"))]
+#[derive(Debug)]
+struct ClientWatchdog {
+    done: Arc<AtomicBool>,
+    fired: Arc<AtomicBool>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+#[cfg(all(unix, feature = "std", feature = "fork"))]
+impl ClientWatchdog {
+    /// Starts watching the client with the given `pid`.
+    /// The `heartbeat` map has to outlive the watchdog, i.e., until [`ClientWatchdog::stop`] returned.
+    fn spawn(pid: libc::pid_t, heartbeat: *const u64, timeout: Duration) -> Self {
+        let done = Arc::new(AtomicBool::new(false));
+        let fired = Arc::new(AtomicBool::new(false));
+        // Raw pointers are not `Send`
+        let heartbeat = heartbeat as usize;
+
+        let thread = {
+            let done = done.clone();
+            let fired = fired.clone();
+            std::thread::spawn(move || {
+                let heartbeat = heartbeat as *const u64;
+                let poll_interval =
+                    (timeout / 10).clamp(Duration::from_millis(10), Duration::from_secs(1));
+                let mut last_beat = 0;
+                let mut last_change = current_time();
+                while !done.load(Ordering::Acquire) {
+                    std::thread::sleep(poll_interval);
+                    let beat = unsafe { heartbeat.read_volatile() };
+                    let now = current_time();
+                    // Only clients that started fuzzing are watched, so a slow startup is never cut short
+                    if beat == 0 || beat != last_beat {
+                        last_beat = beat;
+                        last_change = now;
+                    } else if now.saturating_sub(last_change) > timeout
+                        && !done.load(Ordering::Acquire)
+                    {
+                        log::warn!(
+                            "Client (pid {pid}) made no progress for {timeout:?}, killing it to restart it"
+                        );
+                        fired.store(true, Ordering::Release);
+                        unsafe {
+                            libc::kill(pid, libc::SIGKILL);
+                        }
+                        break;
+                    }
+                }
+            })
+        };
+
+        Self {
+            done,
+            fired,
+            thread,
+        }
+    }
+
+    /// Stops the watchdog after the client exited, returning if the watchdog killed it
+    fn stop(self) -> bool {
+        self.done.store(true, Ordering::Release);
+        drop(self.thread.join());
+        self.fired.load(Ordering::Acquire)
+    }
 }
 
 /// The kind of manager we're creating right now
@@ -429,6 +557,14 @@ where
     serialize_state: LlmpShouldSaveState,
     /// The hooks passed to event manager:
     hooks: EMH,
+    /// Kill and restart a client whose executions stalled for this long, e.g., due to a wedged forkserver.
+    /// The client reports its executions once per iteration of the fuzz loop, i.e., after all stages ran
+    /// on a corpus entry, and whenever it processes events, not after every execution.
+    /// The timeout therefore has to exceed the longest time spent on one corpus entry.
+    /// Only supported with `fork` on Unix. Since a killed client cannot store its state,
+    /// this requires an OOM-safe `serialize_state`, and the restarted client starts with a fresh state.
+    #[builder(default = None)]
+    client_watchdog_timeout: Option<Duration>,
     #[cfg(feature = "adaptive_serialization")]
     time_ref: Handle<TimeObserver>,
     #[builder(setter(skip), default = PhantomData)]
@@ -447,8 +583,10 @@ where
     /// Launch the broker and the clients and fuzz
     pub fn launch(&mut self) -> Result<(Option<S>, LlmpRestartingEventManager<EMH, S, SP>), Error> {
         // We start ourself as child process to actually fuzz
-        let (staterestorer, new_shmem_provider, core_id) = if std::env::var(_ENV_FUZZER_SENDER)
-            .is_err()
+        let (staterestorer, new_shmem_provider, core_id, heartbeat) = if std::env::var(
+            _ENV_FUZZER_SENDER,
+        )
+        .is_err()
         {
             let broker_things = |mut broker: LlmpEventBroker<S::Input, MT, SP>,
                                  remote_broker_addr| {
@@ -555,6 +693,23 @@ where
             // Store the information to a map.
             staterestorer.write_to_env(_ENV_FUZZER_SENDER)?;
 
+            // The clients report their executions to the watchdog through this map
+            #[cfg(all(unix, feature = "fork"))]
+            let mut heartbeat = if self.client_watchdog_timeout.is_some() {
+                if !self.serialize_state.oom_safe() {
+                    return Err(Error::illegal_argument(
+                        "The client watchdog requires an OOM-safe serialize_state, as killed clients cannot store their state",
+                    ));
+                }
+                Some(self.shmem_provider.new_shmem(size_of::<u64>())?)
+            } else {
+                None
+            };
+            #[cfg(not(all(unix, feature = "fork")))]
+            if self.client_watchdog_timeout.is_some() {
+                log::warn!("The client watchdog is only supported with fork on Unix, ignoring it");
+            }
+
             let mut ctr: u64 = 0;
             // Client->parent loop
             loop {
@@ -563,6 +718,10 @@ where
                 // On Unix, we fork (when fork feature is enabled)
                 #[cfg(all(unix, feature = "fork"))]
                 let child_status = {
+                    // The next client has to start fuzzing before it is watched
+                    if let Some(heartbeat) = heartbeat.as_mut() {
+                        heartbeat.fill(0);
+                    }
                     self.shmem_provider.pre_fork()?;
                     match unsafe { fork() }? {
                         ForkResult::Parent(handle) => {
@@ -570,11 +729,28 @@ where
                                 libc::signal(libc::SIGINT, libc::SIG_IGN);
                             }
                             self.shmem_provider.post_fork(false)?;
-                            handle.status()
+                            let watchdog = self.client_watchdog_timeout.zip(
+                                heartbeat
+                                    .as_ref()
+                                    .and_then(|heartbeat| heartbeat.as_ptr_of::<u64>()),
+                            );
+                            let watchdog = watchdog.map(|(timeout, heartbeat)| {
+                                ClientWatchdog::spawn(handle.pid, heartbeat, timeout)
+                            });
+                            let child_status = handle.status();
+                            if watchdog.is_some_and(ClientWatchdog::stop) {
+                                log::warn!("Restarting the stalled client without its state");
+                            }
+                            child_status
                         }
                         ForkResult::Child => {
                             self.shmem_provider.post_fork(true)?;
-                            break (staterestorer, self.shmem_provider.clone(), core_id);
+                            break (
+                                staterestorer,
+                                self.shmem_provider.clone(),
+                                core_id,
+                                heartbeat,
+                            );
                         }
                     }
                 };
@@ -605,6 +781,12 @@ where
                     if let Err(err) = mgr.detach_from_broker(self.broker_port) {
                         log::error!("Failed to detach from broker: {err}");
                     }
+                    if crate::stages::stop_on_objective::is_stop_on_objective_exit_code(
+                        child_status,
+                    ) {
+                        // Pass the objective type on to whoever started the fuzzer
+                        std::process::exit(child_status);
+                    }
                     return Err(Error::shutting_down());
                 }
 
@@ -631,6 +813,7 @@ where
                 StateRestorer::from_env(&mut self.shmem_provider, _ENV_FUZZER_SENDER)?,
                 self.shmem_provider.clone(),
                 None,
+                None,
             )
         };
 
@@ -705,6 +888,8 @@ where
                     ),
                 )
             };
+        mgr.watchdog_heartbeat = heartbeat;
+
         // We reset the staterestorer, the next staterestorer and receiver (after crash) will reuse the page from the initial message.
         if self.serialize_state.oom_safe() {
             mgr.intermediate_save()?;
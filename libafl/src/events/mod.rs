@@ -24,6 +24,7 @@ use core::{
     fmt,
     hash::{BuildHasher, Hasher},
     marker::PhantomData,
+    str::FromStr,
     time::Duration,
 };
 
@@ -108,8 +109,8 @@ use crate::monitors::ClientPerfMonitor;
 use crate::observers::TimeObserver;
 use crate::{inputs::UsesInput, stages::HasCurrentStage, state::UsesState};
 
-/// The log event severity
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+/// The log event severity, ordered from the least to the most severe
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LogSeverity {
     /// Debug severity
     Debug,
@@ -126,12 +127,43 @@ impl From<LogSeverity> for log::Level {
         match value {
             LogSeverity::Debug => log::Level::Debug,
             LogSeverity::Info => log::Level::Info,
-            LogSeverity::Warn => log::Level::Trace,
+            LogSeverity::Warn => log::Level::Warn,
             LogSeverity::Error => log::Level::Error,
         }
     }
 }
 
+impl From<LogSeverity> for log::LevelFilter {
+    fn from(value: LogSeverity) -> Self {
+        log::Level::from(value).to_level_filter()
+    }
+}
+
+impl LogSeverity {
+    /// Only show log events of at least this severity, in all monitors rendering them through [`log`],
+    /// such as the default [`crate::monitors::Monitor::log`]
+    pub fn set_max_level(self) {
+        log::set_max_level(self.into());
+    }
+}
+
+impl FromStr for LogSeverity {
+    type Err = Error;
+
+    /// Parses a severity name, such as the value of a log level flag, ignoring the case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warn" | "warning" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            _ => Err(Error::illegal_argument(format!(
+                "Unknown log severity {s:?}, expected debug, info, warn or error"
+            ))),
+        }
+    }
+}
+
 impl fmt::Display for LogSeverity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -920,7 +952,7 @@ mod tests {
     use tuple_list::tuple_list_type;
 
     use crate::{
-        events::{Event, EventConfig},
+        events::{Event, EventConfig, LogSeverity},
         executors::ExitKind,
         inputs::bytes::BytesInput,
         observers::StdMapObserver,
@@ -969,4 +1001,13 @@ mod tests {
             _ => panic!("mistmatch"),
         };
     }
+
+    #[test]
+    fn test_log_severity() {
+        assert_eq!("warning".parse::<LogSeverity>().unwrap(), LogSeverity::Warn);
+        assert_eq!("INFO".parse::<LogSeverity>().unwrap(), LogSeverity::Info);
+        assert!("verbose".parse::<LogSeverity>().is_err());
+        assert!(LogSeverity::Debug < LogSeverity::Error);
+        assert_eq!(log::Level::from(LogSeverity::Warn), log::Level::Warn);
+    }
 }
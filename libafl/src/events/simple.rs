@@ -286,8 +286,7 @@ where
                 message,
                 phantom: _,
             } => {
-                let (_, _) = (message, severity_level);
-                log::log!((*severity_level).into(), "{message}");
+                monitor.log(ClientId(0), *severity_level, message);
                 Ok(BrokerEventResult::Handled)
             }
             Event::CustomBuf { .. } => Ok(BrokerEventResult::Forward),
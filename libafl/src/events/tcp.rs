@@ -403,9 +403,7 @@ where
                 message,
                 phantom: _,
             } => {
-                let (_, _) = (severity_level, message);
-                // TODO rely on Monitor
-                log::log!((*severity_level).into(), "{message}");
+                monitor.log(client_id, *severity_level, message);
                 Ok(BrokerEventResult::Handled)
             }
             Event::CustomBuf { .. } => Ok(BrokerEventResult::Forward),
@@ -0,0 +1,155 @@
+//! A debugging helper showing which map entries two inputs cover differently,
+//! e.g., to understand what a mutation changed in terms of coverage.
+
+use alloc::vec::Vec;
+
+use libafl_bolts::tuples::Handle;
+
+use crate::{
+    executors::{determinism::run_and_scan_map, Executor, ExitKind, HasObservers},
+    observers::{MapObserver, ObserversTuple},
+    state::UsesState,
+    Error,
+};
+
+/// The outcome of [`diff_coverage`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageDiff {
+    /// The map entries only the first input covered
+    pub only_first: Vec<usize>,
+    /// The map entries only the second input covered
+    pub only_second: Vec<usize>,
+    /// How the runs of the first and the second input exited
+    pub exit_kinds: (ExitKind, ExitKind),
+}
+
+impl CoverageDiff {
+    /// The map entries covered by exactly one of the inputs, in ascending order
+    #[must_use]
+    pub fn symmetric_difference(&self) -> Vec<usize> {
+        let mut entries = [self.only_first.as_slice(), self.only_second.as_slice()].concat();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// If both inputs covered the same map entries
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.only_first.is_empty() && self.only_second.is_empty()
+    }
+}
+
+/// Runs `first` and `second` once each and compares which entries of the given map observer they cover.
+/// Only whether an entry is covered counts, not its hit count.
+///
+/// The result is only meaningful for deterministic targets, see [`crate::executors::check_determinism`].
+pub fn diff_coverage<C, E, EM, O, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut E::State,
+    manager: &mut EM,
+    first: &E::Input,
+    second: &E::Input,
+    map_observer: &Handle<C>,
+) -> Result<CoverageDiff, Error>
+where
+    E: Executor<EM, Z> + HasObservers,
+    E::Observers: ObserversTuple<E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+    O: MapObserver,
+    C: AsRef<O>,
+{
+    let first =
+        run_and_scan_map::<C, E, EM, O, Z>(fuzzer, executor, state, manager, first, map_observer)?;
+    let second =
+        run_and_scan_map::<C, E, EM, O, Z>(fuzzer, executor, state, manager, second, map_observer)?;
+
+    let mut diff = CoverageDiff {
+        only_first: vec![],
+        only_second: vec![],
+        exit_kinds: (first.exit_kind, second.exit_kind),
+    };
+    for (idx, (first, second)) in first.covered().zip(second.covered()).enumerate() {
+        match (first, second) {
+            (true, false) => diff.only_first.push(idx),
+            (false, true) => diff.only_second.push(idx),
+            _ => {}
+        }
+    }
+    log::info!(
+        "Coverage diff: {} map entries only covered by the first input, {} only by the second",
+        diff.only_first.len(),
+        diff.only_second.len()
+    );
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::Handled};
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{coverage_diff::CoverageDiff, diff_coverage, test::MapExecutor, ExitKind},
+        feedbacks::ConstFeedback,
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        state::StdState,
+    };
+
+    #[test]
+    fn test_diff_coverage_runs() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut executor = MapExecutor::new(false);
+        let handle = executor.map_observer().handle();
+
+        // Entries 1 and 2 are shared, the map is reset between the runs
+        let diff = diff_coverage(
+            &mut NopFuzzer::new(),
+            &mut executor,
+            &mut state,
+            &mut NopEventManager::new(),
+            &BytesInput::new(vec![0, 1, 2]),
+            &BytesInput::new(vec![1, 2, 5, 13]),
+            &handle,
+        )
+        .unwrap();
+        assert_eq!(
+            diff,
+            CoverageDiff {
+                only_first: vec![0],
+                only_second: vec![5],
+                exit_kinds: (ExitKind::Ok, ExitKind::Ok),
+            }
+        );
+    }
+
+    #[test]
+    fn test_coverage_diff() {
+        let diff = CoverageDiff {
+            only_first: vec![7, 2],
+            only_second: vec![5],
+            exit_kinds: (ExitKind::Ok, ExitKind::Ok),
+        };
+        assert_eq!(diff.symmetric_difference(), [2, 5, 7]);
+        assert!(!diff.is_empty());
+
+        let diff = CoverageDiff {
+            only_first: vec![],
+            only_second: vec![],
+            ..diff
+        };
+        assert!(diff.is_empty());
+    }
+}
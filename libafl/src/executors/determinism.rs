@@ -0,0 +1,294 @@
+//! A preflight check for targets whose coverage changes between runs of the same input,
+//! which undermines any coverage feedback.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use libafl_bolts::{
+    current_time,
+    tuples::{Handle, MatchNameRef},
+};
+
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    observers::{MapObserver, ObserversTuple},
+    state::UsesState,
+    Error,
+};
+
+/// Below this stability (in percent), [`check_determinism`] warns that the target is flaky
+pub const FLAKY_STABILITY_THRESHOLD: f64 = 90.0;
+
+/// The outcome of [`check_determinism`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeterminismReport {
+    /// The number of runs
+    pub runs: usize,
+    /// The number of runs that did not exit with [`ExitKind::Ok`]
+    pub errored_runs: usize,
+    /// The number of map entries set in any run
+    pub filled_entries: usize,
+    /// The number of map entries whose value differed between runs
+    pub unstable_entries: usize,
+}
+
+impl DeterminismReport {
+    /// The share of filled map entries that were the same in all runs, in percent, like `AFL++`'s stability
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn stability(&self) -> f64 {
+        if self.filled_entries == 0 {
+            100.0
+        } else {
+            (self.filled_entries - self.unstable_entries) as f64 * 100.0
+                / self.filled_entries as f64
+        }
+    }
+
+    /// If the stability is below [`FLAKY_STABILITY_THRESHOLD`]
+    #[must_use]
+    pub fn is_flaky(&self) -> bool {
+        self.stability() < FLAKY_STABILITY_THRESHOLD
+    }
+}
+
+/// A single run of an input, as seen by a map observer, see [`run_and_scan_map`]
+#[derive(Debug, Clone)]
+pub(crate) struct MapRun<T> {
+    /// How the run finished
+    pub(crate) exit_kind: ExitKind,
+    /// How long the execution of the target took
+    pub(crate) exec_time: Duration,
+    /// The usable entries of the map after the run
+    pub(crate) entries: Vec<T>,
+    /// The initial value of the map entries
+    pub(crate) initial: T,
+}
+
+impl<T> MapRun<T>
+where
+    T: PartialEq,
+{
+    /// If each entry was covered by the run, i.e., differs from the initial value
+    pub(crate) fn covered(&self) -> impl Iterator<Item = bool> + '_ {
+        self.entries.iter().map(|entry| *entry != self.initial)
+    }
+}
+
+/// Runs `input` once, with the pre- and post-exec hooks of the observers as in the fuzzing loop,
+/// and scans the usable part of the map of the given map observer afterwards.
+/// The shared step of [`check_determinism`], [`crate::executors::diff_coverage`] and
+/// [`crate::executors::dry_run`].
+pub(crate) fn run_and_scan_map<C, E, EM, O, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut E::State,
+    manager: &mut EM,
+    input: &E::Input,
+    map_observer: &Handle<C>,
+) -> Result<MapRun<O::Entry>, Error>
+where
+    E: Executor<EM, Z> + HasObservers,
+    E::Observers: ObserversTuple<E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+    O: MapObserver,
+    C: AsRef<O>,
+{
+    executor.observers_mut().pre_exec_all(state, input)?;
+    let start = current_time();
+    let exit_kind = executor.run_target(fuzzer, state, manager, input)?;
+    let exec_time = current_time().saturating_sub(start);
+    executor
+        .observers_mut()
+        .post_exec_all(state, input, &exit_kind)?;
+
+    let observers = executor.observers();
+    let map = observers
+        .get(map_observer)
+        .ok_or_else(|| Error::key_not_found(format!("MapObserver {}", map_observer.name())))?
+        .as_ref();
+    Ok(MapRun {
+        exit_kind,
+        exec_time,
+        entries: (0..map.usable_count()).map(|idx| map.get(idx)).collect(),
+        initial: map.initial(),
+    })
+}
+
+/// Runs `input` `runs` times and compares the maps of the given map observer,
+/// reporting how many map entries are nondeterministic. Run it on a seed before the campaign starts.
+///
+/// Logs the stability and warns if the target is flaky.
+pub fn check_determinism<C, E, EM, O, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut E::State,
+    manager: &mut EM,
+    input: &E::Input,
+    map_observer: &Handle<C>,
+    runs: usize,
+) -> Result<DeterminismReport, Error>
+where
+    E: Executor<EM, Z> + HasObservers,
+    E::Observers: ObserversTuple<E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+    O: MapObserver,
+    C: AsRef<O>,
+{
+    if runs < 2 {
+        return Err(Error::illegal_argument(
+            "Checking the determinism needs at least two runs",
+        ));
+    }
+
+    let mut first: Option<MapRun<O::Entry>> = None;
+    let mut unstable: Vec<bool> = vec![];
+    let mut filled: Vec<bool> = vec![];
+    let mut errored_runs = 0;
+    for _ in 0..runs {
+        let run = run_and_scan_map::<C, E, EM, O, Z>(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            input,
+            map_observer,
+        )?;
+        if run.exit_kind != ExitKind::Ok {
+            errored_runs += 1;
+        }
+
+        match &first {
+            None => {
+                unstable = vec![false; run.entries.len()];
+                filled = run.covered().collect();
+                first = Some(run);
+            }
+            Some(first) => {
+                for (idx, (covered, (entry, first_entry))) in run
+                    .covered()
+                    .zip(run.entries.iter().zip(&first.entries))
+                    .enumerate()
+                {
+                    if covered {
+                        filled[idx] = true;
+                    }
+                    if entry != first_entry {
+                        unstable[idx] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let report = DeterminismReport {
+        runs,
+        errored_runs,
+        filled_entries: filled.iter().filter(|filled| **filled).count(),
+        unstable_entries: unstable.iter().filter(|unstable| **unstable).count(),
+    };
+    log::info!(
+        "Determinism check: stability {:.2}% ({} of {} map entries unstable over {runs} runs, {errored_runs} runs errored)",
+        report.stability(),
+        report.unstable_entries,
+        report.filled_entries,
+    );
+    if report.is_flaky() {
+        log::warn!(
+            "The target is flaky: only {:.2}% of its coverage is stable, coverage feedback will be unreliable",
+            report.stability()
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::Handled};
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{check_determinism, determinism::DeterminismReport, test::MapExecutor},
+        feedbacks::ConstFeedback,
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        state::StdState,
+    };
+
+    #[test]
+    fn test_check_determinism() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = NopFuzzer::new();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0, 1, 2]);
+
+        let mut executor = MapExecutor::new(false);
+        let handle = executor.map_observer().handle();
+        let report = check_determinism(
+            &mut fuzzer,
+            &mut executor,
+            &mut state,
+            &mut mgr,
+            &input,
+            &handle,
+            4,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            DeterminismReport {
+                runs: 4,
+                errored_runs: 0,
+                filled_entries: 3,
+                unstable_entries: 0,
+            }
+        );
+
+        // The last entry is only covered every other run
+        let mut executor = MapExecutor::new(true);
+        let report = check_determinism(
+            &mut fuzzer,
+            &mut executor,
+            &mut state,
+            &mut mgr,
+            &input,
+            &handle,
+            4,
+        )
+        .unwrap();
+        assert_eq!(report.filled_entries, 4);
+        assert_eq!(report.unstable_entries, 1);
+        assert!((report.stability() - 75.0).abs() < f64::EPSILON);
+        assert!(report.is_flaky());
+    }
+
+    #[test]
+    fn test_determinism_report() {
+        let report = DeterminismReport {
+            runs: 8,
+            errored_runs: 0,
+            filled_entries: 20,
+            unstable_entries: 4,
+        };
+        assert!((report.stability() - 80.0).abs() < f64::EPSILON);
+        assert!(report.is_flaky());
+
+        let report = DeterminismReport {
+            unstable_entries: 0,
+            ..report
+        };
+        assert!(!report.is_flaky());
+    }
+}
@@ -0,0 +1,199 @@
+//! A dry run of a harness setup, executing a single input to catch misconfigurations
+//! before committing to a long campaign.
+
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+use libafl_bolts::tuples::Handle;
+
+use crate::{
+    executors::{determinism::run_and_scan_map, Executor, ExitKind, HasObservers},
+    observers::{MapObserver, ObserversTuple},
+    state::UsesState,
+    Error,
+};
+
+/// The outcome of [`dry_run`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DryRunReport {
+    /// The usable size of the coverage map, i.e., the map size negotiated with the target, if any
+    pub map_size: usize,
+    /// The number of map entries the input covered
+    pub covered_entries: usize,
+    /// How the execution finished
+    pub exit_kind: ExitKind,
+    /// How long the execution took
+    pub exec_time: Duration,
+}
+
+impl DryRunReport {
+    /// The likely misconfigurations the dry run points to, with a hint each; empty if the setup looks sane
+    #[must_use]
+    pub fn problems(&self) -> Vec<&'static str> {
+        let mut problems = vec![];
+        if self.map_size == 0 {
+            problems.push("the coverage map is empty: check the map size the target reported");
+        }
+        if self.covered_entries == 0 {
+            problems.push(
+                "no coverage was observed: is the target instrumented, and does the observer read the map the target writes to?",
+            );
+        }
+        match self.exit_kind {
+            ExitKind::Ok => {}
+            ExitKind::Crash => problems.push(
+                "the input crashed: check the input mode (stdin, file, or shared memory) and the seed",
+            ),
+            ExitKind::Timeout => problems.push(
+                "the input timed out: raise the timeout, or check that the target does not wait for input it never gets",
+            ),
+            ExitKind::Oom => problems.push("the input ran out of memory: raise the memory limit"),
+            ExitKind::Diff { .. } => {
+                problems.push("the differential executors disagreed on the input");
+            }
+        }
+        problems
+    }
+
+    /// If the setup looks sane
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+impl Display for DryRunReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "map size:        {}", self.map_size)?;
+        writeln!(f, "covered entries: {}", self.covered_entries)?;
+        writeln!(f, "exit kind:       {:?}", self.exit_kind)?;
+        write!(f, "exec time:       {:?}", self.exec_time)?;
+        for problem in self.problems() {
+            write!(f, "\nproblem:         {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Executes `input` once, usually a seed, and reports the map size, the coverage, the exit kind and the exec time.
+/// Call it after the full setup of the client, instead of starting the fuzzing loop, to validate the harness.
+///
+/// Logs the report, and warns about each problem found, see [`DryRunReport::problems`].
+pub fn dry_run<C, E, EM, O, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut E::State,
+    manager: &mut EM,
+    input: &E::Input,
+    map_observer: &Handle<C>,
+) -> Result<DryRunReport, Error>
+where
+    E: Executor<EM, Z> + HasObservers,
+    E::Observers: ObserversTuple<E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+    O: MapObserver,
+    C: AsRef<O>,
+{
+    let run =
+        run_and_scan_map::<C, E, EM, O, Z>(fuzzer, executor, state, manager, input, map_observer)?;
+    let report = DryRunReport {
+        map_size: run.entries.len(),
+        covered_entries: run.covered().filter(|covered| *covered).count(),
+        exit_kind: run.exit_kind,
+        exec_time: run.exec_time,
+    };
+    log::info!("Dry run:\n{report}");
+    for problem in report.problems() {
+        log::warn!("Dry run: {problem}");
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use libafl_bolts::{rands::StdRand, tuples::Handled};
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{
+            dry_run::{dry_run, DryRunReport},
+            test::MapExecutor,
+            ExitKind,
+        },
+        feedbacks::ConstFeedback,
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        state::StdState,
+    };
+
+    #[test]
+    fn test_dry_run_executes() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = NopFuzzer::new();
+        let mut mgr = NopEventManager::new();
+        let mut executor = MapExecutor::new(false);
+        let handle = executor.map_observer().handle();
+
+        let report = dry_run(
+            &mut fuzzer,
+            &mut executor,
+            &mut state,
+            &mut mgr,
+            &BytesInput::new(vec![3, 4, 11]),
+            &handle,
+        )
+        .unwrap();
+        assert_eq!(report.map_size, MapExecutor::<()>::MAP_SIZE);
+        assert_eq!(report.covered_entries, 2);
+        assert_eq!(report.exit_kind, ExitKind::Ok);
+        assert!(report.is_ok());
+
+        // The map is reset before the run: an input covering nothing, which times out
+        let report = dry_run(
+            &mut fuzzer,
+            &mut executor,
+            &mut state,
+            &mut mgr,
+            &BytesInput::new(vec![]),
+            &handle,
+        )
+        .unwrap();
+        assert_eq!(report.covered_entries, 0);
+        assert_eq!(report.exit_kind, ExitKind::Timeout);
+        assert_eq!(report.problems().len(), 2);
+    }
+
+    #[test]
+    fn test_dry_run_report() {
+        let report = DryRunReport {
+            map_size: 65536,
+            covered_entries: 42,
+            exit_kind: ExitKind::Ok,
+            exec_time: Duration::from_millis(1),
+        };
+        assert!(report.is_ok());
+
+        let report = DryRunReport {
+            covered_entries: 0,
+            exit_kind: ExitKind::Timeout,
+            ..report
+        };
+        assert_eq!(report.problems().len(), 2);
+    }
+}
@@ -1,8 +1,8 @@
 //! Expose an `Executor` based on a `Forkserver` in order to execute AFL/AFL++ binaries
 
-use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
+use alloc::{borrow::ToOwned, boxed::Box, string::ToString, sync::Arc, vec::Vec};
 use core::{
-    fmt::{self, Debug, Formatter},
+    fmt::{self, Debug, Display, Formatter},
     marker::PhantomData,
     time::Duration,
 };
@@ -26,6 +26,7 @@ use libafl_bolts::{
     AsSlice, AsSliceMut, Truncate,
 };
 use nix::{
+    errno::Errno,
     sys::{
         select::{pselect, FdSet},
         signal::{kill, SigSet, Signal},
@@ -38,15 +39,26 @@ use nix::{
 #[cfg(feature = "regex")]
 use crate::observers::{get_asan_runtime_flags_with_log_path, AsanBacktraceObserver};
 use crate::{
-    executors::{Executor, ExitKind, HasObservers},
+    executors::{DiffExecutor, Executor, ExitKind, HasAdjustableTimeout, HasObservers},
     inputs::{HasTargetBytes, Input, UsesInput},
     mutators::Tokens,
-    observers::{MapObserver, Observer, ObserversTuple, UsesObservers},
+    observers::{
+        DeliveredInputObserver, ExitCodeObserver, MapObserver, Observer, ObserversTuple,
+        UsesObservers,
+    },
     state::{HasExecutions, State, UsesState},
     Error,
 };
 
 const FORKSRV_FD: i32 = 198;
+
+/// The env var passing the id of a second, context-sensitive coverage map to the target,
+/// see [`ForkserverExecutorBuilder::context_map`]. Its size is passed in `__AFL_CTX_SHM_ID_SIZE`.
+pub const CONTEXT_MAP_SHM_ENV: &str = "__AFL_CTX_SHM_ID";
+
+/// The env var passing the id of a map of harness-defined features to the target,
+/// see [`ForkserverExecutorBuilder::features_map`]. Its size is passed in `__LIBAFL_FEATURES_SHM_ID_SIZE`.
+pub const FEATURES_MAP_SHM_ENV: &str = "__LIBAFL_FEATURES_SHM_ID";
 #[allow(clippy::cast_possible_wrap)]
 const FS_NEW_ERROR: i32 = 0xeffe0000_u32 as i32;
 
@@ -222,6 +234,17 @@ impl ConfigTarget for Command {
     }
 }
 
+/// The options the in-target forkserver announced during the handshake
+#[derive(Debug, Clone, Default)]
+struct ForkserverOptions {
+    /// The map size requested by the target, rounded up to 64 bytes
+    map_size: Option<usize>,
+    /// If the target wants its testcases via shared memory
+    shmem_fuzz: bool,
+    /// The autodictionary sent by the target
+    autodict: Option<Vec<u8>>,
+}
+
 /// The [`Forkserver`] is communication channel with a child process that forks on request of the fuzzer.
 /// The communication happens via pipe.
 #[derive(Debug)]
@@ -331,8 +354,8 @@ impl Forkserver {
             log::warn!("__AFL_SHM_ID not set. It is necessary to set this env, otherwise the forkserver cannot communicate with the fuzzer");
         }
 
-        let mut st_pipe = Pipe::new().unwrap();
-        let mut ctl_pipe = Pipe::new().unwrap();
+        let mut st_pipe = Pipe::new()?;
+        let mut ctl_pipe = Pipe::new()?;
 
         let (stdout, stderr) = if debug_output {
             (Stdio::inherit(), Stdio::inherit())
@@ -340,7 +363,7 @@ impl Forkserver {
             (Stdio::null(), Stdio::null())
         };
 
-        let mut command = Command::new(target);
+        let mut command = Command::new(&target);
 
         // Setup args, stdio
         command
@@ -376,9 +399,14 @@ impl Forkserver {
             .spawn()
         {
             Ok(fsrv_handle) => fsrv_handle,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Err(Error::illegal_argument(format!(
+                    "Could not spawn the forkserver: target {target:?} not found, check the path (or PATH)"
+                )))
+            }
             Err(err) => {
                 return Err(Error::illegal_state(format!(
-                    "Could not spawn the forkserver: {err:#?}"
+                    "Could not spawn the forkserver for target {target:?}: {err:#?}"
                 )))
             }
         };
@@ -497,20 +525,284 @@ impl Forkserver {
             None,
             Some(timeout),
             Some(&SigSet::empty()),
-        )?;
+        )
+        .map_err(|errno| errno_to_os_error(errno, "pselect on the fork server pipe failed"))?;
         if sret > 0 {
-            if self.st_pipe.read_exact(&mut buf).is_ok() {
-                let val: i32 = i32::from_ne_bytes(buf);
-                Ok(Some(val))
-            } else {
-                Err(Error::unknown(
-                    "Unable to communicate with fork server (OOM?)".to_string(),
-                ))
+            match self.st_pipe.read_exact(&mut buf) {
+                Ok(()) => {
+                    let val: i32 = i32::from_ne_bytes(buf);
+                    Ok(Some(val))
+                }
+                Err(err) => Err(Error::os_error(
+                    err,
+                    "Unable to communicate with fork server (OOM?)",
+                )),
             }
         } else {
             Ok(None)
         }
     }
+    /// Performs the initial handshake with a freshly spawned forkserver
+    /// and returns the options announced by the target.
+    fn handshake(&mut self) -> Result<ForkserverOptions, Error> {
+        let (rlen, version_status) = self.read_st()?; // Initial handshake, read 4-bytes hello message from the forkserver.
+
+        if rlen != 4 {
+            return Err(Error::unknown("Failed to start a forkserver".to_string()));
+        }
+
+        if (version_status & FS_NEW_ERROR) == FS_NEW_ERROR {
+            report_error_and_exit(version_status & 0x0000ffff)?;
+        }
+
+        let keep = version_status;
+        let version: u32 = version_status as u32 - 0x41464c00_u32;
+        if (0x41464c00..=0x41464cff).contains(&version_status) {
+            match version {
+                0 => {
+                    return Err(Error::unknown("Fork server version is not assigned, this should not happen. Recompile target."));
+                }
+                FS_NEW_VERSION_MIN..=FS_NEW_VERSION_MAX => {
+                    // good, do nothing
+                }
+                _ => {
+                    return Err(Error::unknown(
+                        "Fork server version is not supported. Recompile the target.",
+                    ));
+                }
+            }
+        }
+
+        let xored_version_status = (version_status as u32 ^ 0xffffffff) as i32;
+
+        let send_len = self.write_ctl(xored_version_status)?;
+        if send_len != 4 {
+            return Err(Error::unknown("Writing to forkserver failed.".to_string()));
+        }
+
+        log::info!(
+            "All right - new fork server model version {} is up",
+            version
+        );
+
+        let (read_len, status) = self.read_st()?;
+        if read_len != 4 {
+            return Err(Error::unknown(
+                "Reading from forkserver failed.".to_string(),
+            ));
+        }
+
+        let mut options = ForkserverOptions::default();
+
+        if status & FS_NEW_OPT_MAPSIZE == FS_NEW_OPT_MAPSIZE {
+            let (read_len, mut map_size) = self.read_st()?;
+            if read_len != 4 {
+                return Err(Error::unknown(
+                    "Failed to read map size from forkserver".to_string(),
+                ));
+            }
+
+            if map_size % 64 != 0 {
+                map_size = ((map_size + 63) >> 6) << 6;
+            }
+            options.map_size = Some(map_size as usize);
+        }
+
+        options.shmem_fuzz = status & FS_NEW_OPT_SHDMEM_FUZZ != 0;
+
+        if status & FS_NEW_OPT_AUTODICT != 0 {
+            // Here unlike shmem input fuzzing, we are forced to read things
+            // hence no self.autotokens.is_some() to check if we proceed
+            let (read_len, dict_size) = self.read_st()?;
+            if read_len != 4 {
+                return Err(Error::unknown(
+                    "Failed to read dictionary size from forkserver".to_string(),
+                ));
+            }
+
+            if !(2..=0xffffff).contains(&dict_size) {
+                return Err(Error::illegal_state(
+                    "Dictionary has an illegal size".to_string(),
+                ));
+            }
+            log::info!("Autodict size {dict_size:x}");
+            let (rlen, buf) = self.read_st_size(dict_size as usize)?;
+
+            if rlen != dict_size as usize {
+                return Err(Error::unknown("Failed to load autodictionary".to_string()));
+            }
+            options.autodict = Some(buf);
+        }
+
+        let (read_len, aflx) = self.read_st()?;
+        if read_len != 4 {
+            return Err(Error::unknown("Reading from forkserver failed".to_string()));
+        }
+
+        if aflx != version_status {
+            return Err(Error::unknown(format!(
+                "Error in forkserver communication ({:x}=>{:x})",
+                keep, aflx
+            )));
+        }
+
+        Ok(options)
+    }
+}
+
+/// Parses a memory limit in `AFL`'s notation for [`ForkserverExecutorBuilder::mem_limit`], in bytes:
+/// a number with an optional unit suffix, `k`, `M`, `G` or `T`, with megabytes if there is none,
+/// or `none` (or `0`) for no limit, returned as `None`.
+pub fn parse_mem_limit(limit: &str) -> Result<Option<u64>, Error> {
+    let limit = limit.trim();
+    if limit.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    let invalid = || Error::illegal_argument(format!("Invalid memory limit {limit:?}"));
+    let (num, shift) = match limit.char_indices().last().ok_or_else(invalid)? {
+        (idx, 'k' | 'K') => (&limit[..idx], 10),
+        (idx, 'm' | 'M') => (&limit[..idx], 20),
+        (idx, 'g' | 'G') => (&limit[..idx], 30),
+        (idx, 't' | 'T') => (&limit[..idx], 40),
+        _ => (limit, 20),
+    };
+    let num: u64 = num.parse().map_err(|_| invalid())?;
+    if num == 0 {
+        return Ok(None);
+    }
+    num.checked_mul(1 << shift).map(Some).ok_or_else(invalid)
+}
+
+/// Maps the raw wait status of a finished child to an [`ExitKind`],
+/// see [`ForkserverExecutorBuilder::exit_classifier`].
+#[derive(Clone)]
+pub struct ExitClassifier(Arc<dyn Fn(i32) -> ExitKind + Send + Sync>);
+
+impl Debug for ExitClassifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExitClassifier").finish_non_exhaustive()
+    }
+}
+
+/// A callback run after each execution of the [`ForkserverExecutor`],
+/// see [`ForkserverExecutorBuilder::post_exec`]
+pub type PostExecHook<OT> = Box<dyn FnMut(&[u8], ExitKind, &OT) + Send>;
+
+/// Something the [`ForkserverExecutorBuilder`] can turn into the [`PostExecHook`] of an executor
+/// observing `OT`: `()` for no hook, or a closure set with [`ForkserverExecutorBuilder::post_exec`].
+pub trait IntoPostExecHook<OT> {
+    /// Converts this into the hook, `None` if there is none
+    fn into_post_exec_hook(self) -> Option<PostExecHook<OT>>;
+}
+
+impl<OT> IntoPostExecHook<OT> for () {
+    fn into_post_exec_hook(self) -> Option<PostExecHook<OT>> {
+        None
+    }
+}
+
+impl<OT, F> IntoPostExecHook<OT> for F
+where
+    F: FnMut(&[u8], ExitKind, &OT) + Send + 'static,
+{
+    fn into_post_exec_hook(self) -> Option<PostExecHook<OT>> {
+        Some(Box::new(self))
+    }
+}
+
+/// The byte order of a [`StdinLengthPrefix`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+/// The length of the input, written on `stdin` before the input itself,
+/// see [`ForkserverExecutorBuilder::stdin_length_prefix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StdinLengthPrefix {
+    width: usize,
+    endianness: Endianness,
+}
+
+impl StdinLengthPrefix {
+    /// Creates a new [`StdinLengthPrefix`] of `width` bytes, at most 8
+    pub fn new(width: usize, endianness: Endianness) -> Result<Self, Error> {
+        if width == 0 || width > 8 {
+            return Err(Error::illegal_argument(format!(
+                "The stdin length prefix has to be 1 to 8 bytes wide, got {width}"
+            )));
+        }
+        Ok(Self { width, endianness })
+    }
+
+    /// The width of the prefix in bytes
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The byte order of the prefix
+    #[must_use]
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// The longest input length the prefix can encode
+    #[must_use]
+    pub fn max_len(&self) -> usize {
+        usize::try_from(u64::MAX >> (64 - 8 * self.width)).unwrap_or(usize::MAX)
+    }
+
+    /// Appends the prefix for an input of `len` bytes to `buf`.
+    /// The `len` has to be at most [`Self::max_len`].
+    pub fn encode(&self, len: usize, buf: &mut Vec<u8>) {
+        debug_assert!(len <= self.max_len());
+        let len = len as u64;
+        match self.endianness {
+            Endianness::Little => buf.extend_from_slice(&len.to_le_bytes()[..self.width]),
+            Endianness::Big => buf.extend_from_slice(&len.to_be_bytes()[8 - self.width..]),
+        }
+    }
+}
+
+/// The default mapping of a wait status to an [`ExitKind`]:
+/// a signal, or the `crash_exitcode` if given, is a [`ExitKind::Crash`], anything else is [`ExitKind::Ok`].
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn default_exit_kind(status: i32, crash_exitcode: Option<i8>) -> ExitKind {
+    let exitcode_is_crash = if let Some(crash_exitcode) = crash_exitcode {
+        (libc::WEXITSTATUS(status) as i8) == crash_exitcode
+    } else {
+        false
+    };
+    if libc::WIFSIGNALED(status) || exitcode_is_crash {
+        ExitKind::Crash
+    } else {
+        ExitKind::Ok
+    }
+}
+
+/// Keeps an `errno` as an [`Error::OsError`], so that [`is_transient_error`] can inspect it
+fn errno_to_os_error(errno: Errno, msg: &'static str) -> Error {
+    Error::os_error(io::Error::from(errno), msg)
+}
+
+/// If `err` is an OS error that may go away when simply trying again,
+/// like an interrupted syscall or a temporary resource exhaustion.
+fn is_transient_error(err: &Error) -> bool {
+    let Error::OsError(io_err, _, _) = err else {
+        return false;
+    };
+    matches!(
+        io_err.kind(),
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+    ) || matches!(
+        io_err.raw_os_error(),
+        Some(libc::EINTR | libc::EAGAIN | libc::ENOMEM | libc::EMFILE | libc::ENFILE)
+    )
 }
 
 /// This [`Executor`] can run binaries compiled for AFL/AFL++ that make use of a forkserver.
@@ -533,6 +825,46 @@ where
     asan_obs: Handle<AsanBacktraceObserver>,
     timeout: TimeSpec,
     crash_exitcode: Option<i8>,
+    /// Overrides the default mapping of wait statuses to exit kinds
+    exit_classifier: Option<ExitClassifier>,
+    /// The `__AFL_LOOP` count the persistent target is expected to be built with
+    persistent_iterations: Option<u32>,
+    /// The iterations served by the current persistent child so far
+    persistent_runs: u32,
+    /// If we already warned about a mismatching persistent loop count
+    persistent_mismatch_warned: bool,
+    /// How long to wait for the forkserver itself to answer before restarting it
+    forkserver_timeout: Option<TimeSpec>,
+    /// The number of forkserver restarts after it became unresponsive
+    forkserver_restarts: u64,
+    /// How often to retry an execution that failed with a transient OS error
+    transient_retries: usize,
+    /// The number of executions retried after a transient OS error
+    transient_retries_performed: u64,
+    /// The environment to respawn the forkserver with
+    envs: Vec<(OsString, OsString)>,
+    /// If the target reads its input from `stdin`
+    use_stdin: bool,
+    /// The length prefix written before each input on `stdin`, if any
+    stdin_length_prefix: Option<StdinLengthPrefix>,
+    /// The buffer holding the prefixed input
+    stdin_buf: Vec<u8>,
+    /// The number of inputs truncated because their length did not fit the length prefix
+    stdin_prefix_truncations: u64,
+    /// The observer to record the delivered bytes of crashing and timing out runs in, if any
+    delivered_input_obs: Option<Handle<DeliveredInputObserver>>,
+    /// The observer to record the exit code of the target in, if any
+    exit_code_obs: Option<Handle<ExitCodeObserver>>,
+    /// The user callback run after each execution, if any
+    post_exec: Option<PostExecHook<OT>>,
+    /// If the target runs in persistent mode
+    is_persistent: bool,
+    /// If the target uses a deferred forkserver
+    is_deferred_frksrv: bool,
+    /// If the child's output is shown
+    debug_child: bool,
+    /// The address space limit of the child in MiB, `0` for none
+    mem_limit: u64,
 }
 
 impl<OT, S, SP> Debug for ForkserverExecutor<OT, S, SP>
@@ -596,12 +928,186 @@ where
     pub fn coverage_map_size(&self) -> Option<usize> {
         self.map_size
     }
+
+    /// The expected `__AFL_LOOP` count of the persistent target, if set
+    pub fn persistent_iterations(&self) -> Option<u32> {
+        self.persistent_iterations
+    }
+
+    /// The number of times the forkserver got restarted because it became unresponsive
+    pub fn forkserver_restarts(&self) -> u64 {
+        self.forkserver_restarts
+    }
+
+    /// The number of executions retried after a transient OS error
+    pub fn transient_retries_performed(&self) -> u64 {
+        self.transient_retries_performed
+    }
+
+    /// The length prefix written before each input on `stdin`, if any
+    pub fn stdin_length_prefix(&self) -> Option<StdinLengthPrefix> {
+        self.stdin_length_prefix
+    }
+
+    /// The number of inputs truncated because their length did not fit the [`StdinLengthPrefix`]
+    pub fn stdin_prefix_truncations(&self) -> u64 {
+        self.stdin_prefix_truncations
+    }
+
+    /// Writes the input to the input file, after its length prefix if one is set
+    fn write_input(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let Some(prefix) = self.stdin_length_prefix else {
+            return self.input_file.write_buf(bytes);
+        };
+        let mut len = bytes.len();
+        if len > prefix.max_len() {
+            if self.stdin_prefix_truncations == 0 {
+                log::warn!(
+                    "Input of {len} bytes does not fit the {} byte stdin length prefix, truncating it",
+                    prefix.width()
+                );
+            }
+            self.stdin_prefix_truncations += 1;
+            len = prefix.max_len();
+        }
+        self.stdin_buf.clear();
+        prefix.encode(len, &mut self.stdin_buf);
+        self.stdin_buf.extend_from_slice(&bytes[..len]);
+        self.input_file.write_buf(&self.stdin_buf)
+    }
+
+    /// Records the bytes delivered for `input` in the last run in the [`DeliveredInputObserver`], if set
+    fn record_delivered_input(&mut self, input: &S::Input)
+    where
+        S::Input: HasTargetBytes,
+    {
+        let Some(delivered_input_obs) = &self.delivered_input_obs else {
+            return;
+        };
+        let Some(observer) = self.observers.get_mut(delivered_input_obs) else {
+            return;
+        };
+        let target_bytes = input.target_bytes();
+        let mut delivered = target_bytes.as_slice();
+        if let Some(map) = self.map.as_ref().filter(|_| self.uses_shmem_testcase) {
+            delivered = &delivered[..delivered.len().min(map.len() - SHMEM_FUZZ_HDR_SIZE)];
+        } else if self.stdin_length_prefix.is_some() {
+            delivered = &self.stdin_buf;
+        }
+        observer.set_delivered(delivered);
+    }
+
+    /// Records the exit code of the last run in the [`ExitCodeObserver`], if set and the target exited
+    fn record_exit_code(&mut self, status: i32) {
+        if !libc::WIFEXITED(status) {
+            return;
+        }
+        let Some(observer) = self
+            .exit_code_obs
+            .as_ref()
+            .and_then(|exit_code_obs| self.observers.get_mut(exit_code_obs))
+        else {
+            return;
+        };
+        observer.set_exit_code(libc::WEXITSTATUS(status));
+    }
+
+    /// Reads the next status from the forkserver, waiting at most for the forkserver timeout, if set.
+    /// Returns `None` if the forkserver did not answer in time.
+    fn read_forkserver_st(&mut self) -> Result<Option<i32>, Error> {
+        if let Some(forkserver_timeout) = self.forkserver_timeout {
+            self.forkserver.read_st_timed(&forkserver_timeout)
+        } else {
+            let (recv_len, val) = self.forkserver.read_st()?;
+            if recv_len != 4 {
+                return Err(Error::unknown(
+                    "Unable to request new process from fork server (OOM?)".to_string(),
+                ));
+            }
+            Ok(Some(val))
+        }
+    }
+
+    /// Kills the unresponsive forkserver and spawns a fresh one in its place.
+    fn restart_forkserver(&mut self) -> Result<(), Error> {
+        log::warn!("The forkserver became unresponsive, restarting it");
+
+        // A hung forkserver may ignore the configured kill signal
+        if let Some(child_pid) = self.forkserver.child_pid {
+            let _ = kill(child_pid, Signal::SIGKILL);
+        }
+        let forkserver_pid = Pid::from_raw(self.forkserver.fsrv_handle.id().try_into().unwrap());
+        let _ = kill(forkserver_pid, Signal::SIGKILL);
+
+        let mut forkserver = Forkserver::with_kill_signal(
+            self.target.clone(),
+            self.args.clone(),
+            self.envs.clone(),
+            self.input_file.as_raw_fd(),
+            self.use_stdin,
+            self.mem_limit,
+            self.is_persistent,
+            self.is_deferred_frksrv,
+            self.debug_child,
+            self.forkserver.kill_signal,
+        )?;
+        forkserver.handshake()?;
+
+        // Dropping the old forkserver reaps it
+        self.forkserver = forkserver;
+        self.forkserver_restarts += 1;
+        self.persistent_runs = 0;
+        Ok(())
+    }
+
+    /// Counts the iterations served by the current persistent child and,
+    /// once it exited normally, warns if it did not match the expected loop count.
+    fn check_persistent_iterations(&mut self, expected: u32, exit_kind: ExitKind) {
+        if exit_kind == ExitKind::Timeout {
+            // The child got killed, its loop count tells us nothing
+            self.persistent_runs = 0;
+            return;
+        }
+
+        self.persistent_runs += 1;
+        let status = self.forkserver.status();
+        if libc::WIFSTOPPED(status) {
+            // The child is waiting for the next iteration
+            return;
+        }
+
+        let runs = core::mem::take(&mut self.persistent_runs);
+        if exit_kind == ExitKind::Ok
+            && libc::WIFEXITED(status)
+            && runs != expected
+            && !self.persistent_mismatch_warned
+        {
+            log::warn!(
+                "Persistent target exited after {runs} iterations, but {expected} were configured. Check the __AFL_LOOP count the target was built with."
+            );
+            self.persistent_mismatch_warned = true;
+        }
+    }
 }
 
+/// A [`DiffExecutor`] running each input through two separately built AFL++ binaries.
+///
+/// Each side has its own [`Forkserver`], so a timeout in one binary only kills that child;
+/// the other side still reports its real exit kind and the mismatch surfaces as
+/// [`ExitKind::Diff`]. Use [`crate::feedbacks::DiffExitKindFeedback`] as objective to keep
+/// inputs on which both binaries diverge.
+///
+/// Both binaries need their own coverage map, so pass a distinct `__AFL_SHM_ID` to each
+/// builder via [`ForkserverExecutorBuilder::env`] instead of exporting it process-wide.
+pub type DiffForkserverExecutor<OTA, OTB, DOT, S, SP> =
+    DiffExecutor<ForkserverExecutor<OTA, S, SP>, ForkserverExecutor<OTB, S, SP>, DOT, OTA, OTB>;
+
 /// The builder for `ForkserverExecutor`
+///
+/// `PE` is the hook set with [`ForkserverExecutorBuilder::post_exec`], `()` if there is none.
 #[derive(Debug)]
 #[allow(clippy::struct_excessive_bools)]
-pub struct ForkserverExecutorBuilder<'a, SP> {
+pub struct ForkserverExecutorBuilder<'a, SP, PE = ()> {
     program: Option<OsString>,
     arguments: Vec<OsString>,
     envs: Vec<(OsString, OsString)>,
@@ -620,9 +1126,18 @@ pub struct ForkserverExecutorBuilder<'a, SP> {
     #[cfg(feature = "regex")]
     asan_obs: Option<Handle<AsanBacktraceObserver>>,
     crash_exitcode: Option<i8>,
+    exit_classifier: Option<ExitClassifier>,
+    persistent_iterations: Option<u32>,
+    forkserver_timeout: Option<Duration>,
+    transient_retries: usize,
+    stdin_length_prefix: Option<(usize, Endianness)>,
+    delivered_input_obs: Option<Handle<DeliveredInputObserver>>,
+    exit_code_obs: Option<Handle<ExitCodeObserver>>,
+    mem_limit: Option<u64>,
+    post_exec: Option<PE>,
 }
 
-impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
+impl<'a, SP, PE> ForkserverExecutorBuilder<'a, SP, PE> {
     /// Builds `ForkserverExecutor`.
     /// This Forkserver will attempt to provide inputs over shared mem when `shmem_provider` is given.
     /// Else this forkserver will pass the input to the target via `stdin`
@@ -632,6 +1147,7 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
     pub fn build<OT, S>(&mut self, observers: OT) -> Result<ForkserverExecutor<OT, S, SP>, Error>
     where
         OT: ObserversTuple<S>,
+        PE: IntoPostExecHook<OT>,
         S: UsesInput,
         S::Input: Input + HasTargetBytes,
         SP: ShMemProvider,
@@ -646,34 +1162,7 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             self.use_stdin
         );
 
-        if self.uses_shmem_testcase && map.is_none() {
-            return Err(Error::illegal_state(
-                "Map must always be set for `uses_shmem_testcase`",
-            ));
-        }
-
-        let timeout: TimeSpec = match self.timeout {
-            Some(t) => t.into(),
-            None => Duration::from_millis(5000).into(),
-        };
-
-        Ok(ForkserverExecutor {
-            target,
-            args: self.arguments.clone(),
-            input_file,
-            uses_shmem_testcase: self.uses_shmem_testcase,
-            forkserver,
-            observers,
-            map,
-            phantom: PhantomData,
-            map_size: self.map_size,
-            timeout,
-            asan_obs: self
-                .asan_obs
-                .clone()
-                .unwrap_or(AsanBacktraceObserver::default().handle()),
-            crash_exitcode: self.crash_exitcode,
-        })
+        self.build_executor(target, forkserver, input_file, map, observers)
     }
 
     /// Builds `ForkserverExecutor` downsizing the coverage map to fit exaclty the AFL++ map size.
@@ -687,6 +1176,7 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
         MO: MapObserver + Truncate, // TODO maybe enforce Entry = u8 for the cov map
         A: Observer<S> + AsRef<MO> + AsMut<MO>,
         OT: ObserversTuple<S> + Prepend<MO, PreprendResult = OT>,
+        PE: IntoPostExecHook<(A, OT)>,
         S: UsesInput,
         S::Input: Input + HasTargetBytes,
         SP: ShMemProvider,
@@ -706,14 +1196,38 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             map_observer.as_mut().truncate(dynamic_map_size);
         }
 
-        let observers = (map_observer, other_observers);
+        self.build_executor(
+            target,
+            forkserver,
+            input_file,
+            map,
+            (map_observer, other_observers),
+        )
+    }
 
+    /// Creates the [`ForkserverExecutor`] from the set up forkserver and the options of this builder
+    fn build_executor<OT, S>(
+        &mut self,
+        target: OsString,
+        forkserver: Forkserver,
+        input_file: InputFile,
+        map: Option<SP::ShMem>,
+        observers: OT,
+    ) -> Result<ForkserverExecutor<OT, S, SP>, Error>
+    where
+        OT: ObserversTuple<S>,
+        PE: IntoPostExecHook<OT>,
+        S: UsesInput,
+        SP: ShMemProvider,
+    {
         if self.uses_shmem_testcase && map.is_none() {
             return Err(Error::illegal_state(
                 "Map must always be set for `uses_shmem_testcase`",
             ));
         }
 
+        let stdin_length_prefix = self.build_stdin_length_prefix()?;
+
         let timeout: TimeSpec = match self.timeout {
             Some(t) => t.into(),
             None => Duration::from_millis(5000).into(),
@@ -735,9 +1249,45 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
                 .clone()
                 .unwrap_or(AsanBacktraceObserver::default().handle()),
             crash_exitcode: self.crash_exitcode,
+            exit_classifier: self.exit_classifier.clone(),
+            persistent_iterations: self.persistent_iterations,
+            persistent_runs: 0,
+            persistent_mismatch_warned: false,
+            forkserver_timeout: self.forkserver_timeout.map(TimeSpec::from),
+            forkserver_restarts: 0,
+            transient_retries: self.transient_retries,
+            transient_retries_performed: 0,
+            envs: self.envs.clone(),
+            use_stdin: self.use_stdin,
+            stdin_length_prefix,
+            stdin_buf: Vec::new(),
+            stdin_prefix_truncations: 0,
+            delivered_input_obs: self.delivered_input_obs.clone(),
+            exit_code_obs: self.exit_code_obs.clone(),
+            post_exec: self
+                .post_exec
+                .take()
+                .and_then(IntoPostExecHook::into_post_exec_hook),
+            is_persistent: self.is_persistent,
+            is_deferred_frksrv: self.is_deferred_frksrv,
+            debug_child: self.debug_child,
+            mem_limit: self.mem_limit.unwrap_or(0),
         })
     }
 
+    /// Checks the stdin length prefix, which needs the input on `stdin`
+    fn build_stdin_length_prefix(&self) -> Result<Option<StdinLengthPrefix>, Error> {
+        let Some((width, endianness)) = self.stdin_length_prefix else {
+            return Ok(None);
+        };
+        if !self.use_stdin || self.uses_shmem_testcase {
+            return Err(Error::illegal_argument(
+                "The stdin length prefix requires the target to read its input from stdin",
+            ));
+        }
+        StdinLengthPrefix::new(width, endianness).map(Some)
+    }
+
     #[allow(clippy::pedantic)]
     fn build_helper(&mut self) -> Result<(Forkserver, InputFile, Option<SP::ShMem>), Error>
     where
@@ -751,14 +1301,27 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             }
         };
 
-        let input_file = InputFile::create(input_filename)?;
+        let input_file = InputFile::create(&input_filename).map_err(|err| {
+            Error::illegal_argument(format!(
+                "Cannot write the input file {input_filename:?} for the target: {err}"
+            ))
+        })?;
 
         let map = match &mut self.shmem_provider {
             None => None,
             Some(provider) => {
                 // setup shared memory
-                let mut shmem = provider.new_shmem(self.max_input_size + SHMEM_FUZZ_HDR_SIZE)?;
-                shmem.write_to_env("__AFL_SHM_FUZZ_ID")?;
+                let mut shmem = provider
+                    .new_shmem(self.max_input_size + SHMEM_FUZZ_HDR_SIZE)
+                    .map_err(|err| {
+                        Error::illegal_state(format!(
+                            "Failed to allocate {} bytes of shared memory for the testcases: {err}",
+                            self.max_input_size + SHMEM_FUZZ_HDR_SIZE
+                        ))
+                    })?;
+                shmem.write_to_env("__AFL_SHM_FUZZ_ID").map_err(|err| {
+                    Error::illegal_state(format!("Failed to set __AFL_SHM_FUZZ_ID: {err}"))
+                })?;
 
                 let size_in_bytes = (self.max_input_size + SHMEM_FUZZ_HDR_SIZE).to_ne_bytes();
                 shmem.as_slice_mut()[..4].clone_from_slice(&size_in_bytes[..4]);
@@ -773,7 +1336,7 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
                 self.envs.clone(),
                 input_file.as_raw_fd(),
                 self.use_stdin,
-                0,
+                self.mem_limit.unwrap_or(0),
                 self.is_persistent,
                 self.is_deferred_frksrv,
                 self.debug_child,
@@ -786,54 +1349,9 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             }
         };
 
-        let (rlen, version_status) = forkserver.read_st()?; // Initial handshake, read 4-bytes hello message from the forkserver.
-
-        if rlen != 4 {
-            return Err(Error::unknown("Failed to start a forkserver".to_string()));
-        }
-
-        if (version_status & FS_NEW_ERROR) == FS_NEW_ERROR {
-            report_error_and_exit(version_status & 0x0000ffff)?;
-        }
+        let options = forkserver.handshake()?;
 
-        let keep = version_status;
-        let version: u32 = version_status as u32 - 0x41464c00_u32;
-        if (0x41464c00..=0x41464cff).contains(&version_status) {
-            match version {
-                0 => {
-                    return Err(Error::unknown("Fork server version is not assigned, this should not happen. Recompile target."));
-                }
-                FS_NEW_VERSION_MIN..=FS_NEW_VERSION_MAX => {
-                    // good, do nothing
-                }
-                _ => {
-                    return Err(Error::unknown(
-                        "Fork server version is not supported. Recompile the target.",
-                    ));
-                }
-            }
-        }
-
-        let xored_version_status = (version_status as u32 ^ 0xffffffff) as i32;
-
-        let send_len = forkserver.write_ctl(xored_version_status)?;
-        if send_len != 4 {
-            return Err(Error::unknown("Writing to forkserver failed.".to_string()));
-        }
-
-        log::info!(
-            "All right - new fork server model version {} is up",
-            version
-        );
-
-        let (read_len, status) = forkserver.read_st()?;
-        if read_len != 4 {
-            return Err(Error::unknown(
-                "Reading from forkserver failed.".to_string(),
-            ));
-        }
-
-        if status & FS_NEW_OPT_MAPSIZE == FS_NEW_OPT_MAPSIZE {
+        if let Some(map_size) = options.map_size {
             // When 0, we assume that map_size was filled by the user or const
             /* TODO autofill map size from the observer
 
@@ -841,25 +1359,15 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
                 self.map_size = Some(map_size as usize);
             }
             */
-            let (read_len, mut map_size) = forkserver.read_st()?;
-            if read_len != 4 {
-                return Err(Error::unknown(
-                    "Failed to read map size from forkserver".to_string(),
-                ));
-            }
-
-            if map_size % 64 != 0 {
-                map_size = ((map_size + 63) >> 6) << 6;
-            }
 
             // TODO set AFL_MAP_SIZE
-            assert!(self.map_size.is_none() || map_size as usize <= self.map_size.unwrap());
+            assert!(self.map_size.is_none() || map_size <= self.map_size.unwrap());
 
             // we'll use this later when we truncate the observer
-            self.map_size = Some(map_size as usize);
+            self.map_size = Some(map_size);
         }
 
-        if status & FS_NEW_OPT_SHDMEM_FUZZ != 0 {
+        if options.shmem_fuzz {
             if map.is_some() {
                 log::info!("Using SHARED MEMORY FUZZING feature.");
                 self.uses_shmem_testcase = true;
@@ -870,44 +1378,12 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             }
         }
 
-        if status & FS_NEW_OPT_AUTODICT != 0 {
-            // Here unlike shmem input fuzzing, we are forced to read things
-            // hence no self.autotokens.is_some() to check if we proceed
-            let (read_len, dict_size) = forkserver.read_st()?;
-            if read_len != 4 {
-                return Err(Error::unknown(
-                    "Failed to read dictionary size from forkserver".to_string(),
-                ));
-            }
-
-            if !(2..=0xffffff).contains(&dict_size) {
-                return Err(Error::illegal_state(
-                    "Dictionary has an illegal size".to_string(),
-                ));
-            }
-            log::info!("Autodict size {dict_size:x}");
-            let (rlen, buf) = forkserver.read_st_size(dict_size as usize)?;
-
-            if rlen != dict_size as usize {
-                return Err(Error::unknown("Failed to load autodictionary".to_string()));
-            }
+        if let Some(dict) = options.autodict {
             if let Some(t) = &mut self.autotokens {
-                t.parse_autodict(&buf, dict_size as usize);
+                t.parse_autodict(&dict, dict.len());
             }
         }
 
-        let (read_len, aflx) = forkserver.read_st()?;
-        if read_len != 4 {
-            return Err(Error::unknown("Reading from forkserver failed".to_string()));
-        }
-
-        if aflx != version_status {
-            return Err(Error::unknown(format!(
-                "Error in forkserver communication ({:x}=>{:x})",
-                keep, aflx
-            )));
-        }
-
         Ok((forkserver, input_file, map))
     }
 
@@ -925,6 +1401,28 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
         self
     }
 
+    #[must_use]
+    /// Set the time to wait for the forkserver itself, as opposed to the child, to respond.
+    ///
+    /// If the forkserver does not hand out a new child, or does not report the status of a
+    /// killed child within this time, the input is reported as [`ExitKind::Timeout`] and
+    /// the forkserver gets restarted, instead of stalling the campaign forever.
+    /// By default, the executor waits indefinitely.
+    pub fn forkserver_timeout(mut self, forkserver_timeout: Duration) -> Self {
+        self.forkserver_timeout = Some(forkserver_timeout);
+        self
+    }
+
+    #[must_use]
+    /// Retry an execution up to `retries` times if it fails with a transient OS error,
+    /// like an interrupted syscall or a temporary resource exhaustion, restarting the forkserver
+    /// in between. Crashes and timeouts of the target are never retried.
+    /// Each retry is logged, to surface environmental problems. By default, nothing is retried.
+    pub fn retry_on_transient(mut self, retries: usize) -> Self {
+        self.transient_retries = retries;
+        self
+    }
+
     #[must_use]
     /// Parse afl style command line
     ///
@@ -1022,6 +1520,45 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
         self
     }
 
+    /// Shares a second coverage map for context-sensitive (or ngram) edges with the target,
+    /// if it was built with instrumentation writing those to a separate map.
+    /// The map id and size are passed to the target only, in [`CONTEXT_MAP_SHM_ENV`].
+    ///
+    /// Observe the map with its own map observer and a separately named feedback,
+    /// combined with the edge feedback through [`crate::feedback_or`].
+    #[must_use]
+    pub fn context_map<SHM>(self, shmem: &SHM) -> Self
+    where
+        SHM: ShMem,
+    {
+        self.env(CONTEXT_MAP_SHM_ENV, shmem.id().to_string()).env(
+            format!("{CONTEXT_MAP_SHM_ENV}_SIZE"),
+            shmem.len().to_string(),
+        )
+    }
+
+    /// Shares a map of harness-defined features with the target, such as compared values
+    /// or app-specific counters, that is whatever the harness considers worth keeping inputs for.
+    /// The map id and size are passed to the target in [`FEATURES_MAP_SHM_ENV`].
+    ///
+    /// The harness contract: before starting the forkserver, the target calls
+    /// `__libafl_map_features_shm` (`map_features_shared_memory` in `libafl_targets`) once,
+    /// then `__libafl_feature_hit(feature)` for each feature it observes. Features are folded into
+    /// the map size and counted like hitcounts, so the map needs no coordination beyond its size.
+    ///
+    /// Observe the map with a [`crate::observers::StdMapObserver`] over the shmem and feed it into a
+    /// separately named [`crate::feedbacks::MaxMapFeedback`], combined with the edge feedback.
+    #[must_use]
+    pub fn features_map<SHM>(self, shmem: &SHM) -> Self
+    where
+        SHM: ShMem,
+    {
+        self.env(FEATURES_MAP_SHM_ENV, shmem.id().to_string()).env(
+            format!("{FEATURES_MAP_SHM_ENV}_SIZE"),
+            shmem.len().to_string(),
+        )
+    }
+
     /// Adds environmental vars to the harness's commandline
     #[must_use]
     pub fn envs<IT, K, V>(mut self, vars: IT) -> Self
@@ -1058,6 +1595,37 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
         moved
     }
 
+    /// Delivers the input in the file at the fixed `path` in each execution, like the `-f` option of `AFL++`.
+    /// Each `@@` passed to [`Self::parse_afl_cmdline`] afterwards is replaced by this path,
+    /// so call it before parsing the command line. Without `@@`, the target has to open the path by itself.
+    ///
+    /// Concurrent fuzzer clients must not share the file, use [`Self::input_file_namespaced`] for them.
+    /// The file is created when building the executor, which fails if it is not writable.
+    #[must_use]
+    pub fn input_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        let path_as_string = path.as_ref().as_os_str().to_os_string();
+        assert!(
+            (self.input_filename.is_none()
+                || self.input_filename.as_ref() == Some(&path_as_string)),
+            "Already specified an input file under a different name. This is not supported"
+        );
+        self.input_filename = Some(path_as_string);
+        self
+    }
+
+    /// Like [`Self::input_file`], but appends `_<namespace>` to the file name, e.g., the id of the fuzzer client,
+    /// so that concurrent clients write to different files. The target then has to get the path through `@@`.
+    #[must_use]
+    pub fn input_file_namespaced<P, N>(self, path: P, namespace: N) -> Self
+    where
+        P: AsRef<Path>,
+        N: Display,
+    {
+        let mut path = path.as_ref().as_os_str().to_os_string();
+        path.push(format!("_{namespace}"));
+        self.input_file(path)
+    }
+
     /// Place the input at this position and set the default filename for the input.
     #[must_use]
     /// The filename includes the PID of the fuzzer to ensure that no two fuzzers write to the same file
@@ -1079,6 +1647,18 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
         self
     }
 
+    /// Runs the target in persistent mode, expecting it to be built with `__AFL_LOOP(iterations)`.
+    ///
+    /// The loop count is compiled into the target and cannot be changed at runtime,
+    /// so the executor validates it instead: if a persistent child exits normally after a
+    /// different number of iterations, a warning is logged once.
+    #[must_use]
+    pub fn persistent_iterations(mut self, iterations: u32) -> Self {
+        self.is_persistent = true;
+        self.persistent_iterations = Some(iterations);
+        self
+    }
+
     /// Treats an execution as a crash if the provided exitcode is returned
     #[must_use]
     pub fn crash_exitcode(mut self, exitcode: i8) -> Self {
@@ -1086,6 +1666,34 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
         self
     }
 
+    /// Overrides how the raw wait status of a finished child is mapped to an [`ExitKind`],
+    /// e.g., for runtimes that report crashes with their own exit codes.
+    /// Timeouts are still detected by the executor itself.
+    /// The default is [`default_exit_kind`], honoring [`Self::crash_exitcode`].
+    #[must_use]
+    pub fn exit_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(i32) -> ExitKind + Send + Sync + 'static,
+    {
+        self.exit_classifier = Some(ExitClassifier(Arc::new(classifier)));
+        self
+    }
+
+    /// Limits the address space of the target to `bytes`, like `-m` of `AFL`, rounded up to whole MiB,
+    /// so that runaway allocations fail in the target rather than exhausting the host. `0` means no limit,
+    /// the default, see [`parse_mem_limit`] for `AFL`'s notation.
+    ///
+    /// The limit is set with `RLIMIT_AS` (`RLIMIT_RSS` on `OpenBSD`) in the forkserver and inherited by
+    /// each child. An allocation beyond it fails in the target, which then usually aborts, reported as
+    /// [`ExitKind::Crash`], or handles the failure itself. Map such exits differently through
+    /// [`Self::exit_classifier`] if needed. Targets built with `ASan` reserve terabytes of address space
+    /// for the shadow memory, so do not limit them, as with `AFL`'s `-m none`.
+    #[must_use]
+    pub fn mem_limit(mut self, bytes: u64) -> Self {
+        self.mem_limit = (bytes != 0).then(|| bytes.div_ceil(1 << 20));
+        self
+    }
+
     /// Call this if the harness uses deferred forkserver mode; default is false
     #[must_use]
     pub fn is_deferred_frksrv(mut self, is_deferred_frksrv: bool) -> Self {
@@ -1106,6 +1714,35 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
         self.kill_signal = Some(kill_signal);
         self
     }
+
+    /// Writes the length of each input as a `width` byte integer on `stdin`, before the input itself,
+    /// for targets expecting a length-prefixed message.
+    /// The `width` has to be 1 to 8 bytes. Inputs longer than the prefix can encode are truncated,
+    /// see [`ForkserverExecutor::stdin_prefix_truncations`].
+    /// Requires the input on `stdin`, so it cannot be combined with an input file or shared memory testcases.
+    #[must_use]
+    pub fn stdin_length_prefix(mut self, width: usize, endianness: Endianness) -> Self {
+        self.stdin_length_prefix = Some((width, endianness));
+        self
+    }
+
+    /// Records the exact bytes delivered to the target for crashing and timing out runs in the given observer,
+    /// including the [`StdinLengthPrefix`] or the truncation to the shared memory testcase size.
+    /// Store them next to the solutions with a [`crate::feedbacks::DeliveredInputFeedback`] in the objective.
+    #[must_use]
+    pub fn delivered_input_observer(mut self, observer: &DeliveredInputObserver) -> Self {
+        self.delivered_input_obs = Some(observer.handle());
+        self
+    }
+
+    /// Records the exit code of the target in the given observer, for runs in which the target exited.
+    /// Use it with an [`crate::feedbacks::ExitCodeFeedback`] to keep inputs for which the harness
+    /// signals interest through its exit code.
+    #[must_use]
+    pub fn exit_code_observer(mut self, observer: &ExitCodeObserver) -> Self {
+        self.exit_code_obs = Some(observer.handle());
+        self
+    }
 }
 
 impl<'a> ForkserverExecutorBuilder<'a, UnixShMemProvider> {
@@ -1135,6 +1772,15 @@ impl<'a> ForkserverExecutorBuilder<'a, UnixShMemProvider> {
             timeout: None,
             asan_obs: None,
             crash_exitcode: None,
+            exit_classifier: None,
+            persistent_iterations: None,
+            forkserver_timeout: None,
+            transient_retries: 0,
+            stdin_length_prefix: None,
+            delivered_input_obs: None,
+            exit_code_obs: None,
+            mem_limit: None,
+            post_exec: None,
         }
     }
 
@@ -1143,6 +1789,40 @@ impl<'a> ForkserverExecutorBuilder<'a, UnixShMemProvider> {
         self,
         shmem_provider: &'a mut SP,
     ) -> ForkserverExecutorBuilder<'a, SP> {
+        self.with_parts(Some(shmem_provider), None)
+    }
+}
+
+impl<'a> Default for ForkserverExecutorBuilder<'a, UnixShMemProvider> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
+    /// Sets a callback run after each execution with the bytes of the input, the [`ExitKind`],
+    /// and the observers, e.g., to collect custom telemetry without implementing an observer.
+    /// It runs before the observers' own `post_exec`, on the hot path, so it should be cheap.
+    ///
+    /// The type of the observers is only known at `build` time, so the closure has to name it,
+    /// e.g., `|input: &[u8], exit_kind, observers: &(MyMapObserver, ())| { .. }`.
+    #[must_use]
+    pub fn post_exec<OT, F>(mut self, hook: F) -> ForkserverExecutorBuilder<'a, SP, F>
+    where
+        F: FnMut(&[u8], ExitKind, &OT) + Send + 'static,
+    {
+        let shmem_provider = self.shmem_provider.take();
+        self.with_parts(shmem_provider, Some(hook))
+    }
+}
+
+impl<'a, SP, PE> ForkserverExecutorBuilder<'a, SP, PE> {
+    /// Moves all options to a builder with the given shared memory provider and post-exec hook
+    fn with_parts<SP2, PE2>(
+        self,
+        shmem_provider: Option<&'a mut SP2>,
+        post_exec: Option<PE2>,
+    ) -> ForkserverExecutorBuilder<'a, SP2, PE2> {
         ForkserverExecutorBuilder {
             program: self.program,
             arguments: self.arguments,
@@ -1154,23 +1834,27 @@ impl<'a> ForkserverExecutorBuilder<'a, UnixShMemProvider> {
             is_deferred_frksrv: self.is_deferred_frksrv,
             autotokens: self.autotokens,
             input_filename: self.input_filename,
-            shmem_provider: Some(shmem_provider),
+            shmem_provider,
             map_size: self.map_size,
-            max_input_size: MAX_INPUT_SIZE_DEFAULT,
-            kill_signal: None,
-            timeout: None,
-            asan_obs: None,
-            crash_exitcode: None,
+            max_input_size: self.max_input_size,
+            kill_signal: self.kill_signal,
+            timeout: self.timeout,
+            #[cfg(feature = "regex")]
+            asan_obs: self.asan_obs,
+            crash_exitcode: self.crash_exitcode,
+            exit_classifier: self.exit_classifier,
+            persistent_iterations: self.persistent_iterations,
+            forkserver_timeout: self.forkserver_timeout,
+            transient_retries: self.transient_retries,
+            stdin_length_prefix: self.stdin_length_prefix,
+            delivered_input_obs: self.delivered_input_obs,
+            exit_code_obs: self.exit_code_obs,
+            mem_limit: self.mem_limit,
+            post_exec,
         }
     }
 }
 
-impl<'a> Default for ForkserverExecutorBuilder<'a, UnixShMemProvider> {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl<EM, OT, S, SP, Z> Executor<EM, Z> for ForkserverExecutor<OT, S, SP>
 where
     OT: ObserversTuple<S>,
@@ -1190,6 +1874,55 @@ where
     ) -> Result<ExitKind, Error> {
         *state.executions_mut() += 1;
 
+        let mut retries = 0;
+        loop {
+            match self.run_target_once(input) {
+                Err(err) if retries < self.transient_retries && is_transient_error(&err) => {
+                    retries += 1;
+                    self.transient_retries_performed += 1;
+                    log::warn!(
+                        "Execution failed with a transient error, retrying ({retries}/{}): {err}",
+                        self.transient_retries
+                    );
+                    // The forkserver may be out of sync after a failed exchange
+                    self.restart_forkserver()?;
+                }
+                res => {
+                    if let (Some(hook), Ok(exit_kind)) = (&mut self.post_exec, &res) {
+                        hook(input.target_bytes().as_slice(), *exit_kind, &self.observers);
+                    }
+                    return res;
+                }
+            }
+        }
+    }
+}
+
+impl<OT, S, SP> HasAdjustableTimeout for ForkserverExecutor<OT, S, SP>
+where
+    SP: ShMemProvider,
+{
+    fn timeout(&self) -> Duration {
+        Duration::new(
+            u64::try_from(self.timeout.tv_sec()).unwrap_or_default(),
+            u32::try_from(self.timeout.tv_nsec()).unwrap_or_default(),
+        )
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout.into();
+    }
+}
+
+impl<OT, S, SP> ForkserverExecutor<OT, S, SP>
+where
+    OT: ObserversTuple<S>,
+    SP: ShMemProvider,
+    S: State + HasExecutions,
+    S::Input: HasTargetBytes,
+{
+    /// Runs the target once, without retrying on transient errors
+    fn run_target_once(&mut self, input: &S::Input) -> Result<ExitKind, Error> {
         let mut exit_kind = ExitKind::Ok;
 
         let last_run_timed_out = self.forkserver.last_run_timed_out_raw();
@@ -1216,7 +1949,7 @@ where
             map.as_slice_mut()[SHMEM_FUZZ_HDR_SIZE..(SHMEM_FUZZ_HDR_SIZE + size)]
                 .copy_from_slice(&target_bytes.as_slice()[..size]);
         } else {
-            self.input_file.write_buf(input.target_bytes().as_slice())?;
+            self.write_input(input.target_bytes().as_slice())?;
         }
 
         let send_len = self.forkserver.write_ctl(last_run_timed_out)?;
@@ -1229,12 +1962,11 @@ where
             ));
         }
 
-        let (recv_pid_len, pid) = self.forkserver.read_st()?;
-        if recv_pid_len != 4 {
-            return Err(Error::unknown(
-                "Unable to request new process from fork server (OOM?)".to_string(),
-            ));
-        }
+        let Some(pid) = self.read_forkserver_st()? else {
+            self.record_delivered_input(input);
+            self.restart_forkserver()?;
+            return Ok(ExitKind::Timeout);
+        };
 
         if pid <= 0 {
             return Err(Error::unknown(
@@ -1246,13 +1978,12 @@ where
 
         if let Some(status) = self.forkserver.read_st_timed(&self.timeout)? {
             self.forkserver.set_status(status);
-            let exitcode_is_crash = if let Some(crash_exitcode) = self.crash_exitcode {
-                (libc::WEXITSTATUS(self.forkserver().status()) as i8) == crash_exitcode
-            } else {
-                false
+            self.record_exit_code(status);
+            exit_kind = match &self.exit_classifier {
+                Some(classifier) => (classifier.0)(status),
+                None => default_exit_kind(status, self.crash_exitcode),
             };
-            if libc::WIFSIGNALED(self.forkserver().status()) || exitcode_is_crash {
-                exit_kind = ExitKind::Crash;
+            if exit_kind == ExitKind::Crash {
                 #[cfg(feature = "regex")]
                 if let Some(asan_observer) = self.observers.get_mut(&self.asan_obs) {
                     asan_observer.parse_asan_output_from_asan_log_file(pid)?;
@@ -1263,13 +1994,29 @@ where
 
             // We need to kill the child in case he has timed out, or we can't get the correct pid in the next call to self.executor.forkserver_mut().read_st()?
             let _ = kill(self.forkserver().child_pid(), self.forkserver.kill_signal);
-            let (recv_status_len, _) = self.forkserver.read_st()?;
-            if recv_status_len != 4 {
-                return Err(Error::unknown("Could not kill timed-out child".to_string()));
+            if self.forkserver_timeout.is_some() {
+                if self.read_forkserver_st()?.is_none() {
+                    self.record_delivered_input(input);
+                    self.restart_forkserver()?;
+                    return Ok(ExitKind::Timeout);
+                }
+            } else {
+                let (recv_status_len, _) = self.forkserver.read_st()?;
+                if recv_status_len != 4 {
+                    return Err(Error::unknown("Could not kill timed-out child".to_string()));
+                }
             }
             exit_kind = ExitKind::Timeout;
         }
 
+        if matches!(exit_kind, ExitKind::Crash | ExitKind::Timeout) {
+            self.record_delivered_input(input);
+        }
+
+        if let Some(expected) = self.persistent_iterations {
+            self.check_persistent_iterations(expected, exit_kind);
+        }
+
         if !libc::WIFSTOPPED(self.forkserver().status()) {
             self.forkserver.reset_child_pid();
         }
@@ -1324,7 +2071,10 @@ mod tests {
     use serial_test::serial;
 
     use crate::{
-        executors::forkserver::ForkserverExecutor,
+        executors::forkserver::{
+            errno_to_os_error, is_transient_error, parse_mem_limit, Endianness, ForkserverExecutor,
+            StdinLengthPrefix,
+        },
         observers::{ConstMapObserver, HitcountsMapObserver},
         Error,
     };
@@ -1365,4 +2115,81 @@ mod tests {
         };
         assert!(result);
     }
+
+    #[test]
+    #[serial]
+    #[cfg_attr(miri, ignore)]
+    fn test_forkserver_missing_target() {
+        let executor = ForkserverExecutor::builder()
+            .program("/nonexistent/libafl_missing_target")
+            .debug_child(false)
+            .build::<_, ()>(tuple_list!());
+
+        match executor {
+            Err(Error::IllegalArgument(s, _)) => {
+                assert!(s.contains("/nonexistent/libafl_missing_target"), "{s}");
+            }
+            Err(err) => panic!("Unexpected error for a missing target: {err:?}"),
+            Ok(_) => panic!("Spawned a missing target"),
+        }
+    }
+
+    #[test]
+    fn test_stdin_length_prefix() {
+        let mut buf = vec![];
+        let prefix = StdinLengthPrefix::new(4, Endianness::Little).unwrap();
+        assert_eq!(prefix.max_len(), u32::MAX as usize);
+        prefix.encode(0x0102, &mut buf);
+        assert_eq!(buf, [0x02, 0x01, 0, 0]);
+
+        buf.clear();
+        let prefix = StdinLengthPrefix::new(2, Endianness::Big).unwrap();
+        assert_eq!(prefix.max_len(), u16::MAX as usize);
+        prefix.encode(0x0102, &mut buf);
+        assert_eq!(buf, [0x01, 0x02]);
+
+        assert!(StdinLengthPrefix::new(0, Endianness::Big).is_err());
+        assert!(StdinLengthPrefix::new(9, Endianness::Little).is_err());
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        let interrupted = std::io::Error::from_raw_os_error(libc::EINTR);
+        assert!(is_transient_error(&Error::from(interrupted)));
+        let exhausted = std::io::Error::from_raw_os_error(libc::EAGAIN);
+        assert!(is_transient_error(&Error::from(exhausted)));
+        let missing = std::io::Error::from_raw_os_error(libc::ENOENT);
+        assert!(!is_transient_error(&Error::from(missing)));
+        assert!(!is_transient_error(&Error::unknown("misbehaving")));
+        assert!(is_transient_error(&errno_to_os_error(
+            nix::errno::Errno::EINTR,
+            "pselect failed"
+        )));
+    }
+
+    #[test]
+    fn test_parse_mem_limit() {
+        assert_eq!(parse_mem_limit("50").unwrap(), Some(50 << 20));
+        assert_eq!(parse_mem_limit("50M").unwrap(), Some(50 << 20));
+        assert_eq!(parse_mem_limit("512k").unwrap(), Some(512 << 10));
+        assert_eq!(parse_mem_limit("2G").unwrap(), Some(2 << 30));
+        assert_eq!(parse_mem_limit("none").unwrap(), None);
+        assert_eq!(parse_mem_limit("0").unwrap(), None);
+        assert!(parse_mem_limit("").is_err());
+        assert!(parse_mem_limit("50X").is_err());
+        assert!(parse_mem_limit("99999999T").is_err());
+    }
+
+    #[test]
+    fn test_input_file_namespaced() {
+        let builder = ForkserverExecutor::builder()
+            .input_file_namespaced("cur_input", 3)
+            .parse_afl_cmdline(["target", "-i", "@@"]);
+        assert_eq!(
+            builder.arguments,
+            [OsString::from("-i"), OsString::from("cur_input_3")]
+        );
+        assert_eq!(builder.input_filename, Some(OsString::from("cur_input_3")));
+        assert!(!builder.use_stdin);
+    }
 }
@@ -19,6 +19,7 @@ pub mod unix_signal_handler {
         feedbacks::Feedback,
         fuzzer::HasObjective,
         inputs::{Input, UsesInput},
+        observers::crash_context::fill_inprocess_crash_context,
         state::{HasCorpus, HasExecutions, HasSolutions},
     };
 
@@ -221,6 +222,8 @@ pub mod unix_signal_handler {
                 }
             }
 
+            fill_inprocess_crash_context(signal, _info, _context.as_deref());
+
             run_observers_and_save_state::<E, EM, OF, Z>(
                 executor,
                 state,
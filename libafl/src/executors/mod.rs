@@ -2,20 +2,24 @@
 
 #[cfg(unix)]
 use alloc::vec::Vec;
-use core::fmt::Debug;
+use core::{fmt::Debug, time::Duration};
 
 pub use combined::CombinedExecutor;
 #[cfg(all(feature = "std", any(unix, doc)))]
 pub use command::CommandExecutor;
+pub use coverage_diff::{diff_coverage, CoverageDiff};
+pub use determinism::{check_determinism, DeterminismReport};
 pub use differential::DiffExecutor;
+pub use dry_run::{dry_run, DryRunReport};
 #[cfg(all(feature = "std", feature = "fork", unix))]
-pub use forkserver::{Forkserver, ForkserverExecutor};
+pub use forkserver::{DiffForkserverExecutor, Forkserver, ForkserverExecutor};
 pub use inprocess::InProcessExecutor;
 #[cfg(all(feature = "std", feature = "fork", unix))]
 pub use inprocess_fork::InProcessForkExecutor;
 #[cfg(unix)]
 use libafl_bolts::os::unix_signals::Signal;
 use libafl_bolts::tuples::RefIndexable;
+pub use rotating::{RotatingExecutor, TargetRotationMetadata};
 use serde::{Deserialize, Serialize};
 pub use shadow::ShadowExecutor;
 pub use with_observers::WithObservers;
@@ -29,7 +33,10 @@ use crate::{
 pub mod combined;
 #[cfg(all(feature = "std", any(unix, doc)))]
 pub mod command;
+pub mod coverage_diff;
+pub mod determinism;
 pub mod differential;
+pub mod dry_run;
 #[cfg(all(feature = "std", feature = "fork", unix))]
 pub mod forkserver;
 pub mod inprocess;
@@ -38,6 +45,7 @@ pub mod inprocess;
 #[cfg(all(feature = "std", unix))]
 pub mod inprocess_fork;
 
+pub mod rotating;
 pub mod shadow;
 
 pub mod with_observers;
@@ -117,6 +125,15 @@ pub trait HasObservers: UsesObservers {
     fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers>;
 }
 
+/// Executors whose timeout can be changed between runs
+pub trait HasAdjustableTimeout {
+    /// The current timeout of a run
+    fn timeout(&self) -> Duration;
+
+    /// Sets the timeout of the next runs
+    fn set_timeout(&mut self, timeout: Duration);
+}
+
 /// An executor takes the given inputs, and runs the harness/target.
 pub trait Executor<EM, Z>: UsesState
 where
@@ -168,13 +185,14 @@ pub fn common_signals() -> Vec<Signal> {
 pub mod test {
     use core::marker::PhantomData;
 
-    use libafl_bolts::{AsSlice, Error};
+    use libafl_bolts::{tuples::RefIndexable, AsSlice, Error};
 
     use crate::{
         events::NopEventManager,
-        executors::{Executor, ExitKind},
+        executors::{Executor, ExitKind, HasObservers},
         fuzzer::test::NopFuzzer,
         inputs::{BytesInput, HasTargetBytes},
+        observers::{MapObserver, StdMapObserver, UsesObservers},
         state::{HasExecutions, NopState, State, UsesState},
     };
 
@@ -231,6 +249,97 @@ pub mod test {
         }
     }
 
+    /// An executor with a map observer named `map` of 8 entries, where each byte of the input covers
+    /// the entry at its value modulo 8. If `flaky`, every other run also covers the last entry.
+    /// Empty inputs time out.
+    #[derive(Debug)]
+    pub struct MapExecutor<S> {
+        observers: (StdMapObserver<'static, u8, false>, ()),
+        flaky: bool,
+        runs: usize,
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> MapExecutor<S> {
+        /// The number of entries of the map
+        pub const MAP_SIZE: usize = 8;
+
+        #[must_use]
+        pub fn new(flaky: bool) -> Self {
+            Self {
+                observers: (StdMapObserver::owned("map", vec![0; Self::MAP_SIZE]), ()),
+                flaky,
+                runs: 0,
+                phantom: PhantomData,
+            }
+        }
+
+        /// The map observer
+        pub fn map_observer(&self) -> &StdMapObserver<'static, u8, false> {
+            &self.observers.0
+        }
+    }
+
+    impl<S> UsesState for MapExecutor<S>
+    where
+        S: State,
+    {
+        type State = S;
+    }
+
+    impl<S> UsesObservers for MapExecutor<S>
+    where
+        S: State,
+    {
+        type Observers = (StdMapObserver<'static, u8, false>, ());
+    }
+
+    impl<S> HasObservers for MapExecutor<S>
+    where
+        S: State,
+    {
+        fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+            RefIndexable::from(&self.observers)
+        }
+
+        fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+            RefIndexable::from(&mut self.observers)
+        }
+    }
+
+    impl<EM, S, Z> Executor<EM, Z> for MapExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: State + HasExecutions,
+        S::Input: HasTargetBytes,
+        Z: UsesState<State = S>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            state: &mut Self::State,
+            _mgr: &mut EM,
+            input: &Self::Input,
+        ) -> Result<ExitKind, Error> {
+            *state.executions_mut() += 1;
+            self.runs += 1;
+
+            let map = &mut self.observers.0;
+            let bytes = input.target_bytes();
+            for byte in bytes.as_slice() {
+                map.set(usize::from(*byte) % Self::MAP_SIZE, 1);
+            }
+            if self.flaky && self.runs % 2 == 0 {
+                map.set(Self::MAP_SIZE - 1, 1);
+            }
+            if bytes.as_slice().is_empty() {
+                Ok(ExitKind::Timeout)
+            } else {
+                Ok(ExitKind::Ok)
+            }
+        }
+    }
+
     #[test]
     fn nop_executor() {
         let empty_input = BytesInput::new(vec![]);
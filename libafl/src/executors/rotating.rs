@@ -0,0 +1,230 @@
+//! A [`RotatingExecutor`] runs one of several targets at a time, rotating through them,
+//! so that all targets are fuzzed with one shared corpus.
+
+use alloc::vec::Vec;
+
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{HasConstLen, NamedTuple, RefIndexable},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    observers::UsesObservers,
+    state::UsesState,
+    Error, HasMetadata,
+};
+
+/// The index of the target the last execution of a [`RotatingExecutor`] ran,
+/// read by the [`crate::feedbacks::RotatingFeedback`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct TargetRotationMetadata {
+    /// The index of the target
+    pub current: usize,
+}
+
+impl_serdeany!(TargetRotationMetadata);
+
+/// An executor wrapping one executor per target, such as several implementations of the same spec,
+/// and running only the current one. The targets are switched with [`RotatingExecutor::rotate`],
+/// usually by the [`crate::stages::TargetRotationStage`] between two corpus entries,
+/// as the observers must not change while an input is executed.
+///
+/// Each execution records the index of the target in the [`TargetRotationMetadata`], so that
+/// a [`crate::feedbacks::RotatingFeedback`] judges the input with the feedback of that target:
+/// an input covering anything new in any target is kept in the shared corpus.
+/// Each observer needs a name unique among the targets, e.g., suffixed with the index of the target,
+/// so that the feedback and its metadata of one target never pick up the observers of another.
+/// To catch divergences between the targets on the same input, use a
+/// [`crate::executors::DiffExecutor`] instead.
+///
+/// Components holding a single observer handle, such as the
+/// [`crate::stages::CalibrationStage`] or the map-based schedulers like the
+/// [`crate::schedulers::MinimizerScheduler`], only find their observer while its target is the
+/// current one and fail once the executor rotated. Use them only with per-target observers that
+/// they do not need, e.g. a [`crate::schedulers::QueueScheduler`] with mutational stages.
+#[derive(Debug)]
+pub struct RotatingExecutor<E> {
+    executors: Vec<E>,
+    current: usize,
+}
+
+impl<E> RotatingExecutor<E> {
+    /// Creates a new [`RotatingExecutor`], starting with the first of the given executors.
+    /// Fails if two targets have an observer of the same name.
+    pub fn new(executors: Vec<E>) -> Result<Self, Error>
+    where
+        E: HasObservers,
+        E::Observers: NamedTuple,
+    {
+        if executors.is_empty() {
+            return Err(Error::illegal_argument(
+                "A RotatingExecutor needs at least one target",
+            ));
+        }
+        let len = <E::Observers as HasConstLen>::LEN;
+        for (target, executor) in executors.iter().enumerate() {
+            let observers = executor.observers();
+            for idx in 0..len {
+                let Some(name) = observers.name(idx) else {
+                    continue;
+                };
+                let clash = executors[..target]
+                    .iter()
+                    .any(|other| (0..len).any(|i| other.observers().name(i) == Some(name)));
+                if clash {
+                    return Err(Error::illegal_argument(format!(
+                        "The observer {name} of target {target} has the same name as an observer of another target"
+                    )));
+                }
+            }
+        }
+        Ok(Self {
+            executors,
+            current: 0,
+        })
+    }
+
+    /// The index of the current target
+    #[must_use]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The number of targets
+    #[must_use]
+    pub fn targets(&self) -> usize {
+        self.executors.len()
+    }
+
+    /// Switches to the next target, returning its index
+    pub fn rotate(&mut self) -> usize {
+        self.current = (self.current + 1) % self.executors.len();
+        self.current
+    }
+
+    /// The executors of all targets
+    pub fn executors(&self) -> &[E] {
+        &self.executors
+    }
+
+    /// The executors of all targets (mutable)
+    pub fn executors_mut(&mut self) -> &mut [E] {
+        &mut self.executors
+    }
+}
+
+impl<E, EM, Z> Executor<EM, Z> for RotatingExecutor<E>
+where
+    E: Executor<EM, Z>,
+    E::State: HasMetadata,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &Self::Input,
+    ) -> Result<ExitKind, Error> {
+        state
+            .metadata_or_insert_with(TargetRotationMetadata::default)
+            .current = self.current;
+        self.executors[self.current].run_target(fuzzer, state, mgr, input)
+    }
+}
+
+impl<E> UsesState for RotatingExecutor<E>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E> UsesObservers for RotatingExecutor<E>
+where
+    E: UsesObservers,
+{
+    type Observers = E::Observers;
+}
+
+impl<E> HasObservers for RotatingExecutor<E>
+where
+    E: HasObservers,
+{
+    #[inline]
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        self.executors[self.current].observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        self.executors[self.current].observers_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{tuples::tuple_list, Named};
+
+    use crate::{
+        events::NopEventManager,
+        executors::{
+            test::NopExecutor, Executor, HasObservers, RotatingExecutor, TargetRotationMetadata,
+            WithObservers,
+        },
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        state::NopState,
+        HasMetadata,
+    };
+
+    type TestTarget =
+        WithObservers<NopExecutor<NopState<BytesInput>>, (StdMapObserver<'static, u8, false>, ())>;
+
+    fn target(name: &'static str) -> TestTarget {
+        WithObservers::new(
+            NopExecutor::new(),
+            tuple_list!(StdMapObserver::owned(name, vec![0_u8; 4])),
+        )
+    }
+
+    #[test]
+    fn test_rotating_executor() {
+        assert!(RotatingExecutor::new(vec![target("edges"), target("edges")]).is_err());
+
+        let mut executor =
+            RotatingExecutor::new(vec![target("edges_0"), target("edges_1")]).unwrap();
+        let mut state = NopState::<BytesInput>::new();
+        let mut fuzzer = NopFuzzer::new();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![1]);
+
+        executor
+            .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
+            .unwrap();
+        assert_eq!(
+            state.metadata::<TargetRotationMetadata>().unwrap().current,
+            0
+        );
+
+        assert_eq!(executor.rotate(), 1);
+        assert_eq!(executor.observers().0.name(), "edges_1");
+        executor
+            .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
+            .unwrap();
+        assert_eq!(
+            state.metadata::<TargetRotationMetadata>().unwrap().current,
+            1
+        );
+
+        assert_eq!(executor.rotate(), 0);
+    }
+}
@@ -0,0 +1,164 @@
+//! Feedback and metadata to stamp user-provided campaign tags (build id, target name, commit hash, ...)
+//! onto every testcase that gets added to a corpus.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use libafl_bolts::{impl_serdeany, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, FeedbackFactory},
+    observers::ObserversTuple,
+    state::State,
+    Error, HasMetadata,
+};
+
+/// Metadata holding arbitrary `key=value` tags describing the campaign a testcase was found in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CampaignTagMetadata {
+    /// The tags, in the order they were given
+    pub tags: Vec<(String, String)>,
+}
+
+impl_serdeany!(CampaignTagMetadata);
+
+impl CampaignTagMetadata {
+    /// Get the value for the given tag key, if present
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Nop feedback that annotates every new testcase with a [`CampaignTagMetadata`].
+/// The testcase is never interesting (use with an Eager OR).
+/// Put it in the objective chain to tag all solutions, e.g. with `--tag key=value` arguments.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CampaignTagFeedback {
+    tags: Vec<(String, String)>,
+}
+
+impl CampaignTagFeedback {
+    /// Creates a new [`CampaignTagFeedback`] from already split `(key, value)` pairs.
+    #[must_use]
+    pub fn new(tags: Vec<(String, String)>) -> Self {
+        Self { tags }
+    }
+
+    /// Creates a new [`CampaignTagFeedback`] by parsing `key=value` strings, as passed on the commandline.
+    /// Only the first `=` separates key and value, the key may not be empty.
+    pub fn from_args<I, T>(args: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let tags = args
+            .into_iter()
+            .map(|arg| {
+                let arg = arg.as_ref();
+                match arg.split_once('=') {
+                    Some((key, value)) if !key.is_empty() => {
+                        Ok((key.to_string(), value.to_string()))
+                    }
+                    _ => Err(Error::illegal_argument(format!(
+                        "Invalid campaign tag {arg:?}, expected key=value"
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Self::new(tags))
+    }
+
+    /// The tags this feedback stamps onto testcases
+    #[must_use]
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+}
+
+impl<S> Feedback<S> for CampaignTagFeedback
+where
+    S: State,
+{
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(false)
+    }
+
+    /// Append the campaign tags to the new testcase
+    #[inline]
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        testcase.add_metadata(CampaignTagMetadata {
+            tags: self.tags.clone(),
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+impl Named for CampaignTagFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("CampaignTagFeedback");
+        &NAME
+    }
+}
+
+impl<S: State, T> FeedbackFactory<CampaignTagFeedback, S, T> for CampaignTagFeedback {
+    fn create_feedback(&self, _ctx: &T) -> CampaignTagFeedback {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feedbacks::campaign_tag::CampaignTagFeedback;
+
+    #[test]
+    fn test_campaign_tag_parse() {
+        let feedback =
+            CampaignTagFeedback::from_args(["build=1234", "target=libpng", "cmd=a=b"]).unwrap();
+        assert_eq!(feedback.tags().len(), 3);
+        assert_eq!(feedback.tags()[2].0, "cmd");
+        assert_eq!(feedback.tags()[2].1, "a=b");
+
+        assert!(CampaignTagFeedback::from_args(["novalue"]).is_err());
+        assert!(CampaignTagFeedback::from_args(["=value"]).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! The [`CoverageSnapshotFeedback`] stores the coverage of the current run in the testcase,
+//! so that solutions carry the coverage of the execution that triggered them.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::marker::PhantomData;
+
+use hashbrown::HashMap;
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle},
+    observers::{MapObserver, ObserversTuple},
+    state::State,
+    Error, HasMetadata,
+};
+
+/// The map indexes covered by the execution a testcase was found with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageSnapshotMetadata {
+    /// The name of the map observer the snapshot was taken from
+    pub observer: Cow<'static, str>,
+    /// The indexes of all entries that differed from the map's initial value
+    pub indexes: Vec<usize>,
+}
+
+impl_serdeany!(CoverageSnapshotMetadata);
+
+/// Ranks the corpus entries by the number of map entries only they cover, according to their
+/// [`CoverageSnapshotMetadata`], the most important entries first.
+///
+/// Entries without a snapshot contribute nothing and come last. Ties are ordered by [`CorpusId`].
+pub fn coverage_ranking<C>(corpus: &C) -> Result<Vec<(CorpusId, usize)>, Error>
+where
+    C: Corpus,
+{
+    let mut hits: HashMap<(Cow<'static, str>, usize), usize> = HashMap::new();
+    for id in corpus.ids() {
+        let testcase = corpus.get(id)?.borrow();
+        if let Ok(snapshot) = testcase.metadata::<CoverageSnapshotMetadata>() {
+            for idx in &snapshot.indexes {
+                *hits.entry((snapshot.observer.clone(), *idx)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut ranking = Vec::with_capacity(corpus.count());
+    for id in corpus.ids() {
+        let testcase = corpus.get(id)?.borrow();
+        // `None` for entries without a snapshot, which sorts them after all others
+        let unique = testcase
+            .metadata::<CoverageSnapshotMetadata>()
+            .ok()
+            .map(|snapshot| {
+                snapshot
+                    .indexes
+                    .iter()
+                    .filter(|idx| hits.get(&(snapshot.observer.clone(), **idx)) == Some(&1))
+                    .count()
+            });
+        ranking.push((id, unique));
+    }
+    ranking
+        .sort_by(|(id_a, unique_a), (id_b, unique_b)| unique_b.cmp(unique_a).then(id_a.cmp(id_b)));
+    Ok(ranking
+        .into_iter()
+        .map(|(id, unique)| (id, unique.unwrap_or(0)))
+        .collect())
+}
+
+/// Nop feedback that snapshots the covered entries of a map into a [`CoverageSnapshotMetadata`].
+/// The testcase is never interesting (use with an Eager OR).
+///
+/// Put it in the objective chain, so that solutions keep the coverage of the run that produced them.
+/// This includes crashes caught by the in-process crash handler, which only runs the objective:
+/// the map is still intact in the crashing client when the handler saves the solution,
+/// whereas the coverage feedback never sees that run.
+#[derive(Debug, Clone)]
+pub struct CoverageSnapshotFeedback<C, O> {
+    map_ref: Handle<C>,
+    phantom: PhantomData<O>,
+}
+
+impl<C, O> CoverageSnapshotFeedback<C, O>
+where
+    C: Named,
+{
+    /// Creates a new [`CoverageSnapshotFeedback`], snapshotting the given map observer
+    #[must_use]
+    pub fn new(map_observer: &C) -> Self {
+        Self {
+            map_ref: map_observer.handle(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<C, O, S> Feedback<S> for CoverageSnapshotFeedback<C, O>
+where
+    C: AsRef<O> + Named,
+    O: MapObserver,
+    S: State,
+{
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let observer = observers
+            .get(&self.map_ref)
+            .ok_or_else(|| Error::key_not_found(format!("MapObserver {}", self.map_ref.name())))?
+            .as_ref();
+        let initial = observer.initial();
+        let indexes = (0..observer.usable_count())
+            .filter(|idx| observer.get(*idx) != initial)
+            .collect();
+        testcase.add_metadata(CoverageSnapshotMetadata {
+            observer: self.map_ref.name().clone(),
+            indexes,
+        });
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+impl<C, O> Named for CoverageSnapshotFeedback<C, O> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("CoverageSnapshotFeedback");
+        &NAME
+    }
+}
+
+impl<C, O> HasObserverHandle for CoverageSnapshotFeedback<C, O> {
+    type Observer = C;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<C> {
+        &self.map_ref
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, vec::Vec};
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::coverage_snapshot::{coverage_ranking, CoverageSnapshotMetadata},
+        inputs::BytesInput,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_coverage_ranking() {
+        let mut corpus = InMemoryCorpus::new();
+        let mut add = |indexes: Option<Vec<usize>>| {
+            let mut testcase = Testcase::new(BytesInput::new(vec![]));
+            if let Some(indexes) = indexes {
+                testcase.add_metadata(CoverageSnapshotMetadata {
+                    observer: Cow::Borrowed("edges"),
+                    indexes,
+                });
+            }
+            corpus.add(testcase).unwrap()
+        };
+        let shared = add(Some(vec![1, 2]));
+        let unique = add(Some(vec![1, 3, 4]));
+        let missing = add(None);
+        let duplicate = add(Some(vec![2]));
+
+        assert_eq!(
+            coverage_ranking(&corpus).unwrap(),
+            [(unique, 2), (shared, 0), (duplicate, 0), (missing, 0)]
+        );
+    }
+}
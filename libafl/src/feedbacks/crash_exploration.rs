@@ -0,0 +1,239 @@
+//! The [`CrashExplorationFeedback`] inverts the usual feedback for crash exploration,
+//! like the `-C` mode of `AFL++`: an input is only kept if it still crashes and covers something new.
+
+use alloc::borrow::Cow;
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+
+use libafl_bolts::{current_time, Named};
+
+use crate::{
+    corpus::Testcase,
+    events::{Event, EventFirer},
+    executors::ExitKind,
+    feedbacks::Feedback,
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    observers::ObserversTuple,
+    state::State,
+    Error,
+};
+
+/// The minimum interval between two reports of the inputs dropped for not crashing
+pub const NON_CRASHING_REPORT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Wraps a coverage feedback, usually the map feedback on the edges observer, so that only inputs
+/// that crash the target and are interesting to the coverage feedback are kept.
+/// Inputs that do not crash are dropped without updating the coverage history, so the corpus maps
+/// the neighborhood of a crash: its variants reaching the crash through different paths.
+///
+/// For crash exploration, seed the corpus with existing crashes, e.g., the solutions directory of
+/// an earlier campaign, and use this as the feedback, with an objective that is never interesting,
+/// such as [`crate::feedbacks::ConstFeedback::False`], as every corpus entry crashes.
+/// Initial inputs that do not crash are not added to the corpus.
+/// Configure the [`crate::stages::CalibrationStage`] with
+/// [`crate::stages::CalibrationStage::with_expected_exit_kind`] and [`ExitKind::Crash`],
+/// else it treats every entry as erroring.
+///
+/// The number of inputs dropped for not crashing is reported as the `non_crashing` user stat,
+/// at most once per [`NON_CRASHING_REPORT_INTERVAL`].
+#[derive(Clone)]
+pub struct CrashExplorationFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// The wrapped coverage feedback
+    pub first: A,
+    /// The name
+    name: Cow<'static, str>,
+    /// The inputs dropped for not crashing
+    non_crashing: u64,
+    /// When we last reported the inputs dropped for not crashing
+    last_report: Option<Duration>,
+    // The previous run's result of `Self::is_interesting`
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> Debug for CrashExplorationFeedback<A, S>
+where
+    A: Feedback<S> + Debug,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CrashExplorationFeedback")
+            .field("name", &self.name)
+            .field("first", &self.first)
+            .field("non_crashing", &self.non_crashing)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, S> Feedback<S> for CrashExplorationFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        self.first.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        // Only the coverage of crashing inputs counts, so that the history maps the crash's neighborhood
+        let res = if *exit_kind == ExitKind::Crash {
+            self.first
+                .is_interesting(state, manager, input, observers, exit_kind)?
+        } else {
+            self.non_crashing += 1;
+            let now = current_time();
+            if self.last_report.map_or(true, |last| {
+                now.saturating_sub(last) >= NON_CRASHING_REPORT_INTERVAL
+            }) {
+                self.last_report = Some(now);
+                manager.fire(
+                    state,
+                    Event::UpdateUserStats {
+                        name: Cow::from("non_crashing"),
+                        value: UserStats::new(
+                            UserStatsValue::Number(self.non_crashing),
+                            AggregatorOps::Sum,
+                        ),
+                        phantom: PhantomData,
+                    },
+                )?;
+            }
+            false
+        };
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[inline]
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        self.first
+            .append_metadata(state, manager, observers, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.first.discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result
+            .ok_or(crate::feedbacks::premature_last_result_err())
+    }
+}
+
+impl<A, S> Named for CrashExplorationFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<A, S> CrashExplorationFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// Creates a new [`CrashExplorationFeedback`], wrapping the coverage feedback `first`
+    pub fn new(first: A) -> Self {
+        let name = Cow::from(format!("CrashExploration({})", first.name()));
+        Self {
+            first,
+            name,
+            non_crashing: 0,
+            last_report: None,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The number of inputs dropped so far for not crashing
+    #[must_use]
+    pub fn non_crashing(&self) -> u64 {
+        self.non_crashing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, CrashExplorationFeedback, Feedback, MaxMapFeedback},
+        inputs::BytesInput,
+        observers::{MapObserver, StdMapObserver},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_crash_exploration() {
+        let observer = StdMapObserver::owned("map", vec![0_u8; 4]);
+        let mut feedback = CrashExplorationFeedback::new(MaxMapFeedback::new(&observer));
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut observers = tuple_list!(observer);
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        // New coverage without a crash is dropped and does not update the history
+        observers.0.set(1, 1);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        assert_eq!(feedback.non_crashing(), 1);
+
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Crash)
+            .unwrap());
+        feedback.discard_metadata(&mut state, &input).unwrap();
+        assert_eq!(feedback.non_crashing(), 1);
+    }
+}
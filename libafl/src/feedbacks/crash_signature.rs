@@ -0,0 +1,353 @@
+//! The [`CrashSignatureFeedback`] deduplicates crashes by a signature of tunable granularity,
+//! made of the signal, the top stack frames, and the faulting page.
+
+use alloc::{borrow::Cow, string::ToString, vec::Vec};
+use core::{marker::PhantomData, time::Duration};
+
+use hashbrown::HashMap;
+use libafl_bolts::{
+    current_time, impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle},
+    observers::{CrashContext, CrashContextObserver, ObserverWithStackFrames, ObserversTuple},
+    state::State,
+    Error, HasNamedMetadata,
+};
+
+/// The prefix of the metadata names
+pub const CRASH_SIGNATURE_FEEDBACK_PREFIX: &str = "crashsignaturefeedback_metadata_";
+
+/// The default number of top stack frames in a [`CrashSignature`]
+pub const DEFAULT_SIGNATURE_FRAMES: usize = 5;
+
+/// The default page size the fault address is truncated to in a [`CrashSignature`]
+pub const DEFAULT_SIGNATURE_PAGE_SIZE: u64 = 0x1000;
+
+/// The signature of a crash, two crashes with the same signature are considered duplicates
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CrashSignature {
+    /// The signal of the crash, if known
+    pub signal: Option<i32>,
+    /// The top stack frames, innermost first, as normalized by the observer
+    /// (see [`crate::observers::normalize_frame`])
+    pub frames: Vec<u64>,
+    /// The page of the faulting address, if known and considered
+    pub fault_page: Option<u64>,
+}
+
+impl CrashSignature {
+    /// Computes the signature of a crash from its context and its stack frames, innermost first.
+    /// The frames are expected to start at the crash, without the frames of the crash handler,
+    /// as collected by [`crate::observers::collect_backtrace_frames`].
+    /// A `page_size` of `0` leaves the faulting address out of the signature.
+    #[must_use]
+    pub fn new(
+        context: Option<&CrashContext>,
+        frames: &[u64],
+        top_frames: usize,
+        page_size: u64,
+    ) -> Self {
+        Self {
+            signal: context.map(|context| context.signal),
+            frames: frames.iter().take(top_frames).copied().collect(),
+            fault_page: context
+                .filter(|_| page_size != 0)
+                .map(|context| context.fault_addr / page_size),
+        }
+    }
+}
+
+/// The state of [`CrashSignatureFeedback`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CrashSignatureMetadata {
+    /// The signatures reported so far, with the time they were last reported
+    pub signatures: HashMap<CrashSignature, Duration>,
+}
+
+impl_serdeany!(CrashSignatureMetadata);
+
+impl CrashSignatureMetadata {
+    /// Records the `signature` seen at `now`, and returns if it is new.
+    ///
+    /// A signature reported before is new once more after `window` has passed since,
+    /// e.g., to catch regressions. Without a `window`, a signature is only ever new once.
+    pub fn update(
+        &mut self,
+        signature: CrashSignature,
+        now: Duration,
+        window: Option<Duration>,
+    ) -> bool {
+        match self.signatures.get_mut(&signature) {
+            Some(reported) => {
+                let expired = window.is_some_and(|window| now.saturating_sub(*reported) >= window);
+                if expired {
+                    *reported = now;
+                }
+                expired
+            }
+            None => {
+                self.signatures.insert(signature, now);
+                true
+            }
+        }
+    }
+}
+
+/// A [`CrashSignatureFeedback`] keeps the signatures of the crashes seen so far, and considers
+/// interesting crashes with an unseen [`CrashSignature`], made of the signal, the top `N` stack
+/// frames, and the faulting page.
+///
+/// The frames are normalized to their module and offset, so the signatures stay comparable across
+/// restarts and clients. With a [`crate::observers::HarnessType::Child`], the frames of the
+/// [`crate::observers::BacktraceObserver`] have to live in shared memory, see
+/// [`crate::observers::BacktraceObserver::with_frames`].
+///
+/// Compared to the [`crate::feedbacks::NewHashFeedback`] hashing the whole stack, the number of
+/// frames tunes how eagerly crashes are merged. The signal and the faulting page are taken from
+/// the [`CrashContextObserver`], if given. Optionally, a signature seen before is reported again
+/// once a time window has passed, e.g., to catch regressions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashSignatureFeedback<O, S> {
+    name: Cow<'static, str>,
+    o_ref: Handle<O>,
+    context_ref: Option<Handle<CrashContextObserver>>,
+    /// The number of top stack frames in the signature
+    top_frames: usize,
+    /// The page size the faulting address is truncated to, `0` to ignore it
+    page_size: u64,
+    /// The time after which a signature seen before is new again
+    window: Option<Duration>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    // The previous run's result of `Self::is_interesting`
+    last_result: Option<bool>,
+    phantom: PhantomData<S>,
+}
+
+impl<O, S> Feedback<S> for CrashSignatureFeedback<O, S>
+where
+    O: ObserverWithStackFrames + Named,
+    S: State + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.add_named_metadata(&self.name, CrashSignatureMetadata::default());
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("The stack frames observer is missing"))?;
+        let context = match &self.context_ref {
+            Some(context_ref) => observers
+                .get(context_ref)
+                .ok_or(Error::illegal_state("CrashContextObserver is missing"))?
+                .last_context(),
+            None => None,
+        };
+
+        // Without any frames, the run did not crash
+        let res = match observer.frames() {
+            Some(frames) => {
+                let signature =
+                    CrashSignature::new(context, frames, self.top_frames, self.page_size);
+                state
+                    .named_metadata_map_mut()
+                    .get_mut::<CrashSignatureMetadata>(&self.name)
+                    .unwrap()
+                    .update(signature, current_time(), self.window)
+            }
+            None => false,
+        };
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+}
+
+impl<O, S> Named for CrashSignatureFeedback<O, S> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<O, S> HasObserverHandle for CrashSignatureFeedback<O, S> {
+    type Observer = O;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<O> {
+        &self.o_ref
+    }
+}
+
+impl<O, S> CrashSignatureFeedback<O, S>
+where
+    O: ObserverWithStackFrames + Named,
+{
+    /// Returns a new [`CrashSignatureFeedback`] taking the stack frames from the given observer,
+    /// keeping the top [`DEFAULT_SIGNATURE_FRAMES`] frames, and without a time window.
+    #[must_use]
+    pub fn new(observer: &O) -> Self {
+        Self {
+            name: Cow::from(CRASH_SIGNATURE_FEEDBACK_PREFIX.to_string() + observer.name()),
+            o_ref: observer.handle(),
+            context_ref: None,
+            top_frames: DEFAULT_SIGNATURE_FRAMES,
+            page_size: DEFAULT_SIGNATURE_PAGE_SIZE,
+            window: None,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Takes the signal and the faulting page of the signature from the given [`CrashContextObserver`]
+    #[must_use]
+    pub fn with_crash_context(mut self, observer: &CrashContextObserver) -> Self {
+        self.context_ref = Some(observer.handle());
+        self
+    }
+
+    /// Keeps the top `top_frames` stack frames in the signature.
+    /// Fewer frames merge more crashes, more frames tell more crashes apart.
+    #[must_use]
+    pub fn with_top_frames(mut self, top_frames: usize) -> Self {
+        self.top_frames = top_frames;
+        self
+    }
+
+    /// Truncates the faulting address to pages of `page_size` bytes, `0` leaves it out of the signature
+    #[must_use]
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Reports a signature seen before again, once `window` has passed since it was last reported
+    #[must_use]
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = Some(window);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{
+        feedbacks::crash_signature::{CrashSignature, CrashSignatureMetadata},
+        observers::CrashContext,
+    };
+
+    #[test]
+    fn test_crash_signature() {
+        let context = CrashContext {
+            valid: 1,
+            signal: 11,
+            fault_addr: 0x1234,
+            pc: 0x30,
+            ..CrashContext::default()
+        };
+        let frames = [0x10, 0x20, 0x30, 0x40, 0x50, 0x60];
+
+        let signature = CrashSignature::new(Some(&context), &frames, 2, 0x1000);
+        assert_eq!(signature.signal, Some(11));
+        assert_eq!(signature.frames, vec![0x10, 0x20]);
+        assert_eq!(signature.fault_page, Some(1));
+
+        let signature = CrashSignature::new(None, &frames, 2, 0x1000);
+        assert_eq!(signature.frames, vec![0x10, 0x20]);
+        assert_eq!(signature.fault_page, None);
+
+        let mut meta = CrashSignatureMetadata::default();
+        let window = Some(Duration::from_secs(60));
+        assert!(meta.update(signature.clone(), Duration::from_secs(0), window));
+        assert!(!meta.update(signature.clone(), Duration::from_secs(30), window));
+        assert!(meta.update(signature.clone(), Duration::from_secs(60), window));
+        assert!(!meta.update(signature, Duration::from_secs(61), None));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_crash_signature_feedback() {
+        use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+        use crate::{
+            corpus::InMemoryCorpus,
+            events::NopEventManager,
+            executors::ExitKind,
+            feedbacks::{ConstFeedback, CrashSignatureFeedback, Feedback},
+            inputs::BytesInput,
+            observers::{BacktraceObserver, HarnessType},
+            state::StdState,
+        };
+
+        let observer = BacktraceObserver::owned("backtrace", HarnessType::External);
+        let mut feedback = CrashSignatureFeedback::new(&observer).with_top_frames(2);
+        let mut observers = tuple_list!(observer);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        // Same top frames, deeper frames differ, only the first is interesting
+        for (hash, frames, expected) in [
+            (1, &[0x10, 0x20, 0x30][..], true),
+            (2, &[0x10, 0x20, 0x40][..], false),
+            (3, &[0x10, 0x50][..], true),
+        ] {
+            observers.0.fill_external(hash, &ExitKind::Crash);
+            observers.0.fill_external_frames(frames);
+            let res = feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Crash)
+                .unwrap();
+            assert_eq!(res, expected);
+        }
+
+        // No crash, no frames
+        observers.0.fill_external(0, &ExitKind::Ok);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+    }
+}
@@ -0,0 +1,231 @@
+//! The [`DeliveredInputFeedback`] stores the exact bytes the target received next to a solution,
+//! for inputs that get encoded or prefixed on delivery.
+
+use alloc::{borrow::Cow, vec::Vec};
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+#[cfg(feature = "std")]
+use libafl_bolts::fs::write_file_atomic;
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::{corpus::Corpus, inputs::Input};
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    observers::{DeliveredInputObserver, ObserversTuple},
+    state::{HasSolutions, State},
+    Error, HasMetadata,
+};
+
+/// The bytes the target received for a testcase, added by the [`DeliveredInputFeedback`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeliveredInputMetadata {
+    /// The delivered bytes
+    pub bytes: Vec<u8>,
+}
+
+impl_serdeany!(DeliveredInputMetadata);
+
+/// Nop feedback that adds the bytes recorded by a [`DeliveredInputObserver`] to the testcase
+/// as [`DeliveredInputMetadata`]. The testcase is never interesting (use with an OR
+/// next to the crash or timeout feedback in the objective).
+///
+/// With [`DeliveredInputFeedback::with_output_dir`], the bytes are also written to `<filename>.delivered`,
+/// so that `cat <filename>.delivered | target` reproduces the crash exactly as the fuzzer ran it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeliveredInputFeedback {
+    o_ref: Handle<DeliveredInputObserver>,
+    name: Cow<'static, str>,
+    #[cfg(feature = "std")]
+    output_dir: Option<PathBuf>,
+}
+
+impl DeliveredInputFeedback {
+    /// Creates a new [`DeliveredInputFeedback`] for the given [`DeliveredInputObserver`]
+    #[must_use]
+    pub fn new(observer: &DeliveredInputObserver) -> Self {
+        Self {
+            o_ref: observer.handle(),
+            name: Cow::from(format!("DeliveredInputFeedback_{}", observer.name())),
+            #[cfg(feature = "std")]
+            output_dir: None,
+        }
+    }
+
+    /// Also writes the delivered bytes to `<dir>/<filename>.delivered`, usually with the solutions directory as `dir`.
+    /// The filename is the one the solution will be stored under, unless the solutions corpus renames it.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn with_output_dir<P>(mut self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.output_dir = Some(dir.into());
+        self
+    }
+}
+
+impl<S> Feedback<S> for DeliveredInputFeedback
+where
+    S: State + HasSolutions,
+{
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("DeliveredInputObserver is missing"))?;
+        let Some(bytes) = observer.delivered() else {
+            return Ok(());
+        };
+
+        #[cfg(feature = "std")]
+        if let Some(dir) = &self.output_dir {
+            let filename = match testcase.filename() {
+                Some(filename) => filename.clone(),
+                None => {
+                    let id = state.solutions().peek_free_id();
+                    testcase
+                        .input()
+                        .as_ref()
+                        .ok_or(Error::illegal_state("The testcase has no input"))?
+                        .generate_name(id.0)
+                }
+            };
+            write_file_atomic(dir.join(format!("{filename}.delivered")), bytes)?;
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = state;
+
+        testcase.add_metadata(DeliveredInputMetadata {
+            bytes: bytes.to_vec(),
+        });
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+impl Named for DeliveredInputFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{tuples::tuple_list, Named};
+
+    use crate::{
+        corpus::Testcase,
+        events::NopEventManager,
+        feedbacks::{DeliveredInputFeedback, DeliveredInputMetadata, Feedback},
+        inputs::BytesInput,
+        observers::DeliveredInputObserver,
+        state::test::test_std_state,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_delivered_input_feedback() {
+        let mut state = test_std_state::<BytesInput>();
+        let mut mgr = NopEventManager::new();
+        let observer = DeliveredInputObserver::new("delivered");
+        let mut feedback = DeliveredInputFeedback::new(&observer);
+        assert_ne!(feedback.name(), observer.name());
+        let mut observers = tuple_list!(observer);
+
+        // Nothing recorded, e.g., for a run that exited normally
+        let mut testcase = Testcase::new(BytesInput::new(vec![1]));
+        feedback
+            .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+            .unwrap();
+        assert!(!testcase.has_metadata::<DeliveredInputMetadata>());
+
+        observers.0.set_delivered(b"\x01\x00\x00\x00A");
+        let mut testcase = Testcase::new(BytesInput::new(b"A".to_vec()));
+        feedback
+            .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+            .unwrap();
+        assert_eq!(
+            testcase.metadata::<DeliveredInputMetadata>().unwrap().bytes,
+            b"\x01\x00\x00\x00A"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore)]
+    fn test_delivered_input_feedback_output_dir() {
+        use std::{env, fs};
+
+        let dir = env::temp_dir().join(format!(
+            "libafl_delivered_input_test_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut state = test_std_state::<BytesInput>();
+        let mut mgr = NopEventManager::new();
+        let mut observer = DeliveredInputObserver::new("delivered");
+        observer.set_delivered(b"delivered");
+        let mut feedback = DeliveredInputFeedback::new(&observer).with_output_dir(&dir);
+        let observers = tuple_list!(observer);
+
+        let mut testcase = Testcase::new(BytesInput::new(b"input".to_vec()));
+        *testcase.filename_mut() = Some("crash".into());
+        feedback
+            .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+            .unwrap();
+        let delivered = fs::read(dir.join("crash.delivered"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(delivered.unwrap(), b"delivered");
+    }
+}
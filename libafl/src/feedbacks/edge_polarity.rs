@@ -0,0 +1,346 @@
+//! The [`EdgePolarityFeedback`] considers inputs interesting that flip the polarity of an edge,
+//! i.e., skip an edge every corpus entry took so far, or take an edge no corpus entry took.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::marker::PhantomData;
+
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle},
+    observers::{MapObserver, ObserversTuple},
+    state::State,
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The default number of corpus entries to observe before edges count as stable
+pub const DEFAULT_MIN_POLARITY_OBSERVATIONS: usize = 16;
+
+/// The per-edge polarity statistics across the corpus, stored in the state keyed by the name of the feedback
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EdgePolarityMetadata {
+    /// The number of corpus entries recorded
+    pub observed: usize,
+    /// The number of recorded corpus entries that took each edge
+    pub taken: Vec<usize>,
+    /// The edges every recorded corpus entry took
+    pub always_taken: Vec<usize>,
+}
+
+impl_serdeany!(EdgePolarityMetadata);
+
+impl EdgePolarityMetadata {
+    /// Records the edges of a corpus entry in a map of `len` entries, `is_taken` tells if it took an edge
+    pub fn record<F>(&mut self, len: usize, is_taken: F)
+    where
+        F: Fn(usize) -> bool,
+    {
+        if self.taken.len() < len {
+            self.taken.resize(len, 0);
+        }
+        for (idx, taken) in self.taken.iter_mut().enumerate().take(len) {
+            if is_taken(idx) {
+                *taken += 1;
+            }
+        }
+        self.observed += 1;
+        let observed = self.observed;
+        self.always_taken = self
+            .taken
+            .iter()
+            .enumerate()
+            .filter(|(_, taken)| **taken == observed)
+            .map(|(idx, _)| idx)
+            .collect();
+    }
+
+    /// If a run flips any edge compared to the recorded corpus entries, see [`Self::flipped`].
+    /// Stops at the first flipped edge and does not allocate, to be cheap enough for every run.
+    #[must_use]
+    pub fn flips<F>(&self, len: usize, is_taken: F) -> bool
+    where
+        F: Fn(usize) -> bool,
+    {
+        self.always_taken
+            .iter()
+            .any(|idx| *idx < len && !is_taken(*idx))
+            || (0..len).any(|idx| is_taken(idx) && self.taken.get(idx).map_or(true, |t| *t == 0))
+    }
+
+    /// The edges a run flips compared to the recorded corpus entries:
+    /// edges all of them took, but the run did not, and edges none of them took, but the run did.
+    #[must_use]
+    pub fn flipped<F>(&self, len: usize, is_taken: F) -> Vec<usize>
+    where
+        F: Fn(usize) -> bool,
+    {
+        (0..len)
+            .filter(|idx| {
+                let taken = self.taken.get(*idx).copied().unwrap_or(0);
+                if is_taken(*idx) {
+                    taken == 0
+                } else {
+                    taken == self.observed
+                }
+            })
+            .collect()
+    }
+}
+
+/// The edges an entry flipped when it was added to the corpus
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlippedEdgesMetadata {
+    /// The flipped edge indexes
+    pub edges: Vec<usize>,
+}
+
+impl_serdeany!(FlippedEdgesMetadata);
+
+/// Considers a testcase interesting if it flips the polarity of a stable edge:
+/// it does not take an edge every corpus entry took so far, or it takes an edge none of them took.
+///
+/// This complements hitcount novelty, which ignores conditional branches that are already covered
+/// in one direction but rarely change their outcome. The statistics are only updated with entries
+/// that are added to the corpus, and edges only count as stable after a minimum number of entries,
+/// see [`EdgePolarityFeedback::with_min_observations`].
+#[derive(Clone, Debug)]
+pub struct EdgePolarityFeedback<C, O> {
+    map_ref: Handle<C>,
+    name: Cow<'static, str>,
+    min_observations: usize,
+    /// If the current run flipped any edge
+    flipped: bool,
+    phantom: PhantomData<O>,
+}
+
+impl<C, O> EdgePolarityFeedback<C, O>
+where
+    C: Named,
+{
+    /// Creates a new [`EdgePolarityFeedback`] for the edges of the map observer
+    #[must_use]
+    pub fn new(map_observer: &C) -> Self {
+        Self {
+            map_ref: map_observer.handle(),
+            name: Cow::from(format!("EdgePolarityFeedback_{}", map_observer.name())),
+            min_observations: DEFAULT_MIN_POLARITY_OBSERVATIONS,
+            flipped: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Only considers edges stable after `min_observations` corpus entries were recorded
+    #[must_use]
+    pub fn with_min_observations(mut self, min_observations: usize) -> Self {
+        self.min_observations = min_observations;
+        self
+    }
+}
+
+impl<C, O, S> Feedback<S> for EdgePolarityFeedback<C, O>
+where
+    C: AsRef<O> + Named,
+    O: MapObserver,
+    S: State + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_named_metadata::<EdgePolarityMetadata>(&self.name) {
+            state.add_named_metadata(&self.name, EdgePolarityMetadata::default());
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.flipped = false;
+        let meta = state.named_metadata::<EdgePolarityMetadata>(&self.name)?;
+        if meta.observed < self.min_observations {
+            return Ok(false);
+        }
+        let observer = observers
+            .get(&self.map_ref)
+            .ok_or_else(|| Error::key_not_found(format!("MapObserver {}", self.map_ref.name())))?
+            .as_ref();
+        let initial = observer.initial();
+        self.flipped = meta.flips(observer.usable_count(), |idx| observer.get(idx) != initial);
+        Ok(self.flipped)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let observer = observers
+            .get(&self.map_ref)
+            .ok_or_else(|| Error::key_not_found(format!("MapObserver {}", self.map_ref.name())))?
+            .as_ref();
+        let initial = observer.initial();
+        let len = observer.usable_count();
+        let is_taken = |idx: usize| observer.get(idx) != initial;
+        let meta = state.named_metadata_or_insert_with(&self.name, EdgePolarityMetadata::default);
+
+        // only collect the flipped edges for the entries added to the corpus
+        if core::mem::take(&mut self.flipped) {
+            testcase.add_metadata(FlippedEdgesMetadata {
+                edges: meta.flipped(len, is_taken),
+            });
+        }
+        meta.record(len, is_taken);
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.flipped = false;
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(self.flipped)
+    }
+}
+
+impl<C, O> Named for EdgePolarityFeedback<C, O> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<C, O> HasObserverHandle for EdgePolarityFeedback<C, O> {
+    type Observer = C;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<C> {
+        &self.map_ref
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use crate::{
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{
+            edge_polarity::{EdgePolarityFeedback, EdgePolarityMetadata, FlippedEdgesMetadata},
+            ConstFeedback, Feedback,
+        },
+        inputs::BytesInput,
+        observers::{MapObserver, StdMapObserver},
+        state::StdState,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_edge_polarity() {
+        let mut meta = EdgePolarityMetadata::default();
+        // edge 0 is always taken, edge 1 sometimes, edge 2 never
+        meta.record(3, |idx| idx == 0);
+        meta.record(3, |idx| idx <= 1);
+
+        assert!(meta.flipped(3, |idx| idx == 0).is_empty());
+        assert!(meta.flipped(3, |idx| idx <= 1).is_empty());
+        assert_eq!(meta.flipped(3, |idx| idx == 1), [0]);
+        assert_eq!(meta.flipped(3, |idx| idx != 1), [2]);
+        assert_eq!(meta.always_taken, [0]);
+
+        assert!(!meta.flips(3, |idx| idx == 0));
+        assert!(meta.flips(3, |idx| idx == 1));
+        assert!(meta.flips(3, |idx| idx != 1));
+    }
+
+    #[test]
+    fn test_edge_polarity_feedback() {
+        let observer = StdMapObserver::owned("map", vec![0_u8; 4]);
+        let mut feedback = EdgePolarityFeedback::<_, StdMapObserver<u8, false>>::new(&observer)
+            .with_min_observations(2);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut observers = tuple_list!(observer);
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        // two corpus entries taking edges 0 and 1, and 0 only
+        for edges in [&[0, 1][..], &[0]] {
+            observers.0.reset_map().unwrap();
+            for edge in edges {
+                observers.0.set(*edge, 1);
+            }
+            assert!(!feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap());
+            let mut testcase = Testcase::new(input.clone());
+            feedback
+                .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+                .unwrap();
+            assert!(!testcase.has_metadata::<FlippedEdgesMetadata>());
+        }
+
+        // edge 1 is not stable, skipping it does not flip anything
+        observers.0.reset_map().unwrap();
+        observers.0.set(0, 1);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+
+        // skipping the always taken edge 0 and taking the never taken edge 3 flips both
+        observers.0.reset_map().unwrap();
+        observers.0.set(3, 1);
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback
+            .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+            .unwrap();
+        assert_eq!(
+            testcase.metadata::<FlippedEdgesMetadata>().unwrap().edges,
+            [0, 3]
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! The [`ExecLogFeedback`] writes a structured record of every execution to a size-bounded binary log,
+//! for post-mortem analysis of why the fuzzer did what it did.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::marker::PhantomData;
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::Named;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{CorpusId, HasCurrentCorpusId, Testcase},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    mutators::LastMutationsMetadata,
+    observers::ObserversTuple,
+    state::{HasExecutions, State},
+    Error, HasMetadata,
+};
+
+/// One execution in the log of an [`ExecLogFeedback`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecLogRecord {
+    /// The number of executions of this client at the time
+    pub executions: u64,
+    /// The scheduled corpus entry the input was derived from, if any
+    pub corpus_id: Option<CorpusId>,
+    /// The mutations applied to the input, recorded by a [`crate::mutators::LoggerScheduledMutator`]
+    pub mutations: Vec<Cow<'static, str>>,
+    /// How the execution finished
+    pub exit_kind: ExitKind,
+    /// If the wrapped feedback found the execution interesting, i.e., it found new coverage
+    pub new_coverage: bool,
+}
+
+/// Reads back all records of an execution log written by an [`ExecLogFeedback`]
+pub fn read_exec_log<P>(path: P) -> Result<Vec<ExecLogRecord>, Error>
+where
+    P: AsRef<Path>,
+{
+    let bytes = fs::read(path)?;
+    let mut records = vec![];
+    let mut rest = bytes.as_slice();
+    while !rest.is_empty() {
+        if rest.len() < 4 {
+            return Err(Error::illegal_state("Truncated execution log"));
+        }
+        let (len, tail) = rest.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        if tail.len() < len {
+            // The last record was cut short, e.g., because the fuzzer crashed while writing it
+            break;
+        }
+        let (record, tail) = tail.split_at(len);
+        records.push(postcard::from_bytes(record)?);
+        rest = tail;
+    }
+    Ok(records)
+}
+
+/// Wraps a feedback, usually the coverage feedback, and appends an [`ExecLogRecord`] for every execution
+/// to a binary log file: the scheduled corpus id, the applied mutations, the exit kind, and whether
+/// the wrapped feedback found new coverage.
+///
+/// This is much heavier than the metadata in the corpus and meant for debugging the fuzzer itself.
+/// Each record is written right away, so the log survives a crash of the fuzzer.
+/// Once the log exceeds its maximum size, it is moved to `<path>.1` (replacing the previous one)
+/// and a new log is started, so at most twice the maximum size is used on disk.
+///
+/// The mutations are only recorded if the mutational stage uses a [`crate::mutators::LoggerScheduledMutator`].
+#[derive(Debug)]
+pub struct ExecLogFeedback<A, S> {
+    /// The wrapped feedback
+    pub first: A,
+    name: Cow<'static, str>,
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_size: u64,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> ExecLogFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// Creates a new [`ExecLogFeedback`] appending to the log at `path`, rotating it after `max_size` bytes
+    pub fn new<P>(first: A, path: P, max_size: u64) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        if max_size == 0 {
+            return Err(Error::illegal_argument(
+                "The execution log needs a maximum size above 0",
+            ));
+        }
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        let name = Cow::from(format!("ExecLogFeedback[{}]", first.name()));
+        Ok(Self {
+            first,
+            name,
+            path,
+            file,
+            written,
+            max_size,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The path of the current log
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path the previous log is moved to on rotation
+    #[must_use]
+    pub fn rotated_path(&self) -> PathBuf {
+        let mut path = OsString::from(&self.path);
+        path.push(".1");
+        PathBuf::from(path)
+    }
+
+    /// Appends a record, rotating the log first if it would grow beyond the maximum size
+    fn write_record(&mut self, record: &ExecLogRecord) -> Result<(), Error> {
+        let encoded = postcard::to_allocvec(record)?;
+        let mut buf = Vec::with_capacity(encoded.len() + 4);
+        buf.extend_from_slice(&u32::try_from(encoded.len())?.to_le_bytes());
+        buf.extend_from_slice(&encoded);
+
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_size {
+            fs::rename(&self.path, self.rotated_path())?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)?;
+            self.written = 0;
+        }
+        self.file.write_all(&buf)?;
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+}
+
+impl<A, S> Feedback<S> for ExecLogFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State + HasExecutions + HasMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        // asks the LoggerScheduledMutator to record the mutations of each input
+        if !state.has_metadata::<LastMutationsMetadata>() {
+            state.add_metadata(LastMutationsMetadata::default());
+        }
+        self.first.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let res = self
+            .first
+            .is_interesting(state, manager, input, observers, exit_kind)?;
+        let mutations = state
+            .metadata_mut::<LastMutationsMetadata>()
+            .map(|meta| core::mem::take(&mut meta.list))
+            .unwrap_or_default();
+        self.write_record(&ExecLogRecord {
+            executions: *state.executions(),
+            corpus_id: state.current_corpus_id()?,
+            mutations,
+            exit_kind: *exit_kind,
+            new_coverage: res,
+        })?;
+        Ok(res)
+    }
+
+    #[inline]
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        self.first
+            .append_metadata(state, manager, observers, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.first.discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.first.last_result()
+    }
+}
+
+impl<A, S> Named for ExecLogFeedback<A, S> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use std::{env, fs};
+
+    use crate::{
+        corpus::CorpusId,
+        executors::ExitKind,
+        feedbacks::{
+            exec_log::{read_exec_log, ExecLogFeedback, ExecLogRecord},
+            ConstFeedback,
+        },
+        inputs::BytesInput,
+        state::NopState,
+    };
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_exec_log_rotation() {
+        let path = env::temp_dir().join(format!("libafl_exec_log_test_{}", std::process::id()));
+        let mut feedback =
+            ExecLogFeedback::<_, NopState<BytesInput>>::new(ConstFeedback::new(true), &path, 64)
+                .unwrap();
+        let record = ExecLogRecord {
+            executions: 1,
+            corpus_id: Some(CorpusId(3)),
+            mutations: vec![Cow::Borrowed("BitFlipMutator")],
+            exit_kind: ExitKind::Ok,
+            new_coverage: true,
+        };
+        for _ in 0..4 {
+            feedback.write_record(&record).unwrap();
+        }
+
+        let current = read_exec_log(feedback.path()).unwrap();
+        let rotated = read_exec_log(feedback.rotated_path()).unwrap();
+        assert!(!current.is_empty() && !rotated.is_empty());
+        assert_eq!(current.len() + rotated.len(), 4);
+        assert!(current.iter().chain(&rotated).all(|r| *r == record));
+        assert!(fs::metadata(feedback.path()).unwrap().len() <= 64);
+
+        fs::remove_file(feedback.rotated_path()).unwrap();
+        fs::remove_file(feedback.path()).unwrap();
+    }
+}
@@ -0,0 +1,281 @@
+//! The [`ExitCodeFeedback`] considers inputs interesting for which the target exits with one of
+//! the configured exit codes, for harnesses that flag inputs as interesting, but not crashing.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::ops::RangeInclusive;
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+use hashbrown::HashSet;
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    observers::{ExitCodeObserver, ObserversTuple},
+    state::State,
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The exit code that made the [`ExitCodeFeedback`] keep a testcase
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitCodeMetadata {
+    /// The exit code of the target
+    pub exit_code: i32,
+}
+
+impl_serdeany!(ExitCodeMetadata);
+
+/// The exit codes an [`ExitCodeFeedback`] already kept a testcase for
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct ExitCodeFeedbackMetadata {
+    /// The exit codes seen so far
+    pub seen: HashSet<i32>,
+}
+
+impl_serdeany!(ExitCodeFeedbackMetadata);
+
+/// Parses exit code ranges, one per line, either a single code such as `42`, or an inclusive range
+/// such as `40-49`. Empty lines and everything after a `#` are ignored.
+pub fn parse_exit_code_ranges(config: &str) -> Result<Vec<RangeInclusive<i32>>, Error> {
+    let parse_code = |code: &str| {
+        code.trim()
+            .parse::<i32>()
+            .map_err(|_| Error::illegal_argument(format!("Invalid exit code {code:?}")))
+    };
+    let mut ranges = vec![];
+    for line in config.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let range = match line.split_once('-') {
+            Some((start, end)) => parse_code(start)?..=parse_code(end)?,
+            None => {
+                let code = parse_code(line)?;
+                code..=code
+            }
+        };
+        if range.is_empty() {
+            return Err(Error::illegal_argument(format!(
+                "Empty exit code range {line:?}"
+            )));
+        }
+        ranges.push(range);
+    }
+    Ok(ranges)
+}
+
+/// Considers an input interesting if the target exited with an exit code in one of the configured
+/// ranges, as recorded by an [`ExitCodeObserver`], regardless of coverage.
+/// Only the first input per exit code is interesting; the exit codes seen so far are kept in the
+/// named [`ExitCodeFeedbackMetadata`] of the state.
+/// The exit code is added to the testcase as [`ExitCodeMetadata`].
+///
+/// Use it in the feedback, combined with the coverage feedback through [`crate::feedback_or`].
+/// Which exit codes count as crashes is decided by the executor, for example through
+/// [`crate::executors::forkserver::ForkserverExecutorBuilder::crash_exitcode`], so the ranges here
+/// should not overlap with those.
+#[derive(Clone, Debug)]
+pub struct ExitCodeFeedback {
+    o_ref: Handle<ExitCodeObserver>,
+    name: Cow<'static, str>,
+    ranges: Vec<RangeInclusive<i32>>,
+    /// The matching exit code of the current run
+    matched: Option<i32>,
+}
+
+impl ExitCodeFeedback {
+    /// Creates a new [`ExitCodeFeedback`], keeping inputs the target exits with a code in `ranges` for
+    #[must_use]
+    pub fn new(observer: &ExitCodeObserver, ranges: Vec<RangeInclusive<i32>>) -> Self {
+        Self {
+            o_ref: observer.handle(),
+            name: Cow::from(format!("ExitCodeFeedback_{}", observer.name())),
+            ranges,
+            matched: None,
+        }
+    }
+
+    /// Creates a new [`ExitCodeFeedback`] with the ranges from a config file,
+    /// see [`parse_exit_code_ranges`] for the format
+    #[cfg(feature = "std")]
+    pub fn from_config_file<P>(observer: &ExitCodeObserver, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let config = fs::read_to_string(path)?;
+        Ok(Self::new(observer, parse_exit_code_ranges(&config)?))
+    }
+
+    /// The configured exit code ranges
+    #[must_use]
+    pub fn ranges(&self) -> &[RangeInclusive<i32>] {
+        &self.ranges
+    }
+
+    /// If `exit_code` is in one of the configured ranges
+    #[must_use]
+    pub fn matches(&self, exit_code: i32) -> bool {
+        self.ranges.iter().any(|range| range.contains(&exit_code))
+    }
+}
+
+impl<S> Feedback<S> for ExitCodeFeedback
+where
+    S: State + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.named_metadata_or_insert_with(&self.name, ExitCodeFeedbackMetadata::default);
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observers.get(&self.o_ref).ok_or_else(|| {
+            Error::key_not_found(format!("ExitCodeObserver {}", self.o_ref.name()))
+        })?;
+        let seen = &state
+            .named_metadata::<ExitCodeFeedbackMetadata>(&self.name)?
+            .seen;
+        self.matched = observer
+            .exit_code()
+            .filter(|exit_code| self.matches(*exit_code) && !seen.contains(exit_code));
+        Ok(self.matched.is_some())
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some(exit_code) = self.matched.take() {
+            state
+                .named_metadata_mut::<ExitCodeFeedbackMetadata>(&self.name)?
+                .seen
+                .insert(exit_code);
+            testcase.add_metadata(ExitCodeMetadata { exit_code });
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.matched = None;
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(self.matched.is_some())
+    }
+}
+
+impl Named for ExitCodeFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, Named};
+
+    use crate::{
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{
+            exit_code::{parse_exit_code_ranges, ExitCodeFeedbackMetadata, ExitCodeMetadata},
+            ConstFeedback, ExitCodeFeedback, Feedback,
+        },
+        inputs::BytesInput,
+        observers::ExitCodeObserver,
+        state::StdState,
+        HasMetadata, HasNamedMetadata,
+    };
+
+    #[test]
+    fn test_exit_code_feedback() {
+        let observer = ExitCodeObserver::new("exit_code");
+        let mut feedback = ExitCodeFeedback::new(&observer, vec![40..=49]);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut observers = tuple_list!(observer);
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        // out of range, new, already seen, new again
+        let mut kept = vec![];
+        for exit_code in [7, 42, 42, 43] {
+            observers.0.set_exit_code(exit_code);
+            if feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap()
+            {
+                let mut testcase = Testcase::new(input.clone());
+                feedback
+                    .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+                    .unwrap();
+                kept.push(testcase.metadata::<ExitCodeMetadata>().unwrap().exit_code);
+            }
+        }
+        assert_eq!(kept, [42, 43]);
+
+        // Initializing the state again, as on a restart, keeps the exit codes seen so far
+        feedback.init_state(&mut state).unwrap();
+        let seen = &state
+            .named_metadata::<ExitCodeFeedbackMetadata>(feedback.name())
+            .unwrap()
+            .seen;
+        assert_eq!(seen.len(), 2);
+        observers.0.set_exit_code(42);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_parse_exit_code_ranges() {
+        let ranges = parse_exit_code_ranges("42\n# reserved\n\n40-49 # parser errors\n").unwrap();
+        assert_eq!(ranges, [42..=42, 40..=49]);
+        assert!(parse_exit_code_ranges("forty-two").is_err());
+        assert!(parse_exit_code_ranges("49-40").is_err());
+    }
+}
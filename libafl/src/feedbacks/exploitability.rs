@@ -0,0 +1,227 @@
+//! The [`ExploitabilityFeedback`] stamps crashes with a rough, conservative guess of their exploitability,
+//! to help prioritizing the triage of solutions.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{
+    impl_serdeany,
+    os::unix_signals::Signal,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    observers::{CrashContext, CrashContextObserver, ObserversTuple, CRASH_ACCESS_WRITE},
+    state::State,
+    Error, HasMetadata,
+};
+
+/// Faults below this address are considered null pointer dereferences
+pub const NEAR_NULL_LIMIT: u64 = 0x10000;
+
+/// A guess of how exploitable a crash is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Exploitability {
+    /// The crash very likely gives control over the execution
+    Exploitable,
+    /// The crash corrupts memory or control flow in a way that is often exploitable
+    ProbablyExploitable,
+    /// The crash is usually a benign denial of service, such as a null pointer dereference
+    ProbablyNotExploitable,
+    /// Not enough information for a guess
+    Unknown,
+}
+
+/// Metadata added to crashes by the [`ExploitabilityFeedback`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExploitabilityMetadata {
+    /// The guessed exploitability
+    pub exploitability: Exploitability,
+    /// The reason for the guess
+    pub reason: Cow<'static, str>,
+    /// The crash context the guess is based on
+    pub context: CrashContext,
+}
+
+impl_serdeany!(ExploitabilityMetadata);
+
+/// Guesses the exploitability of a crash from its context, with deliberately conservative heuristics:
+///
+/// - a fault at the program counter means the target jumped to an attacker-influenced address: exploitable
+/// - an illegal instruction often stems from a corrupted program counter: probably exploitable
+/// - a write fault outside the first [`NEAR_NULL_LIMIT`] bytes is a wild write: probably exploitable
+/// - a fault within the first [`NEAR_NULL_LIMIT`] bytes is a near-null dereference: probably not exploitable
+/// - an arithmetic error is probably not exploitable
+/// - anything else, including read faults and aborts (e.g., sanitizer reports), is unknown
+#[must_use]
+pub fn classify_crash(context: &CrashContext) -> (Exploitability, &'static str) {
+    match Signal::try_from(context.signal) {
+        Ok(Signal::SigSegmentationFault | Signal::SigBus) => {
+            if context.pc != 0 && context.fault_addr == context.pc {
+                (Exploitability::Exploitable, "fault at the program counter")
+            } else if context.fault_addr < NEAR_NULL_LIMIT {
+                (
+                    Exploitability::ProbablyNotExploitable,
+                    "near-null dereference",
+                )
+            } else if context.access == CRASH_ACCESS_WRITE {
+                (Exploitability::ProbablyExploitable, "wild write")
+            } else {
+                (Exploitability::Unknown, "wild read or unknown access")
+            }
+        }
+        Ok(Signal::SigIllegalInstruction) => {
+            (Exploitability::ProbablyExploitable, "illegal instruction")
+        }
+        Ok(Signal::SigFloatingPointException) => {
+            (Exploitability::ProbablyNotExploitable, "arithmetic error")
+        }
+        Ok(Signal::SigAbort) => (Exploitability::Unknown, "abort"),
+        _ => (Exploitability::Unknown, "unclassified signal"),
+    }
+}
+
+/// Nop feedback that adds an [`ExploitabilityMetadata`] to crashes, if the [`CrashContextObserver`]
+/// got a crash context from the target. The testcase is never interesting (use with an OR
+/// next to the crash feedback in the objective).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExploitabilityFeedback {
+    o_ref: Handle<CrashContextObserver>,
+}
+
+impl<S> Feedback<S> for ExploitabilityFeedback
+where
+    S: State,
+{
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("CrashContextObserver is missing"))?;
+        if let Some(context) = observer.last_context() {
+            let (exploitability, reason) = classify_crash(context);
+            testcase.add_metadata(ExploitabilityMetadata {
+                exploitability,
+                reason: Cow::Borrowed(reason),
+                context: *context,
+            });
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+impl Named for ExploitabilityFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.o_ref.name()
+    }
+}
+
+impl ExploitabilityFeedback {
+    /// Creates a new [`ExploitabilityFeedback`] for the given [`CrashContextObserver`]
+    #[must_use]
+    pub fn new(observer: &CrashContextObserver) -> Self {
+        Self {
+            o_ref: observer.handle(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::os::unix_signals::Signal;
+
+    use crate::{
+        feedbacks::exploitability::{classify_crash, Exploitability},
+        observers::{CrashContext, CRASH_ACCESS_READ, CRASH_ACCESS_WRITE},
+    };
+
+    #[test]
+    fn test_classify_crash() {
+        let segv = CrashContext {
+            valid: 1,
+            signal: Signal::SigSegmentationFault.into(),
+            fault_addr: 0x4141_4141,
+            pc: 0x4141_4141,
+            ..CrashContext::default()
+        };
+        assert_eq!(classify_crash(&segv).0, Exploitability::Exploitable);
+
+        let null_write = CrashContext {
+            fault_addr: 0x8,
+            pc: 0x5555_0000,
+            access: CRASH_ACCESS_WRITE,
+            ..segv
+        };
+        assert_eq!(
+            classify_crash(&null_write).0,
+            Exploitability::ProbablyNotExploitable
+        );
+
+        let wild_write = CrashContext {
+            fault_addr: 0x7fff_0000,
+            ..null_write
+        };
+        assert_eq!(
+            classify_crash(&wild_write).0,
+            Exploitability::ProbablyExploitable
+        );
+
+        let wild_read = CrashContext {
+            access: CRASH_ACCESS_READ,
+            ..wild_write
+        };
+        assert_eq!(classify_crash(&wild_read).0, Exploitability::Unknown);
+
+        let abort = CrashContext {
+            signal: Signal::SigAbort.into(),
+            ..segv
+        };
+        assert_eq!(classify_crash(&abort).0, Exploitability::Unknown);
+    }
+}
@@ -0,0 +1,392 @@
+//! The [`InputDedupFeedback`] skips inputs whose content is already in the corpus,
+//! using a bloom filter of content hashes with bounded memory.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::{marker::PhantomData, time::Duration};
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "std")]
+use libafl_bolts::fs::write_file_atomic;
+use libafl_bolts::{current_time, hash_std, impl_serdeany, AsSlice, Named};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::feedbacks::PersistentFeedback;
+use crate::{
+    corpus::Testcase,
+    events::{Event, EventFirer},
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::HasTargetBytes,
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    observers::ObserversTuple,
+    state::State,
+    Error, HasMetadata,
+};
+
+/// The bits of the bloom filter per expected corpus entry
+const SEEN_INPUTS_BITS_PER_ENTRY: usize = 16;
+/// The number of bits set per input, optimal for [`SEEN_INPUTS_BITS_PER_ENTRY`]
+const SEEN_INPUTS_NUM_HASHES: u64 = 11;
+
+/// The default number of corpus entries the [`InputDedupFeedback`] is sized for
+pub const DEFAULT_EXPECTED_CORPUS_COUNT: usize = 1 << 16;
+
+/// The minimum interval between two reports of the inputs skipped as duplicates
+pub const DEDUP_SKIPPED_REPORT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A bloom filter of the content hashes of the inputs added to the corpus,
+/// and the number of inputs the [`InputDedupFeedback`] skipped as duplicates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct SeenInputsMetadata {
+    bits: Vec<u64>,
+    /// The number of inputs skipped as duplicates
+    pub skipped: u64,
+}
+
+impl_serdeany!(SeenInputsMetadata);
+
+impl SeenInputsMetadata {
+    /// Creates a new, empty [`SeenInputsMetadata`], sized for `expected_count` inputs
+    #[must_use]
+    pub fn with_expected_count(expected_count: usize) -> Self {
+        let bits = expected_count.max(1) * SEEN_INPUTS_BITS_PER_ENTRY;
+        Self {
+            bits: vec![0; bits.div_ceil(64)],
+            skipped: 0,
+        }
+    }
+
+    /// The bit positions of `hash`, derived by double hashing
+    #[allow(clippy::cast_possible_truncation)] // the positions are below the number of bits
+    fn positions(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let num_bits = self.bits.len() as u64 * 64;
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        (0..SEEN_INPUTS_NUM_HASHES)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+
+    /// If an input with this content hash may have been inserted already.
+    /// False positives are possible, false negatives are not.
+    #[must_use]
+    pub fn contains(&self, hash: u64) -> bool {
+        self.positions(hash)
+            .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+
+    /// Inserts the content hash of an input
+    pub fn insert(&mut self, hash: u64) {
+        for pos in self.positions(hash) {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+    }
+
+    /// Inserts all inputs of `other`, which has to be sized for the same number of inputs,
+    /// and keeps the higher skipped count
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        if self.bits.len() != other.bits.len() {
+            return Err(Error::illegal_argument(format!(
+                "Cannot merge a filter of {} bits into one of {} bits",
+                other.bits.len() * 64,
+                self.bits.len() * 64
+            )));
+        }
+        for (bits, other_bits) in self.bits.iter_mut().zip(&other.bits) {
+            *bits |= other_bits;
+        }
+        self.skipped = self.skipped.max(other.skipped);
+        Ok(())
+    }
+}
+
+/// A feedback that is not interesting for inputs whose content is already in the corpus,
+/// so that duplicates produced by the mutators are not stored again.
+/// The content hashes of all inputs added to the corpus are kept in a bloom filter in the
+/// [`SeenInputsMetadata`], bounding the memory by the expected number of corpus entries.
+/// The number of skipped inputs is reported as the `dedup_skipped` user stat,
+/// at most once per [`DEDUP_SKIPPED_REPORT_INTERVAL`].
+///
+/// Combine it with the coverage feedback through [`crate::feedback_and_fast`], after the
+/// coverage feedback, so that only inputs that are interesting otherwise get hashed.
+/// Like any bloom filter, it has false positives: about one in 2000 new inputs is skipped
+/// while the corpus holds at most the expected number of entries, more beyond.
+#[derive(Debug, Clone)]
+pub struct InputDedupFeedback<S> {
+    name: Cow<'static, str>,
+    expected_count: usize,
+    /// The content hash of the current input, if it is new
+    hash: Option<u64>,
+    /// When we last reported the inputs skipped as duplicates
+    last_report: Option<Duration>,
+    phantom: PhantomData<S>,
+}
+
+impl<S> InputDedupFeedback<S> {
+    /// Creates a new [`InputDedupFeedback`], sized for [`DEFAULT_EXPECTED_CORPUS_COUNT`] entries
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_expected_count(DEFAULT_EXPECTED_CORPUS_COUNT)
+    }
+
+    /// Creates a new [`InputDedupFeedback`], sized for `expected_count` corpus entries
+    #[must_use]
+    pub fn with_expected_count(expected_count: usize) -> Self {
+        Self {
+            name: Cow::Borrowed("InputDedupFeedback"),
+            expected_count,
+            hash: None,
+            last_report: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> Default for InputDedupFeedback<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Named for InputDedupFeedback<S> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<S> Feedback<S> for InputDedupFeedback<S>
+where
+    S: State + HasMetadata,
+    S::Input: HasTargetBytes,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        let expected_count = self.expected_count;
+        state.metadata_or_insert_with(|| SeenInputsMetadata::with_expected_count(expected_count));
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let hash = hash_std(input.target_bytes().as_slice());
+        let meta = state.metadata_mut::<SeenInputsMetadata>()?;
+        if !meta.contains(hash) {
+            self.hash = Some(hash);
+            return Ok(true);
+        }
+
+        self.hash = None;
+        meta.skipped += 1;
+        let skipped = meta.skipped;
+        let now = current_time();
+        if self.last_report.map_or(true, |last| {
+            now.saturating_sub(last) >= DEDUP_SKIPPED_REPORT_INTERVAL
+        }) {
+            self.last_report = Some(now);
+            manager.fire(
+                state,
+                Event::UpdateUserStats {
+                    name: Cow::Borrowed("dedup_skipped"),
+                    value: UserStats::new(UserStatsValue::Number(skipped), AggregatorOps::Sum),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        Ok(false)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        _testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some(hash) = self.hash.take() {
+            state.metadata_mut::<SeenInputsMetadata>()?.insert(hash);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.hash = None;
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(self.hash.is_some())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> PersistentFeedback<S> for InputDedupFeedback<S>
+where
+    S: HasMetadata,
+{
+    fn persist(&self, state: &S, path: &Path) -> Result<(), Error> {
+        let meta = state.metadata::<SeenInputsMetadata>()?;
+        write_file_atomic(path, &postcard::to_allocvec(meta)?)
+    }
+
+    fn restore(&mut self, state: &mut S, path: &Path) -> Result<(), Error> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let seen: SeenInputsMetadata = postcard::from_bytes(&fs::read(path)?)?;
+        let expected_count = self.expected_count;
+        state
+            .metadata_or_insert_with(|| SeenInputsMetadata::with_expected_count(expected_count))
+            .merge(&seen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{InMemoryCorpus, Testcase},
+        events::{Event, EventFirer},
+        executors::ExitKind,
+        feedbacks::{
+            input_dedup::{InputDedupFeedback, SeenInputsMetadata},
+            ConstFeedback, Feedback,
+        },
+        inputs::BytesInput,
+        state::{test::test_std_state, StdState, UsesState},
+        Error, HasMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Counts the user stats events fired
+    #[derive(Default)]
+    struct UserStatsCounter {
+        fired: usize,
+    }
+
+    impl UsesState for UserStatsCounter {
+        type State = TestState;
+    }
+
+    impl EventFirer for UserStatsCounter {
+        fn should_send(&self) -> bool {
+            true
+        }
+
+        fn fire(&mut self, _state: &mut TestState, event: Event<BytesInput>) -> Result<(), Error> {
+            if let Event::UpdateUserStats { .. } = event {
+                self.fired += 1;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_input_dedup() {
+        let mut feedback = InputDedupFeedback::<TestState>::with_expected_count(16);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = UserStatsCounter::default();
+        let input = BytesInput::new(vec![1, 2, 3]);
+
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback
+            .append_metadata(&mut state, &mut mgr, &(), &mut testcase)
+            .unwrap();
+
+        // every duplicate is counted, but only the first one is reported right away
+        for _ in 0..3 {
+            assert!(!feedback
+                .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+                .unwrap());
+        }
+        assert_eq!(state.metadata::<SeenInputsMetadata>().unwrap().skipped, 3);
+        assert_eq!(mgr.fired, 1);
+    }
+
+    #[test]
+    fn test_seen_inputs() {
+        let mut seen = SeenInputsMetadata::with_expected_count(100);
+        for hash in 0..100_u64 {
+            seen.insert(hash.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        }
+        assert!((0..100_u64).all(|hash| seen.contains(hash.wrapping_mul(0x9e37_79b9_7f4a_7c15))));
+        let false_positives = (100..10_100_u64)
+            .filter(|hash| seen.contains(hash.wrapping_mul(0x9e37_79b9_7f4a_7c15)))
+            .count();
+        assert!(false_positives < 50, "{false_positives} false positives");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore)]
+    fn test_input_dedup_persist() {
+        use std::{env, fs};
+
+        use crate::feedbacks::PersistentFeedback;
+
+        let path = env::temp_dir().join(format!("libafl_input_dedup_test_{}", std::process::id()));
+        let mut feedback = InputDedupFeedback::<TestState>::with_expected_count(16);
+        let mut mgr = UserStatsCounter::default();
+        let input = BytesInput::new(vec![1, 2, 3]);
+
+        let mut state = test_std_state::<BytesInput>();
+        feedback.init_state(&mut state).unwrap();
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback
+            .append_metadata(&mut state, &mut mgr, &(), &mut testcase)
+            .unwrap();
+        feedback.persist(&state, &path).unwrap();
+
+        // After a restart, the input is still known as a duplicate
+        let mut restarted = test_std_state::<BytesInput>();
+        feedback.restore(&mut restarted, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(!feedback
+            .is_interesting(&mut restarted, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+
+        // A filter sized differently cannot be merged
+        let mut resized = InputDedupFeedback::<TestState>::with_expected_count(1024);
+        feedback.persist(&state, &path).unwrap();
+        let res = resized.restore(&mut test_std_state::<BytesInput>(), &path);
+        fs::remove_file(&path).unwrap();
+        assert!(res.is_err());
+    }
+}
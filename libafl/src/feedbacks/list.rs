@@ -1,13 +1,19 @@
 use alloc::borrow::Cow;
 use core::{fmt::Debug, hash::Hash};
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
 
 use hashbrown::HashSet;
+#[cfg(feature = "std")]
+use libafl_bolts::fs::write_file_atomic;
 use libafl_bolts::{
     tuples::{Handle, Handled, MatchNameRef},
     Error, HasRefCnt, Named,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+#[cfg(feature = "std")]
+use crate::feedbacks::PersistentFeedback;
 use crate::{
     events::EventFirer,
     executors::ExitKind,
@@ -149,6 +155,30 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<S, T> PersistentFeedback<S> for ListFeedback<T>
+where
+    S: HasNamedMetadata,
+    T: Debug + Serialize + Hash + Eq + DeserializeOwned + Default + Copy + 'static,
+{
+    fn persist(&self, state: &S, path: &Path) -> Result<(), Error> {
+        let meta = state.named_metadata::<ListFeedbackMetadata<T>>(self.name())?;
+        write_file_atomic(path, &postcard::to_allocvec(&meta.set)?)
+    }
+
+    fn restore(&mut self, state: &mut S, path: &Path) -> Result<(), Error> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let set: HashSet<T> = postcard::from_bytes(&fs::read(path)?)?;
+        state
+            .named_metadata_or_insert_with(self.name(), ListFeedbackMetadata::<T>::default)
+            .set
+            .extend(set);
+        Ok(())
+    }
+}
+
 impl<T> Named for ListFeedback<T>
 where
     T: Debug + Serialize + Hash + Eq + DeserializeOwned,
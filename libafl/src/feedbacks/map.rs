@@ -1,10 +1,10 @@
 //! Map feedback, maximizing or minimizing maps, for example the afl-style map observer.
 
-use alloc::{borrow::Cow, vec::Vec};
+use alloc::{borrow::Cow, sync::Arc, vec::Vec};
 #[rustversion::nightly]
 use core::simd::prelude::SimdOrd;
 use core::{
-    fmt::Debug,
+    fmt::{self, Debug, Formatter},
     marker::PhantomData,
     ops::{BitAnd, BitOr, Deref, DerefMut},
 };
@@ -21,17 +21,21 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 #[cfg(feature = "track_hit_feedbacks")]
 use crate::feedbacks::premature_last_result_err;
 use crate::{
-    corpus::Testcase,
-    events::{Event, EventFirer},
+    corpus::{Corpus, CorpusId, Testcase},
+    events::{Event, EventFirer, LogSeverity},
     executors::ExitKind,
     feedbacks::{Feedback, HasObserverHandle},
     inputs::UsesInput,
     monitors::{AggregatorOps, UserStats, UserStatsValue},
     observers::{CanTrack, MapObserver, Observer, ObserversTuple},
-    state::State,
+    state::{HasCorpus, State},
     Error, HasMetadata, HasNamedMetadata,
 };
 
+/// A reasonable fill ratio of the history map above which a [`MapFeedback`] warns about map saturation,
+/// see [`MapFeedback::with_saturation_threshold`]
+pub const DEFAULT_MAP_SATURATION_THRESHOLD: f64 = 0.6;
+
 /// A [`MapFeedback`] that implements the AFL algorithm using an [`OrReducer`] combining the bits for the history map and the bit from (`HitcountsMapObserver`)[`crate::observers::HitcountsMapObserver`].
 pub type AflMapFeedback<C, O, T> = MapFeedback<C, DifferentIsNovel, O, OrReducer, T>;
 
@@ -49,6 +53,10 @@ pub type MaxMapPow2Feedback<C, O, T> = MapFeedback<C, NextPow2IsNovel, O, MaxRed
 /// A [`MapFeedback`] that strives to maximize the map contents,
 /// but only, if a value is larger than `pow2` of the previous.
 pub type MaxMapOneOrFilledFeedback<C, O, T> = MapFeedback<C, OneOrFilledIsNovel, O, MaxReducer, T>;
+/// A [`MapFeedback`] that only considers newly hit entries as novel, ignoring how often they were hit.
+/// Use it with a plain map observer, instead of a [`crate::observers::HitcountsMapObserver`],
+/// to skip the hitcount classification when only the edge presence matters.
+pub type MaxMapPresenceFeedback<C, O, T> = MapFeedback<C, PresenceIsNovel, O, MaxReducer, T>;
 
 /// A `Reducer` function is used to aggregate values for the novelty search
 pub trait Reducer<T>: 'static
@@ -224,6 +232,20 @@ where
     }
 }
 
+/// Only consider entries that were never hit before as novel
+#[derive(Clone, Debug)]
+pub struct PresenceIsNovel {}
+
+impl<T> IsNovel<T> for PresenceIsNovel
+where
+    T: PrimInt + Default + Copy + 'static,
+{
+    #[inline]
+    fn is_novel(old: T, new: T) -> bool {
+        old == T::zero() && new != T::zero()
+    }
+}
+
 /// A testcase metadata holding a list of indexes of a map
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(
@@ -380,6 +402,178 @@ where
         self.num_covered_map_indexes = 0;
         Ok(())
     }
+
+    /// Drop the whole history, the map is regrown on demand using the observer's initial value
+    pub fn clear(&mut self) {
+        self.history_map.clear();
+        self.num_covered_map_indexes = 0;
+    }
+
+    /// The ratio of covered entries in the history map, between `0.0` and `1.0`
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fill_ratio(&self) -> f64 {
+        if self.history_map.is_empty() {
+            0.0
+        } else {
+            self.num_covered_map_indexes as f64 / self.history_map.len() as f64
+        }
+    }
+}
+
+/// If a [`MapFeedback`] already warned about the saturation of its map,
+/// see [`MapFeedback::with_saturation_threshold`].
+/// Kept as named metadata, so that the warning is not repeated after a restart.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct MapSaturationMetadata {
+    /// If the warning was emitted
+    pub reported: bool,
+}
+
+libafl_bolts::impl_serdeany!(MapSaturationMetadata);
+
+/// Passed to the callback of a [`CoverageCallbackFeedback`] whenever the cumulative coverage
+/// of its [`MapFeedback`] increased
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageIncrease {
+    /// The number of covered map entries, after the increase
+    pub covered: usize,
+    /// The size of the history map
+    pub map_size: usize,
+    /// The id of the [`Testcase`] responsible for the increase, in the corpus it is about to be added to.
+    /// `None` for objectives, which go to the solutions instead.
+    pub corpus_id: Option<CorpusId>,
+}
+
+/// A user callback invoked whenever the cumulative coverage of a [`MapFeedback`] increased,
+/// e.g., to feed live coverage-growth plots of an external dashboard, see [`CoverageCallbackFeedback`].
+#[derive(Clone)]
+pub struct CoverageCallback(Arc<dyn Fn(&CoverageIncrease) + Send + Sync>);
+
+impl Debug for CoverageCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CoverageCallback").finish_non_exhaustive()
+    }
+}
+
+/// Wraps a [`MapFeedback`], invoking a [`CoverageCallback`] whenever the cumulative coverage
+/// of the map feedback increased, with the id the responsible testcase gets in the corpus.
+///
+/// Use [`CoverageCallbackFeedback::for_objective`] when wrapping a map feedback of the objective,
+/// whose inputs go to the solutions, so that the callback gets no corpus id.
+#[derive(Clone, Debug)]
+pub struct CoverageCallbackFeedback<F> {
+    feedback: F,
+    callback: CoverageCallback,
+    objective: bool,
+}
+
+impl<F> CoverageCallbackFeedback<F> {
+    /// Invokes `callback` whenever the cumulative coverage of `feedback` increased
+    pub fn new<CB>(feedback: F, callback: CB) -> Self
+    where
+        CB: Fn(&CoverageIncrease) + Send + Sync + 'static,
+    {
+        Self {
+            feedback,
+            callback: CoverageCallback(Arc::new(callback)),
+            objective: false,
+        }
+    }
+
+    /// Marks the wrapped feedback as part of the objective, see [`CoverageIncrease::corpus_id`]
+    #[must_use]
+    pub fn for_objective(mut self) -> Self {
+        self.objective = true;
+        self
+    }
+
+    /// The wrapped feedback
+    pub fn feedback(&self) -> &F {
+        &self.feedback
+    }
+}
+
+impl<F> Named for CoverageCallbackFeedback<F>
+where
+    F: Named,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.feedback.name()
+    }
+}
+
+impl<C, N, O, R, S, T> Feedback<S> for CoverageCallbackFeedback<MapFeedback<C, N, O, R, T>>
+where
+    MapFeedback<C, N, O, R, T>: Feedback<S>,
+    S: State + HasNamedMetadata + HasCorpus,
+    T: Default + Copy + Serialize + DeserializeOwned + Debug + 'static,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        self.feedback.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.feedback
+            .is_interesting(state, manager, input, observers, exit_kind)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.feedback.last_result()
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        // The map feedback only updates its cumulative coverage once the input gets added
+        let covered_before = state
+            .named_metadata::<MapFeedbackMetadata<T>>(self.feedback.name())
+            .map_or(0, |meta| meta.num_covered_map_indexes);
+        let corpus_id = (!self.objective).then(|| state.corpus().peek_free_id());
+
+        self.feedback
+            .append_metadata(state, manager, observers, testcase)?;
+
+        let meta = state.named_metadata::<MapFeedbackMetadata<T>>(self.feedback.name())?;
+        if meta.num_covered_map_indexes > covered_before {
+            (self.callback.0)(&CoverageIncrease {
+                covered: meta.num_covered_map_indexes,
+                map_size: meta.history_map.len(),
+                corpus_id,
+            });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.feedback.discard_metadata(state, input)
+    }
 }
 
 /// The most common AFL-like feedback type
@@ -393,6 +587,10 @@ pub struct MapFeedback<C, N, O, R, T> {
     map_ref: Handle<C>,
     /// Name of the feedback as shown in the `UserStats`
     stats_name: Cow<'static, str>,
+    /// The number of newly covered entries an input needs to be interesting
+    min_new_edges: usize,
+    /// The fill ratio of the history map above which to warn about map saturation, if any
+    saturation_threshold: Option<f64>,
     // The previous run's result of [`Self::is_interesting`]
     #[cfg(feature = "track_hit_feedbacks")]
     last_result: Option<bool>,
@@ -413,6 +611,11 @@ where
         // Initialize `MapFeedbackMetadata` with an empty vector and add it to the state.
         // The `MapFeedbackMetadata` would be resized on-demand in `is_interesting`
         state.add_named_metadata(&self.name, MapFeedbackMetadata::<T>::default());
+        if self.saturation_threshold.is_some()
+            && !state.has_named_metadata::<MapSaturationMetadata>(&self.name)
+        {
+            state.add_named_metadata(&self.name, MapSaturationMetadata::default());
+        }
         Ok(())
     }
 
@@ -547,6 +750,25 @@ where
             },
         )?;
 
+        if let Some(threshold) = self.saturation_threshold {
+            let fill_ratio = state
+                .named_metadata::<MapFeedbackMetadata<T>>(&self.name)?
+                .fill_ratio();
+            let saturation =
+                state.named_metadata_or_insert_with(&self.name, MapSaturationMetadata::default);
+            if !saturation.reported && fill_ratio > threshold {
+                saturation.reported = true;
+                let message = format!(
+                    "The map of {} is {:.1}% filled ({covered} of {len} entries); with this many collisions, \
+                    new coverage is hard to tell apart. Consider a larger map_size.",
+                    self.name,
+                    fill_ratio * 100.0
+                );
+                log::warn!("{message}");
+                manager.log(state, LogSeverity::Warn, message)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -669,6 +891,11 @@ where
                 }
             }
         }
+
+        if interesting && self.min_new_edges > 1 {
+            interesting = covers_min_new_entries(observer, map_state, self.min_new_edges);
+        }
+
         #[cfg(feature = "track_hit_feedbacks")]
         {
             self.last_result = Some(interesting);
@@ -723,6 +950,8 @@ where
             name: map_observer.name().clone(),
             map_ref: map_observer.handle(),
             stats_name: create_stats_name(map_observer.name()),
+            min_new_edges: 1,
+            saturation_threshold: None,
             #[cfg(feature = "track_hit_feedbacks")]
             last_result: None,
             phantom: PhantomData,
@@ -739,6 +968,8 @@ where
             novelties: if C::NOVELTIES { Some(vec![]) } else { None },
             map_ref: map_observer.handle(),
             stats_name: create_stats_name(&name),
+            min_new_edges: 1,
+            saturation_threshold: None,
             name,
             #[cfg(feature = "track_hit_feedbacks")]
             last_result: None,
@@ -746,6 +977,53 @@ where
         }
     }
 
+    /// Only considers inputs interesting that cover at least `min_new_edges` map entries never covered before.
+    /// The very first input covering anything is always interesting, so the corpus never starts out empty.
+    ///
+    /// This keeps the corpus leaner, at the cost of sensitivity: above `1`, inputs that only reach a new
+    /// hit count bucket, or a single new edge, are dropped, so coverage grows slower, in bigger steps,
+    /// and edges only ever reached one at a time may not be found at all. The default of `1` keeps the
+    /// usual behavior of any novelty being interesting.
+    #[must_use]
+    pub fn with_min_new_edges(mut self, min_new_edges: usize) -> Self {
+        self.min_new_edges = min_new_edges.max(1);
+        self
+    }
+
+    /// The number of newly covered entries an input needs to be interesting
+    #[must_use]
+    pub fn min_new_edges(&self) -> usize {
+        self.min_new_edges
+    }
+
+    /// Warn once the ratio of covered history map entries exceeds `threshold`, `None` disables the warning.
+    /// Disabled by default, [`DEFAULT_MAP_SATURATION_THRESHOLD`] is a reasonable threshold.
+    ///
+    /// A mostly filled map points to a map too small for the target: with many edges colliding
+    /// on the same entries, new coverage goes unnoticed, and fuzzing degrades silently.
+    #[must_use]
+    pub fn with_saturation_threshold(mut self, threshold: Option<f64>) -> Self {
+        self.saturation_threshold = threshold;
+        self
+    }
+
+    /// Forget all coverage accumulated by this feedback, so that previously seen entries are
+    /// considered novel again. Useful to re-energize exploration on a plateau.
+    ///
+    /// The corpus is retained, but every input hitting already known entries will register as
+    /// interesting once more. The history map is regrown on demand with the observer's initial value.
+    pub fn reset_history<S>(&self, state: &mut S) -> Result<(), Error>
+    where
+        S: HasNamedMetadata,
+    {
+        state
+            .named_metadata_map_mut()
+            .get_mut::<MapFeedbackMetadata<T>>(&self.name)
+            .ok_or_else(|| Error::key_not_found(format!("MapFeedbackMetadata for {}", self.name)))?
+            .clear();
+        Ok(())
+    }
+
     #[allow(clippy::wrong_self_convention)]
     #[allow(clippy::needless_range_loop)]
     #[allow(clippy::trivially_copy_pass_by_ref)]
@@ -810,13 +1088,214 @@ where
             }
         }
 
+        if interesting && self.min_new_edges > 1 {
+            interesting = covers_min_new_entries(observer, map_state, self.min_new_edges);
+        }
+
         interesting
     }
 }
 
+/// If the `observer` covers at least `min` entries not covered in the `map_state` yet,
+/// or the `map_state` has nothing covered at all
+fn covers_min_new_entries<O, T>(
+    observer: &O,
+    map_state: &MapFeedbackMetadata<T>,
+    min: usize,
+) -> bool
+where
+    O: MapObserver<Entry = T>,
+    T: PartialEq + Default + Copy + 'static + Serialize,
+{
+    if map_state.num_covered_map_indexes == 0 {
+        return true;
+    }
+    let initial = observer.initial();
+    let mut new_entries = 0;
+    for i in 0..observer.usable_count() {
+        if observer.get(i) != initial && map_state.history_map[i] == initial {
+            new_entries += 1;
+            if new_entries >= min {
+                return true;
+            }
+        }
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::feedbacks::{AllIsNovel, IsNovel, NextPow2IsNovel};
+    use alloc::{string::String, sync::Arc, vec::Vec};
+    #[cfg(feature = "std")]
+    use std::sync::Mutex;
+
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use crate::{
+        corpus::{CorpusId, InMemoryCorpus, Testcase},
+        events::{Event, EventFirer, NopEventManager},
+        executors::ExitKind,
+        feedbacks::{
+            map::covers_min_new_entries, AllIsNovel, ConstFeedback, CoverageCallbackFeedback,
+            CoverageIncrease, Feedback, IsNovel, MapFeedbackMetadata, MapSaturationMetadata,
+            MaxMapFeedback, NextPow2IsNovel, PresenceIsNovel,
+        },
+        inputs::BytesInput,
+        observers::{MapObserver, StdMapObserver},
+        state::{StdState, UsesState},
+        Error, HasNamedMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Records the messages of the log events fired
+    #[derive(Default)]
+    struct LogRecorder {
+        messages: Vec<String>,
+    }
+
+    impl UsesState for LogRecorder {
+        type State = TestState;
+    }
+
+    impl EventFirer for LogRecorder {
+        fn should_send(&self) -> bool {
+            true
+        }
+
+        fn fire(&mut self, _state: &mut TestState, event: Event<BytesInput>) -> Result<(), Error> {
+            if let Event::Log { message, .. } = event {
+                self.messages.push(message);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_map_saturation_warning() {
+        let observer = StdMapObserver::owned("map", vec![0_u8; 4]);
+        let mut feedback = MaxMapFeedback::new(&observer).with_saturation_threshold(Some(0.5));
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut observers = tuple_list!(observer);
+        let mut mgr = LogRecorder::default();
+        let input = BytesInput::new(vec![0]);
+
+        // 1, 2, 3 and 4 of 4 entries covered: crossing 50% warns, staying above does not again
+        for idx in 0..4 {
+            observers.0.set(idx, 1);
+            let mut testcase = Testcase::new(input.clone());
+            feedback
+                .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+                .unwrap();
+            assert_eq!(mgr.messages.len(), usize::from(idx >= 2));
+        }
+        assert!(
+            state
+                .named_metadata::<MapSaturationMetadata>("map")
+                .unwrap()
+                .reported
+        );
+    }
+
+    #[test]
+    fn test_map_saturation_disabled_by_default() {
+        let observer = StdMapObserver::owned("map", vec![1_u8; 4]);
+        let mut feedback = MaxMapFeedback::new(&observer);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let observers = tuple_list!(observer);
+        let mut mgr = LogRecorder::default();
+        let mut testcase = Testcase::new(BytesInput::new(vec![0]));
+        feedback
+            .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+            .unwrap();
+        assert!(mgr.messages.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_coverage_callback() {
+        let observer = StdMapObserver::owned("map", vec![0_u8; 4]);
+        let increases = Arc::new(Mutex::new(Vec::new()));
+        let recorded = increases.clone();
+        let mut feedback =
+            CoverageCallbackFeedback::new(MaxMapFeedback::new(&observer), move |increase| {
+                recorded.lock().unwrap().push(*increase);
+            });
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut observers = tuple_list!(observer);
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        observers.0.set(1, 1);
+        for _ in 0..2 {
+            if feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap()
+            {
+                let mut testcase = Testcase::new(input.clone());
+                feedback
+                    .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+                    .unwrap();
+            }
+        }
+
+        // The second run covered nothing new
+        assert_eq!(
+            *increases.lock().unwrap(),
+            vec![CoverageIncrease {
+                covered: 1,
+                map_size: 4,
+                corpus_id: Some(CorpusId(0)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_min_new_edges() {
+        let mut map_state = MapFeedbackMetadata::<u8>::new(8);
+        let observer = StdMapObserver::owned("map", vec![1_u8, 1, 0, 1, 0, 0, 0, 0]);
+        // Nothing covered yet, always interesting
+        assert!(covers_min_new_entries(&observer, &map_state, 8));
+
+        map_state.history_map[0] = 1;
+        map_state.num_covered_map_indexes = 1;
+        assert!(covers_min_new_entries(&observer, &map_state, 2));
+        assert!(!covers_min_new_entries(&observer, &map_state, 3));
+    }
+
+    #[test]
+    fn test_map_fill_ratio() {
+        let mut map_state = MapFeedbackMetadata::<u8>::new(0);
+        assert!(map_state.fill_ratio().abs() < f64::EPSILON);
+
+        map_state = MapFeedbackMetadata::with_history_map(vec![1_u8, 0, 1, 1, 0, 0, 0, 0], 0);
+        assert!((map_state.fill_ratio() - 0.375).abs() < f64::EPSILON);
+    }
 
     #[test]
     fn test_map_is_novel() {
@@ -836,5 +1315,10 @@ mod tests {
         assert!(!NextPow2IsNovel::is_novel(255_u8, 128));
         assert!(NextPow2IsNovel::is_novel(254_u8, 255));
         assert!(!NextPow2IsNovel::is_novel(255_u8, 255));
+
+        assert!(!PresenceIsNovel::is_novel(0_u8, 0));
+        assert!(PresenceIsNovel::is_novel(0_u8, 1));
+        assert!(PresenceIsNovel::is_novel(0_u8, 200));
+        assert!(!PresenceIsNovel::is_novel(1_u8, 200));
     }
 }
@@ -11,48 +11,104 @@ use core::{
     fmt::{self, Debug, Formatter},
     marker::PhantomData,
 };
+#[cfg(feature = "std")]
+use std::path::Path;
 
+pub use campaign_tag::{CampaignTagFeedback, CampaignTagMetadata};
 #[cfg(feature = "std")]
 pub use concolic::ConcolicFeedback;
+pub use coverage_snapshot::{CoverageSnapshotFeedback, CoverageSnapshotMetadata};
+pub use crash_exploration::CrashExplorationFeedback;
+pub use crash_signature::{CrashSignature, CrashSignatureFeedback, CrashSignatureMetadata};
+pub use delivered_input::{DeliveredInputFeedback, DeliveredInputMetadata};
 pub use differential::DiffFeedback;
+pub use edge_polarity::{EdgePolarityFeedback, EdgePolarityMetadata, FlippedEdgesMetadata};
+#[cfg(feature = "std")]
+pub use exec_log::{read_exec_log, ExecLogFeedback, ExecLogRecord};
+pub use exit_code::{
+    parse_exit_code_ranges, ExitCodeFeedback, ExitCodeFeedbackMetadata, ExitCodeMetadata,
+};
+#[cfg(unix)]
+pub use exploitability::{
+    classify_crash, Exploitability, ExploitabilityFeedback, ExploitabilityMetadata,
+};
+pub use input_dedup::{InputDedupFeedback, SeenInputsMetadata};
 use libafl_bolts::{
     tuples::{Handle, Handled, MatchNameRef},
     Named,
 };
 pub use list::*;
 pub use map::*;
+pub use named_objective::{
+    NamedObjectiveCountMetadata, NamedObjectiveFeedback, NamedObjectivesMetadata,
+};
 #[cfg(feature = "nautilus")]
 pub use nautilus::*;
 #[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedback;
 #[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedbackMetadata;
+pub use objective_count::{ObjectiveCountFeedback, ObjectiveCountMetadata};
+pub use rate_limit::RateLimitedObjectiveFeedback;
+pub use reach_target::{ReachTargetFeedback, ReachedTargetsMetadata};
+pub use rotating::RotatingFeedback;
+pub use seed::{SeedFeedback, SeedLoadingMetadata, SeedPhasePolicy};
 use serde::{Deserialize, Serialize};
+pub use slow_path::{SlowPathFeedback, SlowPathMetadata, SLOW_PATH_PENALTY};
+pub use speed_gate::{SpeedCeiling, SpeedGateMetadata, SpeedGatedFeedback};
+pub use stack_depth::{MaxStackDepthFeedback, StackDepthMetadata};
+pub use template_filename::{FilenamePlaceholder, FilenameTemplate, TemplateFilenameFeedback};
+#[cfg(feature = "std")]
+pub use triage_hook::{TriageHookFeedback, TriageHookMetadata, TRIAGE_EXIT_KIND_ENV};
 
 use crate::{
     corpus::Testcase,
     events::EventFirer,
-    executors::ExitKind,
+    executors::{DiffExitKind, ExitKind},
     observers::{ObserversTuple, TimeObserver},
     state::State,
-    Error,
+    Error, HasMetadata,
 };
+pub mod campaign_tag;
 #[cfg(feature = "std")]
 pub mod concolic;
+pub mod coverage_snapshot;
+pub mod crash_exploration;
+pub mod crash_signature;
 #[cfg(feature = "std")]
 /// The module for list [`CustomTestcaseFilenameFeedback`]
 pub mod custom_testcase_filename;
+pub mod delivered_input;
 pub mod differential;
+pub mod edge_polarity;
+#[cfg(feature = "std")]
+pub mod exec_log;
+pub mod exit_code;
+#[cfg(unix)]
+pub mod exploitability;
+pub mod input_dedup;
 /// The module for list feedback
 pub mod list;
 pub mod map;
+pub mod named_objective;
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "std")]
 pub mod new_hash_feedback;
+pub mod objective_count;
+pub mod rate_limit;
+pub mod reach_target;
+pub mod rotating;
+pub mod seed;
+pub mod slow_path;
+pub mod speed_gate;
+pub mod stack_depth;
 #[cfg(feature = "std")]
 pub mod stdio;
+pub mod template_filename;
 pub mod transferred;
+#[cfg(feature = "std")]
+pub mod triage_hook;
 
 /// Feedbacks evaluate the observers.
 /// Basically, they reduce the information provided by an observer to a value,
@@ -163,6 +219,19 @@ pub trait HasObserverHandle {
     fn observer_handle(&self) -> &Handle<Self::Observer>;
 }
 
+/// A [`Feedback`] accumulating knowledge in the state (like the seen hashes of a [`NewHashFeedback`]),
+/// that can be persisted to and restored from disk, so that restarts do not lose it.
+/// Stages collecting such knowledge, like the [`crate::stages::MagicConstantsStage`], implement it as well.
+#[cfg(feature = "std")]
+pub trait PersistentFeedback<S> {
+    /// Writes the accumulated state of this feedback to `path`
+    fn persist(&self, state: &S, path: &Path) -> Result<(), Error>;
+
+    /// Merges the state previously written by [`PersistentFeedback::persist`] at `path` into `state`.
+    /// Does nothing if there is no file at `path`, i.e., on the first run.
+    fn restore(&mut self, state: &mut S, path: &Path) -> Result<(), Error>;
+}
+
 /// A combined feedback consisting of multiple [`Feedback`]s
 #[derive(Debug)]
 pub struct CombinedFeedback<A, B, FL, S>
@@ -1059,12 +1128,30 @@ impl<S: State, T> FeedbackFactory<TimeoutFeedback, S, T> for TimeoutFeedback {
     }
 }
 
+/// The exit kinds of both sides of a [`crate::executors::DiffExecutor`] run that diverged.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct DiffExitKindMetadata {
+    /// The exitkind of the primary executor
+    pub primary: DiffExitKind,
+    /// The exitkind of the secondary executor
+    pub secondary: DiffExitKind,
+}
+
+libafl_bolts::impl_serdeany!(DiffExitKindMetadata);
+
 /// A [`DiffExitKindFeedback`] checks if there is a difference in the [`crate::executors::ExitKind`]s in a [`crate::executors::DiffExecutor`].
+/// Interesting testcases get a [`DiffExitKindMetadata`] recording both exit kinds,
+/// e.g., which side timed out.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DiffExitKindFeedback {
     #[cfg(feature = "track_hit_feedbacks")]
     // The previous run's result of `Self::is_interesting`
     last_result: Option<bool>,
+    last_diff: Option<DiffExitKindMetadata>,
 }
 
 impl<S> Feedback<S> for DiffExitKindFeedback
@@ -1084,13 +1171,43 @@ where
         EM: EventFirer<State = S>,
         OT: ObserversTuple<S>,
     {
-        let res = matches!(exit_kind, ExitKind::Diff { .. });
+        self.last_diff = match exit_kind {
+            ExitKind::Diff { primary, secondary } => Some(DiffExitKindMetadata {
+                primary: *primary,
+                secondary: *secondary,
+            }),
+            _ => None,
+        };
+        let res = self.last_diff.is_some();
         #[cfg(feature = "track_hit_feedbacks")]
         {
             self.last_result = Some(res);
         }
         Ok(res)
     }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some(diff) = self.last_diff.take() {
+            testcase.add_metadata(diff);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.last_diff = None;
+        Ok(())
+    }
+
     #[cfg(feature = "track_hit_feedbacks")]
     fn last_result(&self) -> Result<bool, Error> {
         self.last_result.ok_or(premature_last_result_err())
@@ -1112,6 +1229,7 @@ impl DiffExitKindFeedback {
         Self {
             #[cfg(feature = "track_hit_feedbacks")]
             last_result: None,
+            last_diff: None,
         }
     }
 }
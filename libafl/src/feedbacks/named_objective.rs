@@ -0,0 +1,207 @@
+//! The [`NamedObjectiveFeedback`] gives an objective a name, so that several objectives
+//! (e.g., "asan", "hang", "assertion") can be told apart and routed to their own corpora.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+use libafl_bolts::{impl_serdeany, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::{Event, EventFirer},
+    executors::ExitKind,
+    feedbacks::Feedback,
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    observers::ObserversTuple,
+    state::State,
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// Testcase metadata listing the names of all [`NamedObjectiveFeedback`]s a solution triggered
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedObjectivesMetadata {
+    /// The names of the triggered objectives
+    pub names: Vec<String>,
+}
+
+impl_serdeany!(NamedObjectivesMetadata);
+
+/// State metadata counting the solutions of one [`NamedObjectiveFeedback`], keyed by its name
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct NamedObjectiveCountMetadata {
+    /// The number of solutions routed to this objective so far
+    pub count: u64,
+}
+
+impl_serdeany!(NamedObjectiveCountMetadata);
+
+/// Wraps an objective (chain) `A` and gives it a name, the id its solutions are routed by.
+///
+/// Combine several of them in the objective, e.g.,
+/// `feedback_or_fast!(NamedObjectiveFeedback::new("asan", asan), NamedObjectiveFeedback::new("hang", TimeoutFeedback::new()))`,
+/// and register a corpus per name with [`crate::fuzzer::StdFuzzer::with_objective_corpus`].
+/// The names of the objectives a solution triggered are stored in a [`NamedObjectivesMetadata`],
+/// and the fuzzer adds it to the corpus of the first one with a registered corpus.
+/// Solutions of objectives without a corpus end up in the state's solutions, as usual.
+/// The per-objective counts are reported as user stats named `objectives_<name>`.
+pub struct NamedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// The wrapped objective
+    pub first: A,
+    /// The name of this objective
+    name: Cow<'static, str>,
+    /// The name of the user stats reporting the count
+    stats_name: Cow<'static, str>,
+    /// If the wrapped objective triggered for the current input
+    hit: bool,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> Debug for NamedObjectiveFeedback<A, S>
+where
+    A: Feedback<S> + Debug,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamedObjectiveFeedback")
+            .field("name", &self.name)
+            .field("first", &self.first)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, S> Feedback<S> for NamedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_named_metadata::<NamedObjectiveCountMetadata>(&self.name) {
+            state.add_named_metadata(&self.name, NamedObjectiveCountMetadata::default());
+        }
+        self.first.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.hit = self
+            .first
+            .is_interesting(state, manager, input, observers, exit_kind)?;
+        Ok(self.hit)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        self.first
+            .append_metadata(state, manager, observers, testcase)?;
+        // In a fast chain, we may not have been asked at all, so only route our own hits
+        if !core::mem::take(&mut self.hit) {
+            return Ok(());
+        }
+
+        testcase
+            .metadata_or_insert_with(NamedObjectivesMetadata::default)
+            .names
+            .push(self.name.to_string());
+
+        let count = {
+            let meta = state
+                .named_metadata_or_insert_with(&self.name, NamedObjectiveCountMetadata::default);
+            meta.count += 1;
+            meta.count
+        };
+        manager.fire(
+            state,
+            Event::UpdateUserStats {
+                name: self.stats_name.clone(),
+                value: UserStats::new(UserStatsValue::Number(count), AggregatorOps::Sum),
+                phantom: PhantomData,
+            },
+        )?;
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.hit = false;
+        self.first.discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.first.last_result()
+    }
+}
+
+impl<A, S> Named for NamedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<A, S> NamedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// Creates a new [`NamedObjectiveFeedback`] called `name`, for the solutions of `first`
+    pub fn new(name: &'static str, first: A) -> Self {
+        Self {
+            first,
+            name: Cow::Borrowed(name),
+            stats_name: Cow::from(format!("objectives_{name}")),
+            hit: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The number of solutions of this objective, as stored in the state
+    pub fn count(&self, state: &S) -> u64
+    where
+        S: HasNamedMetadata,
+    {
+        state
+            .named_metadata::<NamedObjectiveCountMetadata>(&self.name)
+            .map_or(0, |meta| meta.count)
+    }
+}
@@ -1,10 +1,11 @@
 //! The ``NewHashFeedback`` uses the backtrace hash and a hashset to only keep novel cases
 
 use alloc::{borrow::Cow, string::ToString};
-use std::{fmt::Debug, marker::PhantomData};
+use std::{fmt::Debug, fs, marker::PhantomData, path::Path};
 
 use hashbrown::HashSet;
 use libafl_bolts::{
+    fs::write_file_atomic,
     tuples::{Handle, Handled, MatchNameRef},
     Named,
 };
@@ -15,7 +16,7 @@ use crate::feedbacks::premature_last_result_err;
 use crate::{
     events::EventFirer,
     executors::ExitKind,
-    feedbacks::{Feedback, HasObserverHandle},
+    feedbacks::{Feedback, HasObserverHandle, PersistentFeedback},
     inputs::UsesInput,
     observers::{ObserverWithHashField, ObserversTuple},
     state::State,
@@ -147,6 +148,30 @@ where
     }
 }
 
+impl<O, S> PersistentFeedback<S> for NewHashFeedback<O, S>
+where
+    S: HasNamedMetadata,
+{
+    fn persist(&self, state: &S, path: &Path) -> Result<(), Error> {
+        let meta = state.named_metadata::<NewHashFeedbackMetadata>(&self.name)?;
+        write_file_atomic(path, &postcard::to_allocvec(&meta.hash_set)?)
+    }
+
+    fn restore(&mut self, state: &mut S, path: &Path) -> Result<(), Error> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let hash_set: HashSet<u64> = postcard::from_bytes(&fs::read(path)?)?;
+        state
+            .named_metadata_or_insert_with(&self.name, || {
+                NewHashFeedbackMetadata::with_capacity(self.capacity)
+            })
+            .hash_set
+            .extend(hash_set);
+        Ok(())
+    }
+}
+
 impl<O, S> Named for NewHashFeedback<O, S> {
     #[inline]
     fn name(&self) -> &Cow<'static, str> {
@@ -0,0 +1,237 @@
+//! The [`ObjectiveCountFeedback`] counts every input its wrapped feedback reports,
+//! before any deduplication, and reports the total in the stats.
+
+use alloc::borrow::Cow;
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+use libafl_bolts::{impl_serdeany, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::{Event, EventFirer},
+    executors::ExitKind,
+    feedbacks::Feedback,
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    observers::ObserversTuple,
+    state::State,
+    Error, HasNamedMetadata,
+};
+
+/// The number of inputs an [`ObjectiveCountFeedback`] counted, kept in the state so that the
+/// count survives restarts
+#[derive(Default, Clone, Copy, Debug, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct ObjectiveCountMetadata {
+    /// The inputs reported by the wrapped feedback
+    pub total: u64,
+}
+
+impl_serdeany!(ObjectiveCountMetadata);
+
+/// Wraps the part of an objective that detects solutions, such as the
+/// [`crate::feedbacks::CrashFeedback`], and counts every input it reports, reported as the
+/// `total_objectives` user stat, while the objective count shows the unique ones.
+/// The count is kept in the named [`ObjectiveCountMetadata`] of the state.
+///
+/// Combine it with a deduplicating feedback, e.g., by stack hash through the
+/// [`crate::feedbacks::NewHashFeedback`] or by coverage through a map feedback:
+/// `feedback_and_fast!(ObjectiveCountFeedback::new(CrashFeedback::new()), NewHashFeedback::new(&bt_observer))`.
+#[derive(Clone)]
+pub struct ObjectiveCountFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// The wrapped feedback
+    pub first: A,
+    /// The name
+    name: Cow<'static, str>,
+    // The previous run's result of `Self::is_interesting`
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> Debug for ObjectiveCountFeedback<A, S>
+where
+    A: Feedback<S> + Debug,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectiveCountFeedback")
+            .field("name", &self.name)
+            .field("first", &self.first)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, S> Feedback<S> for ObjectiveCountFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_named_metadata::<ObjectiveCountMetadata>(&self.name) {
+            state.add_named_metadata(&self.name, ObjectiveCountMetadata::default());
+        }
+        self.first.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let res = self
+            .first
+            .is_interesting(state, manager, input, observers, exit_kind)?;
+        if res {
+            let meta =
+                state.named_metadata_or_insert_with(&self.name, ObjectiveCountMetadata::default);
+            meta.total += 1;
+            let total = meta.total;
+            manager.fire(
+                state,
+                Event::UpdateUserStats {
+                    name: Cow::Borrowed("total_objectives"),
+                    value: UserStats::new(UserStatsValue::Number(total), AggregatorOps::Sum),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[inline]
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        self.first
+            .append_metadata(state, manager, observers, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.first.discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result
+            .ok_or(crate::feedbacks::premature_last_result_err())
+    }
+}
+
+impl<A, S> Named for ObjectiveCountFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<A, S> ObjectiveCountFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// Creates a new [`ObjectiveCountFeedback`], counting the inputs reported by `first`
+    pub fn new(first: A) -> Self {
+        let name = Cow::from(format!("ObjectiveCount({})", first.name()));
+        Self {
+            first,
+            name,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The number of inputs reported by the wrapped feedback so far
+    pub fn total(&self, state: &S) -> Result<u64, Error>
+    where
+        S: HasNamedMetadata,
+    {
+        Ok(state
+            .named_metadata::<ObjectiveCountMetadata>(&self.name)?
+            .total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, CrashFeedback, Feedback, ObjectiveCountFeedback},
+        inputs::BytesInput,
+        state::{test::test_std_state, StdState},
+    };
+
+    #[test]
+    fn test_objective_count() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ObjectiveCountFeedback::new(CrashFeedback::new());
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        for exit_kind in [ExitKind::Crash, ExitKind::Ok, ExitKind::Crash] {
+            objective
+                .is_interesting(&mut state, &mut mgr, &input, &(), &exit_kind)
+                .unwrap();
+        }
+        assert_eq!(objective.total(&state).unwrap(), 2);
+
+        // A fresh feedback, as after a restart, continues the count kept in the state
+        let restarted = ObjectiveCountFeedback::new(CrashFeedback::new());
+        assert_eq!(restarted.total(&state).unwrap(), 2);
+
+        // A state created without the feedback starts counting on the first objective
+        let mut state = test_std_state::<BytesInput>();
+        objective
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Crash)
+            .unwrap();
+        assert_eq!(objective.total(&state).unwrap(), 1);
+    }
+}
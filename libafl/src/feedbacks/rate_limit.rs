@@ -0,0 +1,263 @@
+//! The [`RateLimitedObjectiveFeedback`] caps the number of solutions saved per time window,
+//! to avoid filling the disk when a misbuilt target crashes on nearly every input.
+
+use alloc::borrow::Cow;
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+
+use libafl_bolts::{current_time, Named};
+
+use crate::{
+    corpus::Testcase, events::EventFirer, executors::ExitKind, feedbacks::Feedback,
+    observers::ObserversTuple, state::State, Error,
+};
+
+/// The default window in which at most `limit` solutions are kept
+pub const DEFAULT_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+/// The default minimum interval between two warnings about dropped solutions
+pub const DEFAULT_RATE_LIMIT_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Wraps an objective (chain) and drops solutions once more than `limit` of them were found
+/// in the current time window.
+///
+/// A target crashing on nearly everything usually means a broken build,
+/// so a warning is logged (at most once per log interval) while solutions get dropped.
+#[derive(Clone)]
+pub struct RateLimitedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// The wrapped objective
+    pub first: A,
+    /// The name
+    name: Cow<'static, str>,
+    /// Maximum number of solutions per window
+    limit: usize,
+    /// The length of a window
+    window: Duration,
+    /// Minimum time between two warnings
+    log_interval: Duration,
+    /// When the current window started
+    window_start: Duration,
+    /// Solutions accepted in the current window
+    accepted_in_window: usize,
+    /// Solutions dropped in total
+    dropped: u64,
+    /// When we last warned about dropped solutions
+    last_log: Option<Duration>,
+    // The previous run's result of `Self::is_interesting`
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> Debug for RateLimitedObjectiveFeedback<A, S>
+where
+    A: Feedback<S> + Debug,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitedObjectiveFeedback")
+            .field("name", &self.name)
+            .field("first", &self.first)
+            .field("limit", &self.limit)
+            .field("window", &self.window)
+            .field("dropped", &self.dropped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, S> Feedback<S> for RateLimitedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        self.first.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let mut res = self
+            .first
+            .is_interesting(state, manager, input, observers, exit_kind)?;
+        if res {
+            res = self.try_accept(current_time());
+        }
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[inline]
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        self.first
+            .append_metadata(state, manager, observers, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.first.discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result
+            .ok_or(crate::feedbacks::premature_last_result_err())
+    }
+}
+
+impl<A, S> Named for RateLimitedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<A, S> RateLimitedObjectiveFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// Creates a new [`RateLimitedObjectiveFeedback`], keeping at most `limit` solutions
+    /// per [`DEFAULT_RATE_LIMIT_WINDOW`].
+    pub fn new(first: A, limit: usize) -> Self {
+        let name = Cow::from(format!("RateLimited({})", first.name()));
+        Self {
+            first,
+            name,
+            limit,
+            window: DEFAULT_RATE_LIMIT_WINDOW,
+            log_interval: DEFAULT_RATE_LIMIT_LOG_INTERVAL,
+            window_start: current_time(),
+            accepted_in_window: 0,
+            dropped: 0,
+            last_log: None,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the length of the window in which at most `limit` solutions are kept
+    #[must_use]
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the minimum interval between two warnings about dropped solutions
+    #[must_use]
+    pub fn with_log_interval(mut self, log_interval: Duration) -> Self {
+        self.log_interval = log_interval;
+        self
+    }
+
+    /// The number of solutions dropped so far
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Decides if a solution found at `now` is kept, updating the window
+    fn try_accept(&mut self, now: Duration) -> bool {
+        if now.saturating_sub(self.window_start) >= self.window {
+            self.window_start = now;
+            self.accepted_in_window = 0;
+        }
+
+        if self.accepted_in_window < self.limit {
+            self.accepted_in_window += 1;
+            return true;
+        }
+
+        self.dropped += 1;
+        if self
+            .last_log
+            .map_or(true, |last| now.saturating_sub(last) >= self.log_interval)
+        {
+            log::warn!(
+                "More than {} solutions per {:?}, dropped {} so far. The target may be misconfigured (e.g., a broken build)!",
+                self.limit,
+                self.window,
+                self.dropped
+            );
+            self.last_log = Some(now);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        feedbacks::{rate_limit::RateLimitedObjectiveFeedback, ConstFeedback},
+        inputs::BytesInput,
+        state::StdState,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_try_accept() {
+        let mut feedback =
+            RateLimitedObjectiveFeedback::<_, TestState>::new(ConstFeedback::new(true), 2)
+                .with_window(Duration::from_secs(1))
+                .with_log_interval(Duration::from_secs(10));
+        let start = feedback.window_start;
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // at most two solutions per window
+        assert!(feedback.try_accept(at(0)));
+        assert!(feedback.try_accept(at(10)));
+        assert!(!feedback.try_accept(at(20)));
+        assert!(!feedback.try_accept(at(30)));
+        assert_eq!(feedback.dropped(), 2);
+        // only the first drop was logged
+        assert_eq!(feedback.last_log, Some(at(20)));
+
+        // a new window starts once the old one elapsed
+        assert!(feedback.try_accept(at(1020)));
+        assert!(feedback.try_accept(at(1030)));
+        assert!(!feedback.try_accept(at(1040)));
+        assert_eq!(feedback.dropped(), 3);
+        assert_eq!(feedback.last_log, Some(at(20)));
+    }
+}
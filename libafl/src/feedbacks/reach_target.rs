@@ -0,0 +1,284 @@
+//! The [`ReachTargetFeedback`] considers inputs interesting that cover one of a set of target map entries
+//! for the first time, e.g., to check whether the fuzzer reaches a specific function.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::marker::PhantomData;
+
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::stages::stop_on_objective::{StopRequestedMetadata, STOP_ON_OBJECTIVE_EXIT_OTHER};
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle},
+    observers::{MapObserver, ObserversTuple},
+    state::State,
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The target map entries reached so far, stored in the state keyed by the name of the feedback.
+/// Also added to the testcase that first reached them, holding only the newly reached targets.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReachedTargetsMetadata {
+    /// The reached target indexes
+    pub reached: Vec<usize>,
+}
+
+impl_serdeany!(ReachedTargetsMetadata);
+
+/// Considers a testcase interesting the first time it covers any of the target entries of a map,
+/// such as the edges into a function of interest. Once reached, a target is not considered again.
+///
+/// Use it in the feedback to keep the inputs reaching targets in the corpus, or in the objective to
+/// report them as solutions. With [`ReachTargetFeedback::stop_when_all_reached`], the campaign ends once
+/// every target was reached: the feedback requests a stop that a [`crate::stages::StopOnObjectiveStage`]
+/// carries out. As that stage also stops on the first solution, use the feedback in the feedback then.
+#[derive(Clone, Debug)]
+pub struct ReachTargetFeedback<C, O> {
+    map_ref: Handle<C>,
+    name: Cow<'static, str>,
+    targets: Vec<usize>,
+    #[cfg(feature = "std")]
+    stop_when_all_reached: bool,
+    /// The targets reached for the first time in the current run
+    newly_reached: Vec<usize>,
+    phantom: PhantomData<O>,
+}
+
+impl<C, O> ReachTargetFeedback<C, O>
+where
+    C: Named,
+{
+    /// Creates a new [`ReachTargetFeedback`] for the given target indexes of the map observer
+    #[must_use]
+    pub fn new(map_observer: &C, targets: Vec<usize>) -> Self {
+        Self {
+            map_ref: map_observer.handle(),
+            name: Cow::from(format!("ReachTargetFeedback_{}", map_observer.name())),
+            targets,
+            #[cfg(feature = "std")]
+            stop_when_all_reached: false,
+            newly_reached: vec![],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Stops the campaign once all targets were reached, see [`ReachTargetFeedback`]
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn stop_when_all_reached(mut self) -> Self {
+        self.stop_when_all_reached = true;
+        self
+    }
+
+    /// The targets
+    #[must_use]
+    pub fn targets(&self) -> &[usize] {
+        &self.targets
+    }
+
+    /// The targets that were not reached yet
+    #[must_use]
+    pub fn unreached<S>(&self, state: &S) -> Vec<usize>
+    where
+        S: HasNamedMetadata,
+    {
+        let reached = state
+            .named_metadata::<ReachedTargetsMetadata>(&self.name)
+            .ok();
+        self.targets
+            .iter()
+            .copied()
+            .filter(|target| reached.map_or(true, |meta| !meta.reached.contains(target)))
+            .collect()
+    }
+}
+
+impl<C, O, S> Feedback<S> for ReachTargetFeedback<C, O>
+where
+    C: AsRef<O> + Named,
+    O: MapObserver,
+    S: State + HasNamedMetadata + HasMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_named_metadata::<ReachedTargetsMetadata>(&self.name) {
+            state.add_named_metadata(&self.name, ReachedTargetsMetadata::default());
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observers
+            .get(&self.map_ref)
+            .ok_or_else(|| Error::key_not_found(format!("MapObserver {}", self.map_ref.name())))?
+            .as_ref();
+        let initial = observer.initial();
+        let usable_count = observer.usable_count();
+        self.newly_reached = self
+            .unreached(state)
+            .into_iter()
+            .filter(|target| *target < usable_count && observer.get(*target) != initial)
+            .collect();
+        Ok(!self.newly_reached.is_empty())
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if self.newly_reached.is_empty() {
+            return Ok(());
+        }
+        let newly_reached = core::mem::take(&mut self.newly_reached);
+        log::info!("Reached targets {newly_reached:?}");
+        state
+            .named_metadata_or_insert_with(&self.name, ReachedTargetsMetadata::default)
+            .reached
+            .extend_from_slice(&newly_reached);
+        testcase.add_metadata(ReachedTargetsMetadata {
+            reached: newly_reached,
+        });
+
+        #[cfg(feature = "std")]
+        if self.stop_when_all_reached && self.unreached(state).is_empty() {
+            log::info!("Reached all targets, requesting to stop");
+            state.add_metadata(StopRequestedMetadata {
+                exit_code: STOP_ON_OBJECTIVE_EXIT_OTHER,
+            });
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.newly_reached.clear();
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(!self.newly_reached.is_empty())
+    }
+}
+
+impl<C, O> Named for ReachTargetFeedback<C, O> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<C, O> HasObserverHandle for ReachTargetFeedback<C, O> {
+    type Observer = C;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<C> {
+        &self.map_ref
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, Named};
+
+    use super::{ReachTargetFeedback, ReachedTargetsMetadata};
+    use crate::{
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, Feedback},
+        inputs::BytesInput,
+        observers::{MapObserver, StdMapObserver},
+        state::StdState,
+        HasMetadata, HasNamedMetadata,
+    };
+
+    #[test]
+    fn test_reach_target() {
+        let observer = StdMapObserver::owned("map", vec![0_u8; 8]);
+        let mut feedback =
+            ReachTargetFeedback::<_, StdMapObserver<u8, false>>::new(&observer, vec![2, 5]);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut observers = tuple_list!(observer);
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        // covering a non-target entry is not interesting
+        observers.0.set(1, 1);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+
+        observers.0.set(2, 1);
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback
+            .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+            .unwrap();
+        assert_eq!(
+            testcase
+                .metadata::<ReachedTargetsMetadata>()
+                .unwrap()
+                .reached,
+            vec![2]
+        );
+        assert_eq!(feedback.unreached(&state), vec![5]);
+
+        // a reached target is not interesting again, and nothing is left over from the last run
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback
+            .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+            .unwrap();
+        assert!(!testcase.has_metadata::<ReachedTargetsMetadata>());
+        assert_eq!(
+            state
+                .named_metadata::<ReachedTargetsMetadata>(feedback.name())
+                .unwrap()
+                .reached,
+            vec![2]
+        );
+    }
+}
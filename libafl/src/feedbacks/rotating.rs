@@ -0,0 +1,166 @@
+//! The [`RotatingFeedback`] judges each input with the feedback of the target it ran on,
+//! for the targets of a [`crate::executors::RotatingExecutor`].
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::marker::PhantomData;
+
+use libafl_bolts::Named;
+
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::{ExitKind, TargetRotationMetadata},
+    feedbacks::Feedback,
+    observers::ObserversTuple,
+    state::State,
+    Error, HasMetadata,
+};
+
+/// A feedback holding one feedback per target of a [`crate::executors::RotatingExecutor`],
+/// usually a map feedback on the map observer of that target.
+/// Each input is judged by the feedback of the target it ran on, as recorded in the
+/// [`TargetRotationMetadata`], so that new coverage in any target keeps it in the shared corpus.
+#[derive(Debug, Clone)]
+pub struct RotatingFeedback<F, S> {
+    name: Cow<'static, str>,
+    feedbacks: Vec<F>,
+    /// The target whose feedback judged the last input
+    last: usize,
+    phantom: PhantomData<S>,
+}
+
+impl<F, S> RotatingFeedback<F, S> {
+    /// Creates a new [`RotatingFeedback`], with the feedbacks of the targets in the order
+    /// of the executors of the [`crate::executors::RotatingExecutor`]
+    pub fn new(feedbacks: Vec<F>) -> Result<Self, Error> {
+        if feedbacks.is_empty() {
+            return Err(Error::illegal_argument(
+                "A RotatingFeedback needs at least one feedback",
+            ));
+        }
+        Ok(Self {
+            name: Cow::Borrowed("RotatingFeedback"),
+            feedbacks,
+            last: 0,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The feedbacks of all targets
+    pub fn feedbacks(&self) -> &[F] {
+        &self.feedbacks
+    }
+}
+
+impl<F, S> Named for RotatingFeedback<F, S> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<F, S> Feedback<S> for RotatingFeedback<F, S>
+where
+    F: Feedback<S>,
+    S: State + HasMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        for feedback in &mut self.feedbacks {
+            feedback.init_state(state)?;
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let current = state
+            .metadata::<TargetRotationMetadata>()
+            .map_or(0, |meta| meta.current);
+        self.last = current.min(self.feedbacks.len() - 1);
+        self.feedbacks[self.last].is_interesting(state, manager, input, observers, exit_kind)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        self.feedbacks[self.last].append_metadata(state, manager, observers, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.feedbacks[self.last].discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.feedbacks[self.last].last_result()
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn append_hit_feedbacks(&self, list: &mut Vec<Cow<'static, str>>) -> Result<(), Error> {
+        self.feedbacks[self.last].append_hit_feedbacks(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        events::NopEventManager,
+        executors::{ExitKind, TargetRotationMetadata},
+        feedbacks::{ConstFeedback, Feedback, RotatingFeedback},
+        inputs::BytesInput,
+        state::NopState,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_rotating_feedback() {
+        assert!(RotatingFeedback::<ConstFeedback, NopState<BytesInput>>::new(Vec::new()).is_err());
+
+        let mut feedback =
+            RotatingFeedback::new(vec![ConstFeedback::new(false), ConstFeedback::new(true)])
+                .unwrap();
+        let mut state = NopState::<BytesInput>::new();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+        let mut is_interesting = |state: &mut NopState<BytesInput>| {
+            feedback
+                .is_interesting(state, &mut mgr, &input, &(), &ExitKind::Ok)
+                .unwrap()
+        };
+
+        // Before any rotation, the first target ran
+        assert!(!is_interesting(&mut state));
+
+        state.add_metadata(TargetRotationMetadata { current: 1 });
+        assert!(is_interesting(&mut state));
+
+        state.add_metadata(TargetRotationMetadata { current: 0 });
+        assert!(!is_interesting(&mut state));
+
+        // An unknown target falls back to the last feedback
+        state.add_metadata(TargetRotationMetadata { current: 5 });
+        assert!(is_interesting(&mut state));
+    }
+}
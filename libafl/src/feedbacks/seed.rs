@@ -0,0 +1,283 @@
+//! The [`SeedFeedback`] decides, per wrapped feedback, whether it is active while the initial
+//! seeds are loaded, afterwards, or both.
+
+use alloc::borrow::Cow;
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+use libafl_bolts::{impl_serdeany, Error, Named};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    corpus::Testcase, events::EventFirer, executors::ExitKind, feedbacks::Feedback,
+    observers::ObserversTuple, state::State, HasMetadata,
+};
+
+/// Metadata which denotes whether we are currently loading the initial seeds.
+/// It is added by the first [`SeedFeedback`] initializing the state.
+/// The `load_initial_inputs` and `generate_initial_inputs` methods of the
+/// [`crate::state::StdState`] set it while they run, and clear it once done,
+/// so that all [`SeedFeedback`]s switch to their post-seed behavior.
+/// Call [`SeedLoadingMetadata::set_loading`] to mark seeds added through other means.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct SeedLoadingMetadata {
+    loading: bool,
+}
+
+impl_serdeany!(SeedLoadingMetadata);
+
+impl SeedLoadingMetadata {
+    /// Indicate to the metadata whether we are currently loading seeds.
+    pub fn set_loading(&mut self, loading: bool) {
+        self.loading = loading;
+    }
+
+    /// Whether we are currently loading seeds
+    #[must_use]
+    pub fn loading(&self) -> bool {
+        self.loading
+    }
+}
+
+/// When the feedback wrapped in a [`SeedFeedback`] is active
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SeedPhasePolicy {
+    /// Only while the initial seeds are loaded, the feedback is neutral afterwards
+    #[default]
+    SeedsOnly,
+    /// Only after the initial seeds are loaded, the feedback is neutral before
+    AfterSeeds,
+    /// Both while and after the initial seeds are loaded
+    Always,
+}
+
+impl SeedPhasePolicy {
+    /// Whether a feedback with this policy is active in the given phase
+    #[must_use]
+    pub fn is_active(&self, loading_seeds: bool) -> bool {
+        match self {
+            Self::SeedsOnly => loading_seeds,
+            Self::AfterSeeds => !loading_seeds,
+            Self::Always => true,
+        }
+    }
+}
+
+/// A feedback wrapper that only runs the inner feedback in the phases selected by its
+/// [`SeedPhasePolicy`]. While inactive, it is not interesting and does not annotate metadata.
+///
+/// Wrap each feedback of a combined feedback separately to choose which of them annotate seeds,
+/// for example run a filename feedback [`SeedPhasePolicy::Always`], but only record
+/// execution times [`SeedPhasePolicy::AfterSeeds`], once calibration took place.
+pub struct SeedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// The wrapped feedback
+    inner: A,
+    /// When the wrapped feedback is active
+    policy: SeedPhasePolicy,
+    name: Cow<'static, str>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    // The previous run's result of `Self::is_interesting`
+    last_result: Option<bool>,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> Debug for SeedFeedback<A, S>
+where
+    A: Feedback<S> + Debug,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeedFeedback")
+            .field("name", &self.name)
+            .field("policy", &self.policy)
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, S> SeedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State + HasMetadata,
+{
+    /// Creates a new [`SeedFeedback`], running `inner` in the phases selected by `policy`.
+    pub fn new(inner: A, policy: SeedPhasePolicy) -> Self {
+        let name = Cow::from(format!("Seed({})", inner.name()));
+        Self {
+            inner,
+            policy,
+            name,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The policy of this feedback
+    #[must_use]
+    pub fn policy(&self) -> SeedPhasePolicy {
+        self.policy
+    }
+
+    /// Sets the policy of this feedback
+    pub fn set_policy(&mut self, policy: SeedPhasePolicy) {
+        self.policy = policy;
+    }
+
+    /// Whether the inner feedback is active for the current phase of the `state`
+    fn is_active(&self, state: &S) -> bool {
+        let loading = state
+            .metadata_map()
+            .get::<SeedLoadingMetadata>()
+            .map_or(false, SeedLoadingMetadata::loading);
+        self.policy.is_active(loading)
+    }
+}
+
+impl<A, S> Named for SeedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<A, S> Feedback<S> for SeedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State + HasMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_metadata::<SeedLoadingMetadata>() {
+            state.add_metadata(SeedLoadingMetadata { loading: false });
+        }
+        self.inner.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let res = self.is_active(state)
+            && self
+                .inner
+                .is_interesting(state, manager, input, observers, exit_kind)?;
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if self.is_active(state) {
+            self.inner
+                .append_metadata(state, manager, observers, testcase)?;
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        if self.is_active(state) {
+            self.inner.discard_metadata(state, input)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{
+            seed::{SeedLoadingMetadata, SeedPhasePolicy},
+            ConstFeedback, Feedback, SeedFeedback,
+        },
+        inputs::BytesInput,
+        state::NopState,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_seed_phase_policy() {
+        assert!(SeedPhasePolicy::SeedsOnly.is_active(true));
+        assert!(!SeedPhasePolicy::SeedsOnly.is_active(false));
+        assert!(!SeedPhasePolicy::AfterSeeds.is_active(true));
+        assert!(SeedPhasePolicy::AfterSeeds.is_active(false));
+        assert!(SeedPhasePolicy::Always.is_active(true));
+        assert!(SeedPhasePolicy::Always.is_active(false));
+    }
+    #[test]
+    fn test_seed_feedback() {
+        let mut state = NopState::<BytesInput>::new();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+        let mut feedback = SeedFeedback::new(ConstFeedback::new(true), SeedPhasePolicy::SeedsOnly);
+        feedback.init_state(&mut state).unwrap();
+
+        let mut interesting = |feedback: &mut SeedFeedback<_, _>, state: &mut NopState<_>| {
+            feedback
+                .is_interesting(state, &mut mgr, &input, &(), &ExitKind::Ok)
+                .unwrap()
+        };
+        // (policy, while loading the seeds, afterwards)
+        for (policy, seeds, after) in [
+            (SeedPhasePolicy::SeedsOnly, true, false),
+            (SeedPhasePolicy::AfterSeeds, false, true),
+            (SeedPhasePolicy::Always, true, true),
+        ] {
+            feedback.set_policy(policy);
+            state
+                .metadata_mut::<SeedLoadingMetadata>()
+                .unwrap()
+                .set_loading(true);
+            assert_eq!(interesting(&mut feedback, &mut state), seeds);
+            state
+                .metadata_mut::<SeedLoadingMetadata>()
+                .unwrap()
+                .set_loading(false);
+            assert_eq!(interesting(&mut feedback, &mut state), after);
+        }
+
+        // While active, the inner feedback decides
+        let mut feedback = SeedFeedback::new(ConstFeedback::new(false), SeedPhasePolicy::Always);
+        feedback.init_state(&mut state).unwrap();
+        assert!(!interesting(&mut feedback, &mut state));
+    }
+}
@@ -0,0 +1,254 @@
+//! The [`SlowPathFeedback`] decides whether timed-out inputs reaching new coverage are kept
+//! in the corpus, as low-priority entries fuzzed with a relaxed timeout.
+
+use alloc::borrow::Cow;
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+
+use libafl_bolts::{impl_serdeany, Error, Named};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    corpus::Testcase, events::EventFirer, executors::ExitKind, feedbacks::Feedback,
+    observers::ObserversTuple, state::State, HasMetadata,
+};
+
+/// The factor the scheduling weight (or power) of a [`SlowPathMetadata`] entry is multiplied with
+pub const SLOW_PATH_PENALTY: f64 = 0.1;
+
+/// Metadata marking a corpus entry that timed out when it was found.
+/// Schedulers select it rarely, see [`SLOW_PATH_PENALTY`], and the
+/// [`crate::stages::RelaxedTimeoutStage`] runs it with the relaxed timeout.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct SlowPathMetadata {
+    /// The timeout to run this entry and its mutants with
+    pub relaxed_timeout: Duration,
+}
+
+impl_serdeany!(SlowPathMetadata);
+
+/// A feedback wrapper deciding what happens to timed-out inputs the inner (coverage) feedback
+/// considers interesting. By default, they are discarded, so only inputs that ran to completion
+/// are kept. With [`SlowPathFeedback::keep_timeouts`], they are added to the corpus with a
+/// [`SlowPathMetadata`], so that coverage only reachable by slow paths is not lost.
+///
+/// Use it with an objective that does not consider all timeouts solutions, as objectives are
+/// evaluated first.
+pub struct SlowPathFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// The wrapped feedback
+    inner: A,
+    /// The timeout to run kept timeouts with, if they are kept
+    relaxed_timeout: Option<Duration>,
+    /// If the current run timed out
+    timed_out: bool,
+    name: Cow<'static, str>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    // The previous run's result of `Self::is_interesting`
+    last_result: Option<bool>,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> Debug for SlowPathFeedback<A, S>
+where
+    A: Feedback<S> + Debug,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlowPathFeedback")
+            .field("name", &self.name)
+            .field("relaxed_timeout", &self.relaxed_timeout)
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, S> SlowPathFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// Creates a new [`SlowPathFeedback`], discarding timed-out inputs
+    pub fn new(inner: A) -> Self {
+        let name = Cow::from(format!("SlowPath({})", inner.name()));
+        Self {
+            inner,
+            relaxed_timeout: None,
+            timed_out: false,
+            name,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Keeps timed-out inputs the inner feedback considers interesting,
+    /// to be fuzzed with the `relaxed_timeout`
+    #[must_use]
+    pub fn keep_timeouts(mut self, relaxed_timeout: Duration) -> Self {
+        self.relaxed_timeout = Some(relaxed_timeout);
+        self
+    }
+
+    /// The relaxed timeout, if timed-out inputs are kept
+    #[must_use]
+    pub fn relaxed_timeout(&self) -> Option<Duration> {
+        self.relaxed_timeout
+    }
+}
+
+impl<A, S> Named for SlowPathFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<A, S> Feedback<S> for SlowPathFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        self.inner.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.timed_out = *exit_kind == ExitKind::Timeout;
+        let res = (!self.timed_out || self.relaxed_timeout.is_some())
+            && self
+                .inner
+                .is_interesting(state, manager, input, observers, exit_kind)?;
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        self.inner
+            .append_metadata(state, manager, observers, testcase)?;
+        if let Some(relaxed_timeout) = self.relaxed_timeout.filter(|_| self.timed_out) {
+            log::info!("Keeping a timed-out input with new coverage as a slow path");
+            testcase.add_metadata(SlowPathMetadata { relaxed_timeout });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.inner.discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{
+        corpus::Testcase,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, Feedback, SlowPathFeedback, SlowPathMetadata},
+        inputs::BytesInput,
+        state::test::test_std_state,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_slow_path_feedback() {
+        let mut state = test_std_state::<BytesInput>();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        // By default, timeouts are discarded
+        let mut feedback = SlowPathFeedback::new(ConstFeedback::new(true));
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Timeout)
+            .unwrap());
+
+        // Kept timeouts are marked as slow paths, the other inputs are not
+        let relaxed_timeout = Duration::from_secs(5);
+        let mut feedback =
+            SlowPathFeedback::new(ConstFeedback::new(true)).keep_timeouts(relaxed_timeout);
+        assert_eq!(feedback.relaxed_timeout(), Some(relaxed_timeout));
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Timeout)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback
+            .append_metadata(&mut state, &mut mgr, &(), &mut testcase)
+            .unwrap();
+        assert_eq!(
+            testcase
+                .metadata::<SlowPathMetadata>()
+                .unwrap()
+                .relaxed_timeout,
+            relaxed_timeout
+        );
+
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        let mut testcase = Testcase::new(input);
+        feedback
+            .append_metadata(&mut state, &mut mgr, &(), &mut testcase)
+            .unwrap();
+        assert!(!testcase.has_metadata::<SlowPathMetadata>());
+
+        // The inner feedback still decides
+        let mut feedback =
+            SlowPathFeedback::new(ConstFeedback::new(false)).keep_timeouts(relaxed_timeout);
+        assert!(!feedback
+            .is_interesting(
+                &mut state,
+                &mut mgr,
+                &BytesInput::new(vec![1]),
+                &(),
+                &ExitKind::Timeout
+            )
+            .unwrap());
+    }
+}
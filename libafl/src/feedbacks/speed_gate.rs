@@ -0,0 +1,461 @@
+//! The [`SpeedGatedFeedback`] only accepts novel inputs that also run fast enough,
+//! to keep pathologically slow inputs from dragging down the throughput of the whole campaign.
+
+use alloc::{borrow::Cow, collections::VecDeque, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    observers::{ObserversTuple, TimeObserver},
+    state::State,
+    Error, HasNamedMetadata,
+};
+
+/// The number of most recently accepted inputs the median execution time is computed over
+pub const SPEED_GATE_WINDOW: usize = 1024;
+
+/// The maximum execution time of an input the [`SpeedGatedFeedback`] accepts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SpeedCeiling {
+    /// Inputs have to run at most this long
+    Absolute(Duration),
+    /// Inputs have to run at most this factor times the median execution time
+    /// of the last [`SPEED_GATE_WINDOW`] inputs accepted. Every input is accepted until the first one was.
+    /// The factor has to be positive and finite.
+    RelativeToMedian(f64),
+}
+
+impl SpeedCeiling {
+    /// The ceiling for the given median execution time, if any
+    #[must_use]
+    pub fn limit(&self, median: Option<Duration>) -> Option<Duration> {
+        match self {
+            Self::Absolute(limit) => Some(*limit),
+            Self::RelativeToMedian(factor) => median.map(|median| {
+                Duration::try_from_secs_f64(median.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+            }),
+        }
+    }
+
+    /// Checks that the factor of a [`SpeedCeiling::RelativeToMedian`] is positive and finite
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::Absolute(_) => Ok(()),
+            Self::RelativeToMedian(factor) if factor.is_finite() && *factor > 0.0 => Ok(()),
+            Self::RelativeToMedian(factor) => Err(Error::illegal_argument(format!(
+                "The speed ceiling factor has to be positive and finite, got {factor}"
+            ))),
+        }
+    }
+}
+
+/// The execution times of the last [`SPEED_GATE_WINDOW`] inputs accepted by a [`SpeedGatedFeedback`],
+/// stored in the state keyed by the name of the feedback
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpeedGateMetadata {
+    /// The execution times of the most recently accepted inputs, oldest first
+    pub exec_times: VecDeque<Duration>,
+}
+
+impl_serdeany!(SpeedGateMetadata);
+
+impl SpeedGateMetadata {
+    /// Adds the execution time of an accepted input, evicting the oldest one once the window is full
+    pub fn add(&mut self, exec_time: Duration) {
+        if self.exec_times.len() >= SPEED_GATE_WINDOW {
+            self.exec_times.pop_front();
+        }
+        self.exec_times.push_back(exec_time);
+    }
+
+    /// The median execution time of the inputs in the window, if any
+    #[must_use]
+    pub fn median(&self) -> Option<Duration> {
+        if self.exec_times.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.exec_times.iter().copied().collect();
+        let mid = sorted.len() / 2;
+        Some(*sorted.select_nth_unstable(mid).1)
+    }
+}
+
+/// Wraps a novelty feedback (chain) `A`, usually a [`crate::feedbacks::MaxMapFeedback`],
+/// and only considers an input interesting if `A` does and it executed within the [`SpeedCeiling`],
+/// as measured by a [`TimeObserver`].
+///
+/// Unlike `feedback_and!`, the ceiling can follow the median speed of the corpus.
+/// Inputs without a measured runtime are judged by `A` alone.
+/// Novelty of rejected inputs is discarded, so a faster input reaching the same coverage is still interesting later.
+pub struct SpeedGatedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// The wrapped novelty feedback
+    pub first: A,
+    name: Cow<'static, str>,
+    time_handle: Handle<TimeObserver>,
+    ceiling: SpeedCeiling,
+    /// The runtime of the current input, if it was accepted and measured
+    runtime: Option<Duration>,
+    /// The number of novel inputs rejected for being too slow
+    rejected: u64,
+    // The previous run's result of `Self::is_interesting`
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+    phantom: PhantomData<S>,
+}
+
+impl<A, S> Debug for SpeedGatedFeedback<A, S>
+where
+    A: Feedback<S> + Debug,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpeedGatedFeedback")
+            .field("name", &self.name)
+            .field("first", &self.first)
+            .field("ceiling", &self.ceiling)
+            .field("rejected", &self.rejected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, S> Feedback<S> for SpeedGatedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_named_metadata::<SpeedGateMetadata>(&self.name) {
+            state.add_named_metadata(&self.name, SpeedGateMetadata::default());
+        }
+        self.first.init_state(state)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.runtime = None;
+        let res = self.gate(state, manager, input, observers, exit_kind)?;
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some(runtime) = self.runtime.take() {
+            state
+                .named_metadata_or_insert_with(&self.name, SpeedGateMetadata::default)
+                .add(runtime);
+        }
+        self.first
+            .append_metadata(state, manager, observers, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.runtime = None;
+        self.first.discard_metadata(state, input)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result
+            .ok_or(crate::feedbacks::premature_last_result_err())
+    }
+}
+
+impl<A, S> SpeedGatedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State + HasNamedMetadata,
+{
+    /// Asks the wrapped feedback and checks the runtime of the novel inputs against the ceiling
+    fn gate<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        if !self
+            .first
+            .is_interesting(state, manager, input, observers, exit_kind)?
+        {
+            return Ok(false);
+        }
+
+        let Some(runtime) = *observers
+            .get(&self.time_handle)
+            .ok_or_else(|| {
+                Error::key_not_found(format!("TimeObserver {}", self.time_handle.name()))
+            })?
+            .last_runtime()
+        else {
+            return Ok(true);
+        };
+        let median = state
+            .named_metadata::<SpeedGateMetadata>(&self.name)
+            .ok()
+            .and_then(SpeedGateMetadata::median);
+        if self
+            .ceiling
+            .limit(median)
+            .is_some_and(|limit| runtime > limit)
+        {
+            self.rejected += 1;
+            log::debug!(
+                "Rejected a novel input running {runtime:?} (ceiling {:?}, {} rejected so far)",
+                self.ceiling,
+                self.rejected
+            );
+            self.first.discard_metadata(state, input)?;
+            return Ok(false);
+        }
+
+        self.runtime = Some(runtime);
+        Ok(true)
+    }
+}
+
+impl<A, S> Named for SpeedGatedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<A, S> SpeedGatedFeedback<A, S>
+where
+    A: Feedback<S>,
+    S: State,
+{
+    /// Creates a new [`SpeedGatedFeedback`], accepting the novel inputs of `first`
+    /// that ran within the given `ceiling`, as measured by `time_observer`.
+    /// Fails if the factor of a [`SpeedCeiling::RelativeToMedian`] is not positive and finite.
+    pub fn new(
+        first: A,
+        time_observer: &TimeObserver,
+        ceiling: SpeedCeiling,
+    ) -> Result<Self, Error> {
+        ceiling.validate()?;
+        let name = Cow::from(format!("SpeedGated({})", first.name()));
+        Ok(Self {
+            first,
+            name,
+            time_handle: time_observer.handle(),
+            ceiling,
+            runtime: None,
+            rejected: 0,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The ceiling inputs have to run within
+    #[must_use]
+    pub fn ceiling(&self) -> SpeedCeiling {
+        self.ceiling
+    }
+
+    /// The number of novel inputs rejected for being too slow so far
+    #[must_use]
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use core::time::Duration;
+
+    use libafl_bolts::{tuples::tuple_list, Named};
+
+    use crate::{
+        events::{EventFirer, NopEventManager},
+        executors::ExitKind,
+        feedbacks::{
+            speed_gate::{SpeedCeiling, SpeedGateMetadata, SPEED_GATE_WINDOW},
+            Feedback, SpeedGatedFeedback,
+        },
+        inputs::BytesInput,
+        observers::{Observer, ObserversTuple, TimeObserver},
+        state::{test::test_std_state, State},
+        Error,
+    };
+
+    /// Finds every input novel and counts how often its novelty got discarded
+    #[derive(Debug, Default)]
+    struct DiscardCounter {
+        discarded: usize,
+    }
+
+    impl Named for DiscardCounter {
+        fn name(&self) -> &Cow<'static, str> {
+            static NAME: Cow<'static, str> = Cow::Borrowed("DiscardCounter");
+            &NAME
+        }
+    }
+
+    impl<S> Feedback<S> for DiscardCounter
+    where
+        S: State,
+    {
+        fn is_interesting<EM, OT>(
+            &mut self,
+            _state: &mut S,
+            _manager: &mut EM,
+            _input: &S::Input,
+            _observers: &OT,
+            _exit_kind: &ExitKind,
+        ) -> Result<bool, Error>
+        where
+            EM: EventFirer<State = S>,
+            OT: ObserversTuple<S>,
+        {
+            Ok(true)
+        }
+
+        fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+            self.discarded += 1;
+            Ok(())
+        }
+
+        #[cfg(feature = "track_hit_feedbacks")]
+        fn last_result(&self) -> Result<bool, Error> {
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn test_speed_ceiling() {
+        let mut meta = SpeedGateMetadata::default();
+        let ceiling = SpeedCeiling::RelativeToMedian(2.0);
+        assert_eq!(ceiling.limit(meta.median()), None);
+
+        for ms in [30, 10, 20] {
+            meta.add(Duration::from_millis(ms));
+        }
+        assert_eq!(meta.median(), Some(Duration::from_millis(20)));
+        assert_eq!(
+            ceiling.limit(meta.median()),
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(
+            SpeedCeiling::Absolute(Duration::from_millis(5)).limit(meta.median()),
+            Some(Duration::from_millis(5))
+        );
+
+        // Huge factors saturate instead of panicking
+        assert_eq!(
+            SpeedCeiling::RelativeToMedian(f64::MAX).limit(meta.median()),
+            Some(Duration::MAX)
+        );
+        for factor in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(SpeedCeiling::RelativeToMedian(factor).validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_speed_gate_window() {
+        let mut meta = SpeedGateMetadata::default();
+        for _ in 0..SPEED_GATE_WINDOW {
+            meta.add(Duration::from_secs(1));
+        }
+        // The old, slow runs are evicted by the fast ones
+        for _ in 0..=SPEED_GATE_WINDOW / 2 {
+            meta.add(Duration::from_millis(1));
+        }
+        assert_eq!(meta.exec_times.len(), SPEED_GATE_WINDOW);
+        assert_eq!(meta.median(), Some(Duration::from_millis(1)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_speed_gate_discards_rejected() {
+        let mut state = test_std_state::<BytesInput>();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+        let time_observer = TimeObserver::new("time");
+        let mut feedback = SpeedGatedFeedback::new(
+            DiscardCounter::default(),
+            &time_observer,
+            SpeedCeiling::Absolute(Duration::from_millis(1)),
+        )
+        .unwrap();
+        feedback.init_state(&mut state).unwrap();
+        let mut observers = tuple_list!(time_observer);
+
+        // Without a measured runtime, the novel input is kept
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        assert_eq!(feedback.first.discarded, 0);
+
+        // Too slow: rejected, and the wrapped feedback forgets the novelty
+        observers.0.pre_exec(&mut state, &input).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        observers
+            .0
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        assert_eq!(feedback.rejected(), 1);
+        assert_eq!(feedback.first.discarded, 1);
+    }
+}
@@ -0,0 +1,216 @@
+//! The [`MaxStackDepthFeedback`] considers inputs interesting that reach a new maximum call-stack depth,
+//! directing the fuzzer towards deep recursion and nesting.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle},
+    observers::{ObserversTuple, StackDepthObserver},
+    state::State,
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The maximum stack depth seen so far, stored in the state keyed by the name of the feedback.
+/// Also added to interesting testcases, holding the depth they reached.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct StackDepthMetadata {
+    /// The maximum stack depth
+    pub max_depth: u64,
+}
+
+impl_serdeany!(StackDepthMetadata);
+
+/// Considers a testcase interesting if it reached a deeper call stack than all previous runs,
+/// as reported by a [`StackDepthObserver`].
+#[derive(Clone, Debug)]
+pub struct MaxStackDepthFeedback {
+    observer_handle: Handle<StackDepthObserver>,
+    name: Cow<'static, str>,
+    /// The depth of the current run, if it is a new maximum
+    new_max: Option<u64>,
+}
+
+impl<S> Feedback<S> for MaxStackDepthFeedback
+where
+    S: State + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_named_metadata::<StackDepthMetadata>(&self.name) {
+            state.add_named_metadata(&self.name, StackDepthMetadata::default());
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let depth = observers
+            .get(&self.observer_handle)
+            .ok_or_else(|| {
+                Error::key_not_found(format!(
+                    "StackDepthObserver {}",
+                    self.observer_handle.name()
+                ))
+            })?
+            .last_depth();
+        let max_depth = state
+            .named_metadata::<StackDepthMetadata>(&self.name)
+            .map_or(0, |meta| meta.max_depth);
+        self.new_max = (depth > max_depth).then_some(depth);
+        Ok(self.new_max.is_some())
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some(depth) = self.new_max {
+            let meta = state.named_metadata_or_insert_with(&self.name, StackDepthMetadata::default);
+            meta.max_depth = meta.max_depth.max(depth);
+            testcase.add_metadata(StackDepthMetadata { max_depth: depth });
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.new_max = None;
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(self.new_max.is_some())
+    }
+}
+
+impl Named for MaxStackDepthFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl HasObserverHandle for MaxStackDepthFeedback {
+    type Observer = StackDepthObserver;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<StackDepthObserver> {
+        &self.observer_handle
+    }
+}
+
+impl MaxStackDepthFeedback {
+    /// Creates a new [`MaxStackDepthFeedback`] for the given [`StackDepthObserver`]
+    #[must_use]
+    pub fn new(observer: &StackDepthObserver) -> Self {
+        Self {
+            observer_handle: observer.handle(),
+            name: Cow::from(format!("MaxStackDepthFeedback_{}", observer.name())),
+            new_max: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr::addr_of_mut;
+
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, Named};
+
+    use crate::{
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, Feedback, MaxStackDepthFeedback, StackDepthMetadata},
+        inputs::BytesInput,
+        observers::{Observer, StackDepthObserver},
+        state::StdState,
+        HasMetadata, HasNamedMetadata,
+    };
+
+    #[test]
+    fn test_max_stack_depth_feedback() {
+        let mut depth = 0_u64;
+        let depth_ptr = addr_of_mut!(depth);
+        let observer = unsafe { StackDepthObserver::from_mut_ptr("stack_depth", depth_ptr) };
+        let mut feedback = MaxStackDepthFeedback::new(&observer);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut observers = tuple_list!(observer);
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        // A new maximum, a shallower run, a deeper but discarded run, a new maximum, no change
+        let mut kept = vec![];
+        for (run_depth, keep) in [(3, true), (2, true), (9, false), (5, true), (5, true)] {
+            observers.0.pre_exec(&mut state, &input).unwrap();
+            unsafe { depth_ptr.write(run_depth) };
+            observers
+                .0
+                .post_exec(&mut state, &input, &ExitKind::Ok)
+                .unwrap();
+            if !feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap()
+            {
+                continue;
+            }
+            if keep {
+                let mut testcase = Testcase::new(input.clone());
+                feedback
+                    .append_metadata(&mut state, &mut mgr, &observers, &mut testcase)
+                    .unwrap();
+                kept.push(testcase.metadata::<StackDepthMetadata>().unwrap().max_depth);
+            } else {
+                feedback.discard_metadata(&mut state, &input).unwrap();
+            }
+        }
+        assert_eq!(kept, [3, 5]);
+        assert_eq!(
+            state
+                .named_metadata::<StackDepthMetadata>(feedback.name())
+                .unwrap()
+                .max_depth,
+            5
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! The [`TemplateFilenameFeedback`] names testcases after a template with placeholders,
+//! e.g., `id:{id},src:{parent},op:{op},time:{t}` for `AFL++`-like filenames.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use libafl_bolts::{current_time, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    mutators::LogMutationMetadata,
+    observers::ObserversTuple,
+    state::{HasCorpus, HasExecutions, HasSolutions, HasStartTime, State},
+    Error, HasMetadata,
+};
+
+/// A placeholder of a [`FilenameTemplate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilenamePlaceholder {
+    /// `{id}`: the id the testcase will get in its corpus
+    Id,
+    /// `{parent}`: the id of the parent testcase, or `none`
+    Parent,
+    /// `{op}`: the logged mutations (joined with `+`) if the testcase carries a [`LogMutationMetadata`],
+    /// `seed` for testcases without a parent and `mutation` otherwise
+    Op,
+    /// `{t}`: the milliseconds since the fuzzer started
+    Time,
+    /// `{execs}`: the number of executions so far
+    Execs,
+}
+
+impl FilenamePlaceholder {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "id" => Some(Self::Id),
+            "parent" => Some(Self::Parent),
+            "op" => Some(Self::Op),
+            "t" => Some(Self::Time),
+            "execs" => Some(Self::Execs),
+            _ => None,
+        }
+    }
+}
+
+/// A part of a [`FilenameTemplate`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum TemplatePart {
+    Literal(String),
+    Placeholder(FilenamePlaceholder),
+}
+
+/// A parsed filename template, e.g., `id:{id},src:{parent},op:{op},time:{t}`.
+/// Use `{{` and `}}` for literal braces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilenameTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl FilenameTemplate {
+    /// Parses a template, failing on unknown placeholders and unbalanced braces
+    pub fn parse(template: &str) -> Result<Self, Error> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(Error::illegal_argument(format!(
+                            "Unclosed '{{' in filename template {template:?}"
+                        )));
+                    }
+                    let placeholder = FilenamePlaceholder::parse(&name).ok_or_else(|| {
+                        Error::illegal_argument(format!(
+                            "Unknown placeholder {{{name}}} in filename template {template:?}, expected one of {{id}}, {{parent}}, {{op}}, {{t}}, {{execs}}"
+                        ))
+                    })?;
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(core::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Placeholder(placeholder));
+                }
+                '}' => {
+                    return Err(Error::illegal_argument(format!(
+                        "Unbalanced '}}' in filename template {template:?}"
+                    )));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        if parts.is_empty() {
+            return Err(Error::illegal_argument("The filename template is empty"));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Expands the template, with `value` returning the value of each placeholder
+    pub fn expand<F>(&self, mut value: F) -> String
+    where
+        F: FnMut(FilenamePlaceholder) -> String,
+    {
+        let mut filename = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => filename.push_str(literal),
+                TemplatePart::Placeholder(placeholder) => filename.push_str(&value(*placeholder)),
+            }
+        }
+        filename
+    }
+}
+
+/// Names each new testcase after a [`FilenameTemplate`], mirroring `AFL++`'s filenames,
+/// without having to write a closure for a [`crate::feedbacks::custom_testcase_filename::CustomTestcaseFilenameFeedback`].
+/// Is never interesting (use with an Eager OR).
+/// Note: Use only in conjunction with a [`Corpus`] type that writes to disk.
+/// Note: In the objective chain, call [`TemplateFilenameFeedback::for_solutions`] so that `{id}` refers to the solutions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateFilenameFeedback {
+    template: FilenameTemplate,
+    solutions: bool,
+}
+
+impl TemplateFilenameFeedback {
+    /// Creates a new [`TemplateFilenameFeedback`], failing if the template is invalid
+    pub fn new(template: &str) -> Result<Self, Error> {
+        Ok(Self {
+            template: FilenameTemplate::parse(template)?,
+            solutions: false,
+        })
+    }
+
+    /// Takes `{id}` from the solutions, for use in the objective chain
+    #[must_use]
+    pub fn for_solutions(mut self) -> Self {
+        self.solutions = true;
+        self
+    }
+}
+
+impl Named for TemplateFilenameFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("TemplateFilenameFeedback");
+        &NAME
+    }
+}
+
+impl<S> Feedback<S> for TemplateFilenameFeedback
+where
+    S: State + HasCorpus + HasSolutions + HasExecutions + HasStartTime,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let id: CorpusId = if self.solutions {
+            state.solutions().peek_free_id()
+        } else {
+            state.corpus().peek_free_id()
+        };
+        let filename = self.template.expand(|placeholder| match placeholder {
+            FilenamePlaceholder::Id => id.to_string(),
+            FilenamePlaceholder::Parent => testcase
+                .parent_id()
+                .map_or_else(|| "none".to_string(), |parent| parent.to_string()),
+            FilenamePlaceholder::Op => {
+                if let Ok(log) = testcase.metadata::<LogMutationMetadata>() {
+                    let mut op = String::new();
+                    for (i, name) in log.list.iter().enumerate() {
+                        if i > 0 {
+                            op.push('+');
+                        }
+                        op.push_str(name);
+                    }
+                    op
+                } else if testcase.parent_id().is_none() {
+                    "seed".to_string()
+                } else {
+                    "mutation".to_string()
+                }
+            }
+            FilenamePlaceholder::Time => current_time()
+                .saturating_sub(*state.start_time())
+                .as_millis()
+                .to_string(),
+            FilenamePlaceholder::Execs => state.executions().to_string(),
+        });
+        *testcase.filename_mut() = Some(filename);
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::feedbacks::template_filename::{FilenamePlaceholder, FilenameTemplate};
+
+    #[test]
+    fn test_filename_template() {
+        let template =
+            FilenameTemplate::parse("id:{id},src:{parent},op:{op},time:{t}{{x}}").unwrap();
+        let filename = template.expand(|placeholder| match placeholder {
+            FilenamePlaceholder::Id => "7".to_string(),
+            FilenamePlaceholder::Parent => "none".to_string(),
+            FilenamePlaceholder::Op => "seed".to_string(),
+            FilenamePlaceholder::Time => "42".to_string(),
+            FilenamePlaceholder::Execs => unreachable!(),
+        });
+        assert_eq!(filename, "id:7,src:none,op:seed,time:42{x}");
+
+        assert!(FilenameTemplate::parse("id:{id},{nope}").is_err());
+        assert!(FilenameTemplate::parse("id:{id").is_err());
+        assert!(FilenameTemplate::parse("id}").is_err());
+        assert!(FilenameTemplate::parse("").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! The [`TriageHookFeedback`] hands each solution to an external triage command
+//! and pauses the fuzzer until the command finished.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    vec::Vec,
+};
+use std::{
+    ffi::{OsStr, OsString},
+    fs,
+    path::PathBuf,
+    process::Command,
+};
+
+use libafl_bolts::{fs::write_file_atomic, impl_serdeany, AsSlice, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Testcase, events::EventFirer, executors::ExitKind, feedbacks::Feedback,
+    inputs::HasTargetBytes, observers::ObserversTuple, state::State, Error, HasMetadata,
+};
+
+/// The env var the triage command gets the exit kind of the solution in, e.g., `Crash` or `Timeout`
+pub const TRIAGE_EXIT_KIND_ENV: &str = "LIBAFL_TRIAGE_EXIT_KIND";
+
+/// The outcome of the triage command, added to the solution by the [`TriageHookFeedback`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriageHookMetadata {
+    /// The exit code of the triage command, `None` if it was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+impl_serdeany!(TriageHookMetadata);
+
+/// Nop feedback that runs a triage command for each solution and blocks until the command returns,
+/// for synchronous triage pipelines. The testcase is never interesting (use with an OR
+/// next to the crash or timeout feedback in the objective).
+///
+/// The command is called with the path of a file holding the target bytes of the solution as its last argument,
+/// and the exit kind in [`TRIAGE_EXIT_KIND_ENV`]. The file is overwritten for the next solution,
+/// so copy it if the command needs it afterwards. A failing command, or one that cannot be started at all,
+/// is logged, but does not stop the fuzzer.
+#[derive(Debug, Clone)]
+pub struct TriageHookFeedback {
+    name: Cow<'static, str>,
+    program: OsString,
+    args: Vec<OsString>,
+    input_path: PathBuf,
+    exit_kind: Option<ExitKind>,
+}
+
+impl TriageHookFeedback {
+    /// Creates a new [`TriageHookFeedback`] running `program` with `args`, followed by the path of the solution.
+    /// The solutions are written to `<work_dir>/.triage_input_<pid>` for the command, `work_dir` is created if needed.
+    pub fn new<P, A, I, W>(program: P, args: A, work_dir: W) -> Result<Self, Error>
+    where
+        P: AsRef<OsStr>,
+        A: IntoIterator<Item = I>,
+        I: AsRef<OsStr>,
+        W: Into<PathBuf>,
+    {
+        let work_dir = work_dir.into();
+        fs::create_dir_all(&work_dir)?;
+        Ok(Self {
+            name: Cow::Borrowed("TriageHookFeedback"),
+            program: program.as_ref().to_owned(),
+            args: args
+                .into_iter()
+                .map(|arg| arg.as_ref().to_owned())
+                .collect(),
+            input_path: work_dir.join(format!(".triage_input_{}", std::process::id())),
+            exit_kind: None,
+        })
+    }
+
+    /// Runs the triage command for the given target bytes, blocking until it returns.
+    /// Returns `None` if the command could not be started.
+    fn run_hook(
+        &self,
+        bytes: &[u8],
+        exit_kind: ExitKind,
+    ) -> Result<Option<TriageHookMetadata>, Error> {
+        write_file_atomic(&self.input_path, bytes)?;
+        log::info!(
+            "Pausing for the triage of a solution ({exit_kind:?}) by {:?}",
+            self.program
+        );
+        let status = match Command::new(&self.program)
+            .args(&self.args)
+            .arg(&self.input_path)
+            .env(TRIAGE_EXIT_KIND_ENV, format!("{exit_kind:?}"))
+            .status()
+        {
+            Ok(status) => status,
+            Err(err) => {
+                log::error!(
+                    "Failed to run the triage command {:?}, skipping the triage: {err}",
+                    self.program
+                );
+                return Ok(None);
+            }
+        };
+        if !status.success() {
+            log::warn!("The triage command failed with {status}");
+        }
+        log::info!("Triage done, resuming");
+        Ok(Some(TriageHookMetadata {
+            exit_code: status.code(),
+        }))
+    }
+}
+
+impl<S> Feedback<S> for TriageHookFeedback
+where
+    S: State,
+    S::Input: HasTargetBytes,
+{
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.exit_kind = Some(*exit_kind);
+        Ok(false)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let Some(input) = testcase.input() else {
+            return Ok(());
+        };
+        let exit_kind = self.exit_kind.take().unwrap_or(ExitKind::Crash);
+        if let Some(meta) = self.run_hook(input.target_bytes().as_slice(), exit_kind)? {
+            testcase.add_metadata(meta);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.exit_kind = None;
+        Ok(())
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+impl Named for TriageHookFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::{executors::ExitKind, feedbacks::triage_hook::TriageHookFeedback};
+
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(miri, ignore)]
+    fn test_triage_hook() {
+        let dir = env::temp_dir().join(format!("libafl_triage_hook_test_{}", std::process::id()));
+        let out = dir.join("triaged");
+        let script = format!("cp \"$0\" {} && exit 3", out.display());
+        let feedback = TriageHookFeedback::new("sh", ["-c", script.as_str()], &dir).unwrap();
+
+        let meta = feedback.run_hook(b"crash", ExitKind::Crash).unwrap();
+        assert_eq!(meta.unwrap().exit_code, Some(3));
+        assert_eq!(fs::read(&out).unwrap(), b"crash");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_triage_hook_spawn_failure() {
+        let dir = env::temp_dir().join(format!(
+            "libafl_triage_hook_spawn_test_{}",
+            std::process::id()
+        ));
+        let feedback =
+            TriageHookFeedback::new("libafl_no_such_triage_command", [""; 0], &dir).unwrap();
+
+        // the fuzzer keeps going without the triage
+        assert!(feedback
+            .run_hook(b"crash", ExitKind::Crash)
+            .unwrap()
+            .is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,11 @@
 //! The `Fuzzer` is the main struct for a fuzz campaign.
 
-use alloc::string::ToString;
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{fmt::Debug, marker::PhantomData, time::Duration};
 
 use libafl_bolts::current_time;
@@ -10,7 +15,7 @@ use crate::{
     corpus::{Corpus, CorpusId, HasCurrentCorpusId, HasTestcase, Testcase},
     events::{Event, EventConfig, EventFirer, EventProcessor, ProgressReporter},
     executors::{Executor, ExitKind, HasObservers},
-    feedbacks::Feedback,
+    feedbacks::{Feedback, NamedObjectivesMetadata},
     inputs::UsesInput,
     mark_feature_time,
     observers::ObserversTuple,
@@ -26,6 +31,11 @@ use crate::{
 #[cfg(feature = "introspection")]
 use crate::{monitors::PerfFeature, state::HasClientPerfMonitor};
 
+#[cfg(feature = "std")]
+pub mod selftest;
+#[cfg(feature = "std")]
+pub use selftest::{selftest, SelftestReport};
+
 /// Send a monitor update all 15 (or more) seconds
 const STATS_TIMEOUT_DEFAULT: Duration = Duration::from_secs(15);
 
@@ -278,6 +288,32 @@ pub enum ExecuteInputResult {
     Solution,
 }
 
+/// A corpus the solutions of one named objective are routed to,
+/// see [`StdFuzzer::with_objective_corpus`]
+pub trait ObjectiveCorpus<I>: Debug {
+    /// Adds a solution to this corpus
+    fn add_solution(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error>;
+
+    /// The number of solutions in this corpus
+    fn solutions_count(&self) -> usize;
+}
+
+impl<C> ObjectiveCorpus<C::Input> for C
+where
+    C: Corpus + Debug,
+{
+    fn add_solution(&mut self, testcase: Testcase<C::Input>) -> Result<CorpusId, Error> {
+        self.add(testcase)
+    }
+
+    fn solutions_count(&self) -> usize {
+        self.count()
+    }
+}
+
+/// The corpora registered for named objectives, with their names
+type ObjectiveCorpora<I> = Vec<(Cow<'static, str>, Box<dyn ObjectiveCorpus<I>>)>;
+
 /// Your default fuzzer instance, for everyday use.
 #[derive(Debug)]
 pub struct StdFuzzer<CS, F, OF, OT>
@@ -290,6 +326,7 @@ where
     scheduler: CS,
     feedback: F,
     objective: OF,
+    objective_corpora: ObjectiveCorpora<<CS::State as UsesInput>::Input>,
     phantom: PhantomData<OT>,
 }
 
@@ -506,6 +543,7 @@ where
                 let executions = *state.executions();
                 // The input is a solution, add it to the respective corpus
                 let mut testcase = Testcase::with_executions(input, executions);
+                testcase.add_metadata(*exit_kind);
                 testcase.set_parent_id_optional(*state.corpus().current());
                 if let Ok(mut tc) = state.current_testcase_mut() {
                     tc.found_objective();
@@ -515,13 +553,13 @@ where
                     .append_hit_feedbacks(testcase.hit_objectives_mut())?;
                 self.objective_mut()
                     .append_metadata(state, manager, observers, &mut testcase)?;
-                state.solutions_mut().add(testcase)?;
+                self.route_solution(state, testcase)?;
 
                 if send_events {
                     manager.fire(
                         state,
                         Event::Objective {
-                            objective_size: state.solutions().count(),
+                            objective_size: self.objective_size(state),
                             executions,
                             time: current_time(),
                         },
@@ -627,18 +665,19 @@ where
         )?;
 
         if is_solution {
+            testcase.add_metadata(exit_kind);
             #[cfg(feature = "track_hit_feedbacks")]
             self.objective_mut()
                 .append_hit_feedbacks(testcase.hit_objectives_mut())?;
             self.objective_mut()
                 .append_metadata(state, manager, &*observers, &mut testcase)?;
-            let idx = state.solutions_mut().add(testcase)?;
+            let idx = self.route_solution(state, testcase)?;
 
             let executions = *state.executions();
             manager.fire(
                 state,
                 Event::Objective {
-                    objective_size: state.solutions().count(),
+                    objective_size: self.objective_size(state),
                     executions,
                     time: current_time(),
                 },
@@ -782,10 +821,105 @@ where
             scheduler,
             feedback,
             objective,
+            objective_corpora: Vec::new(),
             phantom: PhantomData,
         }
     }
 
+    /// Routes the solutions of the [`crate::feedbacks::NamedObjectiveFeedback`] called `name`
+    /// to `corpus`, instead of the state's solutions
+    #[must_use]
+    pub fn with_objective_corpus<C>(mut self, name: &'static str, corpus: C) -> Self
+    where
+        C: Corpus<Input = <CS::State as UsesInput>::Input> + Debug + 'static,
+    {
+        self.objective_corpora
+            .push((Cow::Borrowed(name), Box::new(corpus)));
+        self
+    }
+
+    /// The corpus registered for the named objective `name`, if any
+    #[must_use]
+    pub fn objective_corpus(
+        &self,
+        name: &str,
+    ) -> Option<&dyn ObjectiveCorpus<<CS::State as UsesInput>::Input>> {
+        self.objective_corpora
+            .iter()
+            .find(|(corpus_name, _)| corpus_name == name)
+            .map(|(_, corpus)| &**corpus)
+    }
+
+    /// Adds `input` as a solution of the named objective `objective`, without running it,
+    /// e.g., to import a known crash. It is routed like the solutions found while fuzzing.
+    pub fn add_objective<EM>(
+        &mut self,
+        state: &mut CS::State,
+        manager: &mut EM,
+        input: <CS::State as UsesInput>::Input,
+        objective: &str,
+    ) -> Result<CorpusId, Error>
+    where
+        EM: EventFirer<State = CS::State>,
+        CS::State: HasSolutions,
+    {
+        let executions = *state.executions();
+        let mut testcase = Testcase::with_executions(input, executions);
+        testcase
+            .metadata_or_insert_with(NamedObjectivesMetadata::default)
+            .names
+            .push(String::from(objective));
+        let idx = self.route_solution(state, testcase)?;
+        manager.fire(
+            state,
+            Event::Objective {
+                objective_size: self.objective_size(state),
+                executions,
+                time: current_time(),
+            },
+        )?;
+        Ok(idx)
+    }
+
+    /// Adds a solution to the corpus of the first of its named objectives that has one,
+    /// else to the state's solutions
+    fn route_solution(
+        &mut self,
+        state: &mut CS::State,
+        testcase: Testcase<<CS::State as UsesInput>::Input>,
+    ) -> Result<CorpusId, Error>
+    where
+        CS::State: HasSolutions,
+    {
+        let routed = testcase
+            .metadata::<NamedObjectivesMetadata>()
+            .ok()
+            .and_then(|meta| {
+                meta.names.iter().find_map(|name| {
+                    self.objective_corpora
+                        .iter()
+                        .position(|(corpus_name, _)| corpus_name == name)
+                })
+            });
+        match routed {
+            Some(pos) => self.objective_corpora[pos].1.add_solution(testcase),
+            None => state.solutions_mut().add(testcase),
+        }
+    }
+
+    /// The number of solutions, over the state's solutions and all objective corpora
+    fn objective_size(&self, state: &CS::State) -> usize
+    where
+        CS::State: HasSolutions,
+    {
+        state.solutions().count()
+            + self
+                .objective_corpora
+                .iter()
+                .map(|(_, corpus)| corpus.solutions_count())
+                .sum::<usize>()
+    }
+
     /// Runs the input and triggers observers and feedback
     pub fn execute_input<E, EM>(
         &mut self,
@@ -926,4 +1060,65 @@ pub mod test {
             unimplemented!()
         }
     }
+
+    #[test]
+    fn test_named_objective_corpora() {
+        use libafl_bolts::rands::StdRand;
+
+        use crate::{
+            corpus::{Corpus, InMemoryCorpus},
+            events::NopEventManager,
+            executors::test::MapExecutor,
+            feedback_or_fast,
+            feedbacks::{ConstFeedback, MaxMapFeedback, NamedObjectiveFeedback, TimeoutFeedback},
+            fuzzer::{Evaluator, StdFuzzer},
+            inputs::BytesInput,
+            schedulers::QueueScheduler,
+            state::{HasSolutions, StdState},
+        };
+
+        let mut executor = MapExecutor::new(false);
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = feedback_or_fast!(
+            NamedObjectiveFeedback::new("hang", TimeoutFeedback::new()),
+            NamedObjectiveFeedback::new("coverage", MaxMapFeedback::new(executor.map_observer()))
+        );
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective)
+            .with_objective_corpus("hang", InMemoryCorpus::new())
+            .with_objective_corpus("coverage", InMemoryCorpus::new());
+
+        // The empty input times out, the first other one covers a new entry, its repetition nothing
+        for bytes in [vec![], vec![1], vec![1]] {
+            fuzzer
+                .evaluate_input(&mut state, &mut executor, &mut mgr, BytesInput::new(bytes))
+                .unwrap();
+        }
+        let count = |fuzzer: &StdFuzzer<_, _, _, _>, name| {
+            fuzzer.objective_corpus(name).unwrap().solutions_count()
+        };
+        assert_eq!(count(&fuzzer, "hang"), 1);
+        assert_eq!(count(&fuzzer, "coverage"), 1);
+        assert_eq!(state.solutions().count(), 0);
+
+        // Solutions added by objective id are routed the same way,
+        // those of objectives without a corpus end up in the state's solutions
+        fuzzer
+            .add_objective(&mut state, &mut mgr, BytesInput::new(vec![2]), "hang")
+            .unwrap();
+        fuzzer
+            .add_objective(&mut state, &mut mgr, BytesInput::new(vec![3]), "assertion")
+            .unwrap();
+        assert_eq!(count(&fuzzer, "hang"), 2);
+        assert_eq!(count(&fuzzer, "coverage"), 1);
+        assert_eq!(state.solutions().count(), 1);
+    }
 }
@@ -0,0 +1,218 @@
+//! A self test of the fuzzing pipeline against a built-in trivial target with a known crash,
+//! to validate an installation or build without a real target.
+
+use core::time::Duration;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::{
+    current_nanos, current_time,
+    rands::StdRand,
+    shmem::{ShMemProvider, StdShMemProvider},
+    tuples::tuple_list,
+    AsSlice, AsSliceMut,
+};
+
+#[cfg(all(feature = "fork", unix))]
+use crate::executors::{Executor, ForkserverExecutor};
+use crate::{
+    corpus::{Corpus, InMemoryCorpus, OnDiskCorpus},
+    events::NopEventManager,
+    executors::{ExitKind, InProcessExecutor},
+    feedbacks::{CrashFeedback, MaxMapFeedback},
+    fuzzer::{Evaluator, Fuzzer, StdFuzzer},
+    inputs::{BytesInput, HasTargetBytes},
+    mutators::{havoc_mutations, StdScheduledMutator},
+    observers::StdMapObserver,
+    schedulers::QueueScheduler,
+    stages::StdMutationalStage,
+    state::{HasExecutions, HasSolutions, StdState},
+    Error,
+};
+
+/// The size of the coverage map of the self test target
+const SELFTEST_MAP_SIZE: usize = 16;
+
+/// The input prefix the self test target crashes on
+pub const SELFTEST_CRASH_PREFIX: &[u8] = b"LIB";
+
+/// A minimal `AFL++` forkserver in POSIX shell, run with the input file as its only argument.
+/// It does the new-style handshake without options, then forks a child per request that aborts
+/// on inputs starting with [`SELFTEST_CRASH_PREFIX`], and reports its pid and wait status.
+/// The pipes are opened through `/dev/fd`, as not every shell can redirect fds above 9.
+#[cfg(all(feature = "fork", unix))]
+const SELFTEST_FORKSERVER: &str = r#"
+put() { printf "$(printf '\\%03o\\%03o\\%03o\\%03o' $(($1 & 255)) $(($1 >> 8 & 255)) $(($1 >> 16 & 255)) $(($1 >> 24 & 255)))" >/dev/fd/199; }
+get() { [ "$(dd bs=4 count=1 </dev/fd/198 2>/dev/null | wc -c)" -eq 4 ]; }
+put 1095126017; get || exit 1; put 0; put 1095126017
+while get; do
+  sh -c 'case "$(dd bs=3 count=1 <"$1" 2>/dev/null)" in LIB) kill -ABRT $$;; esac' child "$1" &
+  pid=$!; put $pid; wait $pid; code=$?
+  if [ $code -gt 128 ]; then put $((code - 128)); else put $((code << 8)); fi
+done
+"#;
+
+/// The outcome of a successful [`selftest`]
+#[derive(Debug, Clone)]
+pub struct SelftestReport {
+    /// The executions it took to find the crash
+    pub executions: u64,
+    /// The time it took to find the crash
+    pub elapsed: Duration,
+    /// The directory the crash was written to
+    pub crash_dir: PathBuf,
+}
+
+/// Fuzzes a built-in trivial target until it finds its known crash, to confirm that the pipeline
+/// works in this environment: a shared memory coverage map, a map feedback, a crash objective,
+/// and a solutions corpus on disk, written to `crashes` in `out_dir`.
+///
+/// The target runs in-process, it reports a crash on inputs starting with [`SELFTEST_CRASH_PREFIX`]
+/// and covers a new map entry for each matching byte. Fails if no crash was found within `time_limit`.
+/// Where the [`StdShMemProvider`] is served, the shared memory service has to run already.
+///
+/// With the `fork` feature on unix, it then runs a [`ForkserverExecutor`] against a forkserver
+/// written in shell (`/bin/sh` has to exist), to check the handshake and that both a passing and
+/// the crashing input make the round trip with the right [`ExitKind`]. This covers the protocol,
+/// not the instrumentation of a real target.
+pub fn selftest(out_dir: &Path, time_limit: Duration) -> Result<SelftestReport, Error> {
+    let crash_dir = out_dir.join("crashes");
+
+    let mut shmem_provider = StdShMemProvider::new()?;
+    let mut shmem = shmem_provider.new_shmem(SELFTEST_MAP_SIZE)?;
+    let map_ptr = shmem.as_slice_mut().as_mut_ptr();
+
+    let mut harness = |input: &BytesInput| {
+        let target = input.target_bytes();
+        let buf = target.as_slice();
+        // # Safety
+        // All indexes are within the map, which outlives the executor.
+        unsafe { map_ptr.write(1) };
+        for (idx, expected) in SELFTEST_CRASH_PREFIX.iter().enumerate() {
+            if buf.get(idx) != Some(expected) {
+                return ExitKind::Ok;
+            }
+            unsafe { map_ptr.add(idx + 1).write(1) };
+        }
+        ExitKind::Crash
+    };
+
+    // # Safety
+    // The shared map outlives the observer.
+    let observer =
+        unsafe { StdMapObserver::from_mut_ptr("selftest_edges", map_ptr, SELFTEST_MAP_SIZE) };
+    let mut feedback = MaxMapFeedback::new(&observer);
+    let mut objective = CrashFeedback::new();
+
+    let mut state = StdState::new(
+        StdRand::with_seed(current_nanos()),
+        InMemoryCorpus::new(),
+        OnDiskCorpus::new(&crash_dir)?,
+        &mut feedback,
+        &mut objective,
+    )?;
+    let mut mgr = NopEventManager::new();
+    let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+    let mut executor = InProcessExecutor::new(
+        &mut harness,
+        tuple_list!(observer),
+        &mut fuzzer,
+        &mut state,
+        &mut mgr,
+    )?;
+
+    fuzzer.add_input(
+        &mut state,
+        &mut executor,
+        &mut mgr,
+        BytesInput::new(b"AAAA".to_vec()),
+    )?;
+
+    let mutator = StdScheduledMutator::new(havoc_mutations());
+    let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+    let start = current_time();
+    while state.solutions().count() == 0 {
+        if current_time().saturating_sub(start) > time_limit {
+            return Err(Error::unknown(format!(
+                "The self test found no crash within {time_limit:?} ({} executions)",
+                state.executions()
+            )));
+        }
+        fuzzer.fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr)?;
+    }
+    let elapsed = current_time().saturating_sub(start);
+
+    let written = fs::read_dir(&crash_dir)?
+        .filter_map(Result::ok)
+        .any(|entry| !entry.file_name().to_string_lossy().starts_with('.'));
+    if !written {
+        return Err(Error::illegal_state(format!(
+            "The self test found the crash, but did not write it to {}",
+            crash_dir.display()
+        )));
+    }
+
+    let report = SelftestReport {
+        executions: *state.executions(),
+        elapsed,
+        crash_dir,
+    };
+
+    #[cfg(all(feature = "fork", unix))]
+    {
+        let input_file = out_dir.join(".selftest_input");
+        let mut executor = ForkserverExecutor::builder()
+            .program("/bin/sh")
+            .arg("-c")
+            .arg(SELFTEST_FORKSERVER)
+            .arg("selftest")
+            .arg_input_file(&input_file)
+            .timeout(Duration::from_secs(5))
+            .forkserver_timeout(Duration::from_secs(5))
+            .build(tuple_list!())?;
+        let mut crashing = SELFTEST_CRASH_PREFIX.to_vec();
+        crashing.push(b'!');
+        for (bytes, expected) in [
+            (b"AAAA".to_vec(), ExitKind::Ok),
+            (crashing, ExitKind::Crash),
+        ] {
+            let input = BytesInput::new(bytes);
+            let exit_kind = executor.run_target(&mut fuzzer, &mut state, &mut mgr, &input)?;
+            if exit_kind != expected {
+                return Err(Error::illegal_state(format!(
+                    "The self test forkserver reported {exit_kind:?} instead of {expected:?} for {input:?}"
+                )));
+            }
+        }
+        drop(executor);
+        // The input file may already be gone
+        let _ = fs::remove_file(&input_file);
+    }
+
+    log::info!(
+        "Self test passed: found the crash after {} executions in {elapsed:?}",
+        report.executions
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::fs;
+
+    use crate::fuzzer::selftest::selftest;
+
+    #[test]
+    // the shared memory service does not run in tests
+    #[cfg_attr(any(miri, target_vendor = "apple", target_os = "android"), ignore)]
+    fn test_selftest() {
+        let out_dir = std::env::temp_dir().join(format!("libafl_selftest.{}", std::process::id()));
+        let report = selftest(&out_dir, Duration::from_secs(60)).unwrap();
+        assert!(report.executions > 0);
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
@@ -4,6 +4,7 @@ use alloc::vec::Vec;
 use core::marker::PhantomData;
 
 use libafl_bolts::rands::Rand;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     inputs::{bytes::BytesInput, Input},
@@ -86,13 +87,50 @@ where
     }
 }
 
+/// How the random generators pick the length of a new input
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputLenDistribution {
+    /// Every length from the minimum up to the maximum is equally likely
+    #[default]
+    Uniform,
+    /// Shorter lengths are more likely: first, a power of two is picked uniformly as upper bound,
+    /// then the length below it. Useful for targets that reject large inputs early.
+    Logarithmic,
+}
+
+impl InputLenDistribution {
+    /// Picks a length in `min_size..max_size`, or `min_size` if the range is empty
+    pub fn pick<R>(self, rand: &mut R, min_size: usize, max_size: usize) -> usize
+    where
+        R: Rand,
+    {
+        let span = max_size.saturating_sub(min_size);
+        if span == 0 {
+            return min_size;
+        }
+        match self {
+            Self::Uniform => min_size + rand.below(span),
+            Self::Logarithmic => {
+                let bits = (usize::BITS - span.leading_zeros()) as usize;
+                let bound = 1_usize
+                    .checked_shl(1 + rand.below(bits) as u32)
+                    .unwrap_or(usize::MAX)
+                    .min(span);
+                min_size + rand.below(bound)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 /// Generates random bytes
 pub struct RandBytesGenerator<S>
 where
     S: HasRand,
 {
+    min_size: usize,
     max_size: usize,
+    distribution: InputLenDistribution,
     phantom: PhantomData<S>,
 }
 
@@ -101,10 +139,9 @@ where
     S: HasRand,
 {
     fn generate(&mut self, state: &mut S) -> Result<BytesInput, Error> {
-        let mut size = state.rand_mut().below(self.max_size);
-        if size == 0 {
-            size = 1;
-        }
+        let size = self
+            .distribution
+            .pick(state.rand_mut(), self.min_size, self.max_size);
         let random_bytes: Vec<u8> = (0..size)
             .map(|_| state.rand_mut().below(256) as u8)
             .collect();
@@ -120,10 +157,26 @@ where
     #[must_use]
     pub fn new(max_size: usize) -> Self {
         Self {
+            min_size: 1,
             max_size,
+            distribution: InputLenDistribution::Uniform,
             phantom: PhantomData,
         }
     }
+
+    /// Sets the minimum number of bytes to generate, `1` by default.
+    #[must_use]
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Sets the distribution the lengths of the generated inputs follow.
+    #[must_use]
+    pub fn with_distribution(mut self, distribution: InputLenDistribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -132,7 +185,9 @@ pub struct RandPrintablesGenerator<S>
 where
     S: HasRand,
 {
+    min_size: usize,
     max_size: usize,
+    distribution: InputLenDistribution,
     phantom: PhantomData<S>,
 }
 
@@ -141,10 +196,9 @@ where
     S: HasRand,
 {
     fn generate(&mut self, state: &mut S) -> Result<BytesInput, Error> {
-        let mut size = state.rand_mut().below(self.max_size);
-        if size == 0 {
-            size = 1;
-        }
+        let size = self
+            .distribution
+            .pick(state.rand_mut(), self.min_size, self.max_size);
         let printables = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz \t\n!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~".as_bytes();
         let random_bytes: Vec<u8> = (0..size)
             .map(|_| *state.rand_mut().choose(printables))
@@ -161,8 +215,56 @@ where
     #[must_use]
     pub fn new(max_size: usize) -> Self {
         Self {
+            min_size: 1,
             max_size,
+            distribution: InputLenDistribution::Uniform,
             phantom: PhantomData,
         }
     }
+
+    /// Sets the minimum number of characters to generate, `1` by default.
+    #[must_use]
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Sets the distribution the lengths of the generated inputs follow.
+    #[must_use]
+    pub fn with_distribution(mut self, distribution: InputLenDistribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use crate::generators::InputLenDistribution;
+
+    #[test]
+    fn test_input_len_distribution() {
+        let mut rand = StdRand::with_seed(1337);
+        for distribution in [
+            InputLenDistribution::Uniform,
+            InputLenDistribution::Logarithmic,
+        ] {
+            assert_eq!(distribution.pick(&mut rand, 8, 8), 8);
+            for _ in 0..1000 {
+                let len = distribution.pick(&mut rand, 4, 4096);
+                assert!((4..4096).contains(&len));
+            }
+        }
+
+        let short = |distribution: InputLenDistribution, rand: &mut StdRand| {
+            (0..1000)
+                .filter(|_| distribution.pick(rand, 1, 4096) < 64)
+                .count()
+        };
+        assert!(
+            short(InputLenDistribution::Logarithmic, &mut rand)
+                > short(InputLenDistribution::Uniform, &mut rand)
+        );
+    }
 }
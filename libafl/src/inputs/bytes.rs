@@ -34,6 +34,12 @@ impl Input for BytesInput {
         write_file_atomic(path, &self.bytes)
     }
 
+    /// The raw bytes of this input
+    #[cfg(feature = "std")]
+    fn to_file_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.bytes.clone())
+    }
+
     /// Load the content of this input from a file
     #[cfg(feature = "std")]
     fn from_file<P>(path: P) -> Result<Self, Error>
@@ -46,6 +52,12 @@ impl Input for BytesInput {
         Ok(BytesInput::new(bytes))
     }
 
+    /// Load the content of this input from its raw bytes
+    #[cfg(feature = "std")]
+    fn from_file_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(BytesInput::new(bytes.to_vec()))
+    }
+
     /// Generate a name for this input
     fn generate_name(&self, _idx: usize) -> String {
         let mut hasher = RandomState::with_seeds(0, 0, 0, 0).build_hasher();
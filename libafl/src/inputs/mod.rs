@@ -23,6 +23,9 @@ pub use multi::*;
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 
+pub mod versioned;
+pub use versioned::{deserialize_versioned, serialize_versioned};
+
 use alloc::{
     boxed::Box,
     string::{String, ToString},
@@ -62,15 +65,20 @@ pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug {
 /// An input for the target
 #[cfg(feature = "std")]
 pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug {
-    /// Write this input to the file
+    /// Write this input to the file, in the versioned format of [`serialize_versioned`]
     fn to_file<P>(&self, path: P) -> Result<(), Error>
     where
         P: AsRef<Path>,
     {
-        write_file_atomic(path, &postcard::to_allocvec(self)?)
+        write_file_atomic(path, &self.to_file_bytes()?)
+    }
+
+    /// The bytes [`Input::to_file`] writes, to store this input elsewhere, e.g., in an archive
+    fn to_file_bytes(&self) -> Result<Vec<u8>, Error> {
+        serialize_versioned(self)
     }
 
-    /// Load the content of this input from a file
+    /// Load the content of this input from a file, migrating inputs stored by older versions
     fn from_file<P>(path: P) -> Result<Self, Error>
     where
         P: AsRef<Path>,
@@ -78,7 +86,13 @@ pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug {
         let mut file = File::open(path)?;
         let mut bytes = vec![];
         file.read_to_end(&mut bytes)?;
-        Ok(postcard::from_bytes(&bytes)?)
+        Self::from_file_bytes(&bytes)
+    }
+
+    /// Load the content of this input from bytes [`Input::to_file_bytes`] returned,
+    /// e.g., after decompressing them
+    fn from_file_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        deserialize_versioned(bytes)
     }
 
     /// Generate a name for this input, the user is responsible for making each name of testcase unique.
@@ -129,6 +129,35 @@ impl<I> MultipartInput<I> {
         self.names.push(name);
     }
 
+    /// Inserts a part at the given index, shifting all following parts.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `idx` is greater than the number of parts.
+    pub fn insert_part(&mut self, idx: usize, name: String, part: I) {
+        self.parts.insert(idx, part);
+        self.names.insert(idx, name);
+    }
+
+    /// Removes the part at the given index, returning its name and the part.
+    pub fn remove_part(&mut self, idx: usize) -> Option<(String, I)> {
+        if idx < self.parts.len() {
+            Some((self.names.remove(idx), self.parts.remove(idx)))
+        } else {
+            None
+        }
+    }
+
+    /// Swaps the parts at the given indices, along with their names.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if any index is out of bounds.
+    pub fn swap_parts(&mut self, a: usize, b: usize) {
+        self.parts.swap(a, b);
+        self.names.swap(a, b);
+    }
+
     /// Iterate over the parts of this input; no order is specified.
     pub fn iter(&self) -> impl Iterator<Item = (&str, &I)> {
         self.names.iter().map(String::as_ref).zip(self.parts())
@@ -0,0 +1,137 @@
+//! The versioned on-disk format of serialized inputs.
+//!
+//! Inputs written with [`Input::to_file`] start with a small header,
+//! so that corpora written by older versions of `LibAFL` can still be told apart and migrated.
+//! [`crate::inputs::BytesInput`] is stored as its raw bytes and does not use this format.
+//!
+//! The header consists of [`INPUT_FORMAT_MAGIC`], the format version, and a checksum of the
+//! serialized input, so that a legacy input that happens to start with the magic bytes is not
+//! mistaken for a versioned one.
+//!
+//! [`Input::to_file`]: crate::inputs::Input::to_file
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use libafl_bolts::Error;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The magic bytes every versioned input starts with
+pub const INPUT_FORMAT_MAGIC: &[u8; 4] = b"LAFI";
+
+/// The version of the input format written by this version of `LibAFL`
+pub const INPUT_FORMAT_VERSION: u8 = 1;
+
+/// The size of the header preceding the serialized input: the magic bytes, the version,
+/// and the little endian `u64` checksum of the serialized input
+pub const INPUT_FORMAT_HEADER_LEN: usize = INPUT_FORMAT_MAGIC.len() + 1 + 8;
+
+/// If we already warned about loading inputs in the legacy format
+static LEGACY_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// The 64 bit FNV-1a hash of `bytes`, stable across platforms and builds
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Serializes `input` with [`postcard`], prefixed by the current format header
+pub fn serialize_versioned<I>(input: &I) -> Result<Vec<u8>, Error>
+where
+    I: Serialize,
+{
+    let payload = postcard::to_allocvec(input)?;
+    let mut bytes = Vec::with_capacity(INPUT_FORMAT_HEADER_LEN + payload.len());
+    bytes.extend_from_slice(INPUT_FORMAT_MAGIC);
+    bytes.push(INPUT_FORMAT_VERSION);
+    bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// Splits a versioned input into its version and payload,
+/// or returns `None` if `bytes` do not start with a valid header
+fn split_header(bytes: &[u8]) -> Option<(u8, &[u8])> {
+    let rest = bytes.strip_prefix(INPUT_FORMAT_MAGIC.as_slice())?;
+    let (&version, rest) = rest.split_first()?;
+    if rest.len() < 8 {
+        return None;
+    }
+    let (sum, payload) = rest.split_at(8);
+    (u64::from_le_bytes(sum.try_into().ok()?) == checksum(payload)).then_some((version, payload))
+}
+
+/// Deserializes an input written by [`serialize_versioned`], or by an older version of `LibAFL`.
+///
+/// Inputs without a valid header were written before the format was versioned.
+/// They are decoded as plain [`postcard`]; the files themselves are left untouched.
+/// The first such input logs a warning.
+/// Inputs written by a newer, unknown version result in an error naming that version.
+pub fn deserialize_versioned<I>(bytes: &[u8]) -> Result<I, Error>
+where
+    I: DeserializeOwned,
+{
+    match split_header(bytes) {
+        Some((INPUT_FORMAT_VERSION, payload)) => Ok(postcard::from_bytes(payload)?),
+        Some((version, _)) => Err(Error::serialize(format!(
+            "The input was stored in format version {version}, but this version of LibAFL only supports up to version {INPUT_FORMAT_VERSION}"
+        ))),
+        None => {
+            if !LEGACY_WARNED.swap(true, Ordering::Relaxed) {
+                log::warn!(
+                    "Loading inputs in the legacy unversioned format. The files are not converted, store the inputs again to upgrade them to version {INPUT_FORMAT_VERSION}"
+                );
+            }
+            postcard::from_bytes(bytes).map_err(|err| {
+                Error::serialize(format!(
+                    "Failed to load an input in the legacy unversioned format: {err:?}"
+                ))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::inputs::{
+        versioned::{checksum, deserialize_versioned, serialize_versioned, INPUT_FORMAT_MAGIC},
+        BytesInput, HasMutatorBytes,
+    };
+
+    #[test]
+    fn test_versioned_roundtrip() {
+        let input = BytesInput::new(vec![0x41, 0x00, 0xff]);
+        let bytes = serialize_versioned(&input).unwrap();
+        assert!(bytes.starts_with(INPUT_FORMAT_MAGIC));
+        let loaded: BytesInput = deserialize_versioned(&bytes).unwrap();
+        assert_eq!(loaded, input);
+    }
+
+    #[test]
+    fn test_versioned_legacy() {
+        // A `BytesInput` holding `[1, 2, 3]`, as serialized to disk before the format was versioned
+        const LEGACY: [u8; 4] = [0x03, 0x01, 0x02, 0x03];
+        let loaded: BytesInput = deserialize_versioned(&LEGACY).unwrap();
+        assert_eq!(loaded.bytes(), &[1, 2, 3]);
+
+        // A legacy input of 76 (`b'L'`) bytes, so that it starts with the magic bytes and version
+        let mut content = b"AFI\x01".to_vec();
+        content.resize(76, 0);
+        let legacy = postcard::to_allocvec(&BytesInput::new(content.clone())).unwrap();
+        assert!(legacy.starts_with(b"LAFI\x01"));
+        let loaded: BytesInput = deserialize_versioned(&legacy).unwrap();
+        assert_eq!(loaded.bytes(), &content[..]);
+    }
+
+    #[test]
+    fn test_versioned_unknown_version() {
+        let mut bytes: Vec<u8> = INPUT_FORMAT_MAGIC.to_vec();
+        bytes.push(0xff);
+        bytes.extend_from_slice(&checksum(&[0x00]).to_le_bytes());
+        bytes.push(0x00);
+        assert!(deserialize_versioned::<BytesInput>(&bytes).is_err());
+    }
+}
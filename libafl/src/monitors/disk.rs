@@ -11,7 +11,10 @@ use std::{
 use libafl_bolts::{current_time, format_duration_hms, ClientId};
 use serde_json::json;
 
-use crate::monitors::{ClientStats, Monitor, NopMonitor};
+use crate::{
+    events::LogSeverity,
+    monitors::{ClientStats, Monitor, NopMonitor},
+};
 
 /// Wrap a monitor and log the current state of the monitor into a TOML file.
 #[derive(Debug, Clone)]
@@ -53,6 +56,10 @@ where
         self.base.aggregate(name);
     }
 
+    fn log(&mut self, sender_id: ClientId, severity: LogSeverity, message: &str) {
+        self.base.log(sender_id, severity, message);
+    }
+
     fn display(&mut self, event_msg: &str, sender_id: ClientId) {
         let cur_time = current_time();
 
@@ -208,6 +215,10 @@ where
         self.base.set_start_time(time);
     }
 
+    fn log(&mut self, sender_id: ClientId, severity: LogSeverity, message: &str) {
+        self.base.log(sender_id, severity, message);
+    }
+
     fn display(&mut self, event_msg: &str, sender_id: ClientId) {
         if (self.log_record)(&mut self.base) {
             let file = OpenOptions::new()
@@ -0,0 +1,266 @@
+//! A monitor that wraps a base one and serves the current stats as JSON over HTTP.
+//!
+//! The server is a minimal `std::net` implementation answering `GET /stats`, one thread per connection,
+//! so no HTTP stack is pulled in. It is meant for dashboards polling a fuzzer on a trusted network.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::time::Duration;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, RwLock},
+    thread,
+};
+
+use hashbrown::HashMap;
+use libafl_bolts::{current_time, ClientId};
+use serde_json::json;
+
+use crate::{
+    events::LogSeverity,
+    monitors::{Aggregator, ClientStats, Monitor, NopMonitor},
+    Error,
+};
+
+/// The time a stats client may take to send its request or to receive the answer
+pub const STATS_CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wraps a base monitor and serves the current stats as JSON at `/stats`.
+///
+/// The served object contains the run time, number of clients, corpus size, objectives,
+/// executions, executions per second, the seconds since the last new corpus entry and objective,
+/// and the aggregated user stats (such as the coverage reported by the map feedbacks).
+#[derive(Debug, Clone)]
+pub struct HttpStatsMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    aggregator: Aggregator,
+    /// The latest stats, shared with the server threads
+    stats: Arc<RwLock<String>>,
+    /// The address the stats are served at
+    local_addr: SocketAddr,
+}
+
+impl<M> HttpStatsMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`HttpStatsMonitor`], serving the stats at `http://<addr>/stats`.
+    ///
+    /// Binds to `addr` right away and spawns a thread accepting the connections.
+    /// Each connection is answered on its own thread, so a slow client cannot hold up the others,
+    /// and is dropped after [`STATS_CONNECTION_TIMEOUT`].
+    pub fn new<A>(addr: A, base: M) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        log::info!("Serving stats at http://{local_addr}/stats");
+
+        let stats = Arc::new(RwLock::new(String::from("{}")));
+        let server_stats = stats.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let stats = server_stats.clone();
+                        thread::spawn(move || {
+                            if let Err(err) = serve_stats(stream, &stats) {
+                                log::debug!("Failed to serve stats: {err}");
+                            }
+                        });
+                    }
+                    Err(err) => log::warn!("Failed to accept stats connection: {err}"),
+                }
+            }
+        });
+
+        Ok(Self {
+            base,
+            aggregator: Aggregator::new(),
+            stats,
+            local_addr,
+        })
+    }
+
+    /// The address the stats are served at, e.g., to find the port after binding to port `0`
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Renders the current stats as JSON
+    fn stats_json(&mut self) -> String {
+        let cur_time = current_time();
+        let since = |time: Duration| {
+            if time.is_zero() {
+                None
+            } else {
+                Some(cur_time.saturating_sub(time).as_secs())
+            }
+        };
+        let last_find = self
+            .client_stats()
+            .iter()
+            .map(|client| client.last_corpus_time)
+            .max()
+            .and_then(since);
+        let last_objective = self
+            .client_stats()
+            .iter()
+            .map(|client| client.last_objective_time)
+            .max()
+            .and_then(since);
+        let user_stats: HashMap<&String, String> = self
+            .aggregator
+            .aggregated
+            .iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .collect();
+
+        json!({
+            "run_time": cur_time.saturating_sub(self.start_time()).as_secs(),
+            "clients": self.client_stats_count(),
+            "corpus": self.corpus_size(),
+            "objectives": self.objective_size(),
+            "executions": self.total_execs(),
+            "exec_sec": self.execs_per_sec(),
+            "last_find_secs": last_find,
+            "last_objective_secs": last_objective,
+            "user_stats": user_stats,
+        })
+        .to_string()
+    }
+}
+
+impl HttpStatsMonitor<NopMonitor> {
+    /// Creates a new [`HttpStatsMonitor`] without a base
+    pub fn nop<A>(addr: A) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        Self::new(addr, NopMonitor::new())
+    }
+}
+
+impl<M> Monitor for HttpStatsMonitor<M>
+where
+    M: Monitor,
+{
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    fn start_time(&self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn set_start_time(&mut self, time: Duration) {
+        self.base.set_start_time(time);
+    }
+
+    fn aggregate(&mut self, name: &str) {
+        self.aggregator.aggregate(name, self.base.client_stats());
+        self.base.aggregate(name);
+    }
+
+    fn log(&mut self, sender_id: ClientId, severity: LogSeverity, message: &str) {
+        self.base.log(sender_id, severity, message);
+    }
+
+    fn display(&mut self, event_msg: &str, sender_id: ClientId) {
+        let json = self.stats_json();
+        if let Ok(mut stats) = self.stats.write() {
+            *stats = json;
+        }
+        self.base.display(event_msg, sender_id);
+    }
+}
+
+/// Answers a single HTTP request, with the stats for `GET /stats` and a 404 otherwise
+fn serve_stats(stream: TcpStream, stats: &RwLock<String>) -> Result<(), Error> {
+    stream.set_read_timeout(Some(STATS_CONNECTION_TIMEOUT))?;
+    stream.set_write_timeout(Some(STATS_CONNECTION_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, we do not care about them
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/stats")) => {
+            let body = stats
+                .read()
+                .map_err(|_| Error::illegal_state("The stats lock is poisoned"))?
+                .clone();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        _ => {
+            String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        }
+    };
+
+    let mut stream = reader.into_inner();
+    stream.write_all(response.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use libafl_bolts::ClientId;
+
+    use crate::monitors::{HttpStatsMonitor, Monitor};
+
+    fn get(monitor: &HttpStatsMonitor<impl Monitor>, path: &str) -> String {
+        let mut stream = TcpStream::connect(monitor.local_addr()).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_http_stats_monitor() {
+        let mut monitor = HttpStatsMonitor::nop("127.0.0.1:0").unwrap();
+        monitor.client_stats_insert(ClientId(0));
+        monitor
+            .client_stats_mut_for(ClientId(0))
+            .update_corpus_size(42);
+        monitor.display("Testcase", ClientId(0));
+
+        // A client that never sends its request does not hold up the others
+        let _idle = TcpStream::connect(monitor.local_addr()).unwrap();
+
+        let response = get(&monitor, "/stats");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains("\"corpus\":42"), "{response}");
+
+        let response = get(&monitor, "/other");
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+    }
+}
@@ -21,10 +21,16 @@ use core::{fmt, fmt::Write, time::Duration};
 
 #[cfg(feature = "std")]
 pub use disk::{OnDiskJSONMonitor, OnDiskTOMLMonitor};
+#[cfg(all(feature = "http_stats_monitor", feature = "std"))]
+pub mod http_stats;
+#[cfg(all(feature = "http_stats_monitor", feature = "std"))]
+pub use http_stats::HttpStatsMonitor;
 use hashbrown::HashMap;
 use libafl_bolts::{current_time, format_duration_hms, ClientId};
 use serde::{Deserialize, Serialize};
 
+use crate::events::LogSeverity;
+
 #[cfg(feature = "afl_exec_sec")]
 const CLIENT_STATS_TIME_WINDOW_SECS: u64 = 5; // 5 seconds
 
@@ -581,6 +587,13 @@ pub trait Monitor {
 
     /// Aggregate the results in case there're multiple clients
     fn aggregate(&mut self, _name: &str) {}
+
+    /// Renders a log event fired by a client, see [`crate::events::EventFirer::log`].
+    /// By default, it is logged through [`log`], prefixed with the client, and filtered by its level,
+    /// see [`LogSeverity::set_max_level`].
+    fn log(&mut self, sender_id: ClientId, severity: LogSeverity, message: &str) {
+        log::log!(severity.into(), "[Client {}] {message}", sender_id.0);
+    }
 }
 
 /// Monitor that print exactly nothing.
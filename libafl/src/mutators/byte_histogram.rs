@@ -0,0 +1,118 @@
+//! The [`ByteHistogramMutator`] writes byte values that are under-represented in the campaign so far.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{rands::Rand, Named};
+
+use crate::{
+    inputs::HasMutatorBytes,
+    mutators::{MutationResult, Mutator},
+    observers::ByteHistogramMetadata,
+    state::HasRand,
+    Error, HasMetadata,
+};
+
+/// Overwrites a random byte of the input with a value picked by [`ByteHistogramMetadata::pick_rare`],
+/// i.e., biased towards byte values that rarely appeared in the executed inputs so far.
+///
+/// Needs a [`crate::observers::ByteHistogramObserver`] in the observers to fill the metadata,
+/// skips until then.
+#[derive(Default, Debug)]
+pub struct ByteHistogramMutator;
+
+impl<I, S> Mutator<I, S> for ByteHistogramMutator
+where
+    S: HasMetadata + HasRand,
+    I: HasMutatorBytes,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let size = input.bytes().len();
+        if size == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+        if !state.has_metadata::<ByteHistogramMetadata>() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let off = state.rand_mut().below(size);
+        let random = state.rand_mut().next();
+        let value = state.metadata::<ByteHistogramMetadata>()?.pick_rare(random);
+        if input.bytes()[off] == value {
+            return Ok(MutationResult::Skipped);
+        }
+        input.bytes_mut()[off] = value;
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for ByteHistogramMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("ByteHistogramMutator");
+        &NAME
+    }
+}
+
+impl ByteHistogramMutator {
+    /// Creates a new [`ByteHistogramMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::{ByteHistogramMutator, MutationResult, Mutator},
+        observers::ByteHistogramMetadata,
+        state::test::test_std_state,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_byte_histogram_mutator() {
+        let mut state = test_std_state::<BytesInput>();
+        let mut mutator = ByteHistogramMutator::new();
+
+        // Nothing to pick from yet, and nothing to overwrite
+        let mut input = BytesInput::new(vec![0; 8]);
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Skipped
+        );
+        let mut histogram = vec![1000; 256];
+        histogram[0x42] = 0;
+        let mut meta = ByteHistogramMetadata::new();
+        meta.add_histogram(&histogram);
+        state.add_metadata(meta);
+        assert_eq!(
+            mutator
+                .mutate(&mut state, &mut BytesInput::new(vec![]))
+                .unwrap(),
+            MutationResult::Skipped
+        );
+
+        // One byte is overwritten, mostly with the value never seen
+        let mut rare = 0;
+        for _ in 0..100 {
+            let mut input = BytesInput::new(vec![0; 8]);
+            if mutator.mutate(&mut state, &mut input).unwrap() == MutationResult::Skipped {
+                continue;
+            }
+            let changed: Vec<u8> = input
+                .bytes()
+                .iter()
+                .copied()
+                .filter(|byte| *byte != 0)
+                .collect();
+            assert_eq!(changed.len(), 1);
+            if changed[0] == 0x42 {
+                rare += 1;
+            }
+        }
+        assert!(rare > 50);
+    }
+}
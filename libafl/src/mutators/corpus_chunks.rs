@@ -0,0 +1,407 @@
+//! A dynamic dictionary of byte chunks that are common across the corpus,
+//! and the mutators inserting them into inputs.
+//!
+//! Unlike [`crate::mutators::Tokens`] or cmplog-based mutations, the [`CorpusChunks`] learn
+//! the structure of accepted inputs, which helps on structured binary formats.
+use alloc::{borrow::Cow, vec::Vec};
+
+use hashbrown::{HashMap, HashSet};
+use libafl_bolts::{rands::Rand, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    inputs::HasMutatorBytes,
+    mutators::{buffer_self_copy, mutations::buffer_copy, MutationResult, Mutator},
+    state::{HasCorpus, HasMaxSize, HasRand},
+    Error, HasMetadata,
+};
+
+/// The default maximum number of [`CorpusChunks`] kept
+pub const DEFAULT_CORPUS_CHUNKS_MAX_SIZE: usize = 256;
+/// The default number of mutations between two extractions of [`CorpusChunks`]
+pub const DEFAULT_CORPUS_CHUNKS_INTERVAL: u64 = 4096;
+/// The lengths of the chunks extracted from the corpus
+pub const CORPUS_CHUNK_LENS: [usize; 3] = [4, 8, 16];
+/// Only the newest corpus entries are scanned for chunks
+const MAX_SCANNED_INPUTS: usize = 256;
+/// Only the beginning of each input is scanned for chunks
+const MAX_SCANNED_LEN: usize = 4096;
+
+/// Counts in how many inputs each chunk appears, to extract the most common ones.
+#[derive(Debug, Default)]
+pub struct CorpusChunkCounter {
+    counts: HashMap<Vec<u8>, usize>,
+}
+
+impl CorpusChunkCounter {
+    /// Creates a new, empty [`CorpusChunkCounter`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the chunks of one input, each distinct chunk at most once
+    pub fn add_input(&mut self, bytes: &[u8]) {
+        let bytes = &bytes[..bytes.len().min(MAX_SCANNED_LEN)];
+        let mut seen = HashSet::new();
+        for len in CORPUS_CHUNK_LENS {
+            for chunk in bytes.windows(len) {
+                // Runs of a single byte value are better left to the havoc mutations
+                if chunk.iter().all(|b| *b == chunk[0]) || !seen.insert(chunk) {
+                    continue;
+                }
+                *self.counts.entry(chunk.to_vec()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Returns up to `max_size` chunks appearing in at least two inputs, the most common ones first.
+    /// For equally common chunks, longer ones are preferred.
+    #[must_use]
+    pub fn into_chunks(self, max_size: usize) -> Vec<Vec<u8>> {
+        let mut common: Vec<(Vec<u8>, usize)> = self
+            .counts
+            .into_iter()
+            .filter(|(_, count)| *count >= 2)
+            .collect();
+        common.sort_unstable_by(|(chunk_a, count_a), (chunk_b, count_b)| {
+            count_b
+                .cmp(count_a)
+                .then_with(|| chunk_b.len().cmp(&chunk_a.len()))
+                .then_with(|| chunk_a.cmp(chunk_b))
+        });
+        common.truncate(max_size);
+        common.into_iter().map(|(chunk, _)| chunk).collect()
+    }
+}
+
+/// A state metadata holding byte chunks that are common across the corpus.
+///
+/// The chunks are extracted again at most every `interval` mutations, and only if the corpus grew.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusChunks {
+    chunks: Vec<Vec<u8>>,
+    /// The corpus size at the last extraction
+    corpus_count: usize,
+    /// The mutations since the last extraction
+    mutations: u64,
+    /// The minimum number of mutations between two extractions
+    interval: u64,
+    /// The maximum number of chunks kept
+    max_size: usize,
+}
+
+libafl_bolts::impl_serdeany!(CorpusChunks);
+
+impl Default for CorpusChunks {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_CORPUS_CHUNKS_MAX_SIZE,
+            DEFAULT_CORPUS_CHUNKS_INTERVAL,
+        )
+    }
+}
+
+impl CorpusChunks {
+    /// Creates a new [`CorpusChunks`] metadata keeping at most `max_size` chunks,
+    /// extracted again at most every `interval` mutations.
+    #[must_use]
+    pub fn new(max_size: usize, interval: u64) -> Self {
+        Self {
+            chunks: vec![],
+            corpus_count: 0,
+            // Extract right away on first use
+            mutations: interval,
+            interval,
+            max_size,
+        }
+    }
+
+    /// The number of chunks currently known
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns `true` if no chunks are known
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The chunk at the given index
+    #[must_use]
+    pub fn get(&self, idx: usize) -> Option<&[u8]> {
+        self.chunks.get(idx).map(Vec::as_slice)
+    }
+
+    /// Counts a mutation and returns `true` if the chunks should be extracted again
+    fn tick(&mut self, corpus_count: usize) -> bool {
+        self.mutations += 1;
+        self.mutations >= self.interval && corpus_count != self.corpus_count
+    }
+
+    /// Replaces the chunks after an extraction
+    fn set_chunks(&mut self, chunks: Vec<Vec<u8>>, corpus_count: usize) {
+        self.chunks = chunks;
+        self.corpus_count = corpus_count;
+        self.mutations = 0;
+    }
+}
+
+/// Extracts the [`CorpusChunks`] from the newest corpus entries, if due.
+fn refresh_corpus_chunks<S>(state: &mut S, max_size: usize, interval: u64) -> Result<(), Error>
+where
+    S: HasCorpus + HasMetadata,
+    S::Input: HasMutatorBytes,
+{
+    let corpus_count = state.corpus().count();
+    if !state
+        .metadata_or_insert_with(|| CorpusChunks::new(max_size, interval))
+        .tick(corpus_count)
+    {
+        return Ok(());
+    }
+
+    let mut counter = CorpusChunkCounter::new();
+    let corpus = state.corpus();
+    for id in corpus
+        .ids()
+        .skip(corpus_count.saturating_sub(MAX_SCANNED_INPUTS))
+    {
+        let mut testcase = corpus.get(id)?.borrow_mut();
+        counter.add_input(testcase.load_input(corpus)?.bytes());
+    }
+
+    let meta = state.metadata_mut::<CorpusChunks>()?;
+    let chunks = counter.into_chunks(meta.max_size);
+    log::debug!("Extracted {} common chunks from the corpus", chunks.len());
+    meta.set_chunks(chunks, corpus_count);
+    Ok(())
+}
+
+/// Inserts a random [`CorpusChunks`] entry at a random position in the `Input`.
+#[derive(Debug)]
+pub struct CorpusChunkInsertMutator {
+    max_size: usize,
+    interval: u64,
+}
+
+impl<I, S> Mutator<I, S> for CorpusChunkInsertMutator
+where
+    S: HasCorpus + HasMetadata + HasRand + HasMaxSize,
+    S::Input: HasMutatorBytes,
+    I: HasMutatorBytes,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        refresh_corpus_chunks(state, self.max_size, self.interval)?;
+
+        let max_size = state.max_size();
+        let chunks_len = state.metadata::<CorpusChunks>()?.len();
+        if chunks_len == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+        let idx = state.rand_mut().below(chunks_len);
+
+        let size = input.bytes().len();
+        let off = state.rand_mut().below(size + 1);
+
+        let Some(chunk) = state.metadata::<CorpusChunks>()?.get(idx) else {
+            return Ok(MutationResult::Skipped);
+        };
+        let len = chunk.len();
+        if size + len > max_size {
+            return Ok(MutationResult::Skipped);
+        }
+
+        input.resize(size + len, 0);
+        unsafe {
+            buffer_self_copy(input.bytes_mut(), off, off + len, size - off);
+            buffer_copy(input.bytes_mut(), chunk, 0, off, len);
+        }
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for CorpusChunkInsertMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("CorpusChunkInsertMutator");
+        &NAME
+    }
+}
+
+impl Default for CorpusChunkInsertMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorpusChunkInsertMutator {
+    /// Create a new [`CorpusChunkInsertMutator`] `Mutation`, using the default dictionary size and interval.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_limits(
+            DEFAULT_CORPUS_CHUNKS_MAX_SIZE,
+            DEFAULT_CORPUS_CHUNKS_INTERVAL,
+        )
+    }
+
+    /// Create a new [`CorpusChunkInsertMutator`] `Mutation`, keeping at most `max_size` chunks,
+    /// extracted again at most every `interval` mutations.
+    ///
+    /// The limits only apply if this mutator is the first to create the [`CorpusChunks`] metadata.
+    #[must_use]
+    pub fn with_limits(max_size: usize, interval: u64) -> Self {
+        Self { max_size, interval }
+    }
+}
+
+/// Overwrites a random part of the `Input` with a random [`CorpusChunks`] entry.
+#[derive(Debug)]
+pub struct CorpusChunkReplaceMutator {
+    max_size: usize,
+    interval: u64,
+}
+
+impl<I, S> Mutator<I, S> for CorpusChunkReplaceMutator
+where
+    S: HasCorpus + HasMetadata + HasRand,
+    S::Input: HasMutatorBytes,
+    I: HasMutatorBytes,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        refresh_corpus_chunks(state, self.max_size, self.interval)?;
+
+        let size = input.bytes().len();
+        if size == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let chunks_len = state.metadata::<CorpusChunks>()?.len();
+        if chunks_len == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+        let idx = state.rand_mut().below(chunks_len);
+
+        let off = state.rand_mut().below(size);
+
+        let Some(chunk) = state.metadata::<CorpusChunks>()?.get(idx) else {
+            return Ok(MutationResult::Skipped);
+        };
+        let len = chunk.len().min(size - off);
+
+        unsafe {
+            buffer_copy(input.bytes_mut(), chunk, 0, off, len);
+        }
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for CorpusChunkReplaceMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("CorpusChunkReplaceMutator");
+        &NAME
+    }
+}
+
+impl Default for CorpusChunkReplaceMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorpusChunkReplaceMutator {
+    /// Create a new [`CorpusChunkReplaceMutator`] `Mutation`, using the default dictionary size and interval.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_limits(
+            DEFAULT_CORPUS_CHUNKS_MAX_SIZE,
+            DEFAULT_CORPUS_CHUNKS_INTERVAL,
+        )
+    }
+
+    /// Create a new [`CorpusChunkReplaceMutator`] `Mutation`, keeping at most `max_size` chunks,
+    /// extracted again at most every `interval` mutations.
+    ///
+    /// The limits only apply if this mutator is the first to create the [`CorpusChunks`] metadata.
+    #[must_use]
+    pub fn with_limits(max_size: usize, interval: u64) -> Self {
+        Self { max_size, interval }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        corpus::{Corpus, Testcase},
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::{
+            corpus_chunks::{
+                CorpusChunkCounter, CorpusChunkInsertMutator, CorpusChunkReplaceMutator,
+            },
+            MutationResult, Mutator,
+        },
+        state::{test::test_std_state, HasCorpus},
+    };
+
+    #[test]
+    fn test_corpus_chunk_counter() {
+        let header = b"\x89PNG\r\n\x1a\nIHDR";
+        let mut counter = CorpusChunkCounter::new();
+        counter.add_input(b"\x89PNG\r\n\x1a\nIHDRabcd");
+        counter.add_input(b"\x89PNG\r\n\x1a\nIHDRefgh");
+        counter.add_input(b"AAAAAAAAAAAAAAAAAAAA");
+        counter.add_input(b"AAAAAAAAAAAAAAAAAAAA");
+        let chunks = counter.into_chunks(2);
+
+        // Runs of a single value are skipped, the longest common chunk wins
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].len(), 8);
+        assert!(chunks
+            .iter()
+            .all(|chunk| header.windows(chunk.len()).any(|w| w == chunk.as_slice())));
+    }
+
+    #[test]
+    fn test_corpus_chunk_mutators() {
+        let mut state = test_std_state::<BytesInput>();
+        // Keep only the most common chunk, extracted on every mutation
+        let mut insert = CorpusChunkInsertMutator::with_limits(1, 1);
+        let mut replace = CorpusChunkReplaceMutator::with_limits(1, 1);
+
+        // Nothing to learn from an empty corpus
+        let mut input = BytesInput::new(b"xxxx".to_vec());
+        assert_eq!(
+            insert.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Skipped
+        );
+
+        for entry in [&b"HEADER01abc"[..], &b"HEADER01xyz"[..]] {
+            state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(entry.to_vec())))
+                .unwrap();
+        }
+        assert_eq!(
+            insert.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_eq!(input.bytes().len(), 12);
+        assert!(input.bytes().windows(8).any(|window| window == b"HEADER01"));
+
+        let mut input = BytesInput::new(b"xxxxxxxxxxxxxxxx".to_vec());
+        assert_eq!(
+            replace.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_eq!(input.bytes().len(), 16);
+        assert!(input.bytes().contains(&b'H'));
+    }
+}
@@ -0,0 +1,316 @@
+//! Mutations of length fields, detected from the comparisons logged by an
+//! [`crate::observers::AFLppCmpLogObserver`].
+//!
+//! Many parsers compare a length field read from the input against the actual size of the data.
+//! Mutating such a field on its own mostly trips the length check, so the mutations here change
+//! the declared length together with the payload it describes.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::cmp::min;
+
+use hashbrown::HashMap;
+use libafl_bolts::{rands::Rand, Named};
+
+use crate::{
+    corpus::{CorpusId, HasCurrentCorpusId},
+    inputs::HasMutatorBytes,
+    mutators::{MutationResult, Mutator},
+    observers::cmp::{AFLppCmpValuesMetadata, CmpValues},
+    state::{HasMaxSize, HasRand},
+    Error, HasMetadata,
+};
+
+/// The maximum number of bytes a [`LengthFieldMutator`] grows or shrinks a payload by
+pub const MAX_LENGTH_FIELD_DELTA: usize = 64;
+
+/// The maximum number of length field variants [`crate::mutators::AFLppRedQueen`] emits per input
+pub const MAX_LENGTH_FIELD_VARIANTS: usize = 32;
+
+/// A likely length field in the input, detected from a comparison of its value against a size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthField {
+    /// The offset of the field in the input
+    pub offset: usize,
+    /// The width of the field in bytes
+    pub width: usize,
+    /// If the field is stored big endian
+    pub big_endian: bool,
+    /// The length declared by the field
+    pub declared: u64,
+    /// The size the declared length was compared against
+    pub actual: u64,
+}
+
+impl LengthField {
+    /// The exclusive end offset of the field itself
+    #[must_use]
+    pub fn end(&self) -> usize {
+        self.offset + self.width
+    }
+
+    /// The exclusive end offset of the payload described by the field, capped at `len`
+    #[must_use]
+    pub fn payload_end(&self, len: usize) -> usize {
+        let declared = usize::try_from(self.declared).unwrap_or(usize::MAX);
+        min(self.end().saturating_add(declared), len)
+    }
+
+    /// Checks if `bytes` still hold the declared length at the offset of the field
+    #[must_use]
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        self.end() <= bytes.len()
+            && read_field(bytes, self.offset, self.width, self.big_endian) == self.declared
+    }
+
+    /// Writes `value`, truncated to the width of the field, into `bytes`
+    pub fn write(&self, bytes: &mut [u8], value: u64) {
+        let field = &mut bytes[self.offset..self.end()];
+        if self.big_endian {
+            field.copy_from_slice(&value.to_be_bytes()[8 - self.width..]);
+        } else {
+            field.copy_from_slice(&value.to_le_bytes()[..self.width]);
+        }
+    }
+}
+
+/// Reads the `width` bytes at `offset` as an unsigned integer
+fn read_field(bytes: &[u8], offset: usize, width: usize, big_endian: bool) -> u64 {
+    let mut buf = [0_u8; 8];
+    if big_endian {
+        buf[8 - width..].copy_from_slice(&bytes[offset..offset + width]);
+        u64::from_be_bytes(buf)
+    } else {
+        buf[..width].copy_from_slice(&bytes[offset..offset + width]);
+        u64::from_le_bytes(buf)
+    }
+}
+
+/// Finds likely length fields in `bytes`, given the comparisons logged for this input.
+///
+/// A numeric comparison marks a length field if one of its operands can be found in the input
+/// (in either endianness) and the other operand is a plausible size, i.e., at most the length
+/// of the input.
+/// Single byte comparisons are ignored, as their values match almost anywhere in the input.
+#[must_use]
+pub fn find_length_fields(
+    bytes: &[u8],
+    cmpvals: &HashMap<usize, Vec<CmpValues>>,
+) -> Vec<LengthField> {
+    let len = bytes.len();
+    let mut fields: Vec<LengthField> = Vec::new();
+
+    for cmp in cmpvals.values().flatten() {
+        let width = match cmp {
+            CmpValues::U16(_) => 2,
+            CmpValues::U32(_) => 4,
+            CmpValues::U64(_) => 8,
+            CmpValues::U8(_) | CmpValues::Bytes(_) => continue,
+        };
+        let Some((v0, v1)) = cmp.to_u64_tuple() else {
+            continue;
+        };
+        if len < width || v0 == v1 {
+            continue;
+        }
+
+        for (declared, actual) in [(v0, v1), (v1, v0)] {
+            if declared == 0 || actual > len as u64 {
+                continue;
+            }
+            for big_endian in [false, true] {
+                for offset in 0..=len - width {
+                    if read_field(bytes, offset, width, big_endian) != declared {
+                        continue;
+                    }
+                    let field = LengthField {
+                        offset,
+                        width,
+                        big_endian,
+                        declared,
+                        actual,
+                    };
+                    if !fields.contains(&field) {
+                        fields.push(field);
+                    }
+                }
+            }
+        }
+    }
+    fields
+}
+
+/// Grows (or shrinks) the payload of `field` by `amount` bytes and updates the declared length
+/// accordingly. New payload bytes repeat the last byte of the payload.
+/// Returns `false` if nothing changed.
+pub fn resize_length_field<I>(input: &mut I, field: &LengthField, grow: bool, amount: usize) -> bool
+where
+    I: HasMutatorBytes,
+{
+    let len = input.bytes().len();
+    if field.end() > len || amount == 0 {
+        return false;
+    }
+    let payload_end = field.payload_end(len);
+
+    let declared = if grow {
+        let fill = if payload_end > field.end() {
+            input.bytes()[payload_end - 1]
+        } else {
+            0
+        };
+        input.resize(len + amount, 0);
+        let bytes = input.bytes_mut();
+        bytes.copy_within(payload_end..len, payload_end + amount);
+        bytes[payload_end..payload_end + amount].fill(fill);
+        field.declared.wrapping_add(amount as u64)
+    } else {
+        let amount = min(amount, payload_end - field.end());
+        if amount == 0 {
+            return false;
+        }
+        input.drain(payload_end - amount..payload_end);
+        field.declared.wrapping_sub(amount as u64)
+    };
+
+    field.write(input.bytes_mut(), declared);
+    true
+}
+
+/// A [`Mutator`] changing length fields detected via cmplog together with the payload they describe.
+///
+/// It needs a valid [`AFLppCmpValuesMetadata`] in the state, as filled by the cmplog tracing stage.
+/// Either sets a length field to the size it was compared against,
+/// or grows or shrinks its payload while keeping the field consistent.
+///
+/// The fields are searched once per corpus entry; later mutations of the same entry only use
+/// the cached fields that still hold their declared length in the (possibly mutated) input.
+#[derive(Debug, Default)]
+pub struct LengthFieldMutator {
+    /// The length fields found for the last fuzzed corpus entry
+    cache: Option<(CorpusId, Vec<LengthField>)>,
+}
+
+impl<I, S> Mutator<I, S> for LengthFieldMutator
+where
+    S: HasMetadata + HasRand + HasMaxSize + HasCurrentCorpusId,
+    I: HasMutatorBytes,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let Some(meta) = state.metadata_map().get::<AFLppCmpValuesMetadata>() else {
+            return Ok(MutationResult::Skipped);
+        };
+        let fields = match state.current_corpus_id()? {
+            Some(id) => {
+                if self
+                    .cache
+                    .as_ref()
+                    .map_or(true, |(cached, _)| *cached != id)
+                {
+                    self.cache = Some((id, find_length_fields(input.bytes(), meta.orig_cmpvals())));
+                }
+                let (_, cached) = self.cache.as_ref().unwrap();
+                cached
+                    .iter()
+                    .filter(|field| field.matches(input.bytes()))
+                    .copied()
+                    .collect()
+            }
+            None => find_length_fields(input.bytes(), meta.orig_cmpvals()),
+        };
+        if fields.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let field = fields[state.rand_mut().below(fields.len())];
+        if field.declared != field.actual && state.rand_mut().coinflip(0.5) {
+            // make the declared length pass the check
+            field.write(input.bytes_mut(), field.actual);
+            return Ok(MutationResult::Mutated);
+        }
+
+        let amount = 1 + state.rand_mut().below(MAX_LENGTH_FIELD_DELTA);
+        let grow = state.rand_mut().coinflip(0.5);
+        let amount = if grow {
+            min(amount, state.max_size().saturating_sub(input.bytes().len()))
+        } else {
+            amount
+        };
+
+        if resize_length_field(input, &field, grow, amount) {
+            Ok(MutationResult::Mutated)
+        } else {
+            Ok(MutationResult::Skipped)
+        }
+    }
+}
+
+impl Named for LengthFieldMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("LengthFieldMutator");
+        &NAME
+    }
+}
+
+impl LengthFieldMutator {
+    /// Creates a new [`LengthFieldMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self { cache: None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use hashbrown::HashMap;
+
+    use crate::{
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::length_field::{find_length_fields, resize_length_field, LengthField},
+        observers::cmp::CmpValues,
+    };
+
+    #[test]
+    fn test_length_field() {
+        // a big endian u16 length, followed by a 3 byte payload and a trailer
+        let mut input = BytesInput::new(vec![0xaa, 0x00, 0x03, 1, 2, 3, 0xff]);
+        let mut cmpvals = HashMap::new();
+        cmpvals.insert(0, vec![CmpValues::U16((3, 5))]);
+
+        let fields = find_length_fields(input.bytes(), &cmpvals);
+        assert_eq!(fields.len(), 1);
+        let field = fields[0];
+        assert_eq!((field.offset, field.width, field.big_endian), (1, 2, true));
+        assert_eq!(field.actual, 5);
+
+        assert!(resize_length_field(&mut input, &field, true, 2));
+        assert_eq!(input.bytes(), &[0xaa, 0x00, 0x05, 1, 2, 3, 3, 3, 0xff]);
+
+        let field = find_length_fields(input.bytes(), &cmpvals)[0];
+        assert!(resize_length_field(&mut input, &field, false, 2));
+        assert_eq!(input.bytes(), &[0xaa, 0x00, 0x03, 1, 2, 3, 0xff]);
+    }
+
+    #[test]
+    fn test_length_field_skips_u8() {
+        let input = BytesInput::new(vec![3, 1, 3, 2, 3]);
+        let mut cmpvals = HashMap::new();
+        cmpvals.insert(0, vec![CmpValues::U8((3, 5))]);
+        assert!(find_length_fields(input.bytes(), &cmpvals).is_empty());
+    }
+
+    #[test]
+    fn test_length_field_matches() {
+        let field = LengthField {
+            offset: 1,
+            width: 2,
+            big_endian: false,
+            declared: 3,
+            actual: 5,
+        };
+        assert!(field.matches(&[0, 3, 0, 1]));
+        assert!(!field.matches(&[0, 4, 0, 1]));
+        assert!(!field.matches(&[0, 3]));
+    }
+}
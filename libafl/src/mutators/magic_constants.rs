@@ -0,0 +1,403 @@
+//! Campaign-wide "magic" constants, collected from comparison operands,
+//! and the mutators inserting them into inputs.
+//!
+//! Unlike [`crate::mutators::Tokens`], which are mostly static dictionaries,
+//! [`MagicConstants`] keeps growing (and forgetting) during the whole campaign,
+//! fed by the [`crate::stages::MagicConstantsStage`].
+use alloc::{borrow::Cow, vec::Vec};
+
+use hashbrown::HashMap;
+use libafl_bolts::{rands::Rand, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::CorpusId,
+    inputs::HasMutatorBytes,
+    mutators::{buffer_self_copy, mutations::buffer_copy, MutationResult, Mutator},
+    observers::cmp::CmpValues,
+    state::{HasMaxSize, HasRand},
+    Error, HasMetadata,
+};
+
+/// The default maximum number of [`MagicConstants`] kept
+pub const DEFAULT_MAGIC_CONSTANTS_MAX_SIZE: usize = 1024;
+/// The default number of generations after which a constant neither seen nor useful is dropped
+pub const DEFAULT_MAGIC_CONSTANTS_MAX_AGE: u64 = 256;
+
+/// A state metadata holding constants that appeared in comparisons anywhere in the campaign.
+///
+/// Each constant remembers the generation it was last seen in a comparison, or last helped
+/// to find a new testcase. Constants older than `max_age` generations get aged out,
+/// and if the set grows beyond `max_size`, the oldest constants are evicted first.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MagicConstants {
+    /// The constants, together with the generation they were last seen or used in
+    entries: Vec<(Vec<u8>, u64)>,
+    /// Maps each constant to its index in `entries`, for fast deduplication
+    index: HashMap<Vec<u8>, usize>,
+    /// The current generation, increased with every batch of added comparisons
+    generation: u64,
+    /// The maximum number of constants kept
+    max_size: usize,
+    /// The number of generations after which unused constants get dropped
+    max_age: u64,
+}
+
+libafl_bolts::impl_serdeany!(MagicConstants);
+
+impl Default for MagicConstants {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_MAGIC_CONSTANTS_MAX_SIZE,
+            DEFAULT_MAGIC_CONSTANTS_MAX_AGE,
+        )
+    }
+}
+
+impl MagicConstants {
+    /// Creates a new [`MagicConstants`] metadata keeping at most `max_size` constants,
+    /// each one dropped after `max_age` generations without being seen or used.
+    #[must_use]
+    pub fn new(max_size: usize, max_age: u64) -> Self {
+        Self {
+            entries: vec![],
+            index: HashMap::default(),
+            generation: 0,
+            max_size,
+            max_age,
+        }
+    }
+
+    /// The number of constants currently known
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no constants are known
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The constant at the given index
+    #[must_use]
+    pub fn get(&self, idx: usize) -> Option<&[u8]> {
+        self.entries.get(idx).map(|(bytes, _)| bytes.as_slice())
+    }
+
+    /// Iterates over the constants currently known
+    pub fn constants(&self) -> impl Iterator<Item = &[u8]> {
+        self.entries.iter().map(|(bytes, _)| bytes.as_slice())
+    }
+
+    /// Adds the given constants to the current generation, e.g., the ones of a previous run,
+    /// then enforces the size cap
+    pub fn extend<'a, T>(&mut self, constants: T)
+    where
+        T: IntoIterator<Item = &'a [u8]>,
+    {
+        for bytes in constants {
+            self.add_constant(bytes);
+        }
+        self.evict();
+    }
+
+    /// Adds a single constant, or refreshes it if it is already known
+    pub fn add_constant(&mut self, bytes: &[u8]) {
+        // Trivial constants (all zeros or all ones) are everywhere, they are no magic
+        if bytes.is_empty() || bytes.iter().all(|b| *b == 0 || *b == 0xff) {
+            return;
+        }
+        if let Some(idx) = self.index.get(bytes) {
+            self.entries[*idx].1 = self.generation;
+        } else {
+            self.index.insert(bytes.to_vec(), self.entries.len());
+            self.entries.push((bytes.to_vec(), self.generation));
+        }
+    }
+
+    /// Starts a new generation and adds the operands of the given comparisons,
+    /// then ages out stale constants and enforces the size cap.
+    pub fn add_cmp_values(&mut self, cmp_values: &[CmpValues]) {
+        self.generation += 1;
+        for cmp in cmp_values {
+            match cmp {
+                CmpValues::U8(_) => {
+                    // Single bytes are better left to the havoc mutations
+                }
+                CmpValues::U16((v0, v1)) => {
+                    self.add_constant(&v0.to_le_bytes());
+                    self.add_constant(&v1.to_le_bytes());
+                }
+                CmpValues::U32((v0, v1)) => {
+                    self.add_constant(&v0.to_le_bytes());
+                    self.add_constant(&v1.to_le_bytes());
+                }
+                CmpValues::U64((v0, v1)) => {
+                    self.add_constant(&v0.to_le_bytes());
+                    self.add_constant(&v1.to_le_bytes());
+                }
+                CmpValues::Bytes((v0, v1)) => {
+                    self.add_constant(v0);
+                    self.add_constant(v1);
+                }
+            }
+        }
+        self.evict();
+    }
+
+    /// Marks the constant at `idx` as useful, so it does not age out
+    pub fn refresh(&mut self, idx: usize) {
+        if let Some(entry) = self.entries.get_mut(idx) {
+            entry.1 = self.generation;
+        }
+    }
+
+    /// Drops constants older than `max_age`, then the oldest ones until at most `max_size` remain
+    fn evict(&mut self) {
+        let oldest_allowed = self.generation.saturating_sub(self.max_age);
+        self.entries.retain(|(_, seen)| *seen >= oldest_allowed);
+        if self.entries.len() > self.max_size {
+            self.entries
+                .sort_unstable_by(|(_, seen_a), (_, seen_b)| seen_b.cmp(seen_a));
+            self.entries.truncate(self.max_size);
+        }
+        if self.entries.len() != self.index.len() {
+            self.index = self
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(idx, (bytes, _))| (bytes.clone(), idx))
+                .collect();
+        }
+    }
+}
+
+/// Inserts a random [`MagicConstants`] entry at a random position in the `Input`.
+#[derive(Debug, Default)]
+pub struct MagicConstantInsert {
+    last_used: Option<usize>,
+}
+
+impl<I, S> Mutator<I, S> for MagicConstantInsert
+where
+    S: HasMetadata + HasRand + HasMaxSize,
+    I: HasMutatorBytes,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        self.last_used = None;
+        let max_size = state.max_size();
+        let Some(constants_len) = state
+            .metadata_map()
+            .get::<MagicConstants>()
+            .map(MagicConstants::len)
+            .filter(|len| *len > 0)
+        else {
+            return Ok(MutationResult::Skipped);
+        };
+        let idx = state.rand_mut().below(constants_len);
+
+        let size = input.bytes().len();
+        let off = state.rand_mut().below(size + 1);
+
+        let Some(constant) = state
+            .metadata_map()
+            .get::<MagicConstants>()
+            .and_then(|meta| meta.get(idx))
+        else {
+            return Ok(MutationResult::Skipped);
+        };
+        let len = constant.len();
+        if size + len > max_size {
+            return Ok(MutationResult::Skipped);
+        }
+
+        input.resize(size + len, 0);
+        unsafe {
+            buffer_self_copy(input.bytes_mut(), off, off + len, size - off);
+            buffer_copy(input.bytes_mut(), constant, 0, off, len);
+        }
+
+        self.last_used = Some(idx);
+        Ok(MutationResult::Mutated)
+    }
+
+    fn post_exec(&mut self, state: &mut S, new_corpus_idx: Option<CorpusId>) -> Result<(), Error> {
+        if let (Some(idx), Some(_)) = (self.last_used.take(), new_corpus_idx) {
+            if let Ok(meta) = state.metadata_mut::<MagicConstants>() {
+                meta.refresh(idx);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Named for MagicConstantInsert {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("MagicConstantInsert");
+        &NAME
+    }
+}
+
+impl MagicConstantInsert {
+    /// Create a new [`MagicConstantInsert`] `Mutation`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Overwrites a random part of the `Input` with a random [`MagicConstants`] entry.
+#[derive(Debug, Default)]
+pub struct MagicConstantReplace {
+    last_used: Option<usize>,
+}
+
+impl<I, S> Mutator<I, S> for MagicConstantReplace
+where
+    S: HasMetadata + HasRand,
+    I: HasMutatorBytes,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        self.last_used = None;
+        let size = input.bytes().len();
+        if size == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let Some(constants_len) = state
+            .metadata_map()
+            .get::<MagicConstants>()
+            .map(MagicConstants::len)
+            .filter(|len| *len > 0)
+        else {
+            return Ok(MutationResult::Skipped);
+        };
+        let idx = state.rand_mut().below(constants_len);
+
+        let off = state.rand_mut().below(size);
+
+        let Some(constant) = state
+            .metadata_map()
+            .get::<MagicConstants>()
+            .and_then(|meta| meta.get(idx))
+        else {
+            return Ok(MutationResult::Skipped);
+        };
+        let len = constant.len().min(size - off);
+
+        unsafe {
+            buffer_copy(input.bytes_mut(), constant, 0, off, len);
+        }
+
+        self.last_used = Some(idx);
+        Ok(MutationResult::Mutated)
+    }
+
+    fn post_exec(&mut self, state: &mut S, new_corpus_idx: Option<CorpusId>) -> Result<(), Error> {
+        if let (Some(idx), Some(_)) = (self.last_used.take(), new_corpus_idx) {
+            if let Ok(meta) = state.metadata_mut::<MagicConstants>() {
+                meta.refresh(idx);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Named for MagicConstantReplace {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("MagicConstantReplace");
+        &NAME
+    }
+}
+
+impl MagicConstantReplace {
+    /// Create a new [`MagicConstantReplace`] `Mutation`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::{
+            magic_constants::{MagicConstantInsert, MagicConstantReplace, MagicConstants},
+            MutationResult, Mutator,
+        },
+        observers::cmp::CmpValues,
+        state::test::test_std_state,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_magic_constants_age_and_cap() {
+        let mut constants = MagicConstants::new(2, 1);
+        constants.add_cmp_values(&[
+            CmpValues::U32((0x4141_4141, 0)),
+            CmpValues::Bytes((b"MAGIC".to_vec(), vec![])),
+        ]);
+        // zeros are skipped as trivial
+        assert_eq!(constants.len(), 2);
+
+        constants.add_cmp_values(&[CmpValues::U16((0x1337, 0x4242))]);
+        // the cap keeps the two newest
+        assert_eq!(constants.len(), 2);
+        assert!(constants.get(0).is_some());
+
+        constants.add_cmp_values(&[]);
+        constants.add_cmp_values(&[]);
+        // everything aged out
+        assert!(constants.is_empty());
+    }
+
+    #[test]
+    fn test_magic_constant_mutators() {
+        let mut state = test_std_state::<BytesInput>();
+        let mut input = BytesInput::new(b"xxxxxxxx".to_vec());
+        let mut insert = MagicConstantInsert::new();
+        let mut replace = MagicConstantReplace::new();
+
+        // Nothing to insert yet
+        assert_eq!(
+            insert.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Skipped
+        );
+        assert_eq!(
+            replace.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Skipped
+        );
+        state.add_metadata(MagicConstants::default());
+        assert_eq!(
+            insert.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Skipped
+        );
+
+        state
+            .metadata_mut::<MagicConstants>()
+            .unwrap()
+            .add_constant(b"MAGIC");
+        assert_eq!(
+            insert.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_eq!(input.bytes().len(), 13);
+        assert!(input.bytes().windows(5).any(|window| window == b"MAGIC"));
+
+        let mut input = BytesInput::new(b"xxxxxxxx".to_vec());
+        assert_eq!(
+            replace.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_eq!(input.bytes().len(), 8);
+        // At least the start of the constant is written, in place
+        assert!(input.bytes().contains(&b'M'));
+    }
+}
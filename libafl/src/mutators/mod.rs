@@ -19,6 +19,16 @@ pub mod grimoire;
 pub use grimoire::*;
 pub mod tuneable;
 pub use tuneable::*;
+pub mod magic_constants;
+pub use magic_constants::*;
+pub mod byte_histogram;
+pub use byte_histogram::*;
+pub mod corpus_chunks;
+pub use corpus_chunks::*;
+pub mod length_field;
+pub use length_field::*;
+pub mod regions;
+pub use regions::*;
 
 #[cfg(feature = "unicode")]
 pub mod string;
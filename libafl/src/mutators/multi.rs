@@ -1,8 +1,13 @@
 //! Mutator definitions for [`MultipartInput`]s. See [`crate::inputs::multi`] for details.
 
+use alloc::borrow::Cow;
 use core::cmp::{min, Ordering};
 
-use libafl_bolts::{rands::Rand, Error};
+use libafl_bolts::{
+    rands::Rand,
+    tuples::{tuple_list, tuple_list_type, Merge},
+    Error, Named,
+};
 
 use crate::{
     corpus::{Corpus, CorpusId},
@@ -17,6 +22,7 @@ use crate::{
             BytesSwapMutator, CrossoverInsertMutator, CrossoverReplaceMutator, DwordAddMutator,
             DwordInterestingMutator, QwordAddMutator, WordAddMutator, WordInterestingMutator,
         },
+        scheduled::{havoc_mutations, HavocMutationsType, StdScheduledMutator},
         token_mutations::{I2SRandReplace, TokenInsert, TokenReplace},
         MutationResult, Mutator,
     },
@@ -320,3 +326,309 @@ where
         }
     }
 }
+
+/// The default maximum number of parts a [`PartDuplicateMutator`] grows an input to
+pub const DEFAULT_MAX_PARTS: usize = 16;
+
+/// Duplicates a random part of a [`MultipartInput`], inserting the copy right after the original.
+/// Inputs that already have `max_parts` parts are skipped, so repeated havoc rounds cannot grow
+/// the number of parts without bound.
+#[derive(Debug)]
+pub struct PartDuplicateMutator {
+    max_parts: usize,
+}
+
+impl<I, S> Mutator<MultipartInput<I>, S> for PartDuplicateMutator
+where
+    I: Clone,
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultipartInput<I>,
+    ) -> Result<MutationResult, Error> {
+        if input.parts().is_empty() || input.parts().len() >= self.max_parts {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let idx = state.rand_mut().below(input.parts().len());
+        let name = input.names()[idx].clone();
+        let part = input.parts()[idx].clone();
+        input.insert_part(idx + 1, name, part);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for PartDuplicateMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("PartDuplicateMutator");
+        &NAME
+    }
+}
+
+impl Default for PartDuplicateMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartDuplicateMutator {
+    /// Creates a new [`PartDuplicateMutator`], growing inputs to at most [`DEFAULT_MAX_PARTS`] parts.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_max_parts(DEFAULT_MAX_PARTS)
+    }
+
+    /// Creates a new [`PartDuplicateMutator`], growing inputs to at most `max_parts` parts.
+    #[must_use]
+    pub fn with_max_parts(max_parts: usize) -> Self {
+        Self { max_parts }
+    }
+}
+
+/// Removes a random part of a [`MultipartInput`], keeping at least one part
+#[derive(Debug, Default)]
+pub struct PartDeleteMutator;
+
+impl<I, S> Mutator<MultipartInput<I>, S> for PartDeleteMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultipartInput<I>,
+    ) -> Result<MutationResult, Error> {
+        if input.parts().len() <= 1 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let idx = state.rand_mut().below(input.parts().len());
+        input.remove_part(idx);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for PartDeleteMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("PartDeleteMutator");
+        &NAME
+    }
+}
+
+impl PartDeleteMutator {
+    /// Creates a new [`PartDeleteMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Swaps two random parts of a [`MultipartInput`]
+#[derive(Debug, Default)]
+pub struct PartSwapMutator;
+
+impl<I, S> Mutator<MultipartInput<I>, S> for PartSwapMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultipartInput<I>,
+    ) -> Result<MutationResult, Error> {
+        let len = input.parts().len();
+        if len < 2 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let first = state.rand_mut().below(len);
+        // pick a different part by skipping over `first`
+        let second = (first + 1 + state.rand_mut().below(len - 1)) % len;
+        input.swap_parts(first, second);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for PartSwapMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("PartSwapMutator");
+        &NAME
+    }
+}
+
+impl PartSwapMutator {
+    /// Creates a new [`PartSwapMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Moves a random part of a [`MultipartInput`] to another position, shifting the parts in between
+#[derive(Debug, Default)]
+pub struct PartMoveMutator;
+
+impl<I, S> Mutator<MultipartInput<I>, S> for PartMoveMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultipartInput<I>,
+    ) -> Result<MutationResult, Error> {
+        let len = input.parts().len();
+        if len < 2 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let from = state.rand_mut().below(len);
+        let to = state.rand_mut().below(len - 1);
+        let (name, part) = input.remove_part(from).unwrap();
+        // moving to the same position would be a no-op
+        input.insert_part(if to >= from { to + 1 } else { to }, name, part);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for PartMoveMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("PartMoveMutator");
+        &NAME
+    }
+}
+
+impl PartMoveMutator {
+    /// Creates a new [`PartMoveMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Tuple type of the mutations operating on the parts of a [`MultipartInput`] as a whole
+pub type MultipartMutationsType = tuple_list_type!(
+    PartDuplicateMutator,
+    PartDeleteMutator,
+    PartSwapMutator,
+    PartMoveMutator
+);
+
+/// Tuple type of the mutations that compose the [`MultipartHavocMutator`]
+pub type MultipartHavocMutationsType<I> =
+    <HavocMutationsType<I> as Merge<MultipartMutationsType>>::MergeResult;
+
+/// A havoc mutator for [`MultipartInput`]s, mutating the parts as a whole as well as single parts
+pub type MultipartHavocMutator<I, S> =
+    StdScheduledMutator<MultipartInput<I>, MultipartHavocMutationsType<I>, S>;
+
+/// Get the mutations operating on the parts of a [`MultipartInput`] as a whole
+#[must_use]
+pub fn multipart_mutations() -> MultipartMutationsType {
+    tuple_list!(
+        PartDuplicateMutator::new(),
+        PartDeleteMutator::new(),
+        PartSwapMutator::new(),
+        PartMoveMutator::new(),
+    )
+}
+
+/// Get the mutations that compose the [`MultipartHavocMutator`]:
+/// the havoc mutations, each applied to a random part, and the [`multipart_mutations`].
+/// Like the other mutation sets, it can be merged with further mutations.
+#[must_use]
+pub fn multipart_havoc_mutations<I>() -> MultipartHavocMutationsType<I> {
+    havoc_mutations().merge(multipart_mutations())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use super::*;
+    use crate::{inputs::BytesInput, state::test::test_std_state};
+
+    fn input(parts: &[&str]) -> MultipartInput<BytesInput> {
+        let mut input = MultipartInput::new();
+        for part in parts {
+            input.add_part(
+                String::from(*part),
+                BytesInput::new(part.as_bytes().to_vec()),
+            );
+        }
+        input
+    }
+
+    fn names(input: &MultipartInput<BytesInput>) -> Vec<String> {
+        let mut names = input.names().to_vec();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_part_duplicate_is_bounded() {
+        let mut state = test_std_state::<MultipartInput<BytesInput>>();
+        let mut mutator = PartDuplicateMutator::with_max_parts(4);
+        let mut input = input(&["a", "b"]);
+
+        for _ in 0..16 {
+            mutator.mutate(&mut state, &mut input).unwrap();
+        }
+        assert_eq!(input.parts().len(), 4);
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Skipped
+        );
+        for (name, part) in input.iter() {
+            assert_eq!(name.as_bytes(), part.bytes());
+        }
+    }
+
+    #[test]
+    fn test_part_delete_keeps_one_part() {
+        let mut state = test_std_state::<MultipartInput<BytesInput>>();
+        let mut mutator = PartDeleteMutator::new();
+        let mut input = input(&["a", "b", "c"]);
+
+        for _ in 0..8 {
+            mutator.mutate(&mut state, &mut input).unwrap();
+        }
+        assert_eq!(input.parts().len(), 1);
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Skipped
+        );
+    }
+
+    #[test]
+    fn test_part_swap_and_move_permute() {
+        let mut state = test_std_state::<MultipartInput<BytesInput>>();
+        let original = input(&["a", "b", "c", "d"]);
+
+        let mut swapped = original.clone();
+        PartSwapMutator::new()
+            .mutate(&mut state, &mut swapped)
+            .unwrap();
+        assert_ne!(swapped.names(), original.names());
+        assert_eq!(names(&swapped), names(&original));
+
+        let mut moved = original.clone();
+        PartMoveMutator::new()
+            .mutate(&mut state, &mut moved)
+            .unwrap();
+        assert_ne!(moved.names(), original.names());
+        assert_eq!(names(&moved), names(&original));
+
+        for input in [swapped, moved] {
+            for (name, part) in input.iter() {
+                assert_eq!(name.as_bytes(), part.bytes());
+            }
+        }
+    }
+}
@@ -1278,6 +1278,132 @@ impl SpliceMutator {
     }
 }
 
+/// Two-point splice mutation for inputs with a bytes vector.
+///
+/// Replaces a middle region of the input, between two cut points, with the same region of another corpus entry,
+/// keeping the input's prefix and suffix. The length of the input does not change.
+#[derive(Debug, Default)]
+pub struct TwoPointSpliceMutator;
+
+impl<S> Mutator<S::Input, S> for TwoPointSpliceMutator
+where
+    S: HasCorpus + HasRand,
+    S::Input: HasMutatorBytes,
+{
+    #[allow(clippy::cast_sign_loss)]
+    fn mutate(&mut self, state: &mut S, input: &mut S::Input) -> Result<MutationResult, Error> {
+        // We don't want to use the testcase we're already using for splicing
+        let idx = random_corpus_id_with_disabled!(state.corpus(), state.rand_mut());
+        if let Some(cur) = state.corpus().current() {
+            if idx == *cur {
+                return Ok(MutationResult::Skipped);
+            }
+        }
+
+        let (first_diff, last_diff) = {
+            let mut other_testcase = state.corpus().get_from_all(idx)?.borrow_mut();
+            let other = other_testcase.load_input(state.corpus())?;
+
+            let (f, l) = locate_diffs(input.bytes(), other.bytes());
+
+            if f != l && f >= 0 && l >= 2 {
+                (f as usize, l as usize)
+            } else {
+                return Ok(MutationResult::Skipped);
+            }
+        };
+
+        let start = state.rand_mut().between(first_diff, last_diff);
+        let end = state.rand_mut().between(start + 1, last_diff + 1);
+
+        let other_testcase = state.corpus().get_from_all(idx)?.borrow_mut();
+        // Input will already be loaded.
+        let other = other_testcase.input().as_ref().unwrap();
+
+        input.bytes_mut()[start..end].copy_from_slice(&other.bytes()[start..end]);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for TwoPointSpliceMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("TwoPointSpliceMutator");
+        &NAME
+    }
+}
+
+impl TwoPointSpliceMutator {
+    /// Creates a new [`TwoPointSpliceMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Length-preserving splice mutation for inputs with a bytes vector.
+///
+/// Like the [`SpliceMutator`], it continues the input with the tail of another corpus entry after a single cut point,
+/// but only overwrites as many bytes as both have, so the length of the input does not change.
+#[derive(Debug, Default)]
+pub struct LengthPreservingSpliceMutator;
+
+impl<S> Mutator<S::Input, S> for LengthPreservingSpliceMutator
+where
+    S: HasCorpus + HasRand,
+    S::Input: HasMutatorBytes,
+{
+    #[allow(clippy::cast_sign_loss)]
+    fn mutate(&mut self, state: &mut S, input: &mut S::Input) -> Result<MutationResult, Error> {
+        // We don't want to use the testcase we're already using for splicing
+        let idx = random_corpus_id_with_disabled!(state.corpus(), state.rand_mut());
+        if let Some(cur) = state.corpus().current() {
+            if idx == *cur {
+                return Ok(MutationResult::Skipped);
+            }
+        }
+
+        let (first_diff, last_diff) = {
+            let mut other_testcase = state.corpus().get_from_all(idx)?.borrow_mut();
+            let other = other_testcase.load_input(state.corpus())?;
+
+            let (f, l) = locate_diffs(input.bytes(), other.bytes());
+
+            if f != l && f >= 0 && l >= 2 {
+                (f as usize, l as usize)
+            } else {
+                return Ok(MutationResult::Skipped);
+            }
+        };
+
+        let split_at = state.rand_mut().between(first_diff, last_diff);
+
+        let other_testcase = state.corpus().get_from_all(idx)?.borrow_mut();
+        // Input will already be loaded.
+        let other = other_testcase.input().as_ref().unwrap();
+
+        let end = min(input.bytes().len(), other.bytes().len());
+        input.bytes_mut()[split_at..end].copy_from_slice(&other.bytes()[split_at..end]);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for LengthPreservingSpliceMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("LengthPreservingSpliceMutator");
+        &NAME
+    }
+}
+
+impl LengthPreservingSpliceMutator {
+    /// Creates a new [`LengthPreservingSpliceMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
 // Converts a hex u8 to its u8 value: 'A' -> 10 etc.
 fn from_hex(hex: u8) -> Result<u8, Error> {
     match hex {
@@ -1648,4 +1774,42 @@ mod tests {
             < 500));
         Ok(())
     }
+
+    /// The spliced region is taken from the other corpus entry (all `0x42`), the rest is kept from the input
+    #[test]
+    fn test_splice_variants() -> Result<(), Error> {
+        let base = BytesInput::new((0..10).collect());
+
+        let mut state = test_state();
+        let mut two_point = TwoPointSpliceMutator::new();
+        let mut length_preserving = LengthPreservingSpliceMutator::new();
+
+        for _ in 0..1000 {
+            let mut mutated = base.clone();
+            assert_eq!(
+                two_point.mutate(&mut state, &mut mutated)?,
+                MutationResult::Mutated
+            );
+            assert_eq!(mutated.bytes.len(), base.bytes.len());
+            let start = mutated.bytes.iter().position(|b| *b == 0x42).unwrap();
+            let end = start
+                + mutated.bytes[start..]
+                    .iter()
+                    .take_while(|b| **b == 0x42)
+                    .count();
+            assert_eq!(mutated.bytes[..start], base.bytes[..start]);
+            assert_eq!(mutated.bytes[end..], base.bytes[end..]);
+
+            let mut mutated = base.clone();
+            assert_eq!(
+                length_preserving.mutate(&mut state, &mut mutated)?,
+                MutationResult::Mutated
+            );
+            assert_eq!(mutated.bytes.len(), base.bytes.len());
+            let split_at = mutated.bytes.iter().position(|b| *b == 0x42).unwrap();
+            assert_eq!(mutated.bytes[..split_at], base.bytes[..split_at]);
+            assert!(mutated.bytes[split_at..].iter().all(|b| *b == 0x42));
+        }
+        Ok(())
+    }
 }
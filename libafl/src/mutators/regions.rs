@@ -0,0 +1,238 @@
+//! Restrict mutations to an allowlist of mutable regions of the input.
+//!
+//! The regions are stored in the state as [`MutableRegionsMetadata`].
+//! Since variable-length mutations shift every offset behind them, the bounds of each region are
+//! expressed relative to an anchor, either the start or the end of the input, see [`RegionAnchor`].
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::ops::Range;
+
+use libafl_bolts::{rands::Rand, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::CorpusId,
+    inputs::{BytesInput, HasMutatorBytes},
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, HasMetadata,
+};
+
+/// A position in the input, relative to either its start or its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegionAnchor {
+    /// The given number of bytes after the start of the input
+    Start(usize),
+    /// The given number of bytes before the end of the input
+    End(usize),
+}
+
+impl RegionAnchor {
+    /// Resolves this anchor to an absolute offset in an input of length `len`.
+    /// Returns `None` if the offset lies outside of the input.
+    #[must_use]
+    pub fn resolve(&self, len: usize) -> Option<usize> {
+        match self {
+            Self::Start(offset) => (*offset <= len).then_some(*offset),
+            Self::End(offset) => len.checked_sub(*offset),
+        }
+    }
+}
+
+/// A region of the input that may be mutated, bounded by two [`RegionAnchor`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutableRegion {
+    /// The (inclusive) start of the region
+    pub start: RegionAnchor,
+    /// The (exclusive) end of the region
+    pub end: RegionAnchor,
+}
+
+impl MutableRegion {
+    /// Creates a new [`MutableRegion`] between the `start` and `end` anchors.
+    #[must_use]
+    pub fn new(start: RegionAnchor, end: RegionAnchor) -> Self {
+        Self { start, end }
+    }
+
+    /// Resolves this region to a range in an input of length `len`.
+    /// Returns `None` if the region does not exist in this input.
+    #[must_use]
+    pub fn resolve(&self, len: usize) -> Option<Range<usize>> {
+        let start = self.start.resolve(len)?;
+        let end = self.end.resolve(len)?;
+        (start <= end).then_some(start..end)
+    }
+}
+
+/// The allowlist of [`MutableRegion`]s, consulted by the [`MutableRegionsMutator`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct MutableRegionsMetadata {
+    /// The regions the mutators may operate on
+    pub regions: Vec<MutableRegion>,
+}
+
+libafl_bolts::impl_serdeany!(MutableRegionsMetadata);
+
+impl MutableRegionsMetadata {
+    /// Creates a new [`struct@MutableRegionsMetadata`] from the given regions.
+    #[must_use]
+    pub fn new(regions: Vec<MutableRegion>) -> Self {
+        Self { regions }
+    }
+
+    /// The ranges of all regions that exist in an input of length `len`.
+    #[must_use]
+    pub fn resolve(&self, len: usize) -> Vec<Range<usize>> {
+        self.regions
+            .iter()
+            .filter_map(|region| region.resolve(len))
+            .collect()
+    }
+}
+
+/// A mutator that only lets its inner mutator (usually a scheduled mutator) operate on one of the
+/// regions in the [`MutableRegionsMetadata`] of the state.
+///
+/// The selected region is handed to the inner mutator as a standalone [`BytesInput`], and spliced
+/// back into the input afterwards, so that inserts and deletes only grow or shrink that region.
+/// If the state has no [`MutableRegionsMetadata`], the whole input is mutable.
+#[derive(Debug)]
+pub struct MutableRegionsMutator<M> {
+    name: Cow<'static, str>,
+    inner: M,
+}
+
+impl<M> MutableRegionsMutator<M>
+where
+    M: Named,
+{
+    /// Creates a new [`MutableRegionsMutator`] wrapping the `inner` mutator.
+    pub fn new(inner: M) -> Self {
+        Self {
+            name: Cow::from(alloc::format!("MutableRegionsMutator[{}]", inner.name())),
+            inner,
+        }
+    }
+
+    /// The inner mutator
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    /// The inner mutator (mutable)
+    pub fn inner_mut(&mut self) -> &mut M {
+        &mut self.inner
+    }
+}
+
+impl<M> Named for MutableRegionsMutator<M> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, M, S> Mutator<I, S> for MutableRegionsMutator<M>
+where
+    I: HasMutatorBytes,
+    M: Mutator<BytesInput, S>,
+    S: HasRand + HasMetadata,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let len = input.bytes().len();
+        let ranges = state
+            .metadata_map()
+            .get::<MutableRegionsMetadata>()
+            .filter(|meta| !meta.regions.is_empty())
+            .map(|meta| meta.resolve(len));
+        let Some(mut ranges) = ranges else {
+            // No allowlist, the whole input is mutable
+            let mut whole = BytesInput::new(input.bytes().to_vec());
+            let result = self.inner.mutate(state, &mut whole)?;
+            if result == MutationResult::Mutated {
+                input.splice(.., whole.bytes().iter().copied());
+            }
+            return Ok(result);
+        };
+        if ranges.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let idx = state.rand_mut().below(ranges.len());
+        let range = ranges.swap_remove(idx);
+
+        let mut region = BytesInput::new(input.bytes()[range.clone()].to_vec());
+        let result = self.inner.mutate(state, &mut region)?;
+        if result == MutationResult::Mutated {
+            input.splice(range, region.bytes().iter().copied());
+        }
+        Ok(result)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, new_corpus_idx: Option<CorpusId>) -> Result<(), Error> {
+        self.inner.post_exec(state, new_corpus_idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use libafl_bolts::tuples::tuple_list;
+
+    use crate::{
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::{
+            regions::{MutableRegion, MutableRegionsMetadata, MutableRegionsMutator, RegionAnchor},
+            ByteRandMutator, BytesDeleteMutator, BytesInsertMutator, BytesRandSetMutator, Mutator,
+            StdScheduledMutator,
+        },
+        state::test::test_std_state,
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_region_anchors() {
+        let meta = MutableRegionsMetadata::new(vec![
+            // skip a 4 byte header and a 2 byte checksum
+            MutableRegion::new(RegionAnchor::Start(4), RegionAnchor::End(2)),
+            MutableRegion::new(RegionAnchor::Start(8), RegionAnchor::Start(12)),
+        ]);
+
+        assert_eq!(meta.resolve(16), vec![4..14, 8..12]);
+        // the end anchored bound follows a grown input
+        assert_eq!(meta.resolve(20), vec![4..18, 8..12]);
+        // regions that do not exist in short inputs are dropped
+        assert_eq!(meta.resolve(10), vec![4..8]);
+        assert!(meta.resolve(5).is_empty());
+    }
+
+    #[test]
+    fn test_frozen_bytes_untouched() {
+        let mut state = test_std_state::<BytesInput>();
+        // a 4 byte header and a 2 byte checksum are frozen
+        state.add_metadata(MutableRegionsMetadata::new(vec![MutableRegion::new(
+            RegionAnchor::Start(4),
+            RegionAnchor::End(2),
+        )]));
+        let mut mutator = MutableRegionsMutator::new(StdScheduledMutator::new(tuple_list!(
+            ByteRandMutator::new(),
+            BytesRandSetMutator::new(),
+            BytesInsertMutator::new(),
+            BytesDeleteMutator::new(),
+        )));
+
+        let mut input = BytesInput::new(b"HEAD0123456789CS".to_vec());
+        for _ in 0..256 {
+            mutator.mutate(&mut state, &mut input).unwrap();
+            let bytes = input.bytes();
+            assert_eq!(&bytes[..4], b"HEAD");
+            assert_eq!(&bytes[bytes.len() - 2..], b"CS");
+        }
+    }
+}
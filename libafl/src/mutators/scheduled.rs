@@ -17,14 +17,18 @@ use serde::{Deserialize, Serialize};
 use super::MutationId;
 use crate::{
     corpus::{Corpus, CorpusId},
+    inputs::HasMutatorBytes,
     mutators::{
+        corpus_chunks::{CorpusChunkInsertMutator, CorpusChunkReplaceMutator},
+        magic_constants::{MagicConstantInsert, MagicConstantReplace},
         mutations::{
             BitFlipMutator, ByteAddMutator, ByteDecMutator, ByteFlipMutator, ByteIncMutator,
             ByteInterestingMutator, ByteNegMutator, ByteRandMutator, BytesCopyMutator,
             BytesDeleteMutator, BytesExpandMutator, BytesInsertCopyMutator, BytesInsertMutator,
             BytesRandInsertMutator, BytesRandSetMutator, BytesSetMutator, BytesSwapMutator,
             CrossoverInsertMutator, CrossoverReplaceMutator, DwordAddMutator,
-            DwordInterestingMutator, QwordAddMutator, WordAddMutator, WordInterestingMutator,
+            DwordInterestingMutator, LengthPreservingSpliceMutator, QwordAddMutator, SpliceMutator,
+            TwoPointSpliceMutator, WordAddMutator, WordInterestingMutator,
         },
         token_mutations::{TokenInsert, TokenReplace},
         MutationResult, Mutator, MutatorsTuple,
@@ -67,6 +71,20 @@ impl LogMutationMetadata {
     }
 }
 
+/// The mutations applied to the current input, recorded in the state by a [`LoggerScheduledMutator`]
+/// if the state holds this metadata, e.g., for the [`crate::feedbacks::exec_log::ExecLogFeedback`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct LastMutationsMetadata {
+    /// The names of the applied mutations, in order
+    pub list: Vec<Cow<'static, str>>,
+}
+
+libafl_bolts::impl_serdeany!(LastMutationsMetadata);
+
 /// A [`Mutator`] that composes multiple mutations into one.
 pub trait ComposedByMutations<I, MT, S>
 where
@@ -106,6 +124,10 @@ where
     }
 }
 
+/// The default maximum power of two of stacked mutations of a [`StdScheduledMutator`],
+/// i.e., up to `2^7 = 128` mutations are applied per call
+pub const DEFAULT_MAX_STACK_POW: usize = 7;
+
 /// A [`Mutator`] that schedules one of the embedded mutations on each call.
 pub struct StdScheduledMutator<I, MT, S>
 where
@@ -179,6 +201,10 @@ where
 {
     /// Compute the number of iterations used to apply stacked mutations
     fn iterations(&self, state: &mut S, _: &I) -> u64 {
+        if self.max_stack_pow == 0 {
+            // see `with_max_stack`
+            return 1;
+        }
         1 << (1 + state.rand_mut().below(self.max_stack_pow))
     }
 
@@ -202,12 +228,15 @@ where
                 mutations.names().join(", ")
             )),
             mutations,
-            max_stack_pow: 7,
+            max_stack_pow: DEFAULT_MAX_STACK_POW,
             phantom: PhantomData,
         }
     }
 
     /// Create a new [`StdScheduledMutator`] instance specifying mutations and the maximun number of iterations
+    ///
+    /// Each call stacks `2^n` mutations, with `n` picked uniformly from `1..=max_stack_pow`,
+    /// like AFL's `HAVOC_STACK_POW2`. A `max_stack_pow` of `0` is treated as `1`.
     pub fn with_max_stack_pow(mutations: MT, max_stack_pow: usize) -> Self {
         StdScheduledMutator {
             name: Cow::from(format!(
@@ -215,10 +244,31 @@ where
                 mutations.names().join(", ")
             )),
             mutations,
-            max_stack_pow,
+            max_stack_pow: max_stack_pow.max(1),
             phantom: PhantomData,
         }
     }
+
+    /// Create a new [`StdScheduledMutator`] instance stacking at most `max_stack` mutations per call.
+    ///
+    /// Stack sizes are powers of two, so `max_stack` is rounded down to a power of two.
+    /// A `max_stack` of `0` or `1` applies a single mutation per call.
+    pub fn with_max_stack(mutations: MT, max_stack: usize) -> Self {
+        let mut mutator = Self::new(mutations);
+        mutator.max_stack_pow = max_stack.max(1).ilog2() as usize;
+        mutator
+    }
+
+    /// The maximum power of two of mutations stacked per call
+    #[must_use]
+    pub fn max_stack_pow(&self) -> usize {
+        self.max_stack_pow
+    }
+
+    /// Sets the maximum power of two of mutations stacked per call, see [`Self::with_max_stack_pow`]
+    pub fn set_max_stack_pow(&mut self, max_stack_pow: usize) {
+        self.max_stack_pow = max_stack_pow.max(1);
+    }
 }
 
 /// Tuple type of the mutations that compose the Havoc mutator without crossover mutations
@@ -338,7 +388,41 @@ pub fn tokens_mutations() -> tuple_list_type!(TokenInsert, TokenReplace) {
     tuple_list!(TokenInsert::new(), TokenReplace::new())
 }
 
+/// Get the splice mutations, recombining the input with another corpus entry at one or two cut points
+#[must_use]
+pub fn splice_mutations() -> tuple_list_type!(
+    SpliceMutator,
+    TwoPointSpliceMutator,
+    LengthPreservingSpliceMutator
+) {
+    tuple_list!(
+        SpliceMutator::new(),
+        TwoPointSpliceMutator::new(),
+        LengthPreservingSpliceMutator::new()
+    )
+}
+
+/// Get the mutations that use the campaign-wide [`crate::mutators::MagicConstants`] metadata
+#[must_use]
+pub fn magic_constants_mutations() -> tuple_list_type!(MagicConstantInsert, MagicConstantReplace) {
+    tuple_list!(MagicConstantInsert::new(), MagicConstantReplace::new())
+}
+
+/// Get the mutations that use the [`crate::mutators::CorpusChunks`] learned from the corpus
+#[must_use]
+pub fn corpus_chunks_mutations(
+) -> tuple_list_type!(CorpusChunkInsertMutator, CorpusChunkReplaceMutator) {
+    tuple_list!(
+        CorpusChunkInsertMutator::new(),
+        CorpusChunkReplaceMutator::new()
+    )
+}
+
 /// A logging [`Mutator`] that wraps around a [`StdScheduledMutator`].
+///
+/// The mutations applied to an input are added to its testcase as [`LogMutationMetadata`]
+/// if the input is added to the corpus. If the state holds a [`LastMutationsMetadata`],
+/// they are also recorded there for every input, for an execution log.
 pub struct LoggerScheduledMutator<I, MT, S, SM>
 where
     MT: MutatorsTuple<I, S> + NamedTuple,
@@ -381,7 +465,7 @@ where
 impl<I, MT, S, SM> Mutator<I, S> for LoggerScheduledMutator<I, MT, S, SM>
 where
     MT: MutatorsTuple<I, S> + NamedTuple,
-    S: HasRand + HasCorpus,
+    S: HasRand + HasCorpus + HasMetadata,
     SM: ScheduledMutator<I, MT, S>,
 {
     fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
@@ -425,7 +509,7 @@ where
 impl<I, MT, S, SM> ScheduledMutator<I, MT, S> for LoggerScheduledMutator<I, MT, S, SM>
 where
     MT: MutatorsTuple<I, S> + NamedTuple,
-    S: HasRand + HasCorpus,
+    S: HasRand + HasCorpus + HasMetadata,
     SM: ScheduledMutator<I, MT, S>,
 {
     /// Compute the number of iterations used to apply stacked mutations
@@ -451,6 +535,15 @@ where
                 r = MutationResult::Mutated;
             }
         }
+        if let Ok(meta) = state.metadata_mut::<LastMutationsMetadata>() {
+            let mutations = self.scheduled.mutations();
+            meta.list.clear();
+            meta.list.extend(
+                self.mutation_log
+                    .iter()
+                    .filter_map(|idx| mutations.name(idx.0).cloned()),
+            );
+        }
         Ok(r)
     }
 }
@@ -473,20 +566,200 @@ where
     }
 }
 
+/// The default growth factor of a [`GrowthLimitedScheduledMutator`]
+pub const DEFAULT_MAX_GROWTH: usize = 8;
+
+/// Inputs shorter than this many bytes may grow as if they had this length,
+/// so that tiny or empty inputs can still grow under a [`GrowthLimitedScheduledMutator`]
+pub const GROWTH_LIMIT_MIN_LEN: usize = 64;
+
+/// A [`Mutator`] that wraps around a [`ScheduledMutator`] and stops the stacked mutations early
+/// once the input grew beyond `max_growth` times its original length, truncating it to that limit.
+///
+/// Insert-heavy stacks can otherwise build multi-megabyte intermediates from small seeds,
+/// only for them to be truncated to the maximum size later.
+pub struct GrowthLimitedScheduledMutator<I, MT, S, SM>
+where
+    I: HasMutatorBytes,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    name: Cow<'static, str>,
+    scheduled: SM,
+    max_growth: usize,
+    stopped_stacks: u64,
+    phantom: PhantomData<(I, MT, S)>,
+}
+
+impl<I, MT, S, SM> Debug for GrowthLimitedScheduledMutator<I, MT, S, SM>
+where
+    I: HasMutatorBytes,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GrowthLimitedScheduledMutator with growth factor {} for Input type {}",
+            self.max_growth,
+            core::any::type_name::<I>()
+        )
+    }
+}
+
+impl<I, MT, S, SM> Named for GrowthLimitedScheduledMutator<I, MT, S, SM>
+where
+    I: HasMutatorBytes,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, MT, S, SM> Mutator<I, S> for GrowthLimitedScheduledMutator<I, MT, S, SM>
+where
+    I: HasMutatorBytes,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        self.scheduled_mutate(state, input)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, corpus_idx: Option<CorpusId>) -> Result<(), Error> {
+        self.scheduled.post_exec(state, corpus_idx)
+    }
+}
+
+impl<I, MT, S, SM> ComposedByMutations<I, MT, S> for GrowthLimitedScheduledMutator<I, MT, S, SM>
+where
+    I: HasMutatorBytes,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    #[inline]
+    fn mutations(&self) -> &MT {
+        self.scheduled.mutations()
+    }
+
+    #[inline]
+    fn mutations_mut(&mut self) -> &mut MT {
+        self.scheduled.mutations_mut()
+    }
+}
+
+impl<I, MT, S, SM> ScheduledMutator<I, MT, S> for GrowthLimitedScheduledMutator<I, MT, S, SM>
+where
+    I: HasMutatorBytes,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    #[inline]
+    fn iterations(&self, state: &mut S, input: &I) -> u64 {
+        self.scheduled.iterations(state, input)
+    }
+
+    #[inline]
+    fn schedule(&self, state: &mut S, input: &I) -> MutationId {
+        self.scheduled.schedule(state, input)
+    }
+
+    fn scheduled_mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let mut r = MutationResult::Skipped;
+        let num = self.iterations(state, input);
+        let limit = input
+            .len()
+            .max(GROWTH_LIMIT_MIN_LEN)
+            .saturating_mul(self.max_growth);
+        for _ in 0..num {
+            let idx = self.schedule(state, input);
+            let outcome = self.mutations_mut().get_and_mutate(idx, state, input)?;
+            if outcome == MutationResult::Mutated {
+                r = MutationResult::Mutated;
+            }
+            if input.len() > limit {
+                // truncating is much cheaper than keeping a copy of the last variant around
+                input.resize(limit, 0);
+                self.stopped_stacks += 1;
+                break;
+            }
+        }
+        Ok(r)
+    }
+}
+
+impl<I, MT, S, SM> GrowthLimitedScheduledMutator<I, MT, S, SM>
+where
+    I: HasMutatorBytes,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    /// Creates a new [`GrowthLimitedScheduledMutator`] with the [`DEFAULT_MAX_GROWTH`]
+    pub fn new(scheduled: SM) -> Self {
+        Self::with_max_growth(scheduled, DEFAULT_MAX_GROWTH)
+    }
+
+    /// Creates a new [`GrowthLimitedScheduledMutator`], letting an input grow to at most
+    /// `max_growth` times its original length (at least [`GROWTH_LIMIT_MIN_LEN`]) in one stack of mutations.
+    /// A `max_growth` of `0` is treated as `1`.
+    pub fn with_max_growth(scheduled: SM, max_growth: usize) -> Self {
+        Self {
+            name: Cow::from(format!(
+                "GrowthLimitedScheduledMutator[{}]",
+                scheduled.name()
+            )),
+            scheduled,
+            max_growth: max_growth.max(1),
+            stopped_stacks: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The growth factor
+    pub fn max_growth(&self) -> usize {
+        self.max_growth
+    }
+
+    /// The number of mutation stacks stopped early because the input grew too large
+    pub fn stopped_stacks(&self) -> u64 {
+        self.stopped_stacks
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use libafl_bolts::rands::{StdRand, XkcdRand};
+    use alloc::vec::Vec;
+
+    use libafl_bolts::{
+        rands::{StdRand, XkcdRand},
+        tuples::tuple_list,
+        HasLen,
+    };
 
     use crate::{
         corpus::{Corpus, InMemoryCorpus, Testcase},
         feedbacks::ConstFeedback,
         inputs::{BytesInput, HasMutatorBytes},
         mutators::{
-            mutations::SpliceMutator,
-            scheduled::{havoc_mutations, StdScheduledMutator},
-            Mutator,
+            mutations::{BitFlipMutator, BytesExpandMutator, SpliceMutator},
+            scheduled::{
+                havoc_mutations, GrowthLimitedScheduledMutator, LastMutationsMetadata,
+                LoggerScheduledMutator, StdScheduledMutator, GROWTH_LIMIT_MIN_LEN,
+            },
+            Mutator, ScheduledMutator,
         },
-        state::StdState,
+        state::{test::test_std_state, StdState},
+        HasMetadata,
     };
 
     #[test]
@@ -567,4 +840,64 @@ mod tests {
             assert_ne!(equal_in_a_row, 5);
         }
     }
+
+    #[test]
+    fn test_growth_limit() {
+        let mut corpus: InMemoryCorpus<BytesInput> = InMemoryCorpus::new();
+        corpus.add(Testcase::new(vec![b'a'; 4].into())).unwrap();
+        let mut input = corpus.cloned_input_for_id(corpus.first().unwrap()).unwrap();
+
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0x1337),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut expand = GrowthLimitedScheduledMutator::with_max_growth(
+            StdScheduledMutator::new(tuple_list!(BytesExpandMutator::new())),
+            2,
+        );
+        for _ in 0..16 {
+            let before = input.len();
+            expand.mutate(&mut state, &mut input).unwrap();
+            assert!(input.len() <= before.max(GROWTH_LIMIT_MIN_LEN) * 2);
+        }
+        assert!(expand.stopped_stacks() > 0);
+    }
+
+    #[test]
+    fn test_logger_records_last_mutations() {
+        let mut state = test_std_state::<BytesInput>();
+        state.add_metadata(LastMutationsMetadata::default());
+        let mut logger = LoggerScheduledMutator::new(StdScheduledMutator::new(tuple_list!(
+            BitFlipMutator::new()
+        )));
+
+        let mut input = BytesInput::new(vec![0; 4]);
+        logger.mutate(&mut state, &mut input).unwrap();
+        let list = &state.metadata::<LastMutationsMetadata>().unwrap().list;
+        assert!(!list.is_empty());
+        assert!(list.iter().all(|name| name == "BitFlipMutator"));
+    }
+
+    #[test]
+    fn test_max_stack() {
+        let mut state = test_std_state::<BytesInput>();
+        let input = BytesInput::new(vec![0; 4]);
+
+        for (max_stack, expected) in [(0, 1), (1, 1), (2, 2), (5, 4), (8, 8)] {
+            let mutator =
+                StdScheduledMutator::with_max_stack(tuple_list!(BitFlipMutator::new()), max_stack);
+            let stacks = (0..64)
+                .map(|_| mutator.iterations(&mut state, &input))
+                .collect::<Vec<_>>();
+            assert!(stacks.iter().all(|stack| *stack <= expected));
+            assert!(stacks.contains(&expected));
+        }
+    }
 }
@@ -24,9 +24,15 @@ use serde::{Deserialize, Serialize};
 use crate::mutators::str_decode;
 use crate::{
     corpus::{CorpusId, HasCurrentCorpusId},
-    inputs::{HasMutatorBytes, UsesInput},
+    inputs::{BytesInput, HasMutatorBytes, UsesInput},
     mutators::{
-        buffer_self_copy, mutations::buffer_copy, MultiMutator, MutationResult, Mutator, Named,
+        buffer_self_copy,
+        length_field::{
+            find_length_fields, resize_length_field, MAX_LENGTH_FIELD_DELTA,
+            MAX_LENGTH_FIELD_VARIANTS,
+        },
+        mutations::buffer_copy,
+        MultiMutator, MutationResult, Mutator, Named,
     },
     observers::cmp::{AFLppCmpValuesMetadata, CmpValues, CmpValuesMetadata},
     stages::TaintMetadata,
@@ -617,6 +623,7 @@ const CMP_ATTRIBUTE_IS_TRANSFORM: u8 = 64;
 pub struct AFLppRedQueen {
     enable_transform: bool,
     enable_arith: bool,
+    enable_length_fields: bool,
     text_type: TextType,
     /// We use this variable to check if we scheduled a new `corpus_idx`
     /// - and, hence, need to recalculate `text_type`
@@ -1667,6 +1674,32 @@ where
             }
         }
 
+        if self.enable_length_fields {
+            let cmp_meta = state.metadata::<AFLppCmpValuesMetadata>()?;
+            let mut variants = Vec::new();
+            'fields: for field in find_length_fields(input.bytes(), cmp_meta.orig_cmpvals()) {
+                if field.declared != field.actual {
+                    let mut fixed = input.bytes().to_vec();
+                    field.write(&mut fixed, field.actual);
+                    variants.push(fixed);
+                }
+                for grow in [false, true] {
+                    if variants.len() >= MAX_LENGTH_FIELD_VARIANTS {
+                        break 'fields;
+                    }
+                    if grow && input_len + MAX_LENGTH_FIELD_DELTA > state.max_size() {
+                        continue;
+                    }
+                    let mut resized = BytesInput::new(input.bytes().to_vec());
+                    if resize_length_field(&mut resized, &field, grow, MAX_LENGTH_FIELD_DELTA) {
+                        variants.push(resized.into());
+                    }
+                }
+            }
+            variants.truncate(MAX_LENGTH_FIELD_VARIANTS);
+            ret.append(&mut variants);
+        }
+
         if let Some(max_count) = max_count {
             Ok(ret.into_iter().take(max_count).map(I::from).collect())
         } else {
@@ -1689,6 +1722,7 @@ impl AFLppRedQueen {
         Self {
             enable_transform: false,
             enable_arith: false,
+            enable_length_fields: false,
             text_type: TextType::None,
             last_corpus_idx: None,
         }
@@ -1700,11 +1734,20 @@ impl AFLppRedQueen {
         Self {
             enable_transform: transform,
             enable_arith: arith,
+            enable_length_fields: false,
             text_type: TextType::None,
             last_corpus_idx: None,
         }
     }
 
+    /// Also emit correlated mutations of length fields detected in the cmplog data,
+    /// see [`crate::mutators::length_field`]
+    #[must_use]
+    pub fn with_length_fields(mut self, length_fields: bool) -> Self {
+        self.enable_length_fields = length_fields;
+        self
+    }
+
     #[allow(clippy::needless_range_loop)]
     fn try_add_autotokens(tokens: &mut Tokens, b: &[u8], shape: usize) {
         let mut cons_ff = 0;
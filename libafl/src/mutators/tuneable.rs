@@ -2,12 +2,15 @@
 //! Instead of a random mutator for a random amount of iterations, we can run
 //! a specific mutator for a specified amount of iterations
 
-use alloc::{borrow::Cow, vec::Vec};
+use alloc::{borrow::Cow, string::String, vec::Vec};
 use core::{
     fmt::{self, Debug},
     marker::PhantomData,
 };
+#[cfg(feature = "std")]
+use std::{fs::File, io::BufReader, path::Path};
 
+use hashbrown::HashMap;
 use libafl_bolts::{
     impl_serdeany, math::calculate_cumulative_distribution_in_place, rands::Rand, Named,
 };
@@ -244,6 +247,59 @@ where
             phantom: PhantomData,
         }
     }
+
+    /// Sets the mutation probabilities from relative weights, keyed by mutation name.
+    /// Mutations that are not listed get a weight of `1.0`.
+    /// Fails if a name does not belong to any of the mutations, or if a weight is negative.
+    pub fn set_mutation_weights(
+        &self,
+        state: &mut S,
+        weights: &HashMap<String, f32>,
+    ) -> Result<(), Error> {
+        let names = self.mutations.names();
+        if let Some(unknown) = weights.keys().find(|name| !names.contains(&name.as_str())) {
+            return Err(Error::illegal_argument(format!(
+                "Unknown mutation {unknown} in the mutation weights, expected one of: {}",
+                names.join(", ")
+            )));
+        }
+        if let Some((name, weight)) = weights
+            .iter()
+            .find(|(_, weight)| !weight.is_finite() || **weight < 0.0)
+        {
+            return Err(Error::illegal_argument(format!(
+                "Invalid weight {weight} for mutation {name}"
+            )));
+        }
+
+        let weights: Vec<f32> = names
+            .iter()
+            .map(|name| weights.get(*name).copied().unwrap_or(1.0))
+            .collect();
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return Err(Error::illegal_argument(
+                "At least one mutation needs a weight above 0",
+            ));
+        }
+        TuneableScheduledMutator::<(), (), S>::set_mutation_probabilities(
+            state,
+            weights.iter().map(|weight| weight / total).collect(),
+        )
+    }
+
+    /// Loads relative weights per mutation name from a json file,
+    /// e.g., `{"BitFlipMutator": 2.0, "BytesDeleteMutator": 0.5}`,
+    /// and sets the mutation probabilities accordingly, see [`Self::set_mutation_weights`].
+    #[cfg(feature = "std")]
+    pub fn load_mutation_weights<P>(&self, state: &mut S, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let weights: HashMap<String, f32> =
+            serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        self.set_mutation_weights(state, &weights)
+    }
 }
 
 impl<S> TuneableScheduledMutator<(), (), S>
@@ -361,6 +417,9 @@ where
 
 #[cfg(test)]
 mod test {
+    use alloc::string::String;
+
+    use hashbrown::HashMap;
     use libafl_bolts::tuples::tuple_list;
 
     use super::{
@@ -456,4 +515,40 @@ mod test {
         .is_ok());
         assert!(tuneable.schedule(&mut state, &input) != 1.into());
     }
+
+    #[test]
+    fn test_mutation_weights() {
+        // # Safety
+        // No concurrency per testcase
+        #[cfg(any(not(feature = "serdeany_autoreg"), miri))]
+        unsafe {
+            TuneableScheduledMutatorMetadata::register();
+        }
+
+        let mut state: NopState<BytesInput> = NopState::new();
+        let mutators = tuple_list!(
+            BitFlipMutator::new(),
+            ByteDecMutator::new(),
+            ByteRandMutator::new()
+        );
+        let tuneable = TuneableScheduledMutator::new(&mut state, mutators);
+        let input = BytesInput::new(vec![42]);
+
+        let mut weights = HashMap::new();
+        weights.insert(String::from("NoSuchMutator"), 1.0);
+        assert!(tuneable.set_mutation_weights(&mut state, &weights).is_err());
+
+        weights.clear();
+        weights.insert(String::from("BitFlipMutator"), -1.0);
+        assert!(tuneable.set_mutation_weights(&mut state, &weights).is_err());
+
+        // Unlisted mutations default to a weight of 1
+        weights.insert(String::from("BitFlipMutator"), 0.0);
+        weights.insert(String::from("ByteDecMutator"), 0.0);
+        assert!(tuneable.set_mutation_weights(&mut state, &weights).is_ok());
+        assert_eq!(tuneable.schedule(&mut state, &input), 2.into());
+
+        weights.insert(String::from("ByteRandMutator"), 0.0);
+        assert!(tuneable.set_mutation_weights(&mut state, &weights).is_err());
+    }
 }
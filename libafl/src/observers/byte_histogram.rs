@@ -0,0 +1,159 @@
+//! The [`ByteHistogramObserver`] records the byte-value histogram of each input,
+//! and accumulates a campaign-wide [`ByteHistogramMetadata`].
+
+use alloc::{borrow::Cow, vec, vec::Vec};
+
+use libafl_bolts::{impl_serdeany, AsSlice, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    inputs::{HasTargetBytes, UsesInput},
+    observers::Observer,
+    Error, HasMetadata,
+};
+
+/// The campaign-wide histogram of byte values over all executed inputs
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByteHistogramMetadata {
+    /// How often each byte value appeared, indexed by the value
+    counts: Vec<u64>,
+    /// The sum of all counts
+    total: u64,
+}
+
+impl_serdeany!(ByteHistogramMetadata);
+
+impl Default for ByteHistogramMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ByteHistogramMetadata {
+    /// Creates a new, empty [`ByteHistogramMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; 256],
+            total: 0,
+        }
+    }
+
+    /// Adds a per-input histogram, as recorded by the [`ByteHistogramObserver`]
+    pub fn add_histogram(&mut self, histogram: &[u32]) {
+        for (count, add) in self.counts.iter_mut().zip(histogram) {
+            *count += u64::from(*add);
+            self.total += u64::from(*add);
+        }
+    }
+
+    /// How often the given byte value appeared so far
+    #[must_use]
+    pub fn count(&self, value: u8) -> u64 {
+        self.counts[value as usize]
+    }
+
+    /// The number of bytes accounted for
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Picks a byte value using the given random number, preferring values that appeared less often.
+    ///
+    /// Each value is weighted by how much less often it appeared than the most common one (plus one),
+    /// so values never seen are the most likely, and every value remains possible.
+    #[must_use]
+    pub fn pick_rare(&self, random: u64) -> u8 {
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        let weights_total: u64 = self.counts.iter().map(|count| max - count + 1).sum();
+        let mut target = random % weights_total;
+        for (value, count) in self.counts.iter().enumerate() {
+            let weight = max - count + 1;
+            if target < weight {
+                return value as u8;
+            }
+            target -= weight;
+        }
+        unreachable!("the target is always below the sum of all weights")
+    }
+}
+
+/// An observer recording the byte-value histogram of the current input.
+///
+/// Before each execution, the histogram of the input is also added to the [`ByteHistogramMetadata`]
+/// of the state, which the [`crate::mutators::ByteHistogramMutator`] uses to explore
+/// under-represented byte values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByteHistogramObserver {
+    name: Cow<'static, str>,
+    histogram: Vec<u32>,
+}
+
+impl ByteHistogramObserver {
+    /// Creates a new [`ByteHistogramObserver`] with the given name
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            histogram: vec![0; 256],
+        }
+    }
+
+    /// The histogram of the last input, indexed by byte value
+    #[must_use]
+    pub fn histogram(&self) -> &[u32] {
+        &self.histogram
+    }
+}
+
+impl<S> Observer<S> for ByteHistogramObserver
+where
+    S: UsesInput + HasMetadata,
+    S::Input: HasTargetBytes,
+{
+    fn pre_exec(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.histogram.fill(0);
+        for byte in input.target_bytes().as_slice() {
+            self.histogram[*byte as usize] += 1;
+        }
+        state
+            .metadata_or_insert_with(ByteHistogramMetadata::new)
+            .add_histogram(&self.histogram);
+        Ok(())
+    }
+}
+
+impl Named for ByteHistogramObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::{Rand, StdRand};
+
+    use crate::observers::byte_histogram::ByteHistogramMetadata;
+
+    #[test]
+    fn test_byte_histogram_pick_rare() {
+        let mut histogram = vec![1000; 256];
+        histogram[0x42] = 0;
+        let mut meta = ByteHistogramMetadata::new();
+        meta.add_histogram(&histogram);
+        assert_eq!(meta.total(), 255 * 1000);
+        assert_eq!(meta.count(0x42), 0);
+
+        // 0x42 weighs 1001, every other value 1
+        let mut rand = StdRand::with_seed(1337);
+        let rare = (0..100)
+            .filter(|_| meta.pick_rare(rand.next()) == 0x42)
+            .count();
+        assert!(rare > 50);
+    }
+}
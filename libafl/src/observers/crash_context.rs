@@ -0,0 +1,273 @@
+//! The [`CrashContextObserver`] reads the context of a crash, such as the signal and the faulting address,
+//! as reported by the crash handler of the in-process executors, or by a signal handler in the target.
+
+use alloc::borrow::Cow;
+#[cfg(all(unix, feature = "std"))]
+use core::ptr::addr_of_mut;
+
+#[cfg(all(unix, feature = "std"))]
+use libafl_bolts::os::unix_signals::{ucontext_t, Signal};
+#[cfg(feature = "std")]
+use libafl_bolts::shmem::ShMem;
+use libafl_bolts::{ownedref::OwnedMutPtr, Error, Named};
+#[cfg(all(unix, feature = "std"))]
+use libc::siginfo_t;
+use serde::{Deserialize, Serialize};
+
+use crate::{executors::ExitKind, inputs::UsesInput, observers::Observer};
+
+/// The env var the id of the crash context shared memory is passed to the target in.
+/// The size of the mapping is passed in `__LIBAFL_CRASH_CONTEXT_SHM_ID_SIZE`.
+pub const CRASH_CONTEXT_SHM_ENV: &str = "__LIBAFL_CRASH_CONTEXT_SHM_ID";
+
+/// The memory access that caused a crash, if the target could tell
+pub const CRASH_ACCESS_UNKNOWN: u32 = 0;
+/// The crash was caused by a read
+pub const CRASH_ACCESS_READ: u32 = 1;
+/// The crash was caused by a write
+pub const CRASH_ACCESS_WRITE: u32 = 2;
+
+/// The context of a crash, as written by a signal handler (from its `siginfo_t` and `ucontext_t`).
+/// The layout is shared with the target and must not change.
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CrashContext {
+    /// Set to a non-zero value by the target once it wrote the context
+    pub valid: u32,
+    /// The signal number
+    pub signal: i32,
+    /// The kind of memory access, one of [`CRASH_ACCESS_UNKNOWN`], [`CRASH_ACCESS_READ`], or [`CRASH_ACCESS_WRITE`]
+    pub access: u32,
+    /// Reserved, keeps the following fields aligned
+    pub reserved: u32,
+    /// The faulting address (`si_addr`)
+    pub fault_addr: u64,
+    /// The program counter at the time of the crash
+    pub pc: u64,
+}
+
+/// The context filled by the crash handler of the in-process executors,
+/// see [`CrashContextObserver::inprocess`]
+#[cfg(all(unix, feature = "std"))]
+static mut INPROCESS_CRASH_CONTEXT: CrashContext = CrashContext {
+    valid: 0,
+    signal: 0,
+    access: CRASH_ACCESS_UNKNOWN,
+    reserved: 0,
+    fault_addr: 0,
+    pc: 0,
+};
+
+#[cfg(all(unix, feature = "std"))]
+impl CrashContext {
+    /// Collects the context of a crash from the arguments of a signal handler.
+    /// The program counter and the kind of memory access are only known on `x86_64` and `aarch64` linux.
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn from_signal(signal: Signal, info: &siginfo_t, context: Option<&ucontext_t>) -> Self {
+        #[cfg(target_os = "android")]
+        let fault_addr = (info._pad[0] as u64) | ((info._pad[1] as u64) << 32);
+        #[cfg(not(target_os = "android"))]
+        let fault_addr = unsafe { info.si_addr() } as u64;
+
+        let mut crash_context = Self {
+            valid: 1,
+            signal: signal as i32,
+            fault_addr,
+            ..Self::default()
+        };
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        if let Some(context) = context {
+            let gregs = &context.uc_mcontext.gregs;
+            crash_context.pc = gregs[libc::REG_RIP as usize] as u64;
+            if signal == Signal::SigSegmentationFault {
+                // Bit 1 of the page fault error code is set for writes
+                crash_context.access = if gregs[libc::REG_ERR as usize] & 2 == 0 {
+                    CRASH_ACCESS_READ
+                } else {
+                    CRASH_ACCESS_WRITE
+                };
+            }
+        }
+        #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+        if let Some(context) = context {
+            crash_context.pc = context.uc_mcontext.pc;
+        }
+        #[cfg(not(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        )))]
+        let _ = context;
+        crash_context
+    }
+}
+
+/// Fills the context read by [`CrashContextObserver::inprocess`] observers.
+/// Called by the crash handler of the in-process executors before running the observers.
+///
+/// # Safety
+/// Writes a global; only call it from the crash handler.
+#[cfg(all(unix, feature = "std"))]
+pub(crate) unsafe fn fill_inprocess_crash_context(
+    signal: Signal,
+    info: &siginfo_t,
+    context: Option<&ucontext_t>,
+) {
+    *addr_of_mut!(INPROCESS_CRASH_CONTEXT) = CrashContext::from_signal(signal, info, context);
+}
+
+/// Observes the [`CrashContext`] of a run.
+///
+/// For the in-process executors, create it with [`CrashContextObserver::inprocess`]: their crash
+/// handler fills the context. Other targets install a signal handler that fills the [`CrashContext`],
+/// usually at the start of a small shared memory region (see [`CRASH_CONTEXT_SHM_ENV`]).
+/// The context is reset before each run.
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct CrashContextObserver {
+    name: Cow<'static, str>,
+    /// The context written by the target
+    context: OwnedMutPtr<CrashContext>,
+    /// The context of the last run, if the target wrote one
+    last_context: Option<CrashContext>,
+}
+
+impl CrashContextObserver {
+    /// Creates a new [`CrashContextObserver`] with the given name, reading the given context.
+    #[must_use]
+    pub fn new(name: &'static str, context: OwnedMutPtr<CrashContext>) -> Self {
+        Self {
+            name: Cow::from(name),
+            context,
+            last_context: None,
+        }
+    }
+
+    /// Creates a new [`CrashContextObserver`] reading the context behind a raw pointer.
+    ///
+    /// # Safety
+    /// Will dereference the pointer.
+    /// The context may not move in memory and has to outlive this observer.
+    #[must_use]
+    pub unsafe fn from_mut_ptr(name: &'static str, context: *mut CrashContext) -> Self {
+        Self::new(name, OwnedMutPtr::from_raw_mut(context))
+    }
+
+    /// Creates a new [`CrashContextObserver`] reading the context filled by the crash handler of the
+    /// in-process executors, such as the [`crate::executors::InProcessExecutor`].
+    #[cfg(all(unix, feature = "std"))]
+    #[must_use]
+    pub fn inprocess(name: &'static str) -> Self {
+        // Safety: the context is a static, only written by the crash handler
+        unsafe { Self::from_mut_ptr(name, addr_of_mut!(INPROCESS_CRASH_CONTEXT)) }
+    }
+
+    /// Creates a new [`CrashContextObserver`] reading the context at the start of a shared memory,
+    /// and passes the shared memory to the target in [`CRASH_CONTEXT_SHM_ENV`].
+    ///
+    /// # Safety
+    /// The shared memory has to outlive this observer.
+    #[cfg(feature = "std")]
+    pub unsafe fn from_shmem<SHM>(name: &'static str, shmem: &mut SHM) -> Result<Self, Error>
+    where
+        SHM: ShMem,
+    {
+        let context = shmem.as_mut_ptr_of::<CrashContext>().ok_or_else(|| {
+            Error::illegal_argument(format!(
+                "The crash context shared memory is too small ({} bytes)",
+                shmem.len()
+            ))
+        })?;
+        shmem.write_to_env(CRASH_CONTEXT_SHM_ENV)?;
+        Ok(Self::from_mut_ptr(name, context))
+    }
+
+    /// The crash context of the last run, if the target reported one
+    #[must_use]
+    pub fn last_context(&self) -> Option<&CrashContext> {
+        self.last_context.as_ref()
+    }
+}
+
+impl<S> Observer<S> for CrashContextObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        *self.context.as_mut() = CrashContext::default();
+        self.last_context = None;
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        let context = *self.context.as_ref();
+        self.last_context = (context.valid != 0).then_some(context);
+        Ok(())
+    }
+
+    fn pre_exec_child(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.pre_exec(state, input)
+    }
+
+    fn post_exec_child(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.post_exec(state, input, exit_kind)
+    }
+}
+
+impl Named for CrashContextObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(unix, feature = "std"))]
+mod tests {
+    use libafl_bolts::os::unix_signals::Signal;
+
+    use crate::{
+        executors::ExitKind,
+        inputs::BytesInput,
+        observers::{
+            crash_context::{fill_inprocess_crash_context, CrashContextObserver},
+            Observer,
+        },
+        state::NopState,
+    };
+
+    #[test]
+    fn test_inprocess_crash_context() {
+        let mut observer = CrashContextObserver::inprocess("crash_context");
+        let mut state = NopState::<BytesInput>::new();
+        let input = BytesInput::new(vec![0]);
+
+        observer.pre_exec(&mut state, &input).unwrap();
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert!(observer.last_context().is_none());
+
+        observer.pre_exec(&mut state, &input).unwrap();
+        // As done by the crash handler
+        unsafe {
+            let info: libc::siginfo_t = core::mem::zeroed();
+            fill_inprocess_crash_context(Signal::SigSegmentationFault, &info, None);
+        }
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Crash)
+            .unwrap();
+        let context = observer.last_context().unwrap();
+        assert_eq!(context.signal, libc::SIGSEGV);
+        assert_eq!(context.fault_addr, 0);
+    }
+}
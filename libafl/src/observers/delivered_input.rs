@@ -0,0 +1,85 @@
+//! The [`DeliveredInputObserver`] keeps the exact bytes an executor delivered to the target in a run,
+//! after any encoding or prefixing, so that they can be stored next to crashing inputs.
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use libafl_bolts::{Error, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{inputs::UsesInput, observers::Observer};
+
+/// Observes the bytes delivered to the target for crashing and timing out runs.
+///
+/// The executor fills it, e.g., the [`crate::executors::ForkserverExecutor`] once passed to
+/// [`crate::executors::forkserver::ForkserverExecutorBuilder::delivered_input_observer`].
+/// Runs that exited normally leave it empty, to not copy each input.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeliveredInputObserver {
+    name: Cow<'static, str>,
+    delivered: Option<Vec<u8>>,
+}
+
+impl DeliveredInputObserver {
+    /// Creates a new [`DeliveredInputObserver`] with the given name
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            delivered: None,
+        }
+    }
+
+    /// The bytes delivered to the target in the last run, if recorded
+    #[must_use]
+    pub fn delivered(&self) -> Option<&[u8]> {
+        self.delivered.as_deref()
+    }
+
+    /// Records the bytes delivered to the target in the current run
+    pub fn set_delivered(&mut self, bytes: &[u8]) {
+        let delivered = self.delivered.get_or_insert_with(Vec::new);
+        delivered.clear();
+        delivered.extend_from_slice(bytes);
+    }
+}
+
+impl<S> Observer<S> for DeliveredInputObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.delivered = None;
+        Ok(())
+    }
+}
+
+impl Named for DeliveredInputObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inputs::BytesInput,
+        observers::{DeliveredInputObserver, Observer},
+        state::NopState,
+    };
+
+    #[test]
+    fn test_delivered_input_observer() {
+        let mut state = NopState::<BytesInput>::new();
+        let input = BytesInput::new(vec![0]);
+        let mut observer = DeliveredInputObserver::new("delivered");
+        assert_eq!(observer.delivered(), None);
+
+        observer.set_delivered(b"first");
+        observer.set_delivered(b"2nd");
+        assert_eq!(observer.delivered(), Some(&b"2nd"[..]));
+
+        // Each run starts without delivered bytes
+        observer.pre_exec(&mut state, &input).unwrap();
+        assert_eq!(observer.delivered(), None);
+    }
+}
@@ -0,0 +1,82 @@
+//! The [`ExitCodeObserver`] keeps the exit code of the target process of a run,
+//! so that feedbacks can act on exit codes the harness uses to flag inputs.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{Error, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{inputs::UsesInput, observers::Observer};
+
+/// Observes the exit code of the target process.
+///
+/// The executor fills it, e.g., the [`crate::executors::ForkserverExecutor`] once passed to
+/// [`crate::executors::forkserver::ForkserverExecutorBuilder::exit_code_observer`].
+/// Runs killed by a signal or timing out leave it empty.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExitCodeObserver {
+    name: Cow<'static, str>,
+    exit_code: Option<i32>,
+}
+
+impl ExitCodeObserver {
+    /// Creates a new [`ExitCodeObserver`] with the given name
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            exit_code: None,
+        }
+    }
+
+    /// The exit code of the last run, if the target exited
+    #[must_use]
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Records the exit code of the current run
+    pub fn set_exit_code(&mut self, exit_code: i32) {
+        self.exit_code = Some(exit_code);
+    }
+}
+
+impl<S> Observer<S> for ExitCodeObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.exit_code = None;
+        Ok(())
+    }
+}
+
+impl Named for ExitCodeObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inputs::BytesInput,
+        observers::{ExitCodeObserver, Observer},
+        state::NopState,
+    };
+
+    #[test]
+    fn test_exit_code_observer() {
+        let mut state = NopState::<BytesInput>::new();
+        let input = BytesInput::new(vec![0]);
+        let mut observer = ExitCodeObserver::new("exit_code");
+        assert_eq!(observer.exit_code(), None);
+
+        observer.set_exit_code(42);
+        assert_eq!(observer.exit_code(), Some(42));
+
+        // A run that does not exit, e.g., a timeout, leaves no exit code behind
+        observer.pre_exec(&mut state, &input).unwrap();
+        assert_eq!(observer.exit_code(), None);
+    }
+}
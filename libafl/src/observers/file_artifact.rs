@@ -0,0 +1,163 @@
+//! The [`FileArtifactObserver`] reads an output file the target writes during each run,
+//! so that feedbacks can judge the behavior of the target by what it produced.
+
+use alloc::{borrow::Cow, vec::Vec};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::{hash_std, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::{Observer, ObserverWithHashField},
+    Error,
+};
+
+/// An observer reading the output file (artifact) written by the target after each run.
+///
+/// The artifact is removed before each run, so a stale file of a previous run is never
+/// mistaken for the output of the current one. If the target did not write the file,
+/// [`FileArtifactObserver::artifact`] is `None`.
+///
+/// The hash of the artifact makes it usable with a [`crate::feedbacks::NewHashFeedback`],
+/// as feedback or objective, to keep inputs producing novel outputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileArtifactObserver {
+    name: Cow<'static, str>,
+    path: PathBuf,
+    artifact: Option<Vec<u8>>,
+    hash: Option<u64>,
+}
+
+impl FileArtifactObserver {
+    /// Creates a new [`FileArtifactObserver`] for the artifact the target writes to `path`.
+    #[must_use]
+    pub fn new<P>(name: &'static str, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            name: Cow::from(name),
+            path: path.as_ref().to_path_buf(),
+            artifact: None,
+            hash: None,
+        }
+    }
+
+    /// The path of the artifact
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The content of the artifact of the last run, if the target wrote one
+    #[must_use]
+    pub fn artifact(&self) -> Option<&[u8]> {
+        self.artifact.as_deref()
+    }
+
+    /// Removes the artifact of a previous run, if any
+    fn remove_stale_artifact(&self) -> Result<(), Error> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(Error::os_error(
+                err,
+                format!("Could not remove stale artifact {}", self.path.display()),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Named for FileArtifactObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl ObserverWithHashField for FileArtifactObserver {
+    fn hash(&self) -> Option<u64> {
+        self.hash
+    }
+}
+
+impl<S> Observer<S> for FileArtifactObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.artifact = None;
+        self.hash = None;
+        self.remove_stale_artifact()
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        match fs::read(&self.path) {
+            Ok(artifact) => {
+                self.hash = Some(hash_std(&artifact));
+                self.artifact = Some(artifact);
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(Error::os_error(
+                    err,
+                    format!("Could not read artifact {}", self.path.display()),
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use libafl_bolts::hash_std;
+
+    use crate::{
+        executors::ExitKind,
+        inputs::BytesInput,
+        observers::{file_artifact::FileArtifactObserver, Observer, ObserverWithHashField},
+        state::NopState,
+    };
+
+    #[test]
+    fn test_file_artifact_observer() {
+        let path =
+            std::env::temp_dir().join(format!("libafl_test_file_artifact.{}", std::process::id()));
+        let mut observer = FileArtifactObserver::new("artifact", &path);
+        let mut state = NopState::<BytesInput>::new();
+        let input = BytesInput::new(vec![]);
+
+        fs::write(&path, b"stale").unwrap();
+        observer.pre_exec(&mut state, &input).unwrap();
+        assert!(!path.exists());
+
+        // the target did not write an artifact
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.artifact(), None);
+        assert_eq!(observer.hash(), None);
+
+        observer.pre_exec(&mut state, &input).unwrap();
+        fs::write(&path, b"output").unwrap();
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.artifact(), Some(&b"output"[..]));
+        assert_eq!(observer.hash(), Some(hash_std(b"output")));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
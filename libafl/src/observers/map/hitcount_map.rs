@@ -56,6 +56,9 @@ fn init_count_class_16() {
 ///
 /// [`MapObserver`]s that are not slice-backed, such as `MultiMapObserver`, can use
 /// [`HitcountsIterableMapObserver`] instead.
+///
+/// If only the edge presence matters, skip the classification by observing the plain map
+/// with a [`crate::feedbacks::MaxMapPresenceFeedback`].
 #[derive(Serialize, Deserialize, Clone, Debug, Hash)]
 #[serde(bound = "M: serde::de::DeserializeOwned")]
 pub struct HitcountsMapObserver<M>
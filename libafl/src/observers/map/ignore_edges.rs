@@ -0,0 +1,332 @@
+//! Map observer resetting a list of noisy entries after each run, so they never count as new coverage
+use alloc::{borrow::Cow, vec::Vec};
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+use libafl_bolts::{AsSlice, AsSliceMut, HasLen, Named, Truncate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::{map::MapObserver, DifferentialObserver, Observer, ObserversTuple},
+    Error,
+};
+
+/// Map observer that resets the ignored entries to the map's initial value after each run,
+/// before any feedback classifies the map.
+///
+/// Use it to exclude noisy edges, e.g., of a logging subsystem or timestamp-dependent branches,
+/// which would otherwise be reported as new coverage over and over again.
+/// This is like `AFL++`'s instrumentation denylist, but applied without recompiling the target.
+///
+/// Like the [`crate::observers::HitcountsMapObserver`], it wraps slice-backed map observers.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct IgnoreEdgesMapObserver<M>
+where
+    M: Serialize,
+{
+    base: M,
+    /// The indexes of the ignored entries
+    ignored: Vec<usize>,
+}
+
+impl<S, M> Observer<S> for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver + Observer<S>,
+    S: UsesInput,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)?;
+        self.reset_ignored();
+        Ok(())
+    }
+
+    #[inline]
+    fn pre_exec_child(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.base.pre_exec_child(state, input)
+    }
+
+    #[inline]
+    fn post_exec_child(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.base.post_exec_child(state, input, exit_kind)?;
+        self.reset_ignored();
+        Ok(())
+    }
+}
+
+impl<M> Named for IgnoreEdgesMapObserver<M>
+where
+    M: Named + Serialize + serde::de::DeserializeOwned,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.base.name()
+    }
+}
+
+impl<M> HasLen for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+}
+
+impl<M> AsRef<Self> for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<M> AsMut<Self> for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+impl<M> MapObserver for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Entry = M::Entry;
+
+    #[inline]
+    fn initial(&self) -> M::Entry {
+        self.base.initial()
+    }
+
+    #[inline]
+    fn usable_count(&self) -> usize {
+        self.base.usable_count()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> M::Entry {
+        self.base.get(idx)
+    }
+
+    #[inline]
+    fn set(&mut self, idx: usize, val: M::Entry) {
+        self.base.set(idx, val);
+    }
+
+    /// Count the set bytes in the map
+    fn count_bytes(&self) -> u64 {
+        self.base.count_bytes()
+    }
+
+    /// Reset the map
+    #[inline]
+    fn reset_map(&mut self) -> Result<(), Error> {
+        self.base.reset_map()
+    }
+
+    #[inline]
+    fn hash_simple(&self) -> u64 {
+        self.base.hash_simple()
+    }
+    fn to_vec(&self) -> Vec<M::Entry> {
+        self.base.to_vec()
+    }
+
+    fn how_many_set(&self, indexes: &[usize]) -> usize {
+        self.base.how_many_set(indexes)
+    }
+}
+
+impl<M> Truncate for IgnoreEdgesMapObserver<M>
+where
+    M: Named + Serialize + serde::de::DeserializeOwned + Truncate,
+{
+    fn truncate(&mut self, new_len: usize) {
+        self.base.truncate(new_len);
+    }
+}
+
+impl<'a, M> AsSlice<'a> for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver + AsSlice<'a>,
+{
+    type Entry = <M as AsSlice<'a>>::Entry;
+    type SliceRef = <M as AsSlice<'a>>::SliceRef;
+
+    #[inline]
+    fn as_slice(&'a self) -> Self::SliceRef {
+        self.base.as_slice()
+    }
+}
+
+impl<'a, M> AsSliceMut<'a> for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver + AsSliceMut<'a>,
+{
+    type SliceRefMut = <M as AsSliceMut<'a>>::SliceRefMut;
+    #[inline]
+    fn as_slice_mut(&'a mut self) -> Self::SliceRefMut {
+        self.base.as_slice_mut()
+    }
+}
+
+impl<M> IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    /// Creates a new [`IgnoreEdgesMapObserver`], ignoring the entries at the given indexes of `base`
+    pub fn new(base: M, ignored: Vec<usize>) -> Self {
+        Self { base, ignored }
+    }
+
+    /// Creates a new [`IgnoreEdgesMapObserver`], ignoring the entries listed in the given file,
+    /// see [`load_ignored_edges`].
+    #[cfg(feature = "std")]
+    pub fn from_file<P>(base: M, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::new(base, load_ignored_edges(path)?))
+    }
+
+    /// The indexes of the ignored entries
+    #[must_use]
+    pub fn ignored(&self) -> &[usize] {
+        &self.ignored
+    }
+
+    /// Resets all ignored entries within the usable part of the map to the initial value
+    fn reset_ignored(&mut self) {
+        let initial = self.base.initial();
+        let usable = self.base.usable_count();
+        for &idx in &self.ignored {
+            if idx < usable {
+                self.base.set(idx, initial);
+            }
+        }
+    }
+}
+
+impl<M, OTA, OTB, S> DifferentialObserver<OTA, OTB, S> for IgnoreEdgesMapObserver<M>
+where
+    M: MapObserver + Observer<S> + DifferentialObserver<OTA, OTB, S>,
+    OTA: ObserversTuple<S>,
+    OTB: ObserversTuple<S>,
+    S: UsesInput,
+{
+    fn pre_observe_first(&mut self, observers: &mut OTA) -> Result<(), Error> {
+        self.base.pre_observe_first(observers)
+    }
+
+    fn post_observe_first(&mut self, observers: &mut OTA) -> Result<(), Error> {
+        self.base.post_observe_first(observers)
+    }
+
+    fn pre_observe_second(&mut self, observers: &mut OTB) -> Result<(), Error> {
+        self.base.pre_observe_second(observers)
+    }
+
+    fn post_observe_second(&mut self, observers: &mut OTB) -> Result<(), Error> {
+        self.base.post_observe_second(observers)
+    }
+}
+
+/// Loads a list of map indexes to ignore, one per line, in decimal or `0x`-prefixed hex.
+/// Empty lines and lines starting with `#` are skipped.
+#[cfg(feature = "std")]
+pub fn load_ignored_edges<P>(path: P) -> Result<Vec<usize>, Error>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .map(|(line_no, line)| (line_no, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_no, line)| {
+            let parsed = match line.strip_prefix("0x") {
+                Some(hex) => usize::from_str_radix(hex, 16),
+                None => line.parse(),
+            };
+            parsed.map_err(|err| {
+                Error::illegal_argument(format!(
+                    "Invalid edge index {line:?} in {}:{}: {err}",
+                    path.display(),
+                    line_no + 1
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, Feedback, MaxMapFeedback},
+        inputs::BytesInput,
+        observers::{IgnoreEdgesMapObserver, MapObserver, Observer, StdMapObserver},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_ignored_edges_are_never_interesting() {
+        let observer =
+            IgnoreEdgesMapObserver::new(StdMapObserver::owned("edges", vec![0_u8; 16]), vec![3]);
+        let mut feedback = MaxMapFeedback::new(&observer);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+        let mut observers = tuple_list!(observer);
+
+        for (hit, expected) in [(3, false), (4, true)] {
+            observers.0.pre_exec(&mut state, &input).unwrap();
+            observers.0.set(hit, 1);
+            observers
+                .0
+                .post_exec(&mut state, &input, &ExitKind::Ok)
+                .unwrap();
+            let interesting = feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap();
+            assert_eq!(interesting, expected, "edge {hit}");
+            feedback.discard_metadata(&mut state, &input).unwrap();
+        }
+    }
+}
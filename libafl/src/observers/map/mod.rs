@@ -29,9 +29,15 @@ pub use variable_map::*;
 pub mod hitcount_map;
 pub use hitcount_map::*;
 
+pub mod ignore_edges;
+pub use ignore_edges::*;
+
 pub mod multi_map;
 pub use multi_map::*;
 
+pub mod new_edges;
+pub use new_edges::*;
+
 pub mod owned_map;
 pub use owned_map::*;
 
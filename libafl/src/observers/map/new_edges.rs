@@ -0,0 +1,324 @@
+//! Map observer recording the entries each execution covered for the first time
+use alloc::{borrow::Cow, vec::Vec};
+
+use libafl_bolts::{AsSlice, AsSliceMut, HasLen, Named, Truncate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::{map::MapObserver, DifferentialObserver, Observer, ObserversTuple},
+    Error,
+};
+
+/// The number of map entries checked at once while skipping over empty parts of the map
+const NEW_EDGES_CHUNK: usize = 8;
+
+/// Map observer that records the indexes of the entries the last execution covered
+/// for the first time, queryable after `post_exec` through [`NewEdgesMapObserver::new_edges`].
+///
+/// The covered entries are accumulated by the observer itself, over all executions it observed,
+/// whether their inputs got kept or not. As most of the map stays empty in each run,
+/// empty parts are skipped a word at a time, so only the covered entries are looked at.
+/// Wrap it around the [`crate::observers::HitcountsMapObserver`] to see the classified map.
+///
+/// The accumulated coverage is not serialized, so that observers sent to other clients stay small.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct NewEdgesMapObserver<M>
+where
+    M: Serialize,
+{
+    base: M,
+    /// The entries covered in any observed execution, one bit per entry
+    #[serde(skip)]
+    seen: Vec<u64>,
+    /// The entries the last execution covered for the first time
+    new_edges: Vec<usize>,
+}
+
+impl<S, M> Observer<S> for NewEdgesMapObserver<M>
+where
+    M: MapObserver<Entry = u8> + Observer<S> + for<'a> AsSlice<'a, Entry = u8>,
+    S: UsesInput,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.new_edges.clear();
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)?;
+        self.collect_new_edges();
+        Ok(())
+    }
+
+    #[inline]
+    fn pre_exec_child(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.base.pre_exec_child(state, input)
+    }
+
+    #[inline]
+    fn post_exec_child(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.base.post_exec_child(state, input, exit_kind)
+    }
+}
+
+impl<M> Named for NewEdgesMapObserver<M>
+where
+    M: Named + Serialize + serde::de::DeserializeOwned,
+{
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.base.name()
+    }
+}
+
+impl<M> HasLen for NewEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+}
+
+impl<M> AsRef<Self> for NewEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<M> AsMut<Self> for NewEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+impl<M> MapObserver for NewEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Entry = M::Entry;
+
+    #[inline]
+    fn initial(&self) -> M::Entry {
+        self.base.initial()
+    }
+
+    #[inline]
+    fn usable_count(&self) -> usize {
+        self.base.usable_count()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> M::Entry {
+        self.base.get(idx)
+    }
+
+    #[inline]
+    fn set(&mut self, idx: usize, val: M::Entry) {
+        self.base.set(idx, val);
+    }
+
+    /// Count the set bytes in the map
+    fn count_bytes(&self) -> u64 {
+        self.base.count_bytes()
+    }
+
+    /// Reset the map
+    #[inline]
+    fn reset_map(&mut self) -> Result<(), Error> {
+        self.base.reset_map()
+    }
+
+    #[inline]
+    fn hash_simple(&self) -> u64 {
+        self.base.hash_simple()
+    }
+    fn to_vec(&self) -> Vec<M::Entry> {
+        self.base.to_vec()
+    }
+
+    fn how_many_set(&self, indexes: &[usize]) -> usize {
+        self.base.how_many_set(indexes)
+    }
+}
+
+impl<M> Truncate for NewEdgesMapObserver<M>
+where
+    M: Named + Serialize + serde::de::DeserializeOwned + Truncate,
+{
+    fn truncate(&mut self, new_len: usize) {
+        self.base.truncate(new_len);
+    }
+}
+
+impl<'a, M> AsSlice<'a> for NewEdgesMapObserver<M>
+where
+    M: MapObserver + AsSlice<'a>,
+{
+    type Entry = <M as AsSlice<'a>>::Entry;
+    type SliceRef = <M as AsSlice<'a>>::SliceRef;
+
+    #[inline]
+    fn as_slice(&'a self) -> Self::SliceRef {
+        self.base.as_slice()
+    }
+}
+
+impl<'a, M> AsSliceMut<'a> for NewEdgesMapObserver<M>
+where
+    M: MapObserver + AsSliceMut<'a>,
+{
+    type SliceRefMut = <M as AsSliceMut<'a>>::SliceRefMut;
+    #[inline]
+    fn as_slice_mut(&'a mut self) -> Self::SliceRefMut {
+        self.base.as_slice_mut()
+    }
+}
+
+impl<M> NewEdgesMapObserver<M>
+where
+    M: MapObserver,
+{
+    /// Creates a new [`NewEdgesMapObserver`], wrapping `base`
+    pub fn new(base: M) -> Self {
+        Self {
+            base,
+            seen: vec![],
+            new_edges: vec![],
+        }
+    }
+
+    /// The indexes of the entries the last execution covered for the first time, in ascending order
+    #[must_use]
+    pub fn new_edges(&self) -> &[usize] {
+        &self.new_edges
+    }
+
+    /// If the entry at `idx` was covered in any observed execution
+    #[must_use]
+    pub fn seen(&self, idx: usize) -> bool {
+        self.seen
+            .get(idx / 64)
+            .map_or(false, |word| word & (1_u64 << (idx % 64)) != 0)
+    }
+
+    /// Forgets the accumulated coverage, e.g., after the corpus got minimized
+    pub fn reset_seen(&mut self) {
+        self.seen.clear();
+        self.new_edges.clear();
+    }
+}
+
+impl<M> NewEdgesMapObserver<M>
+where
+    M: MapObserver<Entry = u8> + for<'a> AsSlice<'a, Entry = u8>,
+{
+    /// Records the covered entries that were not seen before
+    fn collect_new_edges(&mut self) {
+        let usable = self.base.usable_count();
+        let map = self.base.as_slice();
+        let map = &map[..usable.min(map.len())];
+        if self.seen.len() * 64 < map.len() {
+            self.seen.resize(map.len().div_ceil(64), 0);
+        }
+
+        for (chunk_idx, chunk) in map.chunks(NEW_EDGES_CHUNK).enumerate() {
+            // Most of the map is empty, skip a whole chunk with a single comparison
+            if let Ok(word) = <[u8; NEW_EDGES_CHUNK]>::try_from(chunk) {
+                if u64::from_ne_bytes(word) == 0 {
+                    continue;
+                }
+            }
+            for (offset, entry) in chunk.iter().enumerate() {
+                if *entry == 0 {
+                    continue;
+                }
+                let idx = chunk_idx * NEW_EDGES_CHUNK + offset;
+                let (word, bit) = (idx / 64, 1 << (idx % 64));
+                if self.seen[word] & bit == 0 {
+                    self.seen[word] |= bit;
+                    self.new_edges.push(idx);
+                }
+            }
+        }
+    }
+}
+
+impl<M, OTA, OTB, S> DifferentialObserver<OTA, OTB, S> for NewEdgesMapObserver<M>
+where
+    M: MapObserver + Observer<S> + DifferentialObserver<OTA, OTB, S>,
+    OTA: ObserversTuple<S>,
+    OTB: ObserversTuple<S>,
+    S: UsesInput,
+{
+    fn pre_observe_first(&mut self, observers: &mut OTA) -> Result<(), Error> {
+        self.base.pre_observe_first(observers)
+    }
+
+    fn post_observe_first(&mut self, observers: &mut OTA) -> Result<(), Error> {
+        self.base.post_observe_first(observers)
+    }
+
+    fn pre_observe_second(&mut self, observers: &mut OTB) -> Result<(), Error> {
+        self.base.pre_observe_second(observers)
+    }
+
+    fn post_observe_second(&mut self, observers: &mut OTB) -> Result<(), Error> {
+        self.base.post_observe_second(observers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        executors::ExitKind,
+        inputs::BytesInput,
+        observers::{MapObserver, NewEdgesMapObserver, Observer, StdMapObserver},
+        state::NopState,
+    };
+
+    #[test]
+    fn test_new_edges() {
+        let mut observer = NewEdgesMapObserver::new(StdMapObserver::owned("edges", vec![0_u8; 20]));
+        let mut state = NopState::<BytesInput>::new();
+        let input = BytesInput::new(vec![0]);
+
+        let runs: [(&[usize], &[usize]); 3] =
+            [(&[3, 17], &[3, 17]), (&[3, 9], &[9]), (&[3, 9, 17], &[])];
+        for (hits, expected) in runs {
+            observer.pre_exec(&mut state, &input).unwrap();
+            observer.reset_map().unwrap();
+            for hit in hits {
+                observer.set(*hit, 1);
+            }
+            observer
+                .post_exec(&mut state, &input, &ExitKind::Ok)
+                .unwrap();
+            assert_eq!(observer.new_edges(), expected);
+        }
+        assert!(observer.seen(9));
+        assert!(!observer.seen(4));
+    }
+}
@@ -1,6 +1,8 @@
 //! Observers give insights about runs of a target, such as coverage, timing, stack depth, and more.
 use alloc::borrow::Cow;
 
+pub mod byte_histogram;
+pub use byte_histogram::{ByteHistogramMetadata, ByteHistogramObserver};
 pub mod cmp;
 pub use cmp::*;
 
@@ -21,11 +23,27 @@ pub mod profiling;
 pub use profiling::*;
 
 pub mod concolic;
+pub mod crash_context;
+pub use crash_context::{
+    CrashContext, CrashContextObserver, CRASH_ACCESS_READ, CRASH_ACCESS_UNKNOWN,
+    CRASH_ACCESS_WRITE, CRASH_CONTEXT_SHM_ENV,
+};
+pub mod delivered_input;
+pub use delivered_input::DeliveredInputObserver;
+pub mod exit_code;
+pub use exit_code::ExitCodeObserver;
+#[cfg(feature = "std")]
+pub mod file_artifact;
+#[cfg(feature = "std")]
+pub use file_artifact::FileArtifactObserver;
 pub mod map;
 pub use map::*;
 
 pub mod value;
 
+pub mod stack_depth;
+pub use stack_depth::{StackDepthObserver, STACK_DEPTH_SHM_ENV};
+
 /// List observer
 pub mod list;
 use core::{fmt::Debug, time::Duration};
@@ -198,6 +216,12 @@ pub trait ObserverWithHashField {
     fn hash(&self) -> Option<u64>;
 }
 
+/// A trait for [`Observer`]`s` with the stack frames of a crash
+pub trait ObserverWithStackFrames {
+    /// The instruction pointers of the stack frames of the last crash, innermost first
+    fn frames(&self) -> Option<&[u64]>;
+}
+
 /// A trait for [`Observer`]`s` which observe over differential execution.
 ///
 /// Differential observers have the following flow during a single execution:
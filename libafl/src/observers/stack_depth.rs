@@ -0,0 +1,151 @@
+//! The [`StackDepthObserver`] reads the maximum call-stack depth reached by the target,
+//! as counted by the instrumentation (like `AFL++`'s `__afl_max_stack_depth`).
+
+use alloc::borrow::Cow;
+
+#[cfg(feature = "std")]
+use libafl_bolts::shmem::ShMem;
+use libafl_bolts::{ownedref::OwnedMutPtr, Error, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{executors::ExitKind, inputs::UsesInput, observers::Observer};
+
+/// The env var the id of the stack depth shared memory is passed to the target in.
+/// The size of the mapping is passed in `__LIBAFL_STACK_DEPTH_SHM_ID_SIZE`.
+pub const STACK_DEPTH_SHM_ENV: &str = "__LIBAFL_STACK_DEPTH_SHM_ID";
+
+/// Observes the maximum call-stack depth of a run.
+///
+/// The instrumentation writes the deepest stack depth it saw to a `u64` counter,
+/// usually at the start of a small shared memory region (see [`STACK_DEPTH_SHM_ENV`]).
+/// The counter is reset before each run.
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct StackDepthObserver {
+    name: Cow<'static, str>,
+    /// The counter written by the target
+    depth: OwnedMutPtr<u64>,
+    /// The depth of the last run
+    last_depth: u64,
+}
+
+impl StackDepthObserver {
+    /// Creates a new [`StackDepthObserver`] with the given name, reading the given counter.
+    #[must_use]
+    pub fn new(name: &'static str, depth: OwnedMutPtr<u64>) -> Self {
+        Self {
+            name: Cow::from(name),
+            depth,
+            last_depth: 0,
+        }
+    }
+
+    /// Creates a new [`StackDepthObserver`] reading the counter behind a raw pointer.
+    ///
+    /// # Safety
+    /// Will dereference the pointer.
+    /// The counter may not move in memory and has to outlive this observer.
+    #[must_use]
+    pub unsafe fn from_mut_ptr(name: &'static str, depth: *mut u64) -> Self {
+        Self::new(name, OwnedMutPtr::from_raw_mut(depth))
+    }
+
+    /// Creates a new [`StackDepthObserver`] reading the counter at the start of a shared memory,
+    /// and passes the shared memory to the target in [`STACK_DEPTH_SHM_ENV`].
+    ///
+    /// # Safety
+    /// The shared memory has to outlive this observer.
+    #[cfg(feature = "std")]
+    pub unsafe fn from_shmem<SHM>(name: &'static str, shmem: &mut SHM) -> Result<Self, Error>
+    where
+        SHM: ShMem,
+    {
+        let depth = shmem.as_mut_ptr_of::<u64>().ok_or_else(|| {
+            Error::illegal_argument(format!(
+                "The stack depth shared memory is too small ({} bytes)",
+                shmem.len()
+            ))
+        })?;
+        shmem.write_to_env(STACK_DEPTH_SHM_ENV)?;
+        Ok(Self::from_mut_ptr(name, depth))
+    }
+
+    /// The maximum stack depth reached by the last run
+    #[must_use]
+    pub fn last_depth(&self) -> u64 {
+        self.last_depth
+    }
+}
+
+impl<S> Observer<S> for StackDepthObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        *self.depth.as_mut() = 0;
+        self.last_depth = 0;
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.last_depth = *self.depth.as_ref();
+        Ok(())
+    }
+
+    fn pre_exec_child(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.pre_exec(state, input)
+    }
+
+    fn post_exec_child(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.post_exec(state, input, exit_kind)
+    }
+}
+
+impl Named for StackDepthObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use libafl_bolts::ownedref::OwnedMutPtr;
+
+    use crate::{
+        executors::ExitKind,
+        inputs::BytesInput,
+        observers::{Observer, StackDepthObserver},
+        state::NopState,
+    };
+
+    #[test]
+    fn test_stack_depth_observer() {
+        let mut state = NopState::<BytesInput>::new();
+        let input = BytesInput::new(vec![0]);
+        let mut observer = StackDepthObserver::new("stack_depth", OwnedMutPtr::Owned(Box::new(7)));
+
+        // The counter is reset before the run and read after it
+        observer.pre_exec(&mut state, &input).unwrap();
+        assert_eq!(*observer.depth.as_ref(), 0);
+        *observer.depth.as_mut() = 42;
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.last_depth(), 42);
+
+        observer.pre_exec(&mut state, &input).unwrap();
+        assert_eq!(observer.last_depth(), 0);
+    }
+}
@@ -1,11 +1,16 @@
 //! the ``StacktraceObserver`` looks up the stacktrace on the execution thread and computes a hash for it for dedupe
 
-use alloc::{borrow::Cow, string::String, vec::Vec};
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(unix)]
+use core::ffi::CStr;
 #[cfg(feature = "casr")]
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
-    string::ToString,
 };
 use std::{
     fmt::Debug,
@@ -16,6 +21,8 @@ use std::{
 };
 
 use backtrace::Backtrace;
+#[cfg(unix)]
+use libafl_bolts::hash_std;
 use libafl_bolts::{ownedref::OwnedRefMut, Named};
 #[allow(unused_imports)]
 #[cfg(feature = "casr")]
@@ -38,7 +45,7 @@ use libcasr::{
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use super::ObserverWithHashField;
+use super::{ObserverWithHashField, ObserverWithStackFrames};
 use crate::{executors::ExitKind, inputs::UsesInput, observers::Observer, Error};
 
 #[cfg(not(feature = "casr"))]
@@ -99,6 +106,116 @@ pub fn collect_backtrace() -> u64 {
     s.finish()
 }
 
+/// The maximum number of frames kept in [`StackFrames`]
+pub const MAX_STACK_FRAMES: usize = 32;
+
+/// The stack frames of a crash, innermost first, normalized by [`normalize_frame`].
+///
+/// Plain old data of a fixed size, so that it can live in shared memory,
+/// for a child process to fill it for the parent (see [`BacktraceObserver::with_frames`]).
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StackFrames {
+    len: usize,
+    frames: [u64; MAX_STACK_FRAMES],
+}
+
+impl StackFrames {
+    /// Keeps the first [`MAX_STACK_FRAMES`] of the given `frames`
+    pub fn set(&mut self, frames: &[u64]) {
+        self.len = frames.len().min(MAX_STACK_FRAMES);
+        self.frames[..self.len].copy_from_slice(&frames[..self.len]);
+    }
+
+    /// Drops all frames
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// The frames, innermost first
+    #[must_use]
+    pub fn as_slice(&self) -> &[u64] {
+        &self.frames[..self.len.min(MAX_STACK_FRAMES)]
+    }
+}
+
+/// Normalizes the instruction pointer of a frame to the module it belongs to and its offset therein,
+/// so that it stays the same across restarts and clients despite ASLR.
+/// Returns `ip` as is if it is not part of a loaded module.
+#[cfg(unix)]
+#[must_use]
+pub fn normalize_frame(ip: u64) -> u64 {
+    // Safety: an all-zero `Dl_info` is valid, `dladdr` only looks the address up and fills the info
+    let mut info: libc::Dl_info = unsafe { core::mem::zeroed() };
+    if unsafe { libc::dladdr(ip as *const libc::c_void, &mut info) } == 0
+        || info.dli_fname.is_null()
+    {
+        return ip;
+    }
+    // Safety: `dladdr` succeeded, so `dli_fname` is a valid C string
+    let module = unsafe { CStr::from_ptr(info.dli_fname) }.to_bytes();
+    let module = module.rsplit(|c| *c == b'/').next().unwrap_or(module);
+    hash_std(module) ^ ip.wrapping_sub(info.dli_fbase as u64)
+}
+
+/// Normalizes the instruction pointer of a frame, on this platform, `ip` is returned as is.
+#[cfg(not(unix))]
+#[must_use]
+pub fn normalize_frame(ip: u64) -> u64 {
+    ip
+}
+
+/// If a frame belongs to the signal trampoline of the OS, on top of which the crash handler runs
+fn is_signal_trampoline(name: &str) -> bool {
+    matches!(
+        name,
+        "__restore_rt" | "_sigtramp" | "__kernel_rt_sigreturn" | "__sigtramp"
+    )
+}
+
+/// If a frame belongs to the fuzzer itself, i.e., to the crash handler or to the backtrace collection
+fn is_fuzzer_frame(name: &str) -> bool {
+    let name = name.trim_start_matches('<');
+    name.starts_with("libafl") || name.starts_with("backtrace::")
+}
+
+/// Collects the frames of the current backtrace, innermost first, normalized by [`normalize_frame`].
+///
+/// The frames of the fuzzer on top of the crash are skipped: everything up to the signal trampoline,
+/// if the backtrace is collected in a signal handler, else the leading frames of `LibAFL` itself.
+#[must_use]
+pub fn collect_backtrace_frames() -> Vec<u64> {
+    let mut b = Backtrace::new_unresolved();
+    b.resolve();
+    let frames = b.frames();
+    let names: Vec<Option<String>> = frames
+        .iter()
+        .map(|frame| {
+            frame
+                .symbols()
+                .first()
+                .and_then(|symbol| symbol.name())
+                .map(|name| name.to_string())
+        })
+        .collect();
+    let start = names
+        .iter()
+        .position(|name| name.as_deref().is_some_and(is_signal_trampoline))
+        .map_or_else(
+            || {
+                names
+                    .iter()
+                    .position(|name| !name.as_deref().is_some_and(is_fuzzer_frame))
+                    .unwrap_or(0)
+            },
+            |trampoline| trampoline + 1,
+        );
+    frames[start..]
+        .iter()
+        .map(|frame| normalize_frame(frame.ip() as u64))
+        .collect()
+}
+
 /// An enum encoding the types of harnesses
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum HarnessType {
@@ -117,17 +234,41 @@ pub struct BacktraceObserver<'a> {
     observer_name: Cow<'static, str>,
     hash: OwnedRefMut<'a, Option<u64>>,
     harness_type: HarnessType,
+    /// The frames of the last crash, collected along with the hash
+    frames: OwnedRefMut<'a, StackFrames>,
 }
 
 impl<'a> BacktraceObserver<'a> {
-    #[cfg(not(feature = "casr"))]
     /// Creates a new [`BacktraceObserver`] with the given name.
+    /// The frames are kept by the observer, use [`Self::with_frames`] for a [`HarnessType::Child`].
     #[must_use]
     pub fn new<S>(
         observer_name: S,
         backtrace_hash: OwnedRefMut<'a, Option<u64>>,
         harness_type: HarnessType,
     ) -> Self
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        Self::with_frames(
+            observer_name,
+            backtrace_hash,
+            OwnedRefMut::owned(StackFrames::default()),
+            harness_type,
+        )
+    }
+
+    #[cfg(not(feature = "casr"))]
+    /// Creates a new [`BacktraceObserver`] with the given name, keeping the frames in `frames`.
+    /// For a [`HarnessType::Child`], the frames are collected in the child, so, like the hash,
+    /// they have to live in shared memory for the parent to see them.
+    #[must_use]
+    pub fn with_frames<S>(
+        observer_name: S,
+        backtrace_hash: OwnedRefMut<'a, Option<u64>>,
+        frames: OwnedRefMut<'a, StackFrames>,
+        harness_type: HarnessType,
+    ) -> Self
     where
         S: Into<Cow<'static, str>>,
     {
@@ -135,15 +276,19 @@ impl<'a> BacktraceObserver<'a> {
             observer_name: observer_name.into(),
             hash: backtrace_hash,
             harness_type,
+            frames,
         }
     }
 
     #[cfg(feature = "casr")]
-    /// Creates a new [`BacktraceObserver`] with the given name.
+    /// Creates a new [`BacktraceObserver`] with the given name, keeping the frames in `frames`.
+    /// For a [`HarnessType::Child`], the frames are collected in the child, so, like the hash,
+    /// they have to live in shared memory for the parent to see them.
     #[must_use]
-    pub fn new<S>(
+    pub fn with_frames<S>(
         observer_name: S,
         backtrace_hash: OwnedRefMut<'a, Option<u64>>,
+        frames: OwnedRefMut<'a, StackFrames>,
         harness_type: HarnessType,
     ) -> Self
     where
@@ -154,6 +299,7 @@ impl<'a> BacktraceObserver<'a> {
             observer_name: observer_name.into(),
             hash: backtrace_hash,
             harness_type,
+            frames,
         }
     }
 
@@ -171,9 +317,24 @@ impl<'a> BacktraceObserver<'a> {
         *self.hash.as_mut() = Some(hash);
     }
 
-    /// Clears the current hash value (sets it to `None`)
+    /// Clears the current hash value (sets it to `None`), and the frames
     fn clear_hash(&mut self) {
         *self.hash.as_mut() = None;
+        self.frames.as_mut().clear();
+    }
+
+    /// Collects the hash and the frames of the current backtrace
+    fn collect(&mut self) {
+        self.update_hash(collect_backtrace());
+        self.frames.as_mut().set(&collect_backtrace_frames());
+    }
+
+    /// Fill the frames if the harness type is external, innermost first, normalized by [`normalize_frame`].
+    /// Call after [`Self::fill_external`], which clears them for runs not crashing.
+    pub fn fill_external_frames(&mut self, frames: &[u64]) {
+        if self.harness_type == HarnessType::External && self.hash.as_ref().is_some() {
+            self.frames.as_mut().set(frames);
+        }
     }
 
     /// Fill the hash value if the harness type is external
@@ -181,6 +342,7 @@ impl<'a> BacktraceObserver<'a> {
         if self.harness_type == HarnessType::External {
             if *exit_kind == ExitKind::Crash {
                 self.update_hash(hash);
+                self.frames.as_mut().clear();
             } else {
                 self.clear_hash();
             }
@@ -196,6 +358,13 @@ impl<'a> ObserverWithHashField for BacktraceObserver<'a> {
     }
 }
 
+impl<'a> ObserverWithStackFrames for BacktraceObserver<'a> {
+    fn frames(&self) -> Option<&[u64]> {
+        let frames = self.frames.as_ref().as_slice();
+        (self.hash.as_ref().is_some() && !frames.is_empty()).then_some(frames)
+    }
+}
+
 impl<'a, S> Observer<S> for BacktraceObserver<'a>
 where
     S: UsesInput,
@@ -208,7 +377,7 @@ where
     ) -> Result<(), Error> {
         if self.harness_type == HarnessType::InProcess {
             if *exit_kind == ExitKind::Crash {
-                self.update_hash(collect_backtrace());
+                self.collect();
             } else {
                 self.clear_hash();
             }
@@ -224,7 +393,7 @@ where
     ) -> Result<(), Error> {
         if self.harness_type == HarnessType::Child {
             if *exit_kind == ExitKind::Crash {
-                self.update_hash(collect_backtrace());
+                self.collect();
             } else {
                 self.clear_hash();
             }
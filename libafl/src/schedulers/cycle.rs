@@ -0,0 +1,290 @@
+//! The [`CycleHookScheduler`] calls a hook whenever its inner scheduler completes a queue cycle,
+//! a natural point to change the fuzzing policy.
+
+use core::fmt::{self, Debug, Formatter};
+
+use crate::{
+    corpus::{CorpusId, Testcase},
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    schedulers::{RemovableScheduler, Scheduler, SchedulerMetadata},
+    state::{HasCorpus, UsesState},
+    Error, HasMetadata,
+};
+
+/// A scheduler wrapper calling `on_cycle_complete` with the number of completed cycles,
+/// each time the `inner` scheduler completes a queue cycle.
+///
+/// The cycles are counted by the inner scheduler in its [`SchedulerMetadata`], as done by the
+/// [`crate::schedulers::PowerQueueScheduler`]. The [`crate::schedulers::WeightedScheduler`] keeps
+/// the metadata, but does not advance its cycle count, so the hook does not fire for it.
+/// If the state has no [`SchedulerMetadata`], as with the [`crate::schedulers::QueueScheduler`],
+/// a cycle is counted each time the inner scheduler wraps around the corpus, i.e., schedules an entry
+/// that does not come after the previous one. This fits schedulers that walk the corpus in order.
+/// The hook can, for example, rotate the power schedule, trigger a corpus minimization,
+/// or reset a plateau clock.
+pub struct CycleHookScheduler<CS, F> {
+    inner: CS,
+    on_cycle_complete: F,
+    /// The entry scheduled last, to count wraps when the inner scheduler does not count cycles
+    last_scheduled: Option<CorpusId>,
+    /// The cycles counted by wraps
+    wraps: u64,
+}
+
+impl<CS, F> Debug for CycleHookScheduler<CS, F>
+where
+    CS: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CycleHookScheduler")
+            .field("inner", &self.inner)
+            .field("last_scheduled", &self.last_scheduled)
+            .field("wraps", &self.wraps)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<CS, F> UsesState for CycleHookScheduler<CS, F>
+where
+    CS: UsesState,
+{
+    type State = CS::State;
+}
+
+impl<CS, F> CycleHookScheduler<CS, F>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+    F: FnMut(&mut CS::State, u64) -> Result<(), Error>,
+{
+    /// Creates a new [`CycleHookScheduler`], calling `on_cycle_complete` after each cycle of `inner`
+    pub fn new(inner: CS, on_cycle_complete: F) -> Self {
+        Self {
+            inner,
+            on_cycle_complete,
+            last_scheduled: None,
+            wraps: 0,
+        }
+    }
+
+    /// The queue cycles completed so far, by the inner scheduler's count if it keeps one
+    fn cycles(&self, state: &CS::State) -> u64 {
+        if state.has_metadata::<SchedulerMetadata>() {
+            cycles_done(state)
+        } else {
+            self.wraps
+        }
+    }
+
+    /// The inner scheduler
+    pub fn inner(&self) -> &CS {
+        &self.inner
+    }
+
+    /// The inner scheduler (mutable)
+    pub fn inner_mut(&mut self) -> &mut CS {
+        &mut self.inner
+    }
+}
+
+/// The number of queue cycles completed so far, as counted in the [`SchedulerMetadata`]
+#[must_use]
+pub fn cycles_done<S>(state: &S) -> u64
+where
+    S: HasMetadata,
+{
+    state
+        .metadata::<SchedulerMetadata>()
+        .map_or(0, SchedulerMetadata::queue_cycles)
+}
+
+impl<CS, F> Scheduler for CycleHookScheduler<CS, F>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+    F: FnMut(&mut CS::State, u64) -> Result<(), Error>,
+{
+    fn on_add(&mut self, state: &mut Self::State, idx: CorpusId) -> Result<(), Error> {
+        self.inner.on_add(state, idx)
+    }
+
+    fn on_evaluation<OT>(
+        &mut self,
+        state: &mut Self::State,
+        input: &<Self::State as UsesInput>::Input,
+        observers: &OT,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<Self::State>,
+    {
+        self.inner.on_evaluation(state, input, observers)
+    }
+
+    fn next(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        let cycles_before = self.cycles(state);
+        let id = self.inner.next(state)?;
+        if !state.has_metadata::<SchedulerMetadata>()
+            && self.last_scheduled.is_some_and(|last| id <= last)
+        {
+            self.wraps += 1;
+        }
+        self.last_scheduled = Some(id);
+        let cycles = self.cycles(state);
+        if cycles > cycles_before {
+            log::info!("Completed queue cycle {cycles}");
+            (self.on_cycle_complete)(state, cycles)?;
+        }
+        Ok(id)
+    }
+
+    fn next_debug(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        self.inner.next_debug(state)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut Self::State,
+        next_idx: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        self.inner.set_current_scheduled(state, next_idx)
+    }
+}
+
+impl<CS, F> RemovableScheduler for CycleHookScheduler<CS, F>
+where
+    CS: RemovableScheduler,
+    CS::State: HasCorpus + HasMetadata,
+    F: FnMut(&mut CS::State, u64) -> Result<(), Error>,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        testcase: &Option<Testcase<<Self::State as UsesInput>::Input>>,
+    ) -> Result<(), Error> {
+        self.inner.on_remove(state, idx, testcase)
+    }
+
+    fn on_replace(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        prev: &Testcase<<Self::State as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        self.inner.on_replace(state, idx, prev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+    use core::cell::RefCell;
+
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        schedulers::{
+            powersched::PowerSchedule, CycleHookScheduler, PowerQueueScheduler, QueueScheduler,
+            Scheduler, StdWeightedScheduler,
+        },
+        state::{HasCorpus, StdState},
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    fn test_state(entries: u8) -> TestState {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        for i in 0..entries {
+            state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(vec![i; 4])))
+                .unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_cycle_hook_counts_wraps() {
+        let mut state = test_state(3);
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let fired_hook = fired.clone();
+        let mut scheduler = CycleHookScheduler::new(
+            QueueScheduler::new(),
+            move |_state: &mut TestState, cycles| {
+                fired_hook.borrow_mut().push(cycles);
+                Ok(())
+            },
+        );
+        let ids: Vec<_> = state.corpus().ids().collect();
+        for id in &ids {
+            scheduler.on_add(&mut state, *id).unwrap();
+        }
+
+        // The queue scheduler keeps no cycle count, so each wrap around the corpus counts as one
+        for _ in 0..7 {
+            scheduler.next(&mut state).unwrap();
+        }
+        assert_eq!(*fired.borrow(), [1, 2]);
+    }
+
+    #[test]
+    fn test_cycle_hook_scheduler_metadata() {
+        let mut state = test_state(4);
+        let observer = StdMapObserver::owned("edges", vec![0_u8; 16]);
+        let inner = PowerQueueScheduler::new(&mut state, &observer, PowerSchedule::FAST);
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let fired_hook = fired.clone();
+        let mut scheduler =
+            CycleHookScheduler::new(inner, move |_state: &mut TestState, cycles| {
+                fired_hook.borrow_mut().push(cycles);
+                Ok(())
+            });
+        let ids: Vec<_> = state.corpus().ids().collect();
+        for id in &ids {
+            scheduler.on_add(&mut state, *id).unwrap();
+        }
+
+        // The power queue scheduler counts a cycle each time it starts over at the first entry
+        for _ in 0..4 {
+            scheduler.next(&mut state).unwrap();
+        }
+        assert!(fired.borrow().is_empty());
+        for _ in 4..9 {
+            scheduler.next(&mut state).unwrap();
+        }
+        assert_eq!(*fired.borrow(), [1, 2]);
+    }
+
+    #[test]
+    fn test_cycle_hook_next_debug() {
+        let mut state = test_state(4);
+        let observer = StdMapObserver::owned("edges", vec![0_u8; 16]);
+        let inner = StdWeightedScheduler::new(&mut state, &observer);
+        let mut scheduler =
+            CycleHookScheduler::new(inner, |_state: &mut TestState, _cycles| Ok(()));
+        let ids: Vec<_> = state.corpus().ids().collect();
+        for id in &ids {
+            scheduler.on_add(&mut state, *id).unwrap();
+        }
+
+        // The probe reaches the inner scheduler through the wrapper
+        let id = scheduler.next_debug(&mut state).unwrap();
+        assert!(ids.contains(&id));
+        assert!(state.corpus().current().is_none());
+    }
+}
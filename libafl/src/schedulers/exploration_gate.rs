@@ -0,0 +1,340 @@
+//! The [`ExplorationGateScheduler`] walks the corpus in pure exploration mode until it reaches
+//! a minimum size and coverage, and only then hands over to an exploiting scheduler,
+//! such as the [`crate::schedulers::StdWeightedScheduler`].
+
+use alloc::borrow::{Cow, ToOwned};
+use core::{fmt::Debug, marker::PhantomData};
+
+use libafl_bolts::{impl_serdeany, Named};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    feedbacks::{MapFeedback, MapFeedbackMetadata},
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    schedulers::{RemovableScheduler, Scheduler},
+    state::{HasCorpus, UsesState},
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// Metadata telling whether the [`ExplorationGateScheduler`] already switched to exploitation
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExplorationGateMetadata {
+    exploiting: bool,
+}
+
+impl_serdeany!(ExplorationGateMetadata);
+
+impl ExplorationGateMetadata {
+    /// If the scheduler exploits, i.e., defers to its inner scheduler
+    #[must_use]
+    pub fn exploiting(&self) -> bool {
+        self.exploiting
+    }
+
+    /// The name of the active mode, for stats
+    #[must_use]
+    pub fn mode(&self) -> &'static str {
+        if self.exploiting {
+            "exploit"
+        } else {
+            "explore"
+        }
+    }
+}
+
+/// A scheduler that stays in pure exploration mode, walking the corpus like a queue,
+/// until the corpus holds at least `min_corpus_size` entries and, if set,
+/// a map feedback covers at least a minimum number of entries.
+/// From then on, it defers to the `inner` scheduler for good.
+///
+/// This keeps an exploiting power schedule from fixating on a few entries of a near-empty corpus
+/// grown from a tiny seed. All events are passed on to the `inner` scheduler in both modes,
+/// so its metadata is up to date when it takes over.
+/// The active mode is kept in the [`ExplorationGateMetadata`] of the state.
+/// `T` is the entry type of the map feedback checked for the coverage threshold.
+#[derive(Debug, Clone)]
+pub struct ExplorationGateScheduler<CS, T = u8> {
+    inner: CS,
+    min_corpus_size: usize,
+    /// The name of the map feedback and the number of entries it has to cover
+    min_coverage: Option<(Cow<'static, str>, usize)>,
+    phantom: PhantomData<T>,
+}
+
+impl<CS, T> UsesState for ExplorationGateScheduler<CS, T>
+where
+    CS: UsesState,
+{
+    type State = CS::State;
+}
+
+impl<CS> ExplorationGateScheduler<CS>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata + HasNamedMetadata,
+{
+    /// Creates a new [`ExplorationGateScheduler`], exploring until the corpus holds `min_corpus_size` entries.
+    #[must_use]
+    pub fn new(inner: CS, min_corpus_size: usize) -> Self {
+        Self {
+            inner,
+            min_corpus_size,
+            min_coverage: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Additionally explores until the [`MapFeedback`] `map_feedback`, tracking a map of any entry type,
+    /// covers at least `min_covered_entries` entries.
+    #[must_use]
+    pub fn with_min_coverage<C, N, O, R, T>(
+        self,
+        map_feedback: &MapFeedback<C, N, O, R, T>,
+        min_covered_entries: usize,
+    ) -> ExplorationGateScheduler<CS, T> {
+        ExplorationGateScheduler {
+            inner: self.inner,
+            min_corpus_size: self.min_corpus_size,
+            min_coverage: Some((map_feedback.name().clone(), min_covered_entries)),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<CS, T> ExplorationGateScheduler<CS, T>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata + HasNamedMetadata,
+    T: Debug + Default + Copy + 'static + Serialize + DeserializeOwned,
+{
+    /// The inner, exploiting scheduler
+    pub fn inner(&self) -> &CS {
+        &self.inner
+    }
+
+    /// The inner, exploiting scheduler (mutable)
+    pub fn inner_mut(&mut self) -> &mut CS {
+        &mut self.inner
+    }
+
+    /// If the corpus is big enough and covers enough entries to start exploiting
+    fn thresholds_reached(&self, state: &CS::State) -> bool {
+        if state.corpus().count() < self.min_corpus_size {
+            return false;
+        }
+        self.min_coverage
+            .as_ref()
+            .map_or(true, |(name, min_covered_entries)| {
+                state
+                    .named_metadata_map()
+                    .get::<MapFeedbackMetadata<T>>(name)
+                    .map_or(false, |meta| {
+                        meta.num_covered_map_indexes >= *min_covered_entries
+                    })
+            })
+    }
+
+    /// If the scheduler exploits, switching over once the thresholds are reached
+    fn exploiting(&self, state: &mut CS::State) -> bool {
+        let meta = state.metadata_or_insert_with(ExplorationGateMetadata::default);
+        if meta.exploiting {
+            return true;
+        }
+        if !self.thresholds_reached(state) {
+            return false;
+        }
+        log::info!(
+            "Corpus reached {} entries, switching from exploration to exploitation",
+            state.corpus().count()
+        );
+        state
+            .metadata_mut::<ExplorationGateMetadata>()
+            .unwrap()
+            .exploiting = true;
+        true
+    }
+
+    /// The entry after the current one, wrapping around, as walked while exploring
+    fn next_in_order(state: &CS::State) -> Result<CorpusId, Error> {
+        if state.corpus().count() == 0 {
+            return Err(Error::empty(
+                "No entries in corpus. This often implies the target is not properly instrumented."
+                    .to_owned(),
+            ));
+        }
+        Ok(state
+            .corpus()
+            .current()
+            .and_then(|id| state.corpus().next(id))
+            .unwrap_or_else(|| state.corpus().first().unwrap()))
+    }
+}
+
+impl<CS, T> Scheduler for ExplorationGateScheduler<CS, T>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata + HasNamedMetadata,
+    T: Debug + Default + Copy + 'static + Serialize + DeserializeOwned,
+{
+    fn on_add(&mut self, state: &mut Self::State, idx: CorpusId) -> Result<(), Error> {
+        self.inner.on_add(state, idx)
+    }
+
+    fn on_evaluation<OT>(
+        &mut self,
+        state: &mut Self::State,
+        input: &<Self::State as UsesInput>::Input,
+        observers: &OT,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<Self::State>,
+    {
+        self.inner.on_evaluation(state, input, observers)
+    }
+
+    fn next(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        if self.exploiting(state) {
+            return self.inner.next(state);
+        }
+
+        let id = Self::next_in_order(state)?;
+        self.set_current_scheduled(state, Some(id))?;
+        Ok(id)
+    }
+
+    fn next_debug(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        // Probe without switching over
+        let exploiting = state
+            .metadata::<ExplorationGateMetadata>()
+            .is_ok_and(ExplorationGateMetadata::exploiting);
+        if exploiting || self.thresholds_reached(state) {
+            return self.inner.next_debug(state);
+        }
+        Self::next_in_order(state)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut Self::State,
+        next_idx: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        self.inner.set_current_scheduled(state, next_idx)
+    }
+}
+
+impl<CS, T> RemovableScheduler for ExplorationGateScheduler<CS, T>
+where
+    CS: RemovableScheduler,
+    CS::State: HasCorpus + HasMetadata + HasNamedMetadata,
+    T: Debug + Default + Copy + 'static + Serialize + DeserializeOwned,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        testcase: &Option<Testcase<<Self::State as UsesInput>::Input>>,
+    ) -> Result<(), Error> {
+        self.inner.on_remove(state, idx, testcase)
+    }
+
+    fn on_replace(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        prev: &Testcase<<Self::State as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        self.inner.on_replace(state, idx, prev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use super::{ExplorationGateMetadata, ExplorationGateScheduler};
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::{ConstFeedback, MapFeedbackMetadata, MaxMapFeedback},
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        schedulers::{QueueScheduler, Scheduler},
+        state::{HasCorpus, StdState},
+        HasMetadata, HasNamedMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    fn add_entry(state: &mut TestState, byte: u8) {
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![byte])))
+            .unwrap();
+    }
+
+    fn mode(state: &TestState) -> &'static str {
+        state.metadata::<ExplorationGateMetadata>().unwrap().mode()
+    }
+
+    #[test]
+    fn test_exploration_gate_corpus_size() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut scheduler = ExplorationGateScheduler::new(QueueScheduler::new(), 2);
+
+        add_entry(&mut state, 0);
+        scheduler.next(&mut state).unwrap();
+        assert_eq!(mode(&state), "explore");
+
+        add_entry(&mut state, 1);
+        scheduler.next(&mut state).unwrap();
+        assert_eq!(mode(&state), "exploit");
+    }
+
+    #[test]
+    fn test_exploration_gate_coverage() {
+        // a map of `u16` entries, as the threshold is not tied to `u8` maps
+        let observer = StdMapObserver::owned("map", vec![0_u16; 8]);
+        let mut feedback = MaxMapFeedback::new(&observer);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut scheduler =
+            ExplorationGateScheduler::new(QueueScheduler::new(), 1).with_min_coverage(&feedback, 4);
+
+        add_entry(&mut state, 0);
+        scheduler.next(&mut state).unwrap();
+        assert_eq!(mode(&state), "explore");
+
+        state
+            .named_metadata_mut::<MapFeedbackMetadata<u16>>("map")
+            .unwrap()
+            .num_covered_map_indexes = 4;
+        scheduler.next(&mut state).unwrap();
+        assert_eq!(mode(&state), "exploit");
+
+        // once exploiting, the scheduler does not switch back
+        state
+            .named_metadata_mut::<MapFeedbackMetadata<u16>>("map")
+            .unwrap()
+            .num_covered_map_indexes = 0;
+        scheduler.next(&mut state).unwrap();
+        assert_eq!(mode(&state), "exploit");
+    }
+}
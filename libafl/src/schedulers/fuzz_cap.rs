@@ -0,0 +1,272 @@
+//! The [`MaxFuzzCountScheduler`] caps how many times a single testcase is scheduled in a campaign,
+//! to spread the fuzzing effort over the corpus.
+
+#[cfg(not(feature = "std"))]
+use alloc::borrow::ToOwned;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::stages::stop_on_objective::{StopRequestedMetadata, STOP_ON_OBJECTIVE_EXIT_OTHER};
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    schedulers::{RemovableScheduler, Scheduler},
+    state::{HasCorpus, UsesState},
+    Error, HasMetadata,
+};
+
+/// A testcase metadata marking a testcase scheduled the maximum number of times
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct FuzzCountExhaustedMetadata {}
+
+libafl_bolts::impl_serdeany!(FuzzCountExhaustedMetadata);
+
+/// A scheduler wrapper skipping the entries of the `inner` scheduler that were already scheduled
+/// `max_fuzz_count` times, as counted by [`Testcase::scheduled_count`].
+///
+/// AFL puts no hard cap on how often an entry is fuzzed, so a power schedule may over-invest in a
+/// few entries. Once an entry reaches the cap, it is marked with a [`FuzzCountExhaustedMetadata`]
+/// and never scheduled again. The `inner` scheduler is asked once per [`Scheduler::next`]; if it
+/// picks an exhausted entry, the next entry of the corpus still below the cap is scheduled instead.
+///
+/// Once all entries are exhausted, the scheduler requests a clean stop by adding a
+/// [`crate::stages::stop_on_objective::StopRequestedMetadata`] to the state, which makes a
+/// [`crate::stages::StopOnObjectiveStage`] exit the fuzzer, and keeps scheduling the pick of the
+/// `inner` scheduler until then. Without the `std` feature, [`Scheduler::next`] fails instead.
+#[derive(Debug, Clone)]
+pub struct MaxFuzzCountScheduler<CS> {
+    inner: CS,
+    max_fuzz_count: usize,
+}
+
+impl<CS> UsesState for MaxFuzzCountScheduler<CS>
+where
+    CS: UsesState,
+{
+    type State = CS::State;
+}
+
+impl<CS> MaxFuzzCountScheduler<CS>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+{
+    /// Creates a new [`MaxFuzzCountScheduler`], scheduling each entry at most `max_fuzz_count` times
+    #[must_use]
+    pub fn new(inner: CS, max_fuzz_count: usize) -> Self {
+        Self {
+            inner,
+            max_fuzz_count,
+        }
+    }
+
+    /// The maximum number of times an entry is scheduled
+    #[must_use]
+    pub fn max_fuzz_count(&self) -> usize {
+        self.max_fuzz_count
+    }
+
+    /// The inner scheduler
+    pub fn inner(&self) -> &CS {
+        &self.inner
+    }
+
+    /// The inner scheduler (mutable)
+    pub fn inner_mut(&mut self) -> &mut CS {
+        &mut self.inner
+    }
+
+    /// If the entry was scheduled the maximum number of times, marking it as exhausted once it was
+    fn exhausted(&self, state: &CS::State, id: CorpusId) -> Result<bool, Error> {
+        if !self.reached_cap(state, id)? {
+            return Ok(false);
+        }
+        let mut testcase = state.corpus().get(id)?.borrow_mut();
+        if testcase.has_metadata::<FuzzCountExhaustedMetadata>() {
+            return Ok(true);
+        }
+        log::debug!(
+            "Corpus entry {id} was scheduled {} times, skipping it from now on",
+            self.max_fuzz_count
+        );
+        testcase.add_metadata(FuzzCountExhaustedMetadata {});
+        Ok(true)
+    }
+
+    /// If the entry was scheduled the maximum number of times, without marking it
+    fn reached_cap(&self, state: &CS::State, id: CorpusId) -> Result<bool, Error> {
+        let testcase = state.corpus().get(id)?.borrow();
+        Ok(testcase.has_metadata::<FuzzCountExhaustedMetadata>()
+            || testcase.scheduled_count() >= self.max_fuzz_count)
+    }
+
+    /// The entry after `id` in the corpus, wrapping around at the end
+    fn next_wrapping(state: &CS::State, id: CorpusId) -> Option<CorpusId> {
+        state.corpus().next(id).or_else(|| state.corpus().first())
+    }
+}
+
+impl<CS> Scheduler for MaxFuzzCountScheduler<CS>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+{
+    fn on_add(&mut self, state: &mut Self::State, idx: CorpusId) -> Result<(), Error> {
+        self.inner.on_add(state, idx)
+    }
+
+    fn on_evaluation<OT>(
+        &mut self,
+        state: &mut Self::State,
+        input: &<Self::State as UsesInput>::Input,
+        observers: &OT,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<Self::State>,
+    {
+        self.inner.on_evaluation(state, input, observers)
+    }
+
+    fn next(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        let id = self.inner.next(state)?;
+        if !self.exhausted(state, id)? {
+            return Ok(id);
+        }
+
+        // Take the next entry below the cap instead, without asking the inner scheduler again
+        let mut next = Self::next_wrapping(state, id);
+        while let Some(candidate) = next.filter(|candidate| *candidate != id) {
+            if !self.exhausted(state, candidate)? {
+                self.set_current_scheduled(state, Some(candidate))?;
+                return Ok(candidate);
+            }
+            next = Self::next_wrapping(state, candidate);
+        }
+
+        #[cfg(feature = "std")]
+        {
+            if !state.has_metadata::<StopRequestedMetadata>() {
+                log::info!(
+                    "All corpus entries were scheduled {} times, requesting to stop",
+                    self.max_fuzz_count
+                );
+                state.add_metadata(StopRequestedMetadata {
+                    exit_code: STOP_ON_OBJECTIVE_EXIT_OTHER,
+                });
+            }
+            Ok(id)
+        }
+        #[cfg(not(feature = "std"))]
+        Err(Error::empty(
+            "All corpus entries were scheduled the maximum number of times".to_owned(),
+        ))
+    }
+
+    fn next_debug(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        let id = self.inner.next_debug(state)?;
+        let mut candidate = Some(id);
+        while let Some(current) = candidate {
+            if !self.reached_cap(state, current)? {
+                return Ok(current);
+            }
+            candidate = Self::next_wrapping(state, current).filter(|next| *next != id);
+        }
+        // All entries are exhausted, `next` returns the inner scheduler's pick then
+        Ok(id)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut Self::State,
+        next_idx: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        self.inner.set_current_scheduled(state, next_idx)
+    }
+}
+
+impl<CS> RemovableScheduler for MaxFuzzCountScheduler<CS>
+where
+    CS: RemovableScheduler,
+    CS::State: HasCorpus + HasMetadata,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        testcase: &Option<Testcase<<Self::State as UsesInput>::Input>>,
+    ) -> Result<(), Error> {
+        self.inner.on_remove(state, idx, testcase)
+    }
+
+    fn on_replace(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        prev: &Testcase<<Self::State as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        self.inner.on_replace(state, idx, prev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::bytes::BytesInput,
+        schedulers::{MaxFuzzCountScheduler, QueueScheduler, Scheduler},
+        state::{HasCorpus, StdState},
+    };
+    #[cfg(feature = "std")]
+    use crate::{stages::stop_on_objective::StopRequestedMetadata, HasMetadata};
+
+    #[test]
+    fn test_max_fuzz_count() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let first = corpus.add(Testcase::new(BytesInput::new(vec![0]))).unwrap();
+        let second = corpus.add(Testcase::new(BytesInput::new(vec![1]))).unwrap();
+
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut scheduler = MaxFuzzCountScheduler::new(QueueScheduler::new(), 1);
+
+        state
+            .corpus()
+            .get(first)
+            .unwrap()
+            .borrow_mut()
+            .set_scheduled_count(1);
+        assert_eq!(scheduler.next(&mut state).unwrap(), second);
+        assert_eq!(scheduler.next(&mut state).unwrap(), second);
+
+        state
+            .corpus()
+            .get(second)
+            .unwrap()
+            .borrow_mut()
+            .set_scheduled_count(1);
+        #[cfg(feature = "std")]
+        {
+            scheduler.next(&mut state).unwrap();
+            assert!(state.has_metadata::<StopRequestedMetadata>());
+        }
+        #[cfg(not(feature = "std"))]
+        assert!(scheduler.next(&mut state).is_err());
+    }
+}
@@ -67,6 +67,26 @@ impl Default for TopRatedsMetadata {
     }
 }
 
+/// Counts the enabled entries of the `corpus` that were not fuzzed yet, i.e., never finished a scheduled round,
+/// returning the number of all such entries and of those among them marked with [`IsFavoredMetadata`].
+pub fn count_pending<C>(corpus: &C) -> Result<(usize, usize), Error>
+where
+    C: Corpus,
+{
+    let mut pending = 0;
+    let mut pending_favored = 0;
+    for idx in corpus.ids() {
+        let testcase = corpus.get(idx)?.borrow();
+        if testcase.scheduled_count() == 0 {
+            pending += 1;
+            if testcase.has_metadata::<IsFavoredMetadata>() {
+                pending_favored += 1;
+            }
+        }
+    }
+    Ok((pending, pending_favored))
+}
+
 /// The [`MinimizerScheduler`] employs a genetic algorithm to compute a subset of the
 /// corpus that exercise all the requested features (e.g. all the coverage seen so far)
 /// prioritizing [`Testcase`]`s` using [`TestcaseScore`]
@@ -238,6 +258,23 @@ where
         Ok(idx)
     }
 
+    /// Probes the next entry, skipping non-favored ones like [`Self::next`], but without culling
+    fn next_debug(&mut self, state: &mut CS::State) -> Result<CorpusId, Error> {
+        let mut idx = self.base.next_debug(state)?;
+        while {
+            let has = !state
+                .corpus()
+                .get(idx)?
+                .borrow()
+                .has_metadata::<IsFavoredMetadata>();
+            has
+        } && state.rand_mut().coinflip(self.skip_non_favored_prob)
+        {
+            idx = self.base.next_debug(state)?;
+        }
+        Ok(idx)
+    }
+
     /// Set current fuzzed corpus id and `scheduled_count`
     fn set_current_scheduled(
         &mut self,
@@ -298,8 +335,12 @@ where
                         old_meta.refcnt() <= 0
                     };
 
-                    if must_remove && self.remove_metadata {
-                        drop(old.metadata_map_mut().remove::<M>());
+                    if must_remove {
+                        // The old entry is no longer top rated for any index, so it is no longer favored
+                        drop(old.metadata_map_mut().remove::<IsFavoredMetadata>());
+                        if self.remove_metadata {
+                            drop(old.metadata_map_mut().remove::<M>());
+                        }
                     }
                 }
 
@@ -340,6 +381,7 @@ where
         };
 
         let mut acc = HashSet::new();
+        let mut favoreds = HashSet::new();
 
         for (key, idx) in &top_rated.map {
             if !acc.contains(key) {
@@ -355,12 +397,39 @@ where
                 }
 
                 entry.add_metadata(IsFavoredMetadata {});
+                favoreds.insert(*idx);
+            }
+        }
+
+        // Top rated entries whose indexes are all covered by other favored entries are no longer favored
+        for idx in top_rated.map.values() {
+            if !favoreds.contains(idx) {
+                drop(
+                    state
+                        .corpus()
+                        .get(*idx)?
+                        .borrow_mut()
+                        .metadata_map_mut()
+                        .remove::<IsFavoredMetadata>(),
+                );
             }
         }
 
         Ok(())
     }
 
+    /// The number of corpus entries that were not fuzzed yet, like `AFL++`'s `pending_total`
+    #[allow(clippy::unused_self)]
+    pub fn pending(&self, state: &CS::State) -> Result<usize, Error> {
+        Ok(count_pending(state.corpus())?.0)
+    }
+
+    /// The number of favored corpus entries that were not fuzzed yet, like `AFL++`'s `pending_favs`
+    #[allow(clippy::unused_self)]
+    pub fn pending_favored(&self, state: &CS::State) -> Result<usize, Error> {
+        Ok(count_pending(state.corpus())?.1)
+    }
+
     /// Get a reference to the base scheduler
     pub fn base(&self) -> &CS {
         &self.base
@@ -4,7 +4,7 @@ use alloc::{borrow::ToOwned, string::ToString};
 use core::marker::PhantomData;
 
 pub mod testcase_score;
-pub use testcase_score::{LenTimeMulTestcaseScore, TestcaseScore};
+pub use testcase_score::{LenTimeMulTestcaseScore, MulTestcaseScore, TestcaseScore};
 
 pub mod queue;
 pub use queue::QueueScheduler;
@@ -15,7 +15,7 @@ pub use minimizer::{
 };
 
 pub mod powersched;
-pub use powersched::{PowerQueueScheduler, SchedulerMetadata};
+pub use powersched::{PowerQueueScheduler, PowerScheduleParams, SchedulerMetadata};
 
 pub mod probabilistic_sampling;
 pub use probabilistic_sampling::ProbabilitySamplingScheduler;
@@ -26,6 +26,18 @@ pub use accounting::CoverageAccountingScheduler;
 pub mod weighted;
 pub use weighted::{StdWeightedScheduler, WeightedScheduler};
 
+pub mod exploration_gate;
+pub use exploration_gate::{ExplorationGateMetadata, ExplorationGateScheduler};
+
+pub mod cycle;
+pub use cycle::{cycles_done, CycleHookScheduler};
+
+pub mod fuzz_cap;
+pub use fuzz_cap::{FuzzCountExhaustedMetadata, MaxFuzzCountScheduler};
+
+pub mod rarity;
+pub use rarity::{EdgeFrequencyMetadata, RarityBucketScheduler, RarityBucketsMetadata};
+
 pub mod tuneable;
 use libafl_bolts::{
     rands::Rand,
@@ -188,6 +200,16 @@ where
     fn next(&mut self, state: &mut Self::State) -> Result<CorpusId, Error>;
     // Increment corpus.current() here if it has no inner
 
+    /// Probes which entry this scheduler would pick next, for testing.
+    /// Unlike [`Scheduler::next`], this neither sets the current corpus id nor updates any scheduling bookkeeping.
+    /// Randomness is still drawn from the state's rand, so seeding it with a fixed value makes the
+    /// sequence of returned ids reproducible and assertable from a test, given a known corpus and metadata.
+    fn next_debug(&mut self, _state: &mut Self::State) -> Result<CorpusId, Error> {
+        Err(Error::not_implemented(
+            "next_debug is not implemented for this scheduler",
+        ))
+    }
+
     /// Set current fuzzed corpus id and `scheduled_count`
     fn set_current_scheduled(
         &mut self,
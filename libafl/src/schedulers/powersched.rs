@@ -46,6 +46,9 @@ pub struct SchedulerMetadata {
     queue_cycles: u64,
     /// The vector to contain the frequency of each execution path.
     n_fuzz: Vec<u32>,
+    /// The tunable constants of the power schedules
+    #[serde(default)]
+    params: PowerScheduleParams,
 }
 
 /// The metadata for runs in the calibration stage.
@@ -62,9 +65,30 @@ impl SchedulerMetadata {
             bitmap_entries: 0,
             queue_cycles: 0,
             n_fuzz: vec![0; N_FUZZ_SIZE],
+            params: PowerScheduleParams::default(),
         }
     }
 
+    /// Creates a new [`struct@SchedulerMetadata`] with the given [`PowerScheduleParams`]
+    #[must_use]
+    pub fn with_params(strat: Option<PowerSchedule>, params: PowerScheduleParams) -> Self {
+        Self {
+            params,
+            ..Self::new(strat)
+        }
+    }
+
+    /// The tunable constants of the power schedules
+    #[must_use]
+    pub fn params(&self) -> &PowerScheduleParams {
+        &self.params
+    }
+
+    /// Sets the tunable constants of the power schedules
+    pub fn set_params(&mut self, params: PowerScheduleParams) {
+        self.params = params;
+    }
+
     /// The powerschedule strategy
     #[must_use]
     pub fn strat(&self) -> Option<PowerSchedule> {
@@ -150,6 +174,92 @@ impl SchedulerMetadata {
     }
 }
 
+/// The tunable constants of the energy calculation of the power schedules,
+/// see [`crate::schedulers::testcase_score::CorpusPowerTestcaseScore`].
+/// The defaults match `AFL++`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PowerScheduleParams {
+    power_beta: f64,
+    max_factor: f64,
+    havoc_max_mult: f64,
+    fast_favored_mult: f64,
+}
+
+impl Default for PowerScheduleParams {
+    fn default() -> Self {
+        Self {
+            power_beta: 1.0,
+            max_factor: 32.0,
+            havoc_max_mult: 64.0,
+            fast_favored_mult: 1.15,
+        }
+    }
+}
+
+impl PowerScheduleParams {
+    /// Creates new [`PowerScheduleParams`].
+    ///
+    /// - `power_beta` is the divisor of the schedule factor (`POWER_BETA` in `AFL++`),
+    ///   lower values give more energy
+    /// - `max_factor` is the cap of the schedule factor, as multiple of `power_beta`.
+    ///   The `exploit` schedule always uses this factor.
+    /// - `havoc_max_mult` is the cap of the final score, as multiple of the base score of `100`
+    ///   (`HAVOC_MAX_MULT` in `AFL++`)
+    /// - `fast_favored_mult` is the bonus multiplier for favored entries in the `fast` schedule
+    ///
+    /// Returns an error if any of them is not a positive, finite number, as the energy would
+    /// become infinite, `NaN` or zero otherwise.
+    pub fn new(
+        power_beta: f64,
+        max_factor: f64,
+        havoc_max_mult: f64,
+        fast_favored_mult: f64,
+    ) -> Result<Self, Error> {
+        for (name, value) in [
+            ("power_beta", power_beta),
+            ("max_factor", max_factor),
+            ("havoc_max_mult", havoc_max_mult),
+            ("fast_favored_mult", fast_favored_mult),
+        ] {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(Error::illegal_argument(format!(
+                    "{name} must be a positive, finite number, got {value}"
+                )));
+            }
+        }
+        Ok(Self {
+            power_beta,
+            max_factor,
+            havoc_max_mult,
+            fast_favored_mult,
+        })
+    }
+
+    /// The divisor of the schedule factor
+    #[must_use]
+    pub fn power_beta(&self) -> f64 {
+        self.power_beta
+    }
+
+    /// The cap of the schedule factor, as multiple of `power_beta`
+    #[must_use]
+    pub fn max_factor(&self) -> f64 {
+        self.max_factor
+    }
+
+    /// The cap of the final score, as multiple of the base score of `100`
+    #[must_use]
+    pub fn havoc_max_mult(&self) -> f64 {
+        self.havoc_max_mult
+    }
+
+    /// The bonus multiplier for favored entries in the `fast` schedule
+    #[must_use]
+    pub fn fast_favored_mult(&self) -> f64 {
+        self.fast_favored_mult
+    }
+}
+
 /// The power schedule to use
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PowerSchedule {
@@ -311,9 +421,43 @@ where
         }
     }
 
+    /// Create a new [`PowerQueueScheduler`], with the given [`PowerScheduleParams`]
+    /// instead of the `AFL++` defaults
+    #[must_use]
+    pub fn with_params(
+        state: &mut S,
+        map_observer: &C,
+        strat: PowerSchedule,
+        params: PowerScheduleParams,
+    ) -> Self {
+        let scheduler = Self::new(state, map_observer, strat);
+        if let Ok(psmeta) = state.metadata_mut::<SchedulerMetadata>() {
+            psmeta.set_params(params);
+        }
+        scheduler
+    }
+
     /// Getter for `strat`
     #[must_use]
     pub fn strat(&self) -> &PowerSchedule {
         &self.strat
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PowerScheduleParams;
+
+    #[test]
+    fn test_power_schedule_params() {
+        let params = PowerScheduleParams::new(2.0, 16.0, 32.0, 1.5).unwrap();
+        assert!((params.power_beta() - 2.0).abs() < f64::EPSILON);
+        assert!((params.max_factor() - 16.0).abs() < f64::EPSILON);
+
+        assert!(PowerScheduleParams::new(0.0, 16.0, 32.0, 1.5).is_err());
+        assert!(PowerScheduleParams::new(-1.0, 16.0, 32.0, 1.5).is_err());
+        assert!(PowerScheduleParams::new(1.0, f64::INFINITY, 32.0, 1.5).is_err());
+        assert!(PowerScheduleParams::new(1.0, 16.0, f64::NAN, 1.5).is_err());
+        assert!(PowerScheduleParams::new(1.0, 16.0, 32.0, 0.0).is_err());
+    }
+}
@@ -0,0 +1,483 @@
+//! The [`RarityBucketScheduler`] buckets corpus entries by the rarity of the edges they cover
+//! and caps the number of entries per bucket, so that common paths do not dominate the corpus.
+
+use alloc::vec::Vec;
+use core::{marker::PhantomData, time::Duration};
+
+use hashbrown::HashMap;
+use libafl_bolts::{
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    feedbacks::MapIndexesMetadata,
+    inputs::UsesInput,
+    observers::{MapObserver, ObserversTuple},
+    schedulers::{minimizer::IsFavoredMetadata, RemovableScheduler, Scheduler},
+    state::{HasCorpus, UsesState},
+    Error, HasMetadata,
+};
+
+/// The number of rarity buckets, one per power of two of the edge hit count
+pub const RARITY_BUCKETS: usize = 64;
+
+/// The number of executions that hit each map entry so far, as tracked by the
+/// [`RarityBucketScheduler`].
+///
+/// Only the map entries covered by the corpus are tracked, counting from the execution that added
+/// them to the corpus, so that each execution only checks the corpus coverage, not the whole map.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct EdgeFrequencyMetadata {
+    hits: Vec<u64>,
+    #[serde(default)]
+    tracked: Vec<usize>,
+}
+
+impl_serdeany!(EdgeFrequencyMetadata);
+
+impl EdgeFrequencyMetadata {
+    /// The hit counts, indexed by map entry
+    #[must_use]
+    pub fn hits(&self) -> &[u64] {
+        &self.hits
+    }
+
+    /// The map entries whose hits are counted
+    #[must_use]
+    pub fn tracked(&self) -> &[usize] {
+        &self.tracked
+    }
+
+    /// Counts a hit of the map entry at `idx`
+    pub fn record(&mut self, idx: usize) {
+        if idx >= self.hits.len() {
+            self.hits.resize(idx + 1, 0);
+        }
+        self.hits[idx] = self.hits[idx].saturating_add(1);
+    }
+
+    /// Starts counting the hits of the given map entries, if not done yet,
+    /// counting the execution that covered them
+    pub fn track(&mut self, indexes: &[usize]) {
+        for idx in indexes {
+            if self.hits.get(*idx).copied().unwrap_or(0) == 0 {
+                self.record(*idx);
+                self.tracked.push(*idx);
+            }
+        }
+    }
+
+    /// The hit count of the rarest of the given map entries, `None` if there are none
+    #[must_use]
+    pub fn rarest(&self, indexes: &[usize]) -> Option<u64> {
+        indexes
+            .iter()
+            .map(|idx| self.hits.get(*idx).copied().unwrap_or(0))
+            .min()
+    }
+
+    /// The rarity bucket for entries whose rarest edge was hit `hits` times,
+    /// lower buckets are rarer
+    #[must_use]
+    pub fn bucket(hits: u64) -> usize {
+        hits.max(1).ilog2() as usize
+    }
+}
+
+/// The corpus entries in each rarity bucket of the [`RarityBucketScheduler`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct RarityBucketsMetadata {
+    buckets: Vec<Vec<CorpusId>>,
+    /// The map entries that were the rarest of each corpus entry when it was added
+    #[serde(default)]
+    rare_edges: HashMap<CorpusId, Vec<usize>>,
+}
+
+impl_serdeany!(RarityBucketsMetadata);
+
+impl Default for RarityBucketsMetadata {
+    fn default() -> Self {
+        Self {
+            buckets: vec![vec![]; RARITY_BUCKETS],
+            rare_edges: HashMap::default(),
+        }
+    }
+}
+
+impl RarityBucketsMetadata {
+    /// The corpus entries in each bucket, from the rarest to the most common
+    #[must_use]
+    pub fn buckets(&self) -> &[Vec<CorpusId>] {
+        &self.buckets
+    }
+
+    /// Removes `id` from its bucket, if it is in one
+    fn remove(&mut self, id: CorpusId) {
+        for bucket in &mut self.buckets {
+            bucket.retain(|entry| *entry != id);
+        }
+        self.rare_edges.remove(&id);
+    }
+}
+
+/// A scheduler wrapper that tracks how often each edge covered by the corpus is hit and puts each
+/// entry in a bucket by the hit count of its rarest edges, see [`EdgeFrequencyMetadata::bucket`].
+///
+/// A new entry covers edges no other entry covers yet, so it starts out in the rarest bucket.
+/// As the hit counts of its edges grow, it belongs to more common buckets. Once a bucket holds more
+/// than `max_per_bucket` entries, its entries are moved to the buckets of their current hit counts,
+/// and each bucket still above the cap gets its least valuable entries removed from the corpus:
+/// the slowest entries that are neither favored, nor currently fuzzed, nor the one just added.
+///
+/// The edges of an entry are taken from its [`MapIndexesMetadata`] when it is added, so the map
+/// feedback has to track indexes. Entries without it are never bucketed nor evicted.
+#[derive(Debug, Clone)]
+pub struct RarityBucketScheduler<C, CS, O> {
+    inner: CS,
+    map_observer_handle: Handle<C>,
+    max_per_bucket: usize,
+    phantom: PhantomData<O>,
+}
+
+impl<C, CS, O> UsesState for RarityBucketScheduler<C, CS, O>
+where
+    CS: UsesState,
+{
+    type State = CS::State;
+}
+
+impl<C, CS, O> RarityBucketScheduler<C, CS, O>
+where
+    CS: RemovableScheduler,
+    CS::State: HasCorpus + HasMetadata,
+    C: AsRef<O> + Named,
+    O: MapObserver,
+{
+    /// Creates a new [`RarityBucketScheduler`], keeping at most `max_per_bucket` entries per bucket
+    #[must_use]
+    pub fn new(inner: CS, map_observer: &C, max_per_bucket: usize) -> Self {
+        Self {
+            inner,
+            map_observer_handle: map_observer.handle(),
+            max_per_bucket,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The maximum number of entries per bucket
+    #[must_use]
+    pub fn max_per_bucket(&self) -> usize {
+        self.max_per_bucket
+    }
+
+    /// The inner scheduler
+    pub fn inner(&self) -> &CS {
+        &self.inner
+    }
+
+    /// The inner scheduler (mutable)
+    pub fn inner_mut(&mut self) -> &mut CS {
+        &mut self.inner
+    }
+
+    /// Starts tracking the edges of the new corpus entry `idx` and puts it in its bucket,
+    /// if it has [`MapIndexesMetadata`]. Returns the bucket.
+    fn add_to_bucket(state: &mut CS::State, idx: CorpusId) -> Result<Option<usize>, Error> {
+        let indexes = {
+            let testcase = state.corpus().get(idx)?.borrow();
+            let Ok(meta) = testcase.metadata::<MapIndexesMetadata>() else {
+                return Ok(None);
+            };
+            meta.list.clone()
+        };
+
+        let frequencies = state.metadata_or_insert_with(EdgeFrequencyMetadata::default);
+        frequencies.track(&indexes);
+        let rarest = frequencies.rarest(&indexes).unwrap_or(0);
+        let rare_edges: Vec<usize> = indexes
+            .into_iter()
+            .filter(|edge| frequencies.hits[*edge] == rarest)
+            .collect();
+        let bucket = EdgeFrequencyMetadata::bucket(rarest);
+
+        let buckets = state.metadata_or_insert_with(RarityBucketsMetadata::default);
+        buckets.buckets[bucket].push(idx);
+        buckets.rare_edges.insert(idx, rare_edges);
+        Ok(Some(bucket))
+    }
+
+    /// Moves the entries of `bucket` to the buckets of the current hit counts of their rare edges.
+    /// Returns all buckets that received entries, and `bucket` itself.
+    fn rebucket(state: &mut CS::State, bucket: usize) -> Result<Vec<usize>, Error> {
+        let moves: Vec<(CorpusId, usize)> = {
+            let frequencies = state.metadata::<EdgeFrequencyMetadata>()?;
+            let buckets = state.metadata::<RarityBucketsMetadata>()?;
+            buckets.buckets[bucket]
+                .iter()
+                .filter_map(|id| {
+                    let edges = buckets.rare_edges.get(id)?;
+                    let current =
+                        EdgeFrequencyMetadata::bucket(frequencies.rarest(edges).unwrap_or(0));
+                    (current != bucket).then_some((*id, current))
+                })
+                .collect()
+        };
+
+        let buckets = state.metadata_mut::<RarityBucketsMetadata>()?;
+        let mut touched = vec![bucket];
+        for (id, current) in moves {
+            buckets.buckets[bucket].retain(|entry| *entry != id);
+            buckets.buckets[current].push(id);
+            if !touched.contains(&current) {
+                touched.push(current);
+            }
+        }
+        Ok(touched)
+    }
+
+    /// Evicts entries from `bucket` until it holds at most `max_per_bucket` entries,
+    /// or no entry may be evicted
+    fn enforce_cap(
+        &mut self,
+        state: &mut CS::State,
+        bucket: usize,
+        added: CorpusId,
+    ) -> Result<(), Error> {
+        loop {
+            let entries = state.metadata::<RarityBucketsMetadata>()?.buckets[bucket].clone();
+            if entries.len() <= self.max_per_bucket {
+                return Ok(());
+            }
+            let Some(victim) = Self::eviction_candidate(state, &entries, added)? else {
+                return Ok(());
+            };
+
+            log::debug!("Rarity bucket {bucket} is full, evicting corpus entry {victim}");
+            let testcase = state.corpus_mut().remove(victim)?;
+            self.on_remove(state, victim, &Some(testcase))?;
+        }
+    }
+
+    /// The entry of `bucket` to evict, if any may be evicted
+    fn eviction_candidate(
+        state: &CS::State,
+        bucket: &[CorpusId],
+        added: CorpusId,
+    ) -> Result<Option<CorpusId>, Error> {
+        let current = *state.corpus().current();
+        let mut candidate: Option<(Duration, CorpusId)> = None;
+        for id in bucket {
+            if *id == added || Some(*id) == current {
+                continue;
+            }
+            let testcase = state.corpus().get(*id)?.borrow();
+            if testcase.has_metadata::<IsFavoredMetadata>() {
+                continue;
+            }
+            let exec_time = testcase.exec_time().unwrap_or_default();
+            if candidate.map_or(true, |(slowest, _)| exec_time > slowest) {
+                candidate = Some((exec_time, *id));
+            }
+        }
+        Ok(candidate.map(|(_, id)| id))
+    }
+}
+
+impl<C, CS, O> Scheduler for RarityBucketScheduler<C, CS, O>
+where
+    CS: RemovableScheduler,
+    CS::State: HasCorpus + HasMetadata,
+    C: AsRef<O> + Named,
+    O: MapObserver,
+{
+    fn on_add(&mut self, state: &mut Self::State, idx: CorpusId) -> Result<(), Error> {
+        // Bucket before the inner scheduler may drop the indexes
+        let bucket = Self::add_to_bucket(state, idx)?;
+        self.inner.on_add(state, idx)?;
+        let Some(bucket) = bucket else {
+            return Ok(());
+        };
+
+        if state.metadata::<RarityBucketsMetadata>()?.buckets[bucket].len() <= self.max_per_bucket {
+            return Ok(());
+        }
+        // The hit counts grew since the entries of the bucket were added
+        for bucket in Self::rebucket(state, bucket)? {
+            self.enforce_cap(state, bucket, idx)?;
+        }
+        Ok(())
+    }
+
+    fn on_evaluation<OT>(
+        &mut self,
+        state: &mut Self::State,
+        input: &<Self::State as UsesInput>::Input,
+        observers: &OT,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<Self::State>,
+    {
+        let observer = observers
+            .get(&self.map_observer_handle)
+            .ok_or_else(|| Error::key_not_found("MapObserver not found"))?
+            .as_ref();
+        let initial = observer.initial();
+        let EdgeFrequencyMetadata { hits, tracked } =
+            state.metadata_or_insert_with(EdgeFrequencyMetadata::default);
+        for idx in tracked.iter() {
+            if observer.get(*idx) != initial {
+                hits[*idx] = hits[*idx].saturating_add(1);
+            }
+        }
+        self.inner.on_evaluation(state, input, observers)
+    }
+
+    fn next(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        self.inner.next(state)
+    }
+
+    fn next_debug(&mut self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        self.inner.next_debug(state)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut Self::State,
+        next_idx: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        self.inner.set_current_scheduled(state, next_idx)
+    }
+}
+
+impl<C, CS, O> RemovableScheduler for RarityBucketScheduler<C, CS, O>
+where
+    CS: RemovableScheduler,
+    CS::State: HasCorpus + HasMetadata,
+    C: AsRef<O> + Named,
+    O: MapObserver,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        testcase: &Option<Testcase<<Self::State as UsesInput>::Input>>,
+    ) -> Result<(), Error> {
+        if let Ok(meta) = state.metadata_mut::<RarityBucketsMetadata>() {
+            meta.remove(idx);
+        }
+        self.inner.on_remove(state, idx, testcase)
+    }
+
+    fn on_replace(
+        &mut self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        prev: &Testcase<<Self::State as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        self.inner.on_replace(state, idx, prev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use crate::{
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        feedbacks::{ConstFeedback, MapIndexesMetadata},
+        inputs::BytesInput,
+        observers::{MapObserver, StdMapObserver},
+        schedulers::{
+            rarity::{EdgeFrequencyMetadata, RarityBucketsMetadata},
+            QueueScheduler, RarityBucketScheduler, Scheduler,
+        },
+        state::{HasCorpus, StdState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_edge_frequency() {
+        let mut meta = EdgeFrequencyMetadata::default();
+        for _ in 0..10 {
+            meta.record(3);
+        }
+        meta.record(1);
+        assert_eq!(meta.rarest(&[1, 3]), Some(1));
+        assert_eq!(meta.rarest(&[3, 7]), Some(0));
+        assert_eq!(meta.rarest(&[]), None);
+        assert_eq!(EdgeFrequencyMetadata::bucket(0), 0);
+        assert_eq!(EdgeFrequencyMetadata::bucket(1), 0);
+        assert_eq!(EdgeFrequencyMetadata::bucket(10), 3);
+    }
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+    type TestObserver = StdMapObserver<'static, u8, false>;
+    type TestScheduler =
+        RarityBucketScheduler<TestObserver, QueueScheduler<TestState>, TestObserver>;
+
+    fn add_entry(state: &mut TestState, scheduler: &mut TestScheduler, edge: usize) -> CorpusId {
+        let mut testcase = Testcase::new(BytesInput::new(vec![0]));
+        testcase.add_metadata(MapIndexesMetadata::new(vec![edge]));
+        let id = state.corpus_mut().add(testcase).unwrap();
+        scheduler.on_add(state, id).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_rarity_eviction() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let observer = StdMapObserver::owned("edges", vec![0_u8; 8]);
+        let mut scheduler: TestScheduler =
+            RarityBucketScheduler::new(QueueScheduler::new(), &observer, 1);
+        let mut observers = tuple_list!(observer);
+
+        let first = add_entry(&mut state, &mut scheduler, 0);
+        // Many executions hit the edge of the first entry, making it common
+        observers.0.set(0, 1);
+        for _ in 0..10 {
+            scheduler
+                .on_evaluation(&mut state, &BytesInput::new(vec![]), &observers)
+                .unwrap();
+        }
+        assert_eq!(
+            state.metadata::<EdgeFrequencyMetadata>().unwrap().hits()[0],
+            11
+        );
+
+        // The first entry moves to a more common bucket instead of getting evicted
+        let second = add_entry(&mut state, &mut scheduler, 1);
+        assert_eq!(state.corpus().count(), 2);
+        let buckets = state.metadata::<RarityBucketsMetadata>().unwrap().buckets();
+        assert_eq!(buckets[0], vec![second]);
+        assert_eq!(buckets[EdgeFrequencyMetadata::bucket(11)], vec![first]);
+
+        // The second entry is still rare, so it gets evicted for the new one
+        let third = add_entry(&mut state, &mut scheduler, 2);
+        assert_eq!(state.corpus().count(), 2);
+        assert!(state.corpus().get(second).is_err());
+        let buckets = state.metadata::<RarityBucketsMetadata>().unwrap().buckets();
+        assert_eq!(buckets[0], vec![third]);
+    }
+}
@@ -6,16 +6,23 @@ use libafl_bolts::{HasLen, HasRefCnt};
 
 use crate::{
     corpus::{Corpus, SchedulerTestcaseMetadata, Testcase},
-    feedbacks::MapIndexesMetadata,
+    feedbacks::{MapIndexesMetadata, SlowPathMetadata, SLOW_PATH_PENALTY},
     schedulers::{
         minimizer::{IsFavoredMetadata, TopRatedsMetadata},
-        powersched::{PowerSchedule, SchedulerMetadata},
+        powersched::{PowerSchedule, PowerScheduleParams, SchedulerMetadata},
     },
     state::HasCorpus,
     Error, HasMetadata,
 };
 
 /// Compute the favor factor of a [`Testcase`]. Higher is better.
+///
+/// This is the extension point to customize the favoring logic of the
+/// [`crate::schedulers::WeightedScheduler`], which selects entries with a probability proportional to
+/// their score and takes care of the selection mechanics. The built-in weighting by length, exec time
+/// and coverage is the [`CorpusWeightTestcaseScore`]. Combine it with a score of your own through the
+/// [`MulTestcaseScore`] to adjust it, rather than replacing it. As the score is computed without
+/// an instance, a configurable score reads its parameters from the metadata of the state.
 pub trait TestcaseScore<S>
 where
     S: HasMetadata + HasCorpus,
@@ -44,13 +51,9 @@ where
     }
 }
 
-/// Constants for powerschedules
-const POWER_BETA: f64 = 1.0;
-const MAX_FACTOR: f64 = POWER_BETA * 32.0;
-const HAVOC_MAX_MULT: f64 = 64.0;
-
 /// The power assigned to each corpus entry
-/// This result is used for power scheduling
+/// This result is used for power scheduling.
+/// The constants are taken from the [`PowerScheduleParams`] in the [`SchedulerMetadata`].
 #[derive(Debug, Clone)]
 pub struct CorpusPowerTestcaseScore<S> {
     phantom: PhantomData<S>,
@@ -69,6 +72,8 @@ where
     )]
     fn compute(state: &S, entry: &mut Testcase<S::Input>) -> Result<f64, Error> {
         let psmeta = state.metadata::<SchedulerMetadata>()?;
+        let params = psmeta.params();
+        let max_factor = params.power_beta() * params.max_factor();
 
         let fuzz_mu = if let Some(strat) = psmeta.strat() {
             if strat == PowerSchedule::COE {
@@ -180,7 +185,7 @@ where
                     // Nothing happens in EXPLORE
                 }
                 PowerSchedule::EXPLOIT => {
-                    factor = MAX_FACTOR;
+                    factor = max_factor;
                 }
                 PowerSchedule::COE => {
                     if libm::log2(f64::from(psmeta.n_fuzz()[tcmeta.n_fuzz_entry()])) > fuzz_mu
@@ -225,7 +230,7 @@ where
                         }
 
                         if favored {
-                            factor *= 1.15;
+                            factor *= params.fast_favored_mult();
                         }
                     }
                 }
@@ -242,11 +247,11 @@ where
 
         if let Some(strat) = psmeta.strat() {
             if strat != PowerSchedule::EXPLORE {
-                if factor > MAX_FACTOR {
-                    factor = MAX_FACTOR;
+                if factor > max_factor {
+                    factor = max_factor;
                 }
 
-                perf_score *= factor / POWER_BETA;
+                perf_score *= factor / params.power_beta();
             }
         }
 
@@ -257,15 +262,38 @@ where
             }
         }
 
+        // Slow paths that timed out when found are fuzzed rarely
+        if entry.has_metadata::<SlowPathMetadata>() {
+            perf_score *= SLOW_PATH_PENALTY;
+        }
+
         // Upper bound
-        if perf_score > HAVOC_MAX_MULT * 100.0 {
-            perf_score = HAVOC_MAX_MULT * 100.0;
+        if perf_score > params.havoc_max_mult() * 100.0 {
+            perf_score = params.havoc_max_mult() * 100.0;
         }
 
         Ok(perf_score)
     }
 }
 
+/// The product of two scores, e.g., to weigh the built-in [`CorpusWeightTestcaseScore`] by a custom
+/// favor factor: `WeightedScheduler<C, MulTestcaseScore<CorpusWeightTestcaseScore<S>, MyScore<S>, S>, O, S>`
+#[derive(Debug, Clone)]
+pub struct MulTestcaseScore<A, B, S> {
+    phantom: PhantomData<(A, B, S)>,
+}
+
+impl<A, B, S> TestcaseScore<S> for MulTestcaseScore<A, B, S>
+where
+    A: TestcaseScore<S>,
+    B: TestcaseScore<S>,
+    S: HasCorpus + HasMetadata,
+{
+    fn compute(state: &S, entry: &mut Testcase<S::Input>) -> Result<f64, Error> {
+        Ok(A::compute(state, entry)? * B::compute(state, entry)?)
+    }
+}
+
 /// The weight for each corpus entry
 /// This result is used for corpus scheduling
 #[derive(Debug, Clone)]
@@ -280,7 +308,12 @@ where
     /// Compute the `weight` used in weighted corpus entry selection algo
     #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
     fn compute(state: &S, entry: &mut Testcase<S::Input>) -> Result<f64, Error> {
-        let mut weight = 1.0;
+        // Slow paths that timed out when found are selected rarely
+        let mut weight = if entry.has_metadata::<SlowPathMetadata>() {
+            SLOW_PATH_PENALTY
+        } else {
+            1.0
+        };
         let psmeta = state.metadata::<SchedulerMetadata>()?;
 
         let tcmeta = entry.metadata::<SchedulerTestcaseMetadata>()?;
@@ -338,3 +371,46 @@ where
         Ok(weight)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use libafl_bolts::rands::StdRand;
+
+    use super::{LenTimeMulTestcaseScore, MulTestcaseScore, TestcaseScore};
+    use crate::{
+        corpus::{InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        state::{test::test_std_state, StdState},
+        Error,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Halves the score of every entry
+    struct HalfTestcaseScore;
+
+    impl TestcaseScore<TestState> for HalfTestcaseScore {
+        fn compute(_state: &TestState, _entry: &mut Testcase<BytesInput>) -> Result<f64, Error> {
+            Ok(0.5)
+        }
+    }
+
+    #[test]
+    fn test_mul_testcase_score() {
+        let state: TestState = test_std_state();
+        let mut entry = Testcase::new(BytesInput::new(vec![0; 4]));
+        entry.set_exec_time(Duration::from_millis(10));
+
+        let score = LenTimeMulTestcaseScore::<TestState>::compute(&state, &mut entry).unwrap();
+        assert!((score - 40.0).abs() < f64::EPSILON);
+        let score =
+            MulTestcaseScore::<LenTimeMulTestcaseScore<TestState>, HalfTestcaseScore, _>::compute(
+                &state, &mut entry,
+            )
+            .unwrap();
+        assert!((score - 20.0).abs() < f64::EPSILON);
+    }
+}
@@ -1,6 +1,7 @@
 //! The queue corpus scheduler with weighted queue item selection [from AFL++](https://github.com/AFLplusplus/AFLplusplus/blob/1d4f1e48797c064ee71441ba555b29fc3f467983/src/afl-fuzz-queue.c#L32).
 //! This queue corpus scheduler needs calibration stage.
 
+use alloc::vec::Vec;
 use core::marker::PhantomData;
 
 use hashbrown::HashMap;
@@ -17,7 +18,7 @@ use crate::{
     observers::{MapObserver, ObserversTuple},
     random_corpus_id,
     schedulers::{
-        powersched::{PowerSchedule, SchedulerMetadata},
+        powersched::{PowerSchedule, PowerScheduleParams, SchedulerMetadata},
         testcase_score::{CorpusWeightTestcaseScore, TestcaseScore},
         AflScheduler, RemovableScheduler, Scheduler,
     },
@@ -94,15 +95,23 @@ impl WeightedScheduleMetadata {
 libafl_bolts::impl_serdeany!(WeightedScheduleMetadata);
 
 /// A corpus scheduler using power schedules with weighted queue item selection algo.
+/// The weight of each entry is computed by `F`, see [`TestcaseScore`] for custom weightings.
 #[derive(Clone, Debug)]
 pub struct WeightedScheduler<C, F, O, S> {
     table_invalidated: bool,
     strat: Option<PowerSchedule>,
     map_observer_handle: Handle<C>,
     last_hash: usize,
+    /// If the testcase selected last must not be selected again right away
+    avoid_repeats: bool,
+    /// The testcase selected last
+    last_selected: Option<CorpusId>,
     phantom: PhantomData<(F, O, S)>,
 }
 
+/// How often [`WeightedScheduler::avoid_repeats`] draws again before falling back to the next corpus entry
+const MAX_REPEAT_REDRAWS: usize = 8;
+
 impl<C, F, O, S> WeightedScheduler<C, F, O, S>
 where
     F: TestcaseScore<S>,
@@ -127,10 +136,86 @@ where
             map_observer_handle: map_observer.handle(),
             last_hash: 0,
             table_invalidated: true,
+            avoid_repeats: false,
+            last_selected: None,
             phantom: PhantomData,
         }
     }
 
+    /// Create a new [`WeightedScheduler`], with the given [`PowerScheduleParams`]
+    /// instead of the `AFL++` defaults
+    #[must_use]
+    pub fn with_schedule_params(
+        state: &mut S,
+        map_observer: &C,
+        strat: Option<PowerSchedule>,
+        params: PowerScheduleParams,
+    ) -> Self {
+        let scheduler = Self::with_schedule(state, map_observer, strat);
+        if let Ok(psmeta) = state.metadata_mut::<SchedulerMetadata>() {
+            psmeta.set_params(params);
+        }
+        scheduler
+    }
+
+    /// Never selects the testcase that was just fuzzed again right away, unless it is the only one.
+    /// This spreads the fuzzing over more entries of small corpora.
+    #[must_use]
+    pub fn avoid_repeats(mut self) -> Self {
+        self.avoid_repeats = true;
+        self
+    }
+
+    /// Pick the next entry from the alias table, rebuilding the table if needed.
+    /// This does not touch the cycle bookkeeping or the current corpus id.
+    fn select_next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        if self.table_invalidated {
+            self.create_alias_table(state)?;
+            self.table_invalidated = false;
+        }
+        if state.corpus().count() == 0 {
+            return Err(Error::empty(
+                "No entries in corpus. This often implies the target is not properly instrumented.",
+            ));
+        }
+
+        let s = random_corpus_id!(state.corpus(), state.rand_mut());
+
+        // Choose a random value between 0.0 and 1.0
+        let probability = state.rand_mut().next_float();
+
+        let wsmeta = state.metadata::<WeightedScheduleMetadata>()?;
+        let idx = if probability < *wsmeta.alias_probability().get(&s).unwrap() {
+            s
+        } else {
+            *wsmeta.alias_table().get(&s).unwrap()
+        };
+        Ok(idx)
+    }
+
+    /// Pick the next entry like [`Self::select_next`], but not the one selected last if [`Self::avoid_repeats`] is set
+    fn select_next_distinct(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        let mut idx = self.select_next(state)?;
+        let Some(last) = self.last_selected.filter(|_| self.avoid_repeats) else {
+            return Ok(idx);
+        };
+        if state.corpus().count() < 2 {
+            return Ok(idx);
+        }
+        for _ in 0..MAX_REPEAT_REDRAWS {
+            if idx != last {
+                return Ok(idx);
+            }
+            idx = self.select_next(state)?;
+        }
+        if idx == last {
+            // The other entries are (almost) never picked, take the next one
+            let corpus = state.corpus();
+            idx = corpus.next(last).or_else(|| corpus.first()).unwrap();
+        }
+        Ok(idx)
+    }
+
     #[must_use]
     /// Getter for `strat`
     pub fn strat(&self) -> &Option<PowerSchedule> {
@@ -149,7 +234,7 @@ where
 
         let mut alias_table: HashMap<CorpusId, CorpusId> = HashMap::default();
         let mut alias_probability: HashMap<CorpusId, f64> = HashMap::default();
-        let mut weights: HashMap<CorpusId, f64> = HashMap::default();
+        let mut weights: Vec<(CorpusId, f64)> = Vec::with_capacity(n);
 
         let mut p_arr: HashMap<CorpusId, f64> = HashMap::default();
         let mut s_arr: HashMap<usize, CorpusId> = HashMap::default();
@@ -160,7 +245,7 @@ where
         for i in state.corpus().ids() {
             let mut testcase = state.corpus().get(i)?.borrow_mut();
             let weight = F::compute(state, &mut *testcase)?;
-            weights.insert(i, weight);
+            weights.push((i, weight));
             sum += weight;
         }
 
@@ -305,49 +390,34 @@ where
         self.on_evaluation_metadata(state, input, observers)
     }
 
-    #[allow(clippy::similar_names, clippy::cast_precision_loss)]
     fn next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
-        if self.table_invalidated {
-            self.create_alias_table(state)?;
-            self.table_invalidated = false;
-        }
+        let idx = self.select_next_distinct(state)?;
+        self.last_selected = Some(idx);
         let corpus_counts = state.corpus().count();
-        if corpus_counts == 0 {
-            Err(Error::empty(
-                "No entries in corpus. This often implies the target is not properly instrumented.",
-            ))
-        } else {
-            let s = random_corpus_id!(state.corpus(), state.rand_mut());
-
-            // Choose a random value between 0.0 and 1.0
-            let probability = state.rand_mut().next_float();
 
-            let wsmeta = state.metadata_mut::<WeightedScheduleMetadata>()?;
+        let wsmeta = state.metadata_mut::<WeightedScheduleMetadata>()?;
 
-            let current_cycles = wsmeta.runs_in_current_cycle();
+        let current_cycles = wsmeta.runs_in_current_cycle();
 
-            // TODO deal with corpus_counts decreasing due to removals
-            if current_cycles >= corpus_counts {
-                wsmeta.set_runs_current_cycle(0);
-            } else {
-                wsmeta.set_runs_current_cycle(current_cycles + 1);
-            }
+        // TODO deal with corpus_counts decreasing due to removals
+        if current_cycles >= corpus_counts {
+            wsmeta.set_runs_current_cycle(0);
+        } else {
+            wsmeta.set_runs_current_cycle(current_cycles + 1);
+        }
 
-            let idx = if probability < *wsmeta.alias_probability().get(&s).unwrap() {
-                s
-            } else {
-                *wsmeta.alias_table().get(&s).unwrap()
-            };
+        // Update depth
+        if current_cycles > corpus_counts {
+            let psmeta = state.metadata_mut::<SchedulerMetadata>()?;
+            psmeta.set_queue_cycles(psmeta.queue_cycles() + 1);
+        }
 
-            // Update depth
-            if current_cycles > corpus_counts {
-                let psmeta = state.metadata_mut::<SchedulerMetadata>()?;
-                psmeta.set_queue_cycles(psmeta.queue_cycles() + 1);
-            }
+        self.set_current_scheduled(state, Some(idx))?;
+        Ok(idx)
+    }
 
-            self.set_current_scheduled(state, Some(idx))?;
-            Ok(idx)
-        }
+    fn next_debug(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        self.select_next_distinct(state)
     }
 
     /// Set current fuzzed corpus id and `scheduled_count`
@@ -365,3 +435,121 @@ where
 
 /// The standard corpus weight, same as in `AFL++`
 pub type StdWeightedScheduler<C, O, S> = WeightedScheduler<C, CorpusWeightTestcaseScore<S>, O, S>;
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use alloc::vec::Vec;
+
+    use libafl_bolts::rands::{Rand, StdRand};
+
+    use crate::{
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        schedulers::{Scheduler, StdWeightedScheduler},
+        state::{HasCorpus, HasRand, StdState},
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+    type TestObserver = StdMapObserver<'static, u8, false>;
+    type TestScheduler = StdWeightedScheduler<TestObserver, TestObserver, TestState>;
+
+    fn scheduled_sequence(debug: bool) -> Vec<CorpusId> {
+        scheduled_sequence_with(debug, 4, false)
+    }
+
+    /// A state with `entries` corpus entries, and a weighted scheduler that knows them
+    fn weighted_setup(entries: u8, avoid_repeats: bool) -> (TestState, TestScheduler) {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+
+        let mut corpus = InMemoryCorpus::new();
+        let mut ids = Vec::new();
+        for i in 0..entries {
+            ids.push(
+                corpus
+                    .add(Testcase::new(BytesInput::new(vec![i; 4])))
+                    .unwrap(),
+            );
+        }
+
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let observer = StdMapObserver::owned("edges", vec![0_u8; 16]);
+        let mut scheduler = StdWeightedScheduler::new(&mut state, &observer);
+        if avoid_repeats {
+            scheduler = scheduler.avoid_repeats();
+        }
+        for id in ids {
+            scheduler.on_add(&mut state, id).unwrap();
+        }
+        (state, scheduler)
+    }
+
+    fn scheduled_sequence_with(debug: bool, entries: u8, avoid_repeats: bool) -> Vec<CorpusId> {
+        let (mut state, mut scheduler) = weighted_setup(entries, avoid_repeats);
+        (0..16)
+            .map(|_| {
+                if debug {
+                    let id = scheduler.next_debug(&mut state).unwrap();
+                    assert!(state.corpus().current().is_none());
+                    id
+                } else {
+                    scheduler.next(&mut state).unwrap()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_next_debug_deterministic() {
+        let first = scheduled_sequence(true);
+        let second = scheduled_sequence(true);
+        assert_eq!(first, second);
+
+        // Probing draws the same randomness as the real thing
+        assert_eq!(first, scheduled_sequence(false));
+    }
+
+    #[test]
+    fn test_weighted_avoid_repeats() {
+        let sequence = scheduled_sequence_with(false, 2, true);
+        assert!(sequence.windows(2).all(|pair| pair[0] != pair[1]));
+
+        // A single entry is still selected over and over
+        let sequence = scheduled_sequence_with(false, 1, true);
+        assert!(sequence.iter().all(|id| *id == sequence[0]));
+    }
+
+    #[test]
+    fn test_weighted_next_debug_keeps_next() {
+        // Probing only draws randomness: with the rand reseeded afterwards, `next` picks the same
+        // entries, even though it avoids repeating the entry it selected last
+        for seed in 0..32 {
+            let picks = |probe: bool| {
+                let (mut state, mut scheduler) = weighted_setup(3, true);
+                scheduler.next(&mut state).unwrap();
+                if probe {
+                    for _ in 0..4 {
+                        scheduler.next_debug(&mut state).unwrap();
+                    }
+                }
+                state.rand_mut().set_seed(seed);
+                (0..4)
+                    .map(|_| scheduler.next(&mut state).unwrap())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(picks(true), picks(false));
+        }
+    }
+}
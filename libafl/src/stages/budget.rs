@@ -0,0 +1,208 @@
+//! The [`ExecutionBudgetStage`] caps the share of all executions spent in the wrapped stage,
+//! e.g., to keep cmplog from crowding out havoc.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+};
+use core::marker::PhantomData;
+
+use hashbrown::HashMap;
+use libafl_bolts::{impl_serdeany, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{Event, EventFirer},
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    stages::Stage,
+    state::{HasExecutions, UsesState},
+    Error, HasMetadata,
+};
+
+/// The executions spent in each [`ExecutionBudgetStage`], by name
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionBudgetMetadata {
+    executions: HashMap<String, u64>,
+}
+
+impl_serdeany!(ExecutionBudgetMetadata);
+
+impl ExecutionBudgetMetadata {
+    /// The executions spent in the stage with the given name so far
+    #[must_use]
+    pub fn executions(&self, name: &str) -> u64 {
+        self.executions.get(name).copied().unwrap_or_default()
+    }
+
+    /// Adds executions spent in the stage with the given name
+    pub fn add_executions(&mut self, name: &str, executions: u64) {
+        *self.executions.entry(name.to_string()).or_default() += executions;
+    }
+}
+
+/// A stage that skips the wrapped stage while the executions spent in it exceed a fraction of all executions.
+///
+/// After each run of the wrapped stage, its actual share is reported as the `<name>_share` user stats.
+#[derive(Debug, Clone)]
+pub struct ExecutionBudgetStage<ST> {
+    name: Cow<'static, str>,
+    stage: ST,
+    fraction: f64,
+}
+
+impl<ST> ExecutionBudgetStage<ST> {
+    /// Creates a new [`ExecutionBudgetStage`] spending at most `fraction` (`0.0` to `1.0`) of all executions in `stage`
+    pub fn new(name: &str, stage: ST, fraction: f64) -> Result<Self, Error> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(Error::illegal_argument(format!(
+                "The execution budget fraction has to be in (0, 1], got {fraction}"
+            )));
+        }
+        Ok(Self {
+            name: Cow::Owned(name.to_string()),
+            stage,
+            fraction,
+        })
+    }
+
+    /// The fraction of all executions the wrapped stage may spend
+    #[must_use]
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    /// The wrapped stage
+    pub fn inner(&self) -> &ST {
+        &self.stage
+    }
+
+    /// The wrapped stage (mutable)
+    pub fn inner_mut(&mut self) -> &mut ST {
+        &mut self.stage
+    }
+
+    /// The share of all executions the wrapped stage spent so far
+    #[allow(clippy::cast_precision_loss)]
+    pub fn share<S>(&self, state: &S) -> f64
+    where
+        S: HasExecutions + HasMetadata,
+    {
+        let total = *state.executions();
+        if total == 0 {
+            return 0.0;
+        }
+        let spent = state
+            .metadata::<ExecutionBudgetMetadata>()
+            .map_or(0, |meta| meta.executions(&self.name));
+        spent as f64 / total as f64
+    }
+
+    /// If the wrapped stage spent more than its budget
+    pub fn is_over_budget<S>(&self, state: &S) -> bool
+    where
+        S: HasExecutions + HasMetadata,
+    {
+        self.share(state) > self.fraction
+    }
+}
+
+impl<ST> Named for ExecutionBudgetStage<ST> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<ST> UsesState for ExecutionBudgetStage<ST>
+where
+    ST: UsesState,
+{
+    type State = ST::State;
+}
+
+impl<E, EM, ST, Z> Stage<E, EM, Z> for ExecutionBudgetStage<ST>
+where
+    E: UsesState<State = Self::State>,
+    EM: EventFirer<State = Self::State>,
+    ST: Stage<E, EM, Z>,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasExecutions + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        if self.is_over_budget(state) {
+            return Ok(());
+        }
+
+        let executions_before = *state.executions();
+        self.stage.perform(fuzzer, executor, state, manager)?;
+        let spent = state.executions().saturating_sub(executions_before);
+        let meta = state.metadata_or_insert_with(ExecutionBudgetMetadata::default);
+        meta.add_executions(&self.name, spent);
+        let spent_total = meta.executions(&self.name);
+
+        let total = *state.executions();
+        manager.fire(
+            state,
+            Event::UpdateUserStats {
+                name: Cow::Owned(format!("{}_share", self.name)),
+                value: UserStats::new(
+                    UserStatsValue::Ratio(spent_total, total),
+                    AggregatorOps::Avg,
+                ),
+                phantom: PhantomData,
+            },
+        )
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        self.stage.restart_progress_should_run(state)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        self.stage.clear_restart_progress(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inputs::BytesInput,
+        stages::budget::{ExecutionBudgetMetadata, ExecutionBudgetStage},
+        state::{HasExecutions, NopState},
+        HasMetadata,
+    };
+
+    #[test]
+    fn test_execution_budget() {
+        assert!(ExecutionBudgetStage::new("cmplog", (), 0.0).is_err());
+        assert!(ExecutionBudgetStage::new("cmplog", (), 1.5).is_err());
+
+        let mut state = NopState::<BytesInput>::new();
+        let stage = ExecutionBudgetStage::new("cmplog", (), 0.3).unwrap();
+        assert!(!stage.is_over_budget(&state));
+
+        *state.executions_mut() = 100;
+        state
+            .metadata_or_insert_with(ExecutionBudgetMetadata::default)
+            .add_executions("cmplog", 30);
+        assert!(!stage.is_over_budget(&state));
+
+        state
+            .metadata_mut::<ExecutionBudgetMetadata>()
+            .unwrap()
+            .add_executions("cmplog", 1);
+        assert!(stage.is_over_budget(&state));
+        assert!((stage.share(&state) - 0.31).abs() < f64::EPSILON);
+    }
+}
@@ -66,23 +66,61 @@ impl Default for UnstableEntriesMetadata {
     }
 }
 
+/// Marks a [`crate::corpus::Testcase`] as calibrated by the [`CalibrationStage`].
+/// The stage skips testcases carrying this metadata, unless periodic recalibration is configured.
+/// Remove it from a testcase to force a recalibration.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct CalibratedMetadata {
+    scheduled_count: usize,
+}
+impl_serdeany!(CalibratedMetadata);
+
+impl CalibratedMetadata {
+    /// Create a new [`struct@CalibratedMetadata`] for a testcase calibrated at the given `scheduled_count`
+    #[must_use]
+    pub fn new(scheduled_count: usize) -> Self {
+        Self { scheduled_count }
+    }
+
+    /// The `scheduled_count` of the testcase at the time of its last calibration
+    #[must_use]
+    pub fn scheduled_count(&self) -> usize {
+        self.scheduled_count
+    }
+}
+
 /// Default name for `CalibrationStage`; derived from AFL++
 pub const CALIBRATION_STAGE_NAME: &str = "calibration";
 /// The calibration stage will measure the average exec time and the target's stability for this input.
+///
+/// When slow-path entries are kept, see [`crate::feedbacks::SlowPathFeedback::keep_timeouts`],
+/// wrap this stage in a [`crate::stages::RelaxedTimeoutStage`], so that they are calibrated
+/// with their relaxed timeout instead of timing out on every run.
 #[derive(Clone, Debug)]
 pub struct CalibrationStage<C, O, OT, S> {
     map_observer_handle: Handle<C>,
     map_name: Cow<'static, str>,
     name: Cow<'static, str>,
+    /// The number of runs per calibration, doubled at most for unstable or erroring entries
     stage_max: usize,
+    /// If the configured runs were already reported in the user stats
+    runs_reported: bool,
     /// If we should track stability
     track_stability: bool,
+    /// Recalibrate a testcase after it got scheduled this many times since its last calibration
+    recalibrate: Option<usize>,
+    /// The exit kind of a run that did not error
+    expected_exit_kind: ExitKind,
     restart_helper: ExecutionCountRestartHelper,
     phantom: PhantomData<(O, OT, S)>,
 }
 
-const CAL_STAGE_START: usize = 4; // AFL++'s CAL_CYCLES_FAST + 1
-const CAL_STAGE_MAX: usize = 8; // AFL++'s CAL_CYCLES + 1
+/// The default number of calibration runs, see [`CalibrationStage::with_runs`]
+pub const DEFAULT_CALIBRATION_RUNS: usize = 4; // AFL++'s CAL_CYCLES_FAST + 1
 
 impl<C, O, OT, S> UsesState for CalibrationStage<C, O, OT, S>
 where
@@ -115,19 +153,49 @@ where
         state: &mut E::State,
         mgr: &mut EM,
     ) -> Result<(), Error> {
-        // Run this stage only once for each corpus entry and only if we haven't already inspected it
+        // Run this stage only once for each corpus entry and only if we haven't already inspected it,
+        // unless periodic recalibration is configured
         {
             let testcase = state.current_testcase()?;
             // println!("calibration; corpus.scheduled_count() : {}", corpus.scheduled_count());
 
-            if testcase.scheduled_count() > 0 {
+            let scheduled_count = testcase.scheduled_count();
+            let should_calibrate = match testcase.metadata::<CalibratedMetadata>() {
+                Ok(meta) => self.recalibrate.is_some_and(|recalibrate| {
+                    scheduled_count >= meta.scheduled_count().saturating_add(recalibrate)
+                }),
+                Err(_) => scheduled_count == 0,
+            };
+            if !should_calibrate {
                 return Ok(());
             }
         }
 
+        if !self.runs_reported {
+            self.runs_reported = true;
+            mgr.fire(
+                state,
+                Event::UpdateUserStats {
+                    name: Cow::from("calibration_runs"),
+                    value: UserStats::new(
+                        UserStatsValue::Number(self.stage_max as u64),
+                        AggregatorOps::Max,
+                    ),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+
         let mut iter = self.stage_max;
+        // As AFL++'s CAL_CYCLES + 1 for the default
+        let iter_max = self.stage_max.saturating_mul(2);
         // If we restarted after a timeout or crash, do less iterations.
-        iter -= usize::try_from(self.restart_helper.execs_since_progress_start(state)?)?;
+        // The first run always happens, and the averages below divide by `iter`.
+        iter = iter
+            .saturating_sub(usize::try_from(
+                self.restart_helper.execs_since_progress_start(state)?,
+            )?)
+            .max(1);
 
         let input = state.current_input_cloned()?;
 
@@ -137,7 +205,7 @@ where
         let mut start = current_time();
 
         let exit_kind = executor.run_target(fuzzer, state, mgr, &input)?;
-        let mut total_time = if exit_kind == ExitKind::Ok {
+        let mut total_time = if exit_kind == self.expected_exit_kind {
             current_time() - start
         } else {
             mgr.log(
@@ -159,8 +227,8 @@ where
 
         let mut unstable_entries: Vec<usize> = vec![];
         let map_len: usize = map_first.len();
-        // Run CAL_STAGE_START - 1 times, increase by 2 for every time a new
-        // run is found to be unstable or to crash with CAL_STAGE_MAX total runs.
+        // Run the configured runs - 1 times, increase by 2 for every time a new
+        // run is found to be unstable or to crash with twice the configured runs in total.
         let mut i = 1;
         let mut has_errors = false;
 
@@ -171,7 +239,7 @@ where
             start = current_time();
 
             let exit_kind = executor.run_target(fuzzer, state, mgr, &input)?;
-            if exit_kind != ExitKind::Ok {
+            if exit_kind != self.expected_exit_kind {
                 if !has_errors {
                     mgr.log(
                         state,
@@ -182,7 +250,7 @@ where
                     has_errors = true;
                 }
 
-                if iter < CAL_STAGE_MAX {
+                if iter < iter_max {
                     iter += 2;
                 };
             };
@@ -219,7 +287,7 @@ where
                     };
                 }
 
-                if !unstable_entries.is_empty() && iter < CAL_STAGE_MAX {
+                if !unstable_entries.is_empty() && iter < iter_max {
                     iter += 2;
                 }
             }
@@ -249,12 +317,38 @@ where
             let mut bitmap_size = map.count_bytes();
             assert!(bitmap_size != 0);
             bitmap_size = bitmap_size.max(1); // just don't make it 0 because we take log2 of it later.
+
+            // On a recalibration, the previous calibration of this testcase is replaced in the totals
+            let previous = {
+                let testcase = state.current_testcase()?;
+                if testcase.has_metadata::<CalibratedMetadata>() {
+                    testcase
+                        .metadata::<SchedulerTestcaseMetadata>()
+                        .ok()
+                        .map(|meta| (meta.cycle_and_time(), meta.bitmap_size()))
+                } else {
+                    None
+                }
+            };
+
             let psmeta = state
                 .metadata_map_mut()
                 .get_mut::<SchedulerMetadata>()
                 .unwrap();
             let handicap = psmeta.queue_cycles();
 
+            if let Some(((old_time, old_iter), old_bitmap_size)) = previous {
+                if old_bitmap_size != 0 {
+                    psmeta.set_exec_time(psmeta.exec_time().saturating_sub(old_time));
+                    psmeta.set_cycles(psmeta.cycles().saturating_sub(old_iter as u64));
+                    psmeta.set_bitmap_size(psmeta.bitmap_size().saturating_sub(old_bitmap_size));
+                    psmeta.set_bitmap_size_log(
+                        psmeta.bitmap_size_log() - libm::log2(old_bitmap_size as f64),
+                    );
+                    psmeta.set_bitmap_entries(psmeta.bitmap_entries().saturating_sub(1));
+                }
+            }
+
             psmeta.set_exec_time(psmeta.exec_time() + total_time);
             psmeta.set_cycles(psmeta.cycles() + (iter as u64));
             psmeta.set_bitmap_size(psmeta.bitmap_size() + bitmap_size);
@@ -294,6 +388,12 @@ where
             data.set_handicap(handicap);
         }
 
+        {
+            let mut testcase = state.current_testcase_mut()?;
+            let scheduled_count = testcase.scheduled_count();
+            testcase.add_metadata(CalibratedMetadata::new(scheduled_count));
+        }
+
         *state.executions_mut() += u64::try_from(i).unwrap();
 
         // Send the stability event to the broker
@@ -362,8 +462,11 @@ where
         Self {
             map_observer_handle: map_feedback.observer_handle().clone(),
             map_name: map_feedback.name().clone(),
-            stage_max: CAL_STAGE_START,
+            stage_max: DEFAULT_CALIBRATION_RUNS,
+            runs_reported: false,
             track_stability: true,
+            recalibrate: None,
+            expected_exit_kind: ExitKind::Ok,
             restart_helper: ExecutionCountRestartHelper::default(),
             phantom: PhantomData,
             name: Cow::Borrowed(CALIBRATION_STAGE_NAME),
@@ -379,8 +482,11 @@ where
         Self {
             map_observer_handle: map_feedback.observer_handle().clone(),
             map_name: map_feedback.name().clone(),
-            stage_max: CAL_STAGE_START,
+            stage_max: DEFAULT_CALIBRATION_RUNS,
+            runs_reported: false,
             track_stability: false,
+            recalibrate: None,
+            expected_exit_kind: ExitKind::Ok,
             restart_helper: ExecutionCountRestartHelper::default(),
             phantom: PhantomData,
             name: Cow::Borrowed(CALIBRATION_STAGE_NAME),
@@ -388,6 +494,52 @@ where
     }
 }
 
+impl<C, O, OT, S> CalibrationStage<C, O, OT, S> {
+    /// Sets the number of runs per calibration, like `AFL++`'s `CAL_CYCLES`.
+    /// Fewer runs speed up the startup on stable targets, more runs characterize flaky ones better.
+    /// Unstable or erroring entries get up to twice as many runs.
+    /// Defaults to [`DEFAULT_CALIBRATION_RUNS`], must be at least `1`.
+    pub fn with_runs(mut self, runs: usize) -> Result<Self, Error> {
+        if runs == 0 {
+            return Err(Error::illegal_argument(
+                "The calibration needs at least one run",
+            ));
+        }
+        self.stage_max = runs;
+        Ok(self)
+    }
+
+    /// The number of runs per calibration
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        self.stage_max
+    }
+
+    /// Periodically recalibrate testcases, once they got scheduled `recalibrate` times since their last calibration.
+    /// Useful for long campaigns, where the timing of the target drifts.
+    /// By default, each testcase is only calibrated once. `recalibrate` must be at least `1`.
+    ///
+    /// A recalibration replaces the previous contribution of the testcase to the [`SchedulerMetadata`] averages.
+    pub fn with_recalibration(mut self, recalibrate: usize) -> Result<Self, Error> {
+        if recalibrate == 0 {
+            return Err(Error::illegal_argument(
+                "Recalibrating after every schedule would calibrate forever, use at least 1",
+            ));
+        }
+        self.recalibrate = Some(recalibrate);
+        Ok(self)
+    }
+
+    /// Sets the exit kind of a regular run, any other exit kind counts as an error.
+    /// Defaults to [`ExitKind::Ok`]; use [`ExitKind::Crash`] for crash exploration,
+    /// see [`crate::feedbacks::CrashExplorationFeedback`], where every corpus entry crashes.
+    #[must_use]
+    pub fn with_expected_exit_kind(mut self, exit_kind: ExitKind) -> Self {
+        self.expected_exit_kind = exit_kind;
+        self
+    }
+}
+
 impl<C, O, OT, S> Named for CalibrationStage<C, O, OT, S> {
     fn name(&self) -> &Cow<'static, str> {
         &self.name
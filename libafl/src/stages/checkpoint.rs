@@ -0,0 +1,415 @@
+//! The [`CheckpointStage`] periodically writes a compact checkpoint of the fuzzer state to disk,
+//! so that a campaign can be resumed after the whole process died, not just a client.
+
+use alloc::{borrow::Cow, boxed::Box, string::String, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+
+use libafl_bolts::{
+    current_time,
+    serdeany::{NamedSerdeAnyMap, SerdeAnyMap},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId},
+    feedbacks::PersistentFeedback,
+    stages::Stage,
+    state::{HasCorpus, HasExecutions, HasRand, UsesState},
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The default interval between two checkpoints
+pub const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// A compact checkpoint of a fuzzer state, as written by the [`CheckpointStage`].
+///
+/// It holds the ids and filenames of the corpus entries, the state's (named) metadata, the RNG and the number
+/// of executions, but not the inputs themselves, which an on-disk corpus already keeps.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint<R> {
+    /// The number of executions at the time of the checkpoint
+    pub executions: u64,
+    /// The ids of the entries in the corpus
+    pub corpus_ids: Vec<CorpusId>,
+    /// The filenames of the entries in the corpus, in the order of `corpus_ids`
+    pub corpus_filenames: Vec<Option<String>>,
+    /// The state of the RNG
+    pub rand: R,
+    /// The metadata of the state
+    pub metadata: SerdeAnyMap,
+    /// The named metadata of the state
+    pub named_metadata: NamedSerdeAnyMap,
+}
+
+/// Serializes exactly like a [`Checkpoint`], borrowing from the state
+#[derive(Serialize)]
+struct CheckpointRef<'a, R> {
+    executions: u64,
+    corpus_ids: Vec<CorpusId>,
+    corpus_filenames: Vec<Option<String>>,
+    rand: &'a R,
+    metadata: &'a SerdeAnyMap,
+    named_metadata: &'a NamedSerdeAnyMap,
+}
+
+impl<R> Checkpoint<R>
+where
+    R: for<'de> Deserialize<'de>,
+{
+    /// Loads a checkpoint written by the [`CheckpointStage`]
+    pub fn load<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(postcard::from_bytes(&fs::read(path)?)?)
+    }
+
+    /// Restores the executions, RNG and (named) metadata of `state` from this checkpoint.
+    ///
+    /// The corpus has to be reloaded separately, e.g., from its on-disk directory, before calling this.
+    /// The metadata may refer to corpus entries by their [`CorpusId`], so every entry of the checkpoint
+    /// has to be back under the same id, with the same filename. Loading the files of
+    /// [`Checkpoint::corpus_filenames`] in order, e.g., with
+    /// [`StdState::load_initial_inputs_by_filenames_forced`](crate::state::StdState::load_initial_inputs_by_filenames_forced),
+    /// into a fresh corpus achieves this if no entry was ever removed.
+    /// Otherwise, this fails and leaves `state` untouched, instead of attaching metadata to the wrong entries.
+    pub fn restore<S>(self, state: &mut S) -> Result<(), Error>
+    where
+        S: HasCorpus + HasRand<Rand = R> + HasExecutions + HasMetadata + HasNamedMetadata,
+    {
+        for (id, filename) in self.corpus_ids.iter().zip(&self.corpus_filenames) {
+            let matches = match state.corpus().get(*id) {
+                Ok(testcase) => testcase.borrow().filename() == filename,
+                Err(_) => false,
+            };
+            if !matches {
+                return Err(Error::illegal_state(format!(
+                    "Corpus entry {id} ({filename:?}) of the checkpoint was not reloaded under the same id"
+                )));
+            }
+        }
+        *state.executions_mut() = self.executions;
+        *state.rand_mut() = self.rand;
+        *state.metadata_map_mut() = self.metadata;
+        *state.named_metadata_map_mut() = self.named_metadata;
+        Ok(())
+    }
+}
+
+/// Writes a [`Checkpoint`] of the state to a file every `interval`.
+///
+/// The checkpoint is written and synced to a temporary file first, named after the writing process so that
+/// several clients can share a `path`, and then renamed over the previous one.
+/// Readers, including a resuming fuzzer, never see a partially written checkpoint.
+/// The stage runs in the fuzzing loop itself, so the state does not change while it is written.
+///
+/// Feedbacks registered with [`CheckpointStage::with_persistent_feedback`] are persisted along with
+/// each checkpoint, see [`CheckpointStage::restore_feedbacks`].
+pub struct CheckpointStage<EM, Z>
+where
+    EM: UsesState,
+{
+    path: PathBuf,
+    interval: Duration,
+    last_checkpoint: Duration,
+    /// The feedbacks to persist with each checkpoint, by name
+    feedbacks: Vec<(Cow<'static, str>, Box<dyn PersistentFeedback<EM::State>>)>,
+    phantom: PhantomData<(EM, Z)>,
+}
+
+impl<EM, Z> Debug for CheckpointStage<EM, Z>
+where
+    EM: UsesState,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckpointStage")
+            .field("path", &self.path)
+            .field("interval", &self.interval)
+            .field("last_checkpoint", &self.last_checkpoint)
+            .field(
+                "feedbacks",
+                &self
+                    .feedbacks
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl<EM, Z> UsesState for CheckpointStage<EM, Z>
+where
+    EM: UsesState,
+{
+    type State = EM::State;
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for CheckpointStage<EM, Z>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasCorpus + HasRand + HasExecutions + HasMetadata + HasNamedMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut Self::State,
+        _manager: &mut EM,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now.saturating_sub(self.last_checkpoint) < self.interval {
+            return Ok(());
+        }
+        self.last_checkpoint = now;
+        Self::write_checkpoint(&self.path, state)?;
+        for (name, feedback) in &self.feedbacks {
+            feedback.persist(state, &Self::feedback_path(&self.path, name))?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(())
+    }
+}
+
+impl<EM, Z> CheckpointStage<EM, Z>
+where
+    EM: UsesState,
+{
+    /// Creates a new [`CheckpointStage`], writing a checkpoint to `path` every [`DEFAULT_CHECKPOINT_INTERVAL`].
+    /// The first checkpoint is written after one interval.
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            interval: DEFAULT_CHECKPOINT_INTERVAL,
+            last_checkpoint: current_time(),
+            feedbacks: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Persists the accumulated state of `feedback` with each checkpoint, to a file next to it,
+    /// named after the checkpoint and the feedback.
+    ///
+    /// The fuzzer owns its feedbacks, so pass a clone: the state of a [`PersistentFeedback`]
+    /// lives in the (named) metadata of the fuzzer state, which the clone shares.
+    #[must_use]
+    pub fn with_persistent_feedback<F>(mut self, feedback: F) -> Self
+    where
+        F: PersistentFeedback<EM::State> + Named + 'static,
+    {
+        self.feedbacks
+            .push((feedback.name().clone(), Box::new(feedback)));
+        self
+    }
+
+    /// Restores the state of the feedbacks registered with [`CheckpointStage::with_persistent_feedback`]
+    /// from the files written with the last checkpoint, if any.
+    /// Call it on resume, after [`Checkpoint::restore`], which would otherwise replace the restored metadata.
+    pub fn restore_feedbacks(&mut self, state: &mut EM::State) -> Result<(), Error> {
+        for (name, feedback) in &mut self.feedbacks {
+            feedback.restore(state, &Self::feedback_path(&self.path, name))?;
+        }
+        Ok(())
+    }
+
+    /// The file the state of the feedback named `name` is persisted to, next to the checkpoint at `path`
+    fn feedback_path(path: &Path, name: &str) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_owned();
+        file_name.push(format!(".{name}"));
+        path.with_file_name(file_name)
+    }
+
+    /// Sets the interval between two checkpoints
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Atomically writes a [`Checkpoint`] of `state` to `path`
+    pub fn write_checkpoint(path: &Path, state: &EM::State) -> Result<(), Error>
+    where
+        EM::State: HasCorpus + HasRand + HasExecutions + HasMetadata + HasNamedMetadata,
+    {
+        let corpus_ids: Vec<CorpusId> = state.corpus().ids().collect();
+        let corpus_filenames = corpus_ids
+            .iter()
+            .map(|id| Ok(state.corpus().get(*id)?.borrow().filename().clone()))
+            .collect::<Result<_, Error>>()?;
+        let checkpoint = CheckpointRef {
+            executions: *state.executions(),
+            corpus_ids,
+            corpus_filenames,
+            rand: state.rand(),
+            metadata: state.metadata_map(),
+            named_metadata: state.named_metadata_map(),
+        };
+        let Some(name) = path.file_name() else {
+            return Err(Error::illegal_argument(format!(
+                "Invalid checkpoint path {}",
+                path.display()
+            )));
+        };
+        let tmp_path =
+            path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(&postcard::to_allocvec(&checkpoint)?)?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        log::info!(
+            "Wrote a checkpoint with {} corpus entries to {}",
+            checkpoint.corpus_ids.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec::Vec};
+    use core::time::Duration;
+    use std::fs;
+
+    use libafl_bolts::{ownedref::OwnedMutPtr, rands::StdRand};
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::test::NopExecutor,
+        feedbacks::{ConstFeedback, Feedback, ListFeedback, ListFeedbackMetadata},
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        observers::ListObserver,
+        stages::{
+            checkpoint::{Checkpoint, CheckpointStage},
+            Stage,
+        },
+        state::{HasCorpus, HasExecutions, StdState},
+        HasNamedMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    fn test_state(entries: usize) -> TestState {
+        let mut corpus = InMemoryCorpus::new();
+        for i in 0..entries {
+            let mut testcase = Testcase::new(BytesInput::new(vec![i as u8]));
+            *testcase.filename_mut() = Some(format!("entry-{i}"));
+            corpus.add(testcase).unwrap();
+        }
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let path =
+            std::env::temp_dir().join(format!("libafl_test_checkpoint.{}", std::process::id()));
+        let mut state = test_state(2);
+        *state.executions_mut() = 1234;
+        CheckpointStage::<NopEventManager<TestState>, ()>::write_checkpoint(&path, &state).unwrap();
+
+        let mut resumed = test_state(2);
+        let checkpoint = Checkpoint::<StdRand>::load(&path).unwrap();
+        assert_eq!(checkpoint.corpus_ids.len(), 2);
+        checkpoint.restore(&mut resumed).unwrap();
+        assert_eq!(*resumed.executions(), 1234);
+
+        // The entries were reloaded under different ids, so the metadata must not be restored
+        let mut reordered = test_state(0);
+        for i in [1_u8, 0] {
+            let mut testcase = Testcase::new(BytesInput::new(vec![i]));
+            *testcase.filename_mut() = Some(format!("entry-{i}"));
+            reordered.corpus_mut().add(testcase).unwrap();
+        }
+        let checkpoint = Checkpoint::<StdRand>::load(&path).unwrap();
+        assert!(checkpoint.restore(&mut reordered).is_err());
+        assert_eq!(*reordered.executions(), 0);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_persists_feedbacks() {
+        let dir = std::env::temp_dir().join(format!(
+            "libafl_test_checkpoint_feedbacks.{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("checkpoint");
+        let observer = ListObserver::<u64>::new("list", OwnedMutPtr::Owned(Box::new(vec![])));
+
+        let mut state = test_state(0);
+        let mut feedback = ListFeedback::new(&observer);
+        Feedback::<TestState>::init_state(&mut feedback, &mut state).unwrap();
+        state
+            .named_metadata_mut::<ListFeedbackMetadata<u64>>("list")
+            .unwrap()
+            .set
+            .extend([1, 2]);
+
+        let mut stage =
+            CheckpointStage::<NopEventManager<TestState>, NopFuzzer<TestState>>::new(path.clone())
+                .with_interval(Duration::ZERO)
+                .with_persistent_feedback(feedback.clone());
+        stage
+            .perform(
+                &mut NopFuzzer::new(),
+                &mut NopExecutor::new(),
+                &mut state,
+                &mut NopEventManager::new(),
+            )
+            .unwrap();
+        assert!(path.exists());
+
+        let mut resumed = test_state(0);
+        Feedback::<TestState>::init_state(&mut feedback, &mut resumed).unwrap();
+        stage.restore_feedbacks(&mut resumed).unwrap();
+        let mut restored: Vec<u64> = resumed
+            .named_metadata::<ListFeedbackMetadata<u64>>("list")
+            .unwrap()
+            .set
+            .iter()
+            .copied()
+            .collect();
+        restored.sort_unstable();
+        assert_eq!(restored, [1, 2]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
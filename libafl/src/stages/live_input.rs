@@ -0,0 +1,368 @@
+//! The [`LiveInputStage`] evaluates inputs submitted by external tools while the fuzzer runs,
+//! e.g., by a symbolic executor in a hybrid fuzzing setup, or a generator piping inputs to stdin.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::{marker::PhantomData, time::Duration};
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{channel, sync_channel, Receiver, Sender},
+    thread,
+};
+
+use libafl_bolts::Named;
+
+use crate::{
+    corpus::HasCurrentCorpusId,
+    fuzzer::Evaluator,
+    stages::{RetryRestartHelper, Stage},
+    state::{State, UsesState},
+    Error, HasNamedMetadata,
+};
+
+/// Default name for [`LiveInputStage`]
+pub const LIVE_INPUT_STAGE_NAME: &str = "live_input";
+
+/// The default number of inputs read from stdin that may wait for evaluation,
+/// see [`LiveInputStage::with_stdin`]
+pub const DEFAULT_STDIN_SEEDS_CAPACITY: usize = 64;
+
+/// The time a client of [`LiveInputStage::with_tcp_listener`] gets to send its input
+pub const LIVE_INPUT_TCP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How inputs are delimited in a stream, see [`LiveInputStage::with_reader`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LiveInputFraming {
+    /// One input per line, the trailing newline is stripped and empty lines are skipped
+    Newline,
+    /// Each input is preceded by its length, as little endian `u32`
+    LengthPrefixed,
+}
+
+/// The error for an input exceeding `max_size` bytes
+fn input_too_large(max_size: usize) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("live input exceeds the maximum size of {max_size} bytes"),
+    )
+}
+
+/// Reads everything sent on `stream` until it is closed, at most `max_size` bytes
+/// and for at most [`LIVE_INPUT_TCP_TIMEOUT`]
+fn read_tcp_input(mut stream: TcpStream, max_size: usize) -> io::Result<Vec<u8>> {
+    stream.set_read_timeout(Some(LIVE_INPUT_TCP_TIMEOUT))?;
+    let mut buf = vec![];
+    let limit = u64::try_from(max_size)
+        .unwrap_or(u64::MAX)
+        .saturating_add(1);
+    stream.take(limit).read_to_end(&mut buf)?;
+    if buf.len() > max_size {
+        return Err(input_too_large(max_size));
+    }
+    Ok(buf)
+}
+
+/// Reads the next input of at most `max_size` bytes from `reader`, or `None` at the end of the stream.
+/// Larger inputs are an error, as the framing of the rest of the stream is lost.
+fn read_framed<R>(
+    reader: &mut R,
+    framing: LiveInputFraming,
+    max_size: usize,
+) -> io::Result<Option<Vec<u8>>>
+where
+    R: BufRead,
+{
+    match framing {
+        LiveInputFraming::Newline => loop {
+            let mut buf = vec![];
+            // One more byte for the newline
+            let limit = u64::try_from(max_size)
+                .unwrap_or(u64::MAX)
+                .saturating_add(1);
+            if reader.by_ref().take(limit).read_until(b'\n', &mut buf)? == 0 {
+                return Ok(None);
+            }
+            if buf.last() == Some(&b'\n') {
+                buf.pop();
+            } else if buf.len() > max_size {
+                return Err(input_too_large(max_size));
+            }
+            if !buf.is_empty() {
+                return Ok(Some(buf));
+            }
+        },
+        LiveInputFraming::LengthPrefixed => {
+            let mut len = [0_u8; 4];
+            match reader.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err),
+            }
+            let len = usize::try_from(u32::from_le_bytes(len))
+                .ok()
+                .filter(|len| *len <= max_size)
+                .ok_or_else(|| input_too_large(max_size))?;
+            let mut buf = vec![0_u8; len];
+            reader.read_exact(&mut buf)?;
+            Ok(Some(buf))
+        }
+    }
+}
+
+/// A stage that drains a channel of externally submitted inputs and evaluates each of them,
+/// adding the interesting ones to the corpus. Unlike the sync from disk, inputs arrive concurrently
+/// and are picked up on the next run of this stage.
+///
+/// Submit inputs through the [`Sender`] of [`LiveInputStage::channel`], or over tcp with
+/// [`LiveInputStage::with_tcp_listener`], or stream them with [`LiveInputStage::with_stdin`]. Create the stage in the fuzzing client, since
+/// threads do not survive a fork of the restarting event manager.
+#[derive(Debug)]
+pub struct LiveInputStage<I, S> {
+    name: Cow<'static, str>,
+    receiver: Receiver<I>,
+    phantom: PhantomData<S>,
+}
+
+impl<I, S> LiveInputStage<I, S> {
+    /// Creates a new [`LiveInputStage`] evaluating all inputs received on `receiver`
+    #[must_use]
+    pub fn new(receiver: Receiver<I>) -> Self {
+        Self {
+            name: Cow::Borrowed(LIVE_INPUT_STAGE_NAME),
+            receiver,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new [`LiveInputStage`] and the [`Sender`] to submit inputs to it
+    #[must_use]
+    pub fn channel() -> (Sender<I>, Self) {
+        let (sender, receiver) = channel();
+        (sender, Self::new(receiver))
+    }
+
+    /// Creates a new [`LiveInputStage`] accepting inputs on the given tcp listener.
+    /// Each connection submits one input: everything sent until the connection is closed.
+    /// Clients are served concurrently, each in its own thread. Inputs larger than `max_size`
+    /// bytes, or not sent completely within [`LIVE_INPUT_TCP_TIMEOUT`], are dropped.
+    #[must_use]
+    pub fn with_tcp_listener(listener: TcpListener, max_size: usize) -> Self
+    where
+        I: From<Vec<u8>> + Send + 'static,
+    {
+        let (sender, stage) = Self::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log::warn!("Failed to accept a live input connection: {err}");
+                        continue;
+                    }
+                };
+                let sender = sender.clone();
+                thread::spawn(move || match read_tcp_input(stream, max_size) {
+                    Ok(buf) => {
+                        // If the stage is gone, the input is dropped
+                        let _ = sender.send(I::from(buf));
+                    }
+                    Err(err) => log::warn!("Failed to receive a live input: {err}"),
+                });
+            }
+        });
+        stage
+    }
+
+    /// Creates a new [`LiveInputStage`] reading inputs from `reader`, delimited as per `framing`,
+    /// until the end of the stream. At most `capacity` inputs wait for evaluation, after that
+    /// reading blocks until the stage catches up, so a fast producer is slowed down instead
+    /// of filling the memory.
+    /// Reading stops at the first input larger than `max_size` bytes, usually the
+    /// [`crate::state::HasMaxSize::max_size`] of the state.
+    #[must_use]
+    pub fn with_reader<R>(
+        reader: R,
+        framing: LiveInputFraming,
+        capacity: usize,
+        max_size: usize,
+    ) -> Self
+    where
+        I: From<Vec<u8>> + Send + 'static,
+        R: Read + Send + 'static,
+    {
+        let (sender, receiver) = sync_channel(capacity);
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            loop {
+                match read_framed(&mut reader, framing, max_size) {
+                    Ok(Some(buf)) => {
+                        if sender.send(I::from(buf)).is_err() {
+                            // The stage is gone
+                            break;
+                        }
+                    }
+                    Ok(None) => {
+                        log::info!("The live input stream ended");
+                        break;
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to read a live input: {err}");
+                        break;
+                    }
+                }
+            }
+        });
+        Self::new(receiver)
+    }
+
+    /// Creates a new [`LiveInputStage`] reading inputs from the stdin of the fuzzer, so that a
+    /// generator process, such as a model suggesting inputs, can pipe candidates in.
+    /// See [`LiveInputStage::with_reader`].
+    #[must_use]
+    pub fn with_stdin(framing: LiveInputFraming, capacity: usize, max_size: usize) -> Self
+    where
+        I: From<Vec<u8>> + Send + 'static,
+    {
+        Self::with_reader(io::stdin(), framing, capacity, max_size)
+    }
+}
+
+impl<I, S> Named for LiveInputStage<I, S> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> UsesState for LiveInputStage<I, S>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<E, EM, S, Z> Stage<E, EM, Z> for LiveInputStage<S::Input, S>
+where
+    E: UsesState<State = S>,
+    EM: UsesState<State = S>,
+    S: State + HasCurrentCorpusId + HasNamedMetadata,
+    Z: Evaluator<E, EM, State = S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let mut evaluated = 0_usize;
+        while let Ok(input) = self.receiver.try_recv() {
+            fuzzer.evaluate_input(state, executor, manager, input)?;
+            evaluated += 1;
+        }
+        if evaluated > 0 {
+            log::debug!("Evaluated {evaluated} live inputs");
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        // Make sure we don't get stuck crashing on a live input
+        RetryRestartHelper::restart_progress_should_run(state, self, 3)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryRestartHelper::clear_restart_progress(state, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use std::{
+        io::{Cursor, Write},
+        net::{TcpListener, TcpStream},
+        time::Duration,
+    };
+
+    use crate::{
+        inputs::BytesInput,
+        stages::live_input::{read_framed, LiveInputFraming, LiveInputStage},
+        state::NopState,
+    };
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_live_input_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stage =
+            LiveInputStage::<BytesInput, NopState<BytesInput>>::with_tcp_listener(listener, 8);
+
+        // A stalled client does not block the others
+        let mut stalled = TcpStream::connect(addr).unwrap();
+        stalled.write_all(b"stalled").unwrap();
+
+        let mut too_large = TcpStream::connect(addr).unwrap();
+        too_large.write_all(b"too large input").unwrap();
+        drop(too_large);
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"live").unwrap();
+        drop(stream);
+
+        let input = stage.receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(input, BytesInput::new(b"live".to_vec()));
+        drop(stalled);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_live_input_reader() {
+        let lines = Cursor::new(b"first\n\nsecond".to_vec());
+        let stage = LiveInputStage::<BytesInput, NopState<BytesInput>>::with_reader(
+            lines,
+            LiveInputFraming::Newline,
+            1,
+            16,
+        );
+        let inputs: Vec<_> = stage.receiver.iter().collect();
+        assert_eq!(
+            inputs,
+            [
+                BytesInput::new(b"first".to_vec()),
+                BytesInput::new(b"second".to_vec())
+            ]
+        );
+
+        let mut stream = 2_u32.to_le_bytes().to_vec();
+        stream.extend_from_slice(b"a\n");
+        stream.extend_from_slice(&0_u32.to_le_bytes());
+        let stage = LiveInputStage::<BytesInput, NopState<BytesInput>>::with_reader(
+            Cursor::new(stream),
+            LiveInputFraming::LengthPrefixed,
+            1,
+            16,
+        );
+        let inputs: Vec<_> = stage.receiver.iter().collect();
+        assert_eq!(
+            inputs,
+            [BytesInput::new(b"a\n".to_vec()), BytesInput::new(vec![])]
+        );
+    }
+
+    #[test]
+    fn test_live_input_max_size() {
+        let mut stream = Cursor::new(b"1234\n12345\n".to_vec());
+        assert_eq!(
+            read_framed(&mut stream, LiveInputFraming::Newline, 4).unwrap(),
+            Some(b"1234".to_vec())
+        );
+        assert!(read_framed(&mut stream, LiveInputFraming::Newline, 4).is_err());
+
+        // A huge length is rejected before allocating the buffer
+        let mut stream = Cursor::new(u32::MAX.to_le_bytes().to_vec());
+        assert!(read_framed(&mut stream, LiveInputFraming::LengthPrefixed, 4).is_err());
+    }
+}
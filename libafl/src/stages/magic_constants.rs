@@ -0,0 +1,228 @@
+//! The [`MagicConstantsStage`] collects the comparison operands of the last execution
+//! into the campaign-wide [`MagicConstants`] metadata.
+
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "std")]
+use libafl_bolts::fs::write_file_atomic;
+use libafl_bolts::Named;
+
+#[cfg(feature = "std")]
+use crate::feedbacks::PersistentFeedback;
+use crate::{
+    mutators::MagicConstants,
+    observers::cmp::CmpValuesMetadata,
+    stages::Stage,
+    state::{HasCorpus, UsesState},
+    Error, HasMetadata,
+};
+
+/// A stage that merges the current [`CmpValuesMetadata`] into the [`MagicConstants`] of the state.
+///
+/// Place it right after a cmplog [`crate::stages::TracingStage`], so that the operands
+/// of every traced testcase end up in the campaign-wide set used by
+/// [`crate::mutators::MagicConstantInsert`] and [`crate::mutators::MagicConstantReplace`].
+///
+/// The constants can be kept across restarts by registering a clone of the stage with
+/// [`crate::stages::CheckpointStage::with_persistent_feedback`].
+#[derive(Debug, Clone)]
+pub struct MagicConstantsStage<EM, Z> {
+    name: Cow<'static, str>,
+    max_size: usize,
+    max_age: u64,
+    phantom: PhantomData<(EM, Z)>,
+}
+
+impl<EM, Z> UsesState for MagicConstantsStage<EM, Z>
+where
+    EM: UsesState,
+{
+    type State = EM::State;
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for MagicConstantsStage<EM, Z>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasCorpus + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut Self::State,
+        _manager: &mut EM,
+    ) -> Result<(), Error> {
+        let Some(cmp_values) = state.metadata_map_mut().remove::<CmpValuesMetadata>() else {
+            return Ok(());
+        };
+        let (max_size, max_age) = (self.max_size, self.max_age);
+        state
+            .metadata_or_insert_with(|| MagicConstants::new(max_size, max_age))
+            .add_cmp_values(&cmp_values.list);
+        state.metadata_map_mut().insert_boxed(cmp_values);
+        Ok(())
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(())
+    }
+}
+
+impl<EM, Z> MagicConstantsStage<EM, Z> {
+    /// Creates a new [`MagicConstantsStage`], using the default size cap and maximum age
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_limits(
+            crate::mutators::magic_constants::DEFAULT_MAGIC_CONSTANTS_MAX_SIZE,
+            crate::mutators::magic_constants::DEFAULT_MAGIC_CONSTANTS_MAX_AGE,
+        )
+    }
+
+    /// Creates a new [`MagicConstantsStage`], keeping at most `max_size` constants,
+    /// each one dropped after `max_age` runs of this stage without being seen or used
+    #[must_use]
+    pub fn with_limits(max_size: usize, max_age: u64) -> Self {
+        Self {
+            name: Cow::Borrowed("MagicConstantsStage"),
+            max_size,
+            max_age,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<EM, Z> Default for MagicConstantsStage<EM, Z> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EM, Z> Named for MagicConstantsStage<EM, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(feature = "std")]
+impl<EM, S, Z> PersistentFeedback<S> for MagicConstantsStage<EM, Z>
+where
+    S: HasMetadata,
+{
+    fn persist(&self, state: &S, path: &Path) -> Result<(), Error> {
+        let constants: Vec<&[u8]> = state.metadata::<MagicConstants>()?.constants().collect();
+        write_file_atomic(path, &postcard::to_allocvec(&constants)?)
+    }
+
+    fn restore(&mut self, state: &mut S, path: &Path) -> Result<(), Error> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let constants: Vec<Vec<u8>> = postcard::from_bytes(&fs::read(path)?)?;
+        let (max_size, max_age) = (self.max_size, self.max_age);
+        state
+            .metadata_or_insert_with(|| MagicConstants::new(max_size, max_age))
+            .extend(constants.iter().map(Vec::as_slice));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::test::NopExecutor,
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        mutators::MagicConstants,
+        observers::cmp::{CmpValues, CmpValuesMetadata},
+        stages::{MagicConstantsStage, Stage},
+        state::{test::test_std_state, StdState},
+        HasMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_magic_constants_stage() {
+        let mut state: TestState = test_std_state();
+        let mut stage =
+            MagicConstantsStage::<NopEventManager<TestState>, NopFuzzer<TestState>>::with_limits(
+                16, 4,
+            );
+        let mut perform = |state: &mut TestState| {
+            stage
+                .perform(
+                    &mut NopFuzzer::new(),
+                    &mut NopExecutor::new(),
+                    state,
+                    &mut NopEventManager::new(),
+                )
+                .unwrap();
+        };
+
+        // Nothing traced yet
+        perform(&mut state);
+        assert!(!state.has_metadata::<MagicConstants>());
+
+        let mut cmp_values = CmpValuesMetadata::new();
+        cmp_values
+            .list
+            .push(CmpValues::U32((0x4141_4141, 0x1337_1337)));
+        state.add_metadata(cmp_values);
+        perform(&mut state);
+        assert_eq!(state.metadata::<MagicConstants>().unwrap().len(), 2);
+        // The traced operands stay around for the following stages
+        assert_eq!(state.metadata::<CmpValuesMetadata>().unwrap().list.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore)]
+    fn test_magic_constants_persist() {
+        use alloc::vec::Vec;
+        use std::{env, fs};
+
+        use crate::feedbacks::PersistentFeedback;
+
+        let path = env::temp_dir().join(format!(
+            "libafl_magic_constants_test_{}",
+            std::process::id()
+        ));
+        let mut state = test_std_state::<BytesInput>();
+        let mut constants = MagicConstants::default();
+        constants.extend([&b"MAGIC"[..], &b"\x13\x37"[..]]);
+        state.add_metadata(constants);
+
+        let mut stage = MagicConstantsStage::<(), ()>::new();
+        stage.persist(&state, &path).unwrap();
+
+        let mut restarted = test_std_state::<BytesInput>();
+        stage.restore(&mut restarted, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let restored: Vec<&[u8]> = restarted
+            .metadata::<MagicConstants>()
+            .unwrap()
+            .constants()
+            .collect();
+        assert_eq!(restored, [&b"MAGIC"[..], &b"\x13\x37"[..]]);
+    }
+}
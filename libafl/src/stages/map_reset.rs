@@ -0,0 +1,204 @@
+//! The [`MapFeedbackResetStage`] resets the coverage history of a [`MapFeedback`] once the fuzzer plateaus,
+//! so that already known entries are considered novel again.
+
+use alloc::borrow::Cow;
+use core::{marker::PhantomData, time::Duration};
+
+use libafl_bolts::{current_time, Named};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    feedbacks::{MapFeedback, MapFeedbackMetadata},
+    stages::Stage,
+    state::{HasCorpus, UsesState},
+    Error, HasNamedMetadata,
+};
+
+/// A stage that resets the history map of a [`MapFeedback`] when the corpus did not grow for a given time.
+///
+/// Use carefully: the corpus is retained, but every input hitting already known entries will
+/// register as interesting once more, until the history is rebuilt.
+/// This helps escaping deep plateaus by re-energizing the exploration.
+#[derive(Debug, Clone)]
+pub struct MapFeedbackResetStage<EM, T, Z> {
+    /// The name of the [`MapFeedback`] whose history gets reset
+    name: Cow<'static, str>,
+    /// Reset after this time without new corpus entries
+    plateau: Duration,
+    /// The corpus size at the last observed progress
+    last_corpus_count: usize,
+    /// The time of the last observed progress (or reset)
+    last_progress: Duration,
+    /// The number of resets performed by this stage
+    resets: usize,
+    phantom: PhantomData<(EM, T, Z)>,
+}
+
+impl<EM, T, Z> MapFeedbackResetStage<EM, T, Z> {
+    /// Create a new [`MapFeedbackResetStage`] for the given `feedback`,
+    /// resetting its history after `plateau` time without new corpus entries.
+    #[must_use]
+    pub fn new<C, N, O, R>(feedback: &MapFeedback<C, N, O, R, T>, plateau: Duration) -> Self {
+        Self {
+            name: feedback.name().clone(),
+            plateau,
+            last_corpus_count: 0,
+            last_progress: current_time(),
+            resets: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The number of resets performed so far
+    #[must_use]
+    pub fn resets(&self) -> usize {
+        self.resets
+    }
+}
+
+impl<EM, T, Z> UsesState for MapFeedbackResetStage<EM, T, Z>
+where
+    EM: UsesState,
+{
+    type State = EM::State;
+}
+
+impl<E, EM, T, Z> Stage<E, EM, Z> for MapFeedbackResetStage<EM, T, Z>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasCorpus + HasNamedMetadata,
+    T: Default + Copy + 'static + Serialize + DeserializeOwned + PartialEq,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut Self::State,
+        _manager: &mut EM,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        let corpus_count = state.corpus().count();
+        if corpus_count != self.last_corpus_count {
+            self.last_corpus_count = corpus_count;
+            self.last_progress = now;
+            return Ok(());
+        }
+
+        if now.saturating_sub(self.last_progress) < self.plateau {
+            return Ok(());
+        }
+
+        state
+            .named_metadata_map_mut()
+            .get_mut::<MapFeedbackMetadata<T>>(&self.name)
+            .ok_or_else(|| Error::key_not_found(format!("MapFeedbackMetadata for {}", self.name)))?
+            .clear();
+
+        log::info!(
+            "No new corpus entries for {:?}, resetting the history of {}",
+            self.plateau,
+            self.name
+        );
+        self.resets += 1;
+        self.last_progress = now;
+        Ok(())
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::test::NopExecutor,
+        feedbacks::{ConstFeedback, MapFeedbackMetadata, MaxMapFeedback},
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        stages::{MapFeedbackResetStage, Stage},
+        state::{HasCorpus, StdState},
+        HasNamedMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_map_feedback_reset() {
+        let observer = StdMapObserver::owned("map", vec![0_u8; 4]);
+        let mut feedback = MaxMapFeedback::new(&observer);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let plateau = Duration::from_millis(100);
+        let mut stage =
+            MapFeedbackResetStage::<NopEventManager<TestState>, u8, NopFuzzer<TestState>>::new(
+                &feedback, plateau,
+            );
+        let mut perform = |state: &mut TestState| {
+            stage
+                .perform(
+                    &mut NopFuzzer::new(),
+                    &mut NopExecutor::new(),
+                    state,
+                    &mut NopEventManager::new(),
+                )
+                .unwrap();
+        };
+        let history_len = |state: &TestState| {
+            state
+                .named_metadata::<MapFeedbackMetadata<u8>>("map")
+                .unwrap()
+                .history_map
+                .len()
+        };
+        *state
+            .named_metadata_mut::<MapFeedbackMetadata<u8>>("map")
+            .unwrap() = MapFeedbackMetadata::with_history_map(vec![1, 1, 0, 0], 0);
+
+        // Before the plateau, the history is left alone
+        perform(&mut state);
+        assert_eq!(history_len(&state), 4);
+
+        // New corpus entries restart the plateau
+        std::thread::sleep(plateau * 2);
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![0])))
+            .unwrap();
+        perform(&mut state);
+        assert_eq!(history_len(&state), 4);
+
+        // No progress for the whole plateau: reset
+        std::thread::sleep(plateau * 2);
+        perform(&mut state);
+        assert_eq!(history_len(&state), 0);
+        assert_eq!(stage.resets(), 1);
+    }
+}
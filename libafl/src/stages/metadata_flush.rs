@@ -0,0 +1,236 @@
+//! The [`MetadataFlushStage`] periodically writes the metadata of changed [`crate::corpus::Testcase`]s
+//! to hidden `.<filename>.meta` sidecar files, so that it survives a hard crash of the fuzzer.
+//! Restore it on resume with [`load_flushed_metadata`].
+
+use core::{marker::PhantomData, time::Duration};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::{current_time, fs::write_file_atomic, serdeany::SerdeAnyMap};
+use serde::Deserialize;
+
+use crate::{
+    corpus::{ondisk::OnDiskMetadata, Corpus},
+    stages::Stage,
+    state::{HasCorpus, State, UsesState},
+    Error, HasMetadata,
+};
+
+/// The metadata of a testcase, as written to its sidecar by [`flush_corpus_metadata`]
+#[derive(Debug, Deserialize)]
+pub struct FlushedMetadata {
+    /// The dynamic metadata
+    pub metadata: SerdeAnyMap,
+    /// The exec time of the testcase
+    pub exec_time: Option<Duration>,
+    /// The amount of executions at discovery time
+    pub executions: u64,
+}
+
+/// The sidecars are hidden, so that they are not mistaken for inputs when loading the corpus directory
+fn sidecar_path(dir: &Path, filename: &str) -> PathBuf {
+    dir.join(format!(".{filename}.meta"))
+}
+
+/// Writes the metadata of all testcases in the corpus that changed since the last flush
+/// to `<dir>/.<filename>.meta`, atomically. Testcases without a filename are skipped.
+///
+/// Returns the number of sidecars written.
+pub fn flush_corpus_metadata<C>(corpus: &C, dir: &Path) -> Result<usize, Error>
+where
+    C: Corpus,
+{
+    let mut flushed = 0;
+    for id in corpus.ids() {
+        let mut testcase = corpus.get(id)?.borrow_mut();
+        if !testcase.metadata_dirty() {
+            continue;
+        }
+        let Some(filename) = testcase.filename() else {
+            continue;
+        };
+        let path = sidecar_path(dir, filename);
+        let serialized = serde_json::to_vec(&OnDiskMetadata {
+            metadata: testcase.metadata_map(),
+            exec_time: testcase.exec_time(),
+            executions: testcase.executions(),
+        })?;
+        write_file_atomic(path, &serialized)?;
+        testcase.set_metadata_dirty(false);
+        flushed += 1;
+    }
+    Ok(flushed)
+}
+
+/// Restores the metadata written by [`flush_corpus_metadata`] into the testcases of the corpus
+/// with a matching filename, replacing their current metadata, e.g., after reloading the inputs on resume.
+///
+/// Returns the number of testcases restored.
+pub fn load_flushed_metadata<C>(corpus: &C, dir: &Path) -> Result<usize, Error>
+where
+    C: Corpus,
+{
+    let mut loaded = 0;
+    for id in corpus.ids() {
+        let mut testcase = corpus.get(id)?.borrow_mut();
+        let Some(filename) = testcase.filename() else {
+            continue;
+        };
+        let path = sidecar_path(dir, filename);
+        if !path.is_file() {
+            continue;
+        }
+        let flushed: FlushedMetadata = serde_json::from_slice(&fs::read(path)?)?;
+        *testcase.metadata_map_mut() = flushed.metadata;
+        *testcase.exec_time_mut() = flushed.exec_time;
+        *testcase.executions_mut() = flushed.executions;
+        testcase.set_metadata_dirty(false);
+        loaded += 1;
+    }
+    Ok(loaded)
+}
+
+/// A stage that flushes the metadata of changed corpus entries to disk, at most once per interval.
+/// Unchanged entries are not rewritten, see [`crate::corpus::Testcase::metadata_dirty`].
+#[derive(Debug)]
+pub struct MetadataFlushStage<S> {
+    dir: PathBuf,
+    interval: Duration,
+    last_flush: Duration,
+    phantom: PhantomData<S>,
+}
+
+impl<S> MetadataFlushStage<S> {
+    /// Creates a new [`MetadataFlushStage`] writing sidecars to `dir`, which is created if needed.
+    /// Usually, `dir` is the directory of the on-disk corpus.
+    pub fn new<P>(dir: P, interval: Duration) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            interval,
+            last_flush: Duration::ZERO,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The directory the sidecars are written to
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl<S> UsesState for MetadataFlushStage<S>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<E, EM, S, Z> Stage<E, EM, Z> for MetadataFlushStage<S>
+where
+    E: UsesState<State = S>,
+    EM: UsesState<State = S>,
+    S: HasCorpus + State,
+    Z: UsesState<State = S>,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut Self::State,
+        _manager: &mut EM,
+    ) -> Result<(), Error> {
+        let cur = current_time();
+        if cur.saturating_sub(self.last_flush) < self.interval {
+            return Ok(());
+        }
+        let flushed = flush_corpus_metadata(state.corpus(), &self.dir)?;
+        log::debug!("Flushed the metadata of {flushed} corpus entries");
+        self.last_flush = cur;
+        Ok(())
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use std::{env, fs};
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, SchedulerTestcaseMetadata, Testcase},
+        inputs::BytesInput,
+        stages::metadata_flush::{flush_corpus_metadata, load_flushed_metadata},
+        HasMetadata,
+    };
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_metadata_flush() {
+        // # Safety
+        // No concurrency per testcase
+        #[cfg(any(not(feature = "serdeany_autoreg"), miri))]
+        unsafe {
+            SchedulerTestcaseMetadata::register();
+        }
+
+        let dir =
+            env::temp_dir().join(format!("libafl_metadata_flush_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for (depth, name) in [(1_u8, "a"), (2, "b")] {
+            let mut testcase =
+                Testcase::with_filename(BytesInput::new(vec![depth]), String::from(name));
+            testcase.add_metadata(SchedulerTestcaseMetadata::new(depth.into()));
+            corpus.add(testcase).unwrap();
+        }
+        let id = corpus.first().unwrap();
+
+        assert_eq!(flush_corpus_metadata(&corpus, &dir).unwrap(), 2);
+        assert!(dir.join(".a.meta").is_file());
+        // Nothing changed since
+        assert_eq!(flush_corpus_metadata(&corpus, &dir).unwrap(), 0);
+
+        corpus
+            .get(id)
+            .unwrap()
+            .borrow_mut()
+            .add_metadata(SchedulerTestcaseMetadata::new(5));
+        assert_eq!(flush_corpus_metadata(&corpus, &dir).unwrap(), 1);
+
+        // Lose the metadata in memory, then restore it
+        *corpus.get(id).unwrap().borrow_mut().metadata_map_mut() = Default::default();
+        assert_eq!(load_flushed_metadata(&corpus, &dir).unwrap(), 2);
+        let testcase = corpus.get(id).unwrap().borrow();
+        assert!(!testcase.metadata_dirty());
+        assert_eq!(
+            testcase
+                .metadata::<SchedulerTestcaseMetadata>()
+                .unwrap()
+                .depth(),
+            5
+        );
+        drop(testcase);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
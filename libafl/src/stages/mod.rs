@@ -7,7 +7,10 @@ Other stages may enrich [`crate::corpus::Testcase`]s with metadata.
 use alloc::{borrow::Cow, boxed::Box, vec::Vec};
 use core::{fmt, marker::PhantomData};
 
-pub use calibrate::CalibrationStage;
+pub use budget::{ExecutionBudgetMetadata, ExecutionBudgetStage};
+pub use calibrate::{CalibratedMetadata, CalibrationStage};
+#[cfg(feature = "std")]
+pub use checkpoint::{Checkpoint, CheckpointStage};
 pub use colorization::*;
 #[cfg(feature = "std")]
 pub use concolic::ConcolicTracingStage;
@@ -22,18 +25,34 @@ use libafl_bolts::{
     tuples::{HasConstLen, IntoVec},
     Named,
 };
+#[cfg(feature = "std")]
+pub use live_input::{LiveInputFraming, LiveInputStage};
 pub use logics::*;
-pub use mutational::{MutationalStage, StdMutationalStage};
-pub use power::{PowerMutationalStage, StdPowerMutationalStage};
+pub use magic_constants::MagicConstantsStage;
+pub use map_reset::MapFeedbackResetStage;
+#[cfg(feature = "std")]
+pub use metadata_flush::{flush_corpus_metadata, load_flushed_metadata, MetadataFlushStage};
+pub use mutational::{MutationalSliceMetadata, MutationalStage, PreExecFilter, StdMutationalStage};
+pub use power::{split_power_budget, PowerMutationalStage, StdPowerMutationalStage};
+pub use revalidation::{CorpusRevalidationMetadata, CorpusRevalidationStage};
 use serde::{Deserialize, Serialize};
-pub use stats::AflStatsStage;
+pub use slow_path::{RelaxedTimeoutStage, TestcaseTimeoutMetadata};
+#[cfg(feature = "std")]
+pub use stats::{AflFuzzerStats, AFLPP_FUZZER_STATS_KEYS};
+pub use stats::{AflStatsStage, CorpusSizeHistogram};
+#[cfg(feature = "std")]
+pub use stop_on_objective::StopOnObjectiveStage;
 #[cfg(feature = "unicode")]
 pub use string::*;
 #[cfg(feature = "std")]
 pub use sync::*;
+pub use target_rotation::TargetRotationStage;
 pub use tmin::{
     MapEqualityFactory, MapEqualityFeedback, StdTMinMutationalStage, TMinMutationalStage,
 };
+pub use toggle::{
+    register_stage_toggles, set_stage_enabled, toggle_stage, StageToggleMetadata, ToggleableStage,
+};
 pub use tracing::{ShadowTracingStage, TracingStage};
 pub use tuneable::*;
 use tuple_list::NonEmptyTuple;
@@ -56,7 +75,10 @@ pub mod mutational;
 pub mod push;
 pub mod tmin;
 
+pub mod budget;
 pub mod calibrate;
+#[cfg(feature = "std")]
+pub mod checkpoint;
 pub mod colorization;
 #[cfg(feature = "std")]
 pub mod concolic;
@@ -65,13 +87,25 @@ pub mod dump;
 pub mod generalization;
 /// The [`generation::GenStage`] generates a single input and evaluates it.
 pub mod generation;
+#[cfg(feature = "std")]
+pub mod live_input;
 pub mod logics;
+pub mod magic_constants;
+pub mod map_reset;
+#[cfg(feature = "std")]
+pub mod metadata_flush;
 pub mod power;
+pub mod revalidation;
+pub mod slow_path;
 pub mod stats;
+#[cfg(feature = "std")]
+pub mod stop_on_objective;
 #[cfg(feature = "unicode")]
 pub mod string;
 #[cfg(feature = "std")]
 pub mod sync;
+pub mod target_rotation;
+pub mod toggle;
 pub mod tracing;
 pub mod tuneable;
 
@@ -1,10 +1,16 @@
 //| The [`MutationalStage`] is the default stage used during fuzzing.
 //! For the current input, it will perform a range of random mutations, and then run them in the executor.
 
-use alloc::borrow::Cow;
-use core::marker::PhantomData;
+use alloc::{borrow::Cow, boxed::Box, rc::Rc};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
 
-use libafl_bolts::{rands::Rand, Named};
+use hashbrown::HashMap;
+use libafl_bolts::{current_time, impl_serdeany, rands::Rand, Named};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     corpus::{Corpus, CorpusId, Testcase},
@@ -73,6 +79,65 @@ where
     }
 }
 
+/// A cheap predicate, run on every mutated input before it gets executed.
+/// Inputs failing the predicate are skipped without spending an execution.
+/// This cuts down on wasted executions for targets with strict early rejection,
+/// e.g. "must start with magic bytes" or "must be a valid JSON prefix".
+#[derive(Clone)]
+pub struct PreExecFilter<I> {
+    predicate: Rc<dyn Fn(&I) -> bool>,
+    skipped: u64,
+}
+
+impl<I> PreExecFilter<I> {
+    /// Creates a new [`PreExecFilter`] from the given predicate.
+    /// Inputs for which the predicate returns `false` will not be executed.
+    pub fn new(predicate: Box<dyn Fn(&I) -> bool>) -> Self {
+        Self {
+            predicate: Rc::from(predicate),
+            skipped: 0,
+        }
+    }
+
+    /// Runs the predicate on the given input, counting it if it gets skipped
+    pub fn accepts(&mut self, input: &I) -> bool {
+        let accepted = (self.predicate)(input);
+        if !accepted {
+            self.skipped += 1;
+        }
+        accepted
+    }
+
+    /// The number of inputs rejected by this filter so far
+    #[must_use]
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+/// Named metadata of a mutational stage, keyed by the name of the stage, holding the testcases whose
+/// mutational round got cut short by a fairness time slice, see [`MutationalStage::fairness_slice`].
+/// The next time such a testcase gets scheduled, the stage resumes with the remaining iterations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct MutationalSliceMetadata {
+    /// The iterations left over from the interrupted round, per testcase
+    pub remaining: HashMap<CorpusId, usize>,
+}
+
+impl_serdeany!(MutationalSliceMetadata);
+
+impl<I> Debug for PreExecFilter<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreExecFilter")
+            .field("skipped", &self.skipped)
+            .finish_non_exhaustive()
+    }
+}
+
 /// A Mutational stage is the stage in a fuzzing run that mutates inputs.
 /// Mutational stages will usually have a range of mutations that are
 /// being applied to the input one by one, between executions.
@@ -97,6 +162,34 @@ where
     /// Gets the number of executions this mutator already did since it got first called in this fuzz round.
     fn execs_since_progress_start(&mut self, state: &mut Z::State) -> Result<u64, Error>;
 
+    /// Decides if a mutated input should be executed at all, see [`PreExecFilter`].
+    /// Inputs for which this returns `false` are skipped.
+    #[inline]
+    fn pre_exec_filter(&mut self, _state: &mut Z::State, _input: &I) -> bool {
+        true
+    }
+
+    /// The maximum wall-clock time a single [`Self::perform_mutational`] call may spend on one testcase.
+    /// Once exceeded, the stage yields back to the scheduler and hands the remaining iterations
+    /// to [`Self::store_remaining_iterations`], to resume them the next time the testcase gets scheduled.
+    /// This keeps single high-power testcases from monopolizing the fuzzer.
+    #[inline]
+    fn fairness_slice(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Stores the iterations left over when the [`Self::fairness_slice`] ran out on the current testcase,
+    /// e.g., in a [`MutationalSliceMetadata`] named after this stage, for [`Self::iterations`] to resume them.
+    /// Stages with a fairness slice need to implement this, the default drops the remaining iterations.
+    #[inline]
+    fn store_remaining_iterations(
+        &mut self,
+        _state: &mut Z::State,
+        _remaining: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// Runs this (mutational) stage for the given testcase
     #[allow(clippy::cast_possible_wrap)] // more than i32 stages on 32 bit system - highly unlikely...
     fn perform_mutational(
@@ -115,6 +208,7 @@ where
             .saturating_sub(self.execs_since_progress_start(state)?);
         */
         let num = self.iterations(state)?;
+        let fairness_slice = self.fairness_slice();
         let mut testcase = state.current_testcase_mut()?;
 
         let Ok(input) = I::try_transform_from(&mut testcase, state) else {
@@ -123,14 +217,22 @@ where
         drop(testcase);
         mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
 
-        for _ in 0..num {
+        let slice_start = current_time();
+        for i in 0..num {
+            if let Some(slice) = fairness_slice {
+                if i > 0 && current_time() - slice_start >= slice {
+                    self.store_remaining_iterations(state, num - i)?;
+                    break;
+                }
+            }
+
             let mut input = input.clone();
 
             start_timer!(state);
             let mutated = self.mutator_mut().mutate(state, &mut input)?;
             mark_feature_time!(state, PerfFeature::Mutate);
 
-            if mutated == MutationResult::Skipped {
+            if mutated == MutationResult::Skipped || !self.pre_exec_filter(state, &input) {
                 continue;
             }
 
@@ -161,6 +263,8 @@ pub struct StdMutationalStage<E, EM, I, M, Z> {
     max_iterations: usize,
     /// The progress helper for this mutational stage
     restart_helper: ExecutionCountRestartHelper,
+    /// An optional filter deciding which mutated inputs to execute
+    pre_exec_filter: Option<PreExecFilter<I>>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, Z)>,
 }
@@ -194,6 +298,13 @@ where
     fn execs_since_progress_start(&mut self, state: &mut <Z>::State) -> Result<u64, Error> {
         self.restart_helper.execs_since_progress_start(state)
     }
+
+    #[inline]
+    fn pre_exec_filter(&mut self, _state: &mut Z::State, input: &I) -> bool {
+        self.pre_exec_filter
+            .as_mut()
+            .map_or(true, |filter| filter.accepts(input))
+    }
 }
 
 impl<E, EM, I, M, Z> UsesState for StdMutationalStage<E, EM, I, M, Z>
@@ -282,9 +393,25 @@ where
             mutator,
             max_iterations,
             restart_helper: ExecutionCountRestartHelper::default(),
+            pre_exec_filter: None,
             phantom: PhantomData,
         }
     }
+
+    /// Only execute mutated inputs accepted by the given predicate, see [`PreExecFilter`]
+    #[must_use]
+    pub fn with_pre_exec_filter(mut self, predicate: Box<dyn Fn(&I) -> bool>) -> Self {
+        self.pre_exec_filter = Some(PreExecFilter::new(predicate));
+        self
+    }
+
+    /// The number of mutated inputs skipped by the [`PreExecFilter`], if any
+    #[must_use]
+    pub fn pre_exec_skipped(&self) -> u64 {
+        self.pre_exec_filter
+            .as_ref()
+            .map_or(0, PreExecFilter::skipped)
+    }
 }
 
 /// A mutational stage that operates on multiple inputs, as returned by [`MultiMutator::multi_mutate`].
@@ -1,21 +1,46 @@
 //! The power schedules. This stage should be invoked after the calibration stage.
 
 use alloc::borrow::Cow;
-use core::{fmt::Debug, marker::PhantomData};
+use core::{fmt::Debug, marker::PhantomData, time::Duration};
 
 use libafl_bolts::Named;
 
 use crate::{
+    corpus::Corpus,
     executors::{Executor, HasObservers},
     fuzzer::Evaluator,
     mutators::Mutator,
     schedulers::{testcase_score::CorpusPowerTestcaseScore, TestcaseScore},
-    stages::{mutational::MutatedTransform, ExecutionCountRestartHelper, MutationalStage, Stage},
+    stages::{
+        mutational::{MutatedTransform, MutationalSliceMetadata},
+        ExecutionCountRestartHelper, MutationalStage, Stage,
+    },
     state::{HasCorpus, HasCurrentTestcase, HasExecutions, HasRand, UsesState},
-    Error, HasMetadata,
+    Error, HasMetadata, HasNamedMetadata,
 };
 /// Default name for `PowerMutationalStage`; derived from AFL++
 pub const POWER_MUTATIONAL_STAGE_NAME: &str = "power";
+
+/// Splits the power budget of a testcase across several [`PowerMutationalStage`]s by the given ratios,
+/// returning the share of each stage, to pass to [`PowerMutationalStage::with_budget_share`].
+///
+/// For example, `[1.0, 3.0, 1.0]` for a token, a havoc, and a splice stage
+/// spends 20% of the power on tokens, 60% on havoc, and 20% on splicing.
+/// All ratios need to be finite and above 0, leave out a stage instead of giving it no budget.
+pub fn split_power_budget<const N: usize>(ratios: [f64; N]) -> Result<[f64; N], Error> {
+    if N == 0
+        || ratios
+            .iter()
+            .any(|ratio| !ratio.is_finite() || *ratio <= 0.0)
+    {
+        return Err(Error::illegal_argument(format!(
+            "Invalid power budget ratios: {ratios:?}"
+        )));
+    }
+    let total: f64 = ratios.iter().sum();
+    Ok(ratios.map(|ratio| ratio / total))
+}
+
 /// The mutational stage using power schedules
 #[derive(Clone, Debug)]
 pub struct PowerMutationalStage<E, F, EM, I, M, Z> {
@@ -24,6 +49,10 @@ pub struct PowerMutationalStage<E, F, EM, I, M, Z> {
     mutator: M,
     /// Helper for restarts
     restart_helper: ExecutionCountRestartHelper,
+    /// The maximum time spent on one testcase per round, if any
+    fairness_slice: Option<Duration>,
+    /// The share of the power of a testcase this stage spends
+    budget_share: f64,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, F, EM, I, Z)>,
 }
@@ -47,7 +76,7 @@ where
     EM: UsesState<State = E::State>,
     F: TestcaseScore<E::State>,
     M: Mutator<I, E::State>,
-    E::State: HasCorpus + HasMetadata + HasRand + HasExecutions,
+    E::State: HasCorpus + HasMetadata + HasNamedMetadata + HasRand + HasExecutions,
     Z: Evaluator<E, EM, State = E::State>,
     I: MutatedTransform<E::Input, E::State> + Clone,
 {
@@ -64,18 +93,57 @@ where
     }
 
     /// Gets the number of iterations as a random number
-    #[allow(clippy::cast_sign_loss)]
+    #[allow(
+        clippy::cast_sign_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss
+    )]
     fn iterations(&self, state: &mut E::State) -> Result<usize, Error> {
+        // Resume a round cut short by the fairness slice
+        let current = *state.corpus().current();
+        if let Some(id) = current {
+            if let Ok(slice) = state.named_metadata_mut::<MutationalSliceMetadata>(&self.name) {
+                if let Some(remaining) = slice.remaining.remove(&id) {
+                    return Ok(remaining);
+                }
+            }
+        }
+
         // Update handicap
         let mut testcase = state.current_testcase_mut()?;
         let score = F::compute(state, &mut testcase)? as usize;
 
-        Ok(score)
+        if score == 0 {
+            return Ok(0);
+        }
+        // Each stage sharing the budget still mutates the testcase at least once
+        Ok(((score as f64 * self.budget_share).round() as usize).max(1))
     }
 
     fn execs_since_progress_start(&mut self, state: &mut <Z>::State) -> Result<u64, Error> {
         self.restart_helper.execs_since_progress_start(state)
     }
+
+    #[inline]
+    fn fairness_slice(&self) -> Option<Duration> {
+        self.fairness_slice
+    }
+
+    fn store_remaining_iterations(
+        &mut self,
+        state: &mut E::State,
+        remaining: usize,
+    ) -> Result<(), Error> {
+        let id = state
+            .corpus()
+            .current()
+            .ok_or_else(|| Error::illegal_state("No current corpus entry to slice"))?;
+        state
+            .named_metadata_or_insert_with(&self.name, MutationalSliceMetadata::default)
+            .remaining
+            .insert(id, remaining);
+        Ok(())
+    }
 }
 
 impl<E, F, EM, I, M, Z> Stage<E, EM, Z> for PowerMutationalStage<E, F, EM, I, M, Z>
@@ -84,7 +152,7 @@ where
     EM: UsesState<State = E::State>,
     F: TestcaseScore<E::State>,
     M: Mutator<I, E::State>,
-    E::State: HasCorpus + HasMetadata + HasRand + HasExecutions,
+    E::State: HasCorpus + HasMetadata + HasNamedMetadata + HasRand + HasExecutions,
     Z: Evaluator<E, EM, State = E::State>,
     I: MutatedTransform<E::Input, E::State> + Clone,
 {
@@ -128,10 +196,69 @@ where
             mutator,
             phantom: PhantomData,
             restart_helper: ExecutionCountRestartHelper::default(),
+            fairness_slice: None,
+            budget_share: 1.0,
+        }
+    }
+
+    /// Sets the name of this stage, which has to be unique if several [`PowerMutationalStage`]s
+    /// with a fairness slice run in the same fuzzer, see [`Self::with_fairness_slice`]
+    #[must_use]
+    pub fn with_name(mut self, name: Cow<'static, str>) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Spends only the given share of the power of each testcase in this stage,
+    /// to run several stages with distinct mutators on the same power budget.
+    /// Compute the shares with [`split_power_budget`].
+    ///
+    /// The share needs to be in `(0, 1]`. A testcase with any power left still gets at least one iteration.
+    pub fn with_budget_share(mut self, share: f64) -> Result<Self, Error> {
+        if share.is_nan() || share <= 0.0 || share > 1.0 {
+            return Err(Error::illegal_argument(format!(
+                "The power budget share needs to be in (0, 1], got {share}"
+            )));
         }
+        self.budget_share = share;
+        Ok(self)
+    }
+
+    /// The share of the power of each testcase spent in this stage
+    #[must_use]
+    pub fn budget_share(&self) -> f64 {
+        self.budget_share
+    }
+
+    /// Limits the time spent on a single testcase per round to `slice`, regardless of its power.
+    /// Remaining iterations are resumed the next time the testcase gets scheduled, see [`MutationalSliceMetadata`].
+    /// They are stored under the name of this stage, so give each stage sharing a power budget its own name
+    /// with [`Self::with_name`].
+    #[must_use]
+    pub fn with_fairness_slice(mut self, slice: Duration) -> Self {
+        self.fairness_slice = Some(slice);
+        self
     }
 }
 
 /// The standard powerscheduling stage
 pub type StdPowerMutationalStage<E, EM, I, M, Z> =
     PowerMutationalStage<E, CorpusPowerTestcaseScore<<E as UsesState>::State>, EM, I, M, Z>;
+
+#[cfg(test)]
+mod tests {
+    use crate::stages::power::split_power_budget;
+
+    #[test]
+    fn test_split_power_budget() {
+        let shares = split_power_budget([1.0, 3.0, 1.0]).unwrap();
+        assert!((shares[0] - 0.2).abs() < 1e-9);
+        assert!((shares[1] - 0.6).abs() < 1e-9);
+        assert!((shares[2] - 0.2).abs() < 1e-9);
+
+        assert!(split_power_budget([0.0, 0.0]).is_err());
+        assert!(split_power_budget([1.0, 0.0]).is_err());
+        assert!(split_power_budget([1.0, -1.0]).is_err());
+        assert!(split_power_budget([f64::NAN]).is_err());
+    }
+}
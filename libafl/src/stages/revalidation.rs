@@ -0,0 +1,352 @@
+//! The [`CorpusRevalidationStage`] re-executes the whole corpus once after the observer configuration changed,
+//! e.g., when resuming a campaign with a different map size or ignore-edges list.
+
+use alloc::vec::Vec;
+use core::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use libafl_bolts::{
+    current_time, hasher_std, impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, SchedulerTestcaseMetadata},
+    executors::{Executor, ExitKind, HasObservers},
+    fuzzer::HasScheduler,
+    observers::{MapObserver, ObserversTuple},
+    schedulers::RemovableScheduler,
+    stages::{calibrate::CalibratedMetadata, Stage},
+    state::{HasCorpus, HasExecutions, State, UsesState},
+    Error, HasMetadata,
+};
+
+/// Tracks the observer configuration the corpus was last validated with,
+/// and the progress of a running revalidation.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorpusRevalidationMetadata {
+    /// The fingerprint of the configuration the corpus is valid for
+    pub fingerprint: u64,
+    /// The corpus entries still to re-execute
+    pending: Vec<CorpusId>,
+    /// The entry being re-executed, set if the fuzzer restarted while executing it
+    current: Option<CorpusId>,
+    /// Invalid entries to remove once they are no longer the entry being fuzzed
+    #[serde(default)]
+    deferred: Vec<CorpusId>,
+}
+
+impl_serdeany!(CorpusRevalidationMetadata);
+
+impl CorpusRevalidationMetadata {
+    /// If a revalidation is still in progress
+    #[must_use]
+    pub fn in_progress(&self) -> bool {
+        !self.pending.is_empty() || self.current.is_some()
+    }
+}
+
+/// Re-executes every corpus entry once when the observer configuration changed since the last run,
+/// to refresh the execution time and coverage metadata the schedulers rely on.
+///
+/// The configuration is fingerprinted from the usable size of the map observer and a user-supplied `config`,
+/// e.g., the ignored edges of an [`crate::observers::IgnoreEdgesMapObserver`].
+/// On a fresh campaign, the fingerprint is only recorded. Add this stage first, so it runs on startup.
+///
+/// Entries that no longer execute cleanly or no longer produce any coverage are removed from the corpus.
+/// The entry currently being fuzzed is only removed in a later run of the stage, once another entry is fuzzed,
+/// so that the stages following this one, and the fuzzer, can still access it.
+/// Revalidated entries lose their [`CalibratedMetadata`], so a [`crate::stages::CalibrationStage`] recalibrates them.
+#[derive(Debug, Clone)]
+pub struct CorpusRevalidationStage<C, O, OT, S> {
+    map_observer_handle: Handle<C>,
+    config_hash: u64,
+    phantom: PhantomData<(O, OT, S)>,
+}
+
+impl<C, O, OT, S> UsesState for CorpusRevalidationStage<C, O, OT, S>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<C, E, EM, O, OT, Z> Stage<E, EM, Z> for CorpusRevalidationStage<C, O, OT, E::State>
+where
+    E: Executor<EM, Z> + HasObservers<Observers = OT>,
+    EM: UsesState<State = E::State>,
+    O: MapObserver,
+    C: AsRef<O>,
+    OT: ObserversTuple<E::State>,
+    E::State: HasCorpus + HasMetadata + HasExecutions,
+    Z: HasScheduler<State = E::State>,
+    Z::Scheduler: RemovableScheduler,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let fingerprint = self.fingerprint(executor.observers())?;
+        let Some(meta) = state.metadata_map().get::<CorpusRevalidationMetadata>() else {
+            // A fresh campaign: the corpus was just evaluated with the current configuration
+            state.add_metadata(CorpusRevalidationMetadata {
+                fingerprint,
+                ..CorpusRevalidationMetadata::default()
+            });
+            return Ok(());
+        };
+        let (old_fingerprint, crashed, in_progress) =
+            (meta.fingerprint, meta.current, meta.in_progress());
+
+        let mut removed = Self::remove_deferred(fuzzer, state)?;
+        let mut revalidated = 0;
+        if old_fingerprint != fingerprint {
+            log::info!(
+                "The observer configuration changed, revalidating {} corpus entries",
+                state.corpus().count()
+            );
+            let pending = state.corpus().ids().collect();
+            let meta = state.metadata_mut::<CorpusRevalidationMetadata>()?;
+            meta.fingerprint = fingerprint;
+            meta.pending = pending;
+            meta.current = None;
+        } else if let Some(id) = crashed {
+            log::warn!("Corpus entry {id} crashed or timed out during revalidation, removing it");
+            state.metadata_mut::<CorpusRevalidationMetadata>()?.current = None;
+            if state.corpus().get(id).is_ok() {
+                Self::remove(fuzzer, state, id)?;
+                removed += 1;
+            }
+        } else if !in_progress {
+            if removed > 0 {
+                log::info!("Removed {removed} invalid corpus entries");
+            }
+            return Ok(());
+        }
+
+        loop {
+            let meta = state.metadata_mut::<CorpusRevalidationMetadata>()?;
+            let Some(id) = meta.pending.pop() else {
+                break;
+            };
+            // Entries removed in the meantime have nothing to revalidate
+            let Ok(input) = state.corpus().cloned_input_for_id(id) else {
+                continue;
+            };
+            // Remember the entry, so that it gets removed if it takes the fuzzer down
+            state.metadata_mut::<CorpusRevalidationMetadata>()?.current = Some(id);
+
+            executor.observers_mut().pre_exec_all(state, &input)?;
+            let start = current_time();
+            let exit_kind = executor.run_target(fuzzer, state, manager, &input)?;
+            let exec_time = current_time() - start;
+            *state.executions_mut() += 1;
+            executor
+                .observers_mut()
+                .post_exec_all(state, &input, &exit_kind)?;
+
+            state.metadata_mut::<CorpusRevalidationMetadata>()?.current = None;
+
+            let bitmap_size = executor.observers()[&self.map_observer_handle]
+                .as_ref()
+                .count_bytes();
+            if exit_kind != ExitKind::Ok || bitmap_size == 0 {
+                log::info!(
+                    "Corpus entry {id} is no longer valid ({exit_kind:?}, {bitmap_size} map entries set), removing it"
+                );
+                Self::remove(fuzzer, state, id)?;
+                removed += 1;
+                continue;
+            }
+
+            let mut testcase = state.corpus().get(id)?.borrow_mut();
+            testcase.set_exec_time(exec_time);
+            testcase.metadata_map_mut().remove::<CalibratedMetadata>();
+            if let Ok(meta) = testcase.metadata_mut::<SchedulerTestcaseMetadata>() {
+                meta.set_bitmap_size(bitmap_size);
+            }
+            revalidated += 1;
+        }
+
+        log::info!(
+            "Revalidated the corpus: {revalidated} entries refreshed, {removed} entries removed"
+        );
+        Ok(())
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // The progress is tracked in the `CorpusRevalidationMetadata`
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // The progress is tracked in the `CorpusRevalidationMetadata`
+        Ok(())
+    }
+}
+
+impl<C, O, OT, S> CorpusRevalidationStage<C, O, OT, S>
+where
+    O: MapObserver,
+    C: AsRef<O>,
+    OT: ObserversTuple<S>,
+    S: HasCorpus,
+{
+    /// Creates a new [`CorpusRevalidationStage`] for the given map observer.
+    /// Any change of `config` or of the usable size of the map triggers a revalidation.
+    #[must_use]
+    pub fn new<H>(map_observer: &C, config: &H) -> Self
+    where
+        C: Named,
+        H: Hash + ?Sized,
+    {
+        let mut hasher = hasher_std();
+        config.hash(&mut hasher);
+        Self {
+            map_observer_handle: map_observer.handle(),
+            config_hash: hasher.finish(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The fingerprint of the current configuration
+    fn fingerprint(&self, observers: &OT) -> Result<u64, Error> {
+        let map = observers
+            .get(&self.map_observer_handle)
+            .ok_or_else(|| {
+                Error::key_not_found(format!("MapObserver {}", self.map_observer_handle.name()))
+            })?
+            .as_ref();
+        let mut hasher = hasher_std();
+        self.config_hash.hash(&mut hasher);
+        map.usable_count().hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// Removes an entry from the corpus and tells the scheduler about it.
+    /// The entry currently being fuzzed is deferred to a later run, see [`Self::remove_deferred`].
+    fn remove<Z>(fuzzer: &mut Z, state: &mut S, id: CorpusId) -> Result<(), Error>
+    where
+        Z: HasScheduler<State = S>,
+        Z::Scheduler: RemovableScheduler,
+        S: HasMetadata,
+    {
+        if state.corpus().current() == &Some(id) {
+            state
+                .metadata_mut::<CorpusRevalidationMetadata>()?
+                .deferred
+                .push(id);
+            return Ok(());
+        }
+        let testcase = state.corpus_mut().remove(id)?;
+        fuzzer.scheduler_mut().on_remove(state, id, &Some(testcase))
+    }
+
+    /// Removes the deferred entries that are no longer being fuzzed, returning how many were removed
+    fn remove_deferred<Z>(fuzzer: &mut Z, state: &mut S) -> Result<usize, Error>
+    where
+        Z: HasScheduler<State = S>,
+        Z::Scheduler: RemovableScheduler,
+        S: HasMetadata,
+    {
+        let deferred =
+            core::mem::take(&mut state.metadata_mut::<CorpusRevalidationMetadata>()?.deferred);
+        let mut removed = 0;
+        for id in deferred {
+            if state.corpus().get(id).is_err() {
+                continue;
+            }
+            Self::remove(fuzzer, state, id)?;
+            if state.corpus().get(id).is_err() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::test::MapExecutor,
+        fuzzer::StdFuzzer,
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        schedulers::QueueScheduler,
+        stages::{CorpusRevalidationStage, Stage},
+        state::{test::test_std_state, HasCorpus, StdState},
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_revalidation_defers_current_entry() {
+        let mut state: TestState = test_std_state();
+        let mut executor = MapExecutor::new(false);
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), (), ());
+        let mut mgr = NopEventManager::new();
+
+        // The empty inputs time out, so they are no longer valid
+        let valid = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![1])))
+            .unwrap();
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![])))
+            .unwrap();
+        let current = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![])))
+            .unwrap();
+        *state.corpus_mut().current_mut() = Some(current);
+
+        let mut old_stage =
+            CorpusRevalidationStage::<_, StdMapObserver<'static, u8, false>, _, _>::new(
+                executor.map_observer(),
+                "old config",
+            );
+        let mut new_stage = CorpusRevalidationStage::new(executor.map_observer(), "new config");
+        let mut perform = |stage: &mut CorpusRevalidationStage<_, _, _, _>,
+                           state: &mut TestState| {
+            stage
+                .perform(&mut fuzzer, &mut executor, state, &mut mgr)
+                .unwrap();
+        };
+
+        // A fresh campaign only records the configuration
+        perform(&mut old_stage, &mut state);
+        assert_eq!(state.corpus().count(), 3);
+
+        // The invalid entry being fuzzed stays, until another one is
+        perform(&mut new_stage, &mut state);
+        assert_eq!(state.corpus().count(), 2);
+        assert!(state.corpus().get(current).is_ok());
+
+        perform(&mut new_stage, &mut state);
+        assert!(state.corpus().get(current).is_ok());
+
+        *state.corpus_mut().current_mut() = Some(valid);
+        perform(&mut new_stage, &mut state);
+        assert_eq!(state.corpus().count(), 1);
+        assert!(state.corpus().get(current).is_err());
+    }
+}
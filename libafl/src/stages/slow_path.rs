@@ -0,0 +1,351 @@
+//! The [`RelaxedTimeoutStage`] runs the wrapped stage with a relaxed timeout on slow-path entries,
+//! or with the timeout of the entry itself, see [`TestcaseTimeoutMetadata`].
+
+use core::time::Duration;
+
+use libafl_bolts::impl_serdeany;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::EventFirer,
+    executors::HasAdjustableTimeout,
+    feedbacks::SlowPathMetadata,
+    stages::Stage,
+    state::{HasCorpus, HasCurrentTestcase, UsesState},
+    Error, HasMetadata,
+};
+
+/// The timeout of a corpus entry, used instead of the global timeout of the executor for the entry
+/// and the inputs derived from it by the [`RelaxedTimeoutStage`]. Derived for slow entries, see
+/// [`RelaxedTimeoutStage::with_testcase_timeouts`], or added by the user.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestcaseTimeoutMetadata {
+    /// The timeout of the entry
+    pub timeout: Duration,
+}
+
+impl_serdeany!(TestcaseTimeoutMetadata);
+
+impl TestcaseTimeoutMetadata {
+    /// Creates a new [`TestcaseTimeoutMetadata`]
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+/// A stage wrapper that sets the timeout of the executor for the current corpus entry while running
+/// the wrapped stage, restoring the global timeout afterwards:
+/// to the timeout of the entry, if it has a [`TestcaseTimeoutMetadata`], else to its relaxed timeout,
+/// if it is slower than the global one and the entry has a [`SlowPathMetadata`],
+/// as added by a [`crate::feedbacks::SlowPathFeedback`].
+///
+/// Wrap all stages executing inputs derived from the entry, so that legitimately slow entries
+/// and their mutants do not count as hangs.
+/// This includes the [`crate::stages::CalibrationStage`]: unwrapped, it runs slow-path entries
+/// under the global timeout, so every calibration run times out, counts as an error and
+/// measures a bogus exec time of one second.
+#[derive(Debug, Clone)]
+pub struct RelaxedTimeoutStage<ST> {
+    stage: ST,
+    /// The multiple of the exec time to give slower entries as their own timeout
+    testcase_timeout_multiplier: Option<u32>,
+}
+
+impl<ST> RelaxedTimeoutStage<ST> {
+    /// Creates a new [`RelaxedTimeoutStage`], wrapping `stage`
+    pub fn new(stage: ST) -> Self {
+        Self {
+            stage,
+            testcase_timeout_multiplier: None,
+        }
+    }
+
+    /// Gives each entry whose exec time times `multiplier` exceeds the global timeout of the executor
+    /// that much time as its own timeout, in a [`TestcaseTimeoutMetadata`], once it has an exec time.
+    /// Wrap the [`crate::stages::CalibrationStage`], which measures it, so that the timeout applies
+    /// from the next time the entry gets scheduled. Entries that already have their own timeout,
+    /// including one added by the user, keep it.
+    ///
+    /// The exec time is the average of the calibration runs, where a run that hits the timeout
+    /// counts as the full timeout in effect. Entries that sometimes hang thus get a timeout biased
+    /// towards `multiplier` times that timeout, rather than `multiplier` times their usual exec time.
+    #[must_use]
+    pub fn with_testcase_timeouts(mut self, multiplier: u32) -> Self {
+        self.testcase_timeout_multiplier = Some(multiplier);
+        self
+    }
+
+    /// The wrapped stage
+    pub fn inner(&self) -> &ST {
+        &self.stage
+    }
+
+    /// The wrapped stage (mutable)
+    pub fn inner_mut(&mut self) -> &mut ST {
+        &mut self.stage
+    }
+}
+
+impl<ST> UsesState for RelaxedTimeoutStage<ST>
+where
+    ST: UsesState,
+{
+    type State = ST::State;
+}
+
+impl<E, EM, ST, Z> Stage<E, EM, Z> for RelaxedTimeoutStage<ST>
+where
+    E: UsesState<State = Self::State> + HasAdjustableTimeout,
+    EM: EventFirer<State = Self::State>,
+    ST: Stage<E, EM, Z>,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasCorpus,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let timeout = executor.timeout();
+        let entry_timeout = {
+            let testcase = state.current_testcase()?;
+            if let Ok(meta) = testcase.metadata::<TestcaseTimeoutMetadata>() {
+                Some(meta.timeout)
+            } else {
+                testcase
+                    .metadata::<SlowPathMetadata>()
+                    .ok()
+                    .map(|meta| meta.relaxed_timeout.max(timeout))
+            }
+        };
+        let res = if let Some(entry_timeout) = entry_timeout {
+            executor.set_timeout(entry_timeout);
+            let res = self.stage.perform(fuzzer, executor, state, manager);
+            executor.set_timeout(timeout);
+            res
+        } else {
+            self.stage.perform(fuzzer, executor, state, manager)
+        };
+
+        if let Some(multiplier) = self.testcase_timeout_multiplier {
+            let mut testcase = state.current_testcase_mut()?;
+            if !testcase.has_metadata::<TestcaseTimeoutMetadata>() {
+                if let Some(exec_time) = *testcase.exec_time() {
+                    let entry_timeout = exec_time.saturating_mul(multiplier);
+                    if entry_timeout > timeout {
+                        testcase.add_metadata(TestcaseTimeoutMetadata::new(entry_timeout));
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        self.stage.restart_progress_should_run(state)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        self.stage.clear_restart_progress(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{Corpus, HasCurrentCorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::HasAdjustableTimeout,
+        feedbacks::{ConstFeedback, SlowPathMetadata},
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        stages::{RelaxedTimeoutStage, Stage, TestcaseTimeoutMetadata},
+        state::{HasCorpus, StdState, UsesState},
+        Error, HasMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    struct TimeoutExecutor {
+        timeout: Duration,
+    }
+
+    impl UsesState for TimeoutExecutor {
+        type State = TestState;
+    }
+
+    impl HasAdjustableTimeout for TimeoutExecutor {
+        fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        fn set_timeout(&mut self, timeout: Duration) {
+            self.timeout = timeout;
+        }
+    }
+
+    /// Records the timeout of the executor it runs with
+    #[derive(Default)]
+    struct RecordTimeoutStage {
+        timeouts: Vec<Duration>,
+    }
+
+    impl UsesState for RecordTimeoutStage {
+        type State = TestState;
+    }
+
+    impl<EM, Z> Stage<TimeoutExecutor, EM, Z> for RecordTimeoutStage
+    where
+        EM: UsesState<State = TestState>,
+        Z: UsesState<State = TestState>,
+    {
+        fn perform(
+            &mut self,
+            _fuzzer: &mut Z,
+            executor: &mut TimeoutExecutor,
+            _state: &mut TestState,
+            _manager: &mut EM,
+        ) -> Result<(), Error> {
+            self.timeouts.push(executor.timeout());
+            Ok(())
+        }
+
+        fn restart_progress_should_run(&mut self, _state: &mut TestState) -> Result<bool, Error> {
+            Ok(true)
+        }
+
+        fn clear_restart_progress(&mut self, _state: &mut TestState) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_relaxed_timeout_stage() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let global = Duration::from_millis(100);
+        let mut plain = Testcase::new(BytesInput::new(vec![0]));
+        plain.add_metadata(SlowPathMetadata {
+            relaxed_timeout: Duration::from_millis(10),
+        });
+        let mut slow = Testcase::new(BytesInput::new(vec![1]));
+        slow.add_metadata(SlowPathMetadata {
+            relaxed_timeout: Duration::from_secs(1),
+        });
+        let mut own = Testcase::new(BytesInput::new(vec![2]));
+        own.add_metadata(TestcaseTimeoutMetadata::new(Duration::from_millis(50)));
+        let ids = [plain, slow, own].map(|testcase| state.corpus_mut().add(testcase).unwrap());
+
+        let mut stage = RelaxedTimeoutStage::new(RecordTimeoutStage::default());
+        let mut executor = TimeoutExecutor { timeout: global };
+        let mut fuzzer = NopFuzzer::new();
+        let mut mgr = NopEventManager::new();
+        for id in ids {
+            state.set_corpus_idx(id).unwrap();
+            stage
+                .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+                .unwrap();
+            assert_eq!(executor.timeout(), global);
+        }
+
+        // A relaxed timeout never lowers the global one, the timeout of the entry itself wins
+        assert_eq!(
+            stage.inner().timeouts,
+            [global, Duration::from_secs(1), Duration::from_millis(50)]
+        );
+    }
+
+    #[test]
+    fn test_derived_testcase_timeouts() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let global = Duration::from_millis(100);
+        let mut fast = Testcase::new(BytesInput::new(vec![0]));
+        fast.set_exec_time(Duration::from_millis(10));
+        let mut slow = Testcase::new(BytesInput::new(vec![1]));
+        slow.set_exec_time(Duration::from_millis(40));
+        let mut own = Testcase::new(BytesInput::new(vec![2]));
+        own.set_exec_time(Duration::from_millis(40));
+        own.add_metadata(TestcaseTimeoutMetadata::new(Duration::from_millis(50)));
+        let uncalibrated = Testcase::new(BytesInput::new(vec![3]));
+        let ids = [fast, slow, own, uncalibrated]
+            .map(|testcase| state.corpus_mut().add(testcase).unwrap());
+
+        let mut stage =
+            RelaxedTimeoutStage::new(RecordTimeoutStage::default()).with_testcase_timeouts(3);
+        let mut executor = TimeoutExecutor { timeout: global };
+        let mut fuzzer = NopFuzzer::new();
+        let mut mgr = NopEventManager::new();
+        for _ in 0..2 {
+            for id in ids {
+                state.set_corpus_idx(id).unwrap();
+                stage
+                    .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+                    .unwrap();
+            }
+        }
+
+        let timeout_of = |state: &TestState, idx: usize| {
+            state
+                .corpus()
+                .get(ids[idx])
+                .unwrap()
+                .borrow()
+                .metadata::<TestcaseTimeoutMetadata>()
+                .ok()
+                .map(|meta| meta.timeout)
+        };
+        // Derived against the timeout of the executor, only above it, never over the user's
+        assert_eq!(timeout_of(&state, 0), None);
+        assert_eq!(timeout_of(&state, 1), Some(Duration::from_millis(120)));
+        assert_eq!(timeout_of(&state, 2), Some(Duration::from_millis(50)));
+        assert_eq!(timeout_of(&state, 3), None);
+        // and applied from the next schedule on
+        assert_eq!(
+            stage.inner().timeouts,
+            [
+                global,
+                global,
+                Duration::from_millis(50),
+                global,
+                global,
+                Duration::from_millis(120),
+                Duration::from_millis(50),
+                global
+            ]
+        );
+    }
+}
@@ -1,27 +1,273 @@
 //! Stage to compute/report AFL stats
 
+use alloc::string::String;
 #[cfg(feature = "std")]
-use alloc::{borrow::Cow, string::ToString};
-use core::{marker::PhantomData, time::Duration};
+use alloc::{borrow::Cow, string::ToString, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
 
-use libafl_bolts::current_time;
+#[cfg(feature = "std")]
+use libafl_bolts::Named;
+use libafl_bolts::{current_time, HasLen};
 #[cfg(feature = "std")]
 use serde_json::json;
 
 use crate::{
     corpus::{Corpus, HasCurrentCorpusId},
     events::EventFirer,
-    schedulers::minimizer::IsFavoredMetadata,
+    inputs::UsesInput,
+    schedulers::minimizer::count_pending,
     stages::Stage,
-    state::{HasCorpus, HasImported, UsesState},
-    Error, HasMetadata,
+    state::{HasCorpus, HasExecutions, HasImported, HasSolutions, HasStartTime, UsesState},
+    Error, HasMetadata, HasNamedMetadata,
 };
 #[cfg(feature = "std")]
 use crate::{
+    corpus::{CorpusId, SchedulerTestcaseMetadata},
     events::Event,
+    feedbacks::MapFeedbackMetadata,
     monitors::{AggregatorOps, UserStats, UserStatsValue},
+    schedulers::{cycles_done, minimizer::IsFavoredMetadata, ExplorationGateMetadata},
+    stages::calibrate::UnstableEntriesMetadata,
 };
 
+/// The header of `AFL++`'s `plot_data`, as read by `afl-plot`
+#[cfg(feature = "std")]
+const PLOT_DATA_HEADER: &str = "# relative_time, cycles_done, cur_item, corpus_count, pending_total, pending_favs, map_size, saved_crashes, saved_hangs, max_depth, execs_per_sec, total_execs, edges_found\n";
+
+/// The keys of `AFL++`'s `fuzzer_stats`, in the order `AFL++` 4.x writes them, as read by `afl-whatsup`
+#[cfg(feature = "std")]
+pub const AFLPP_FUZZER_STATS_KEYS: &[&str] = &[
+    "start_time",
+    "last_update",
+    "run_time",
+    "fuzzer_pid",
+    "cycles_done",
+    "cycles_wo_finds",
+    "time_wo_finds",
+    "fuzz_time",
+    "calibration_time",
+    "cmplog_time",
+    "sync_time",
+    "trim_time",
+    "execs_done",
+    "execs_per_sec",
+    "execs_ps_last_min",
+    "corpus_count",
+    "corpus_favored",
+    "corpus_found",
+    "corpus_imported",
+    "corpus_variable",
+    "max_depth",
+    "cur_item",
+    "pending_favs",
+    "pending_total",
+    "stability",
+    "bitmap_cvg",
+    "saved_crashes",
+    "saved_hangs",
+    "total_tmout",
+    "last_find",
+    "last_crash",
+    "last_hang",
+    "execs_since_crash",
+    "exec_timeout",
+    "slowest_exec_ms",
+    "peak_rss_mb",
+    "cpu_affinity",
+    "edges_found",
+    "total_edges",
+    "var_byte_count",
+    "havoc_expansion",
+    "auto_dict_entries",
+    "testcache_size",
+    "testcache_count",
+    "testcache_evict",
+    "afl_banner",
+    "afl_version",
+    "target_mode",
+    "command_line",
+];
+
+/// The contents of an `AFL++`-compatible `fuzzer_stats` file, written as `key : value` lines
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AflFuzzerStats {
+    fields: Vec<(Cow<'static, str>, String)>,
+}
+
+#[cfg(feature = "std")]
+impl AflFuzzerStats {
+    /// Sets the field `key`, in place if it is set already, else appended after all other fields
+    pub fn set<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<Cow<'static, str>>,
+        V: ToString,
+    {
+        let key = key.into();
+        let value = value.to_string();
+        match self
+            .fields
+            .iter_mut()
+            .find(|(existing, _)| *existing == key)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.fields.push((key, value)),
+        }
+    }
+
+    /// The value of the field `key`, if set
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(existing, _)| existing == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The fields, in the order they are written
+    #[must_use]
+    pub fn fields(&self) -> &[(Cow<'static, str>, String)] {
+        &self.fields
+    }
+
+    /// Writes the stats to `path`, through a temporary file that replaces it at once,
+    /// so that tools polling the file never read a partial one
+    pub fn write_to(&self, path: &Path) -> Result<(), Error> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, self.to_string())?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for AflFuzzerStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.fields {
+            writeln!(f, "{key:<18}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// When the corpus and the solutions last grew, as tracked by the [`AflStatsStage`] for the `fuzzer_stats`
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+struct FindTracker {
+    initialized: bool,
+    corpus_count: usize,
+    solutions_count: usize,
+    last_find: Option<Duration>,
+    last_crash: Option<Duration>,
+    cycles_at_last_find: u64,
+    execs_at_last_crash: u64,
+    /// The time and the executions at the last report
+    last_report: Option<(Duration, u64)>,
+}
+
+#[cfg(feature = "std")]
+impl FindTracker {
+    /// Notes new corpus entries and solutions since the last update. The initial corpus is no find.
+    fn update(
+        &mut self,
+        now: Duration,
+        corpus_count: usize,
+        solutions_count: usize,
+        cycles: u64,
+        execs: u64,
+    ) {
+        if self.initialized && corpus_count > self.corpus_count {
+            self.last_find = Some(now);
+            self.cycles_at_last_find = cycles;
+        }
+        if self.initialized && solutions_count > self.solutions_count {
+            self.last_crash = Some(now);
+            self.execs_at_last_crash = execs;
+        }
+        self.initialized = true;
+        self.corpus_count = corpus_count;
+        self.solutions_count = solutions_count;
+    }
+}
+
+/// The exclusive upper bounds of the buckets of a [`CorpusSizeHistogram`], in bytes.
+/// Inputs at least as big as the last bound fall into an additional, open bucket.
+pub const CORPUS_SIZE_BUCKETS: [usize; 5] = [64, 256, 1024, 4096, 16384];
+
+/// A histogram of the sizes of the inputs in the corpus, bucketed by [`CORPUS_SIZE_BUCKETS`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CorpusSizeHistogram {
+    counts: [usize; CORPUS_SIZE_BUCKETS.len() + 1],
+}
+
+impl CorpusSizeHistogram {
+    /// Computes the histogram for all inputs in the `corpus`, using the cached length where available
+    pub fn from_corpus<C>(corpus: &C) -> Result<Self, Error>
+    where
+        C: Corpus,
+        C::Input: HasLen,
+    {
+        let mut histogram = Self::default();
+        for id in corpus.ids() {
+            let len = corpus.get(id)?.borrow_mut().load_len(corpus)?;
+            histogram.add(len);
+        }
+        Ok(histogram)
+    }
+
+    /// Counts an input of `len` bytes
+    pub fn add(&mut self, len: usize) {
+        let bucket = CORPUS_SIZE_BUCKETS
+            .iter()
+            .position(|bound| len < *bound)
+            .unwrap_or(CORPUS_SIZE_BUCKETS.len());
+        self.counts[bucket] += 1;
+    }
+
+    /// The number of inputs per bucket
+    #[must_use]
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// The label of the bucket at `idx`, such as `64-256B`
+    #[must_use]
+    pub fn label(idx: usize) -> String {
+        match idx {
+            0 => format!("<{}B", CORPUS_SIZE_BUCKETS[0]),
+            idx if idx < CORPUS_SIZE_BUCKETS.len() => format!(
+                "{}-{}B",
+                CORPUS_SIZE_BUCKETS[idx - 1],
+                CORPUS_SIZE_BUCKETS[idx]
+            ),
+            _ => format!(">={}B", CORPUS_SIZE_BUCKETS[CORPUS_SIZE_BUCKETS.len() - 1]),
+        }
+    }
+}
+
+impl Display for CorpusSizeHistogram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (idx, count) in self.counts.iter().enumerate() {
+            if idx != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {count}", Self::label(idx))?;
+        }
+        Ok(())
+    }
+}
+
 /// The [`AflStatsStage`] is a simple stage that computes and reports some stats.
 #[derive(Debug, Clone)]
 pub struct AflStatsStage<E, EM, Z>
@@ -30,10 +276,6 @@ where
     EM: EventFirer<State = E::State>,
     Z: UsesState<State = E::State>,
 {
-    // the number of testcases that have been fuzzed
-    has_fuzzed_size: usize,
-    // the number of "favored" testcases
-    is_favored_size: usize,
     // the number of testcases found by itself
     own_finds_size: usize,
     // the number of testcases imported by other fuzzers
@@ -42,6 +284,20 @@ where
     last_report_time: Duration,
     // the interval that we report all stats
     stats_report_interval: Duration,
+    // the `plot_data` file and the name of the map feedback to take the coverage from
+    #[cfg(feature = "std")]
+    plot_data: Option<(PathBuf, Cow<'static, str>)>,
+    // the `fuzzer_stats` file and the name of the map feedback to take the coverage from
+    #[cfg(feature = "std")]
+    fuzzer_stats: Option<(PathBuf, Cow<'static, str>)>,
+    // the fields overriding or extending the computed `fuzzer_stats`
+    #[cfg(feature = "std")]
+    fuzzer_stats_fields: Vec<(Cow<'static, str>, String)>,
+    // when the corpus and the solutions last grew
+    #[cfg(feature = "std")]
+    finds: FindTracker,
+    // computes the corpus size histogram, if enabled
+    size_histogram: Option<fn(&E::State) -> Result<CorpusSizeHistogram, Error>>,
 
     phantom: PhantomData<(E, EM, Z)>,
 }
@@ -60,7 +316,13 @@ where
     E: UsesState,
     EM: EventFirer<State = E::State>,
     Z: UsesState<State = E::State>,
-    E::State: HasImported + HasCorpus + HasMetadata,
+    E::State: HasImported
+        + HasCorpus
+        + HasMetadata
+        + HasNamedMetadata
+        + HasSolutions
+        + HasExecutions
+        + HasStartTime,
 {
     fn perform(
         &mut self,
@@ -75,37 +337,52 @@ where
             ));
         };
 
+        #[cfg(feature = "std")]
+        self.finds.update(
+            current_time(),
+            state.corpus().count(),
+            state.solutions().count(),
+            cycles_done(state),
+            *state.executions(),
+        );
+
         // Report your stats every `STATS_REPORT_INTERVAL`
         // compute pending, pending_favored, imported, own_finds
-        {
-            let testcase = state.corpus().get(corpus_idx)?.borrow();
-            if testcase.scheduled_count() == 0 {
-                self.has_fuzzed_size += 1;
-                if testcase.has_metadata::<IsFavoredMetadata>() {
-                    self.is_favored_size += 1;
-                }
-            } else {
-                return Ok(());
-            }
-        }
-
         let corpus_size = state.corpus().count();
-        let pending_size = corpus_size - self.has_fuzzed_size;
-        let pend_favored_size = corpus_size - self.is_favored_size;
         self.imported_size = *state.imported();
         self.own_finds_size = corpus_size - self.imported_size;
 
         let cur = current_time();
 
         if cur.checked_sub(self.last_report_time).unwrap_or_default() > self.stats_report_interval {
+            let (pending_size, pend_favored_size) = count_pending(state.corpus())?;
+            let size_histogram = self
+                .size_histogram
+                .map(|compute| compute(state))
+                .transpose()?;
             #[cfg(feature = "std")]
             {
-                let json = json!({
+                self.write_plot_data(state, corpus_idx, pending_size, pend_favored_size)?;
+                self.write_fuzzer_stats(state, corpus_idx, pending_size, pend_favored_size)?;
+                let mut json = json!({
                         "pending":pending_size,
                         "pend_fav":pend_favored_size,
                         "own_finds":self.own_finds_size,
                         "imported":self.imported_size,
+                        "cycles_done":cycles_done(state),
                 });
+                if let Some(gate) = state.metadata_map().get::<ExplorationGateMetadata>() {
+                    json["schedule_mode"] = json!(gate.mode());
+                }
+                if let Some(histogram) = &size_histogram {
+                    let buckets: serde_json::Map<String, serde_json::Value> = histogram
+                        .counts()
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, count)| (CorpusSizeHistogram::label(idx), json!(count)))
+                        .collect();
+                    json["corpus_sizes"] = serde_json::Value::Object(buckets);
+                }
                 _manager.fire(
                     state,
                     Event::UpdateUserStats {
@@ -126,6 +403,9 @@ where
                 self.own_finds_size,
                 self.imported_size
             );
+            if let Some(histogram) = size_histogram {
+                log::info!("corpus sizes: {histogram}");
+            }
             self.last_report_time = cur;
         }
 
@@ -162,6 +442,295 @@ where
     }
 }
 
+impl<E, EM, Z> AflStatsStage<E, EM, Z>
+where
+    E: UsesState,
+    EM: EventFirer<State = E::State>,
+    Z: UsesState<State = E::State>,
+    E::State: HasImported + HasCorpus + HasMetadata,
+    <E::State as UsesInput>::Input: HasLen,
+{
+    /// Also reports a [`CorpusSizeHistogram`] at every report, as `corpus_sizes` in the json stats.
+    /// This iterates the whole corpus, loading inputs whose length is not cached yet.
+    #[must_use]
+    pub fn with_size_histogram(mut self) -> Self {
+        self.size_histogram = Some(|state| CorpusSizeHistogram::from_corpus(state.corpus()));
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E, EM, Z> AflStatsStage<E, EM, Z>
+where
+    E: UsesState,
+    EM: EventFirer<State = E::State>,
+    Z: UsesState<State = E::State>,
+    E::State: HasImported
+        + HasCorpus
+        + HasMetadata
+        + HasNamedMetadata
+        + HasSolutions
+        + HasExecutions
+        + HasStartTime,
+{
+    /// Appends a row to an `AFL++`-compatible `plot_data` file at every report, so that `afl-plot` works unchanged.
+    /// The coverage columns are taken from the given map feedback, which has to track a map of `u8`.
+    ///
+    /// `LibAFL` does not tell crashes and hangs apart in the solutions, so all solutions count as `saved_crashes`.
+    #[must_use]
+    pub fn with_plot_data<F, P>(mut self, path: P, map_feedback: &F) -> Self
+    where
+        F: Named,
+        P: AsRef<Path>,
+    {
+        self.plot_data = Some((path.as_ref().to_path_buf(), map_feedback.name().clone()));
+        self
+    }
+
+    /// Appends the current stats to the `plot_data` file, if any, writing the header to a new file first
+    fn write_plot_data(
+        &self,
+        state: &E::State,
+        corpus_idx: CorpusId,
+        pending_size: usize,
+        pend_favored_size: usize,
+    ) -> Result<(), Error> {
+        let Some((path, map_name)) = &self.plot_data else {
+            return Ok(());
+        };
+
+        let relative_time = current_time().saturating_sub(*state.start_time());
+        let cycles_done = cycles_done(state);
+        let (edges_found, map_len) = state
+            .named_metadata::<MapFeedbackMetadata<u8>>(map_name)
+            .map_or((0, 0), |meta| {
+                (meta.num_covered_map_indexes, meta.history_map.len())
+            });
+        #[allow(clippy::cast_precision_loss)]
+        let map_size = if map_len == 0 {
+            0.0
+        } else {
+            edges_found as f64 * 100.0 / map_len as f64
+        };
+        let max_depth = corpus_summary(state.corpus())?.max_depth;
+        let total_execs = *state.executions();
+        #[allow(clippy::cast_precision_loss)]
+        let execs_per_sec = if relative_time.is_zero() {
+            0.0
+        } else {
+            total_execs as f64 / relative_time.as_secs_f64()
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(PLOT_DATA_HEADER.as_bytes())?;
+        }
+        writeln!(
+            file,
+            "{}, {cycles_done}, {corpus_idx}, {}, {pending_size}, {pend_favored_size}, {map_size:.2}%, {}, 0, {max_depth}, {execs_per_sec:.2}, {total_execs}, {edges_found}",
+            relative_time.as_secs(),
+            state.corpus().count(),
+            state.solutions().count(),
+        )?;
+        Ok(())
+    }
+
+    /// Writes an `AFL++`-compatible `fuzzer_stats` file at every report, with all fields of
+    /// [`AFLPP_FUZZER_STATS_KEYS`], so that `afl-whatsup` and other `AFL++` tooling work unchanged.
+    /// The coverage fields are taken from the given map feedback, which has to track a map of `u8`.
+    ///
+    /// The stability follows `AFL++`: the share of the covered entries that are stable.
+    /// The last find and crash are only noticed when this stage runs, so they are as precise as
+    /// the time spent per corpus entry. As with the `plot_data`, all solutions count as `saved_crashes`.
+    /// Fields `LibAFL` does not track, such as `exec_timeout` or `peak_rss_mb`, are written as `0`,
+    /// set them with [`AflStatsStage::with_fuzzer_stats_field`] where known.
+    #[must_use]
+    pub fn with_fuzzer_stats<F, P>(mut self, path: P, map_feedback: &F) -> Self
+    where
+        F: Named,
+        P: AsRef<Path>,
+    {
+        self.fuzzer_stats = Some((path.as_ref().to_path_buf(), map_feedback.name().clone()));
+        self
+    }
+
+    /// Sets the field `key` of the `fuzzer_stats` to a fixed `value`, overriding the computed one,
+    /// such as the `afl_banner`, or adding a field of its own
+    #[must_use]
+    pub fn with_fuzzer_stats_field<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: ToString,
+    {
+        let key = key.into();
+        self.fuzzer_stats_fields
+            .retain(|(existing, _)| *existing != key);
+        self.fuzzer_stats_fields.push((key, value.to_string()));
+        self
+    }
+
+    /// Writes the current stats to the `fuzzer_stats` file, if any
+    #[allow(clippy::cast_precision_loss)]
+    fn write_fuzzer_stats(
+        &mut self,
+        state: &E::State,
+        corpus_idx: CorpusId,
+        pending_size: usize,
+        pend_favored_size: usize,
+    ) -> Result<(), Error> {
+        let Some((path, map_name)) = &self.fuzzer_stats else {
+            return Ok(());
+        };
+
+        let now = current_time();
+        let start_time = *state.start_time();
+        let run_time = now.saturating_sub(start_time);
+        let total_execs = *state.executions();
+        let cycles_done = cycles_done(state);
+        let summary = corpus_summary(state.corpus())?;
+        let (edges_found, map_len) = state
+            .named_metadata::<MapFeedbackMetadata<u8>>(map_name)
+            .map_or((0, 0), |meta| {
+                (meta.num_covered_map_indexes, meta.history_map.len())
+            });
+        let unstable = state
+            .metadata_map()
+            .get::<UnstableEntriesMetadata>()
+            .map_or(0, |meta| meta.unstable_entries().len());
+        let stability = if edges_found == 0 {
+            100.0
+        } else {
+            edges_found.saturating_sub(unstable) as f64 * 100.0 / edges_found as f64
+        };
+        let bitmap_cvg = if map_len == 0 {
+            0.0
+        } else {
+            edges_found as f64 * 100.0 / map_len as f64
+        };
+        let per_sec = |execs: u64, time: Duration| {
+            if time.is_zero() {
+                0.0
+            } else {
+                execs as f64 / time.as_secs_f64()
+            }
+        };
+        let execs_per_sec = per_sec(total_execs, run_time);
+        let execs_ps_last_min = self
+            .finds
+            .last_report
+            .map_or(execs_per_sec, |(time, execs)| {
+                per_sec(total_execs.saturating_sub(execs), now.saturating_sub(time))
+            });
+        self.finds.last_report = Some((now, total_execs));
+        let secs = |time: Option<Duration>| time.map_or(0, |time| time.as_secs());
+
+        let mut stats = AflFuzzerStats::default();
+        stats.set("start_time", start_time.as_secs());
+        stats.set("last_update", now.as_secs());
+        stats.set("run_time", run_time.as_secs());
+        stats.set("fuzzer_pid", std::process::id());
+        stats.set("cycles_done", cycles_done);
+        stats.set(
+            "cycles_wo_finds",
+            cycles_done.saturating_sub(self.finds.cycles_at_last_find),
+        );
+        stats.set(
+            "time_wo_finds",
+            now.saturating_sub(self.finds.last_find.unwrap_or(start_time))
+                .as_secs(),
+        );
+        stats.set("fuzz_time", run_time.as_secs());
+        for key in ["calibration_time", "cmplog_time", "sync_time", "trim_time"] {
+            stats.set(key, 0);
+        }
+        stats.set("execs_done", total_execs);
+        stats.set("execs_per_sec", format!("{execs_per_sec:.2}"));
+        stats.set("execs_ps_last_min", format!("{execs_ps_last_min:.2}"));
+        stats.set("corpus_count", state.corpus().count());
+        stats.set("corpus_favored", summary.favored);
+        stats.set("corpus_found", self.own_finds_size);
+        stats.set("corpus_imported", self.imported_size);
+        stats.set("corpus_variable", 0);
+        stats.set("max_depth", summary.max_depth);
+        stats.set("cur_item", corpus_idx);
+        stats.set("pending_favs", pend_favored_size);
+        stats.set("pending_total", pending_size);
+        stats.set("stability", format!("{stability:.2}%"));
+        stats.set("bitmap_cvg", format!("{bitmap_cvg:.2}%"));
+        stats.set("saved_crashes", state.solutions().count());
+        stats.set("saved_hangs", 0);
+        stats.set("total_tmout", 0);
+        stats.set("last_find", secs(self.finds.last_find));
+        stats.set("last_crash", secs(self.finds.last_crash));
+        stats.set("last_hang", 0);
+        stats.set(
+            "execs_since_crash",
+            total_execs.saturating_sub(self.finds.execs_at_last_crash),
+        );
+        stats.set("exec_timeout", 0);
+        stats.set("slowest_exec_ms", summary.slowest_exec.as_millis());
+        stats.set("peak_rss_mb", 0);
+        stats.set("cpu_affinity", -1);
+        stats.set("edges_found", edges_found);
+        stats.set("total_edges", map_len);
+        stats.set("var_byte_count", unstable);
+        for key in [
+            "havoc_expansion",
+            "auto_dict_entries",
+            "testcache_size",
+            "testcache_count",
+            "testcache_evict",
+        ] {
+            stats.set(key, 0);
+        }
+        stats.set("afl_banner", "libafl");
+        stats.set("afl_version", concat!("libafl-", env!("CARGO_PKG_VERSION")));
+        stats.set("target_mode", "default");
+        stats.set(
+            "command_line",
+            std::env::args().collect::<Vec<_>>().join(" "),
+        );
+        for (key, value) in &self.fuzzer_stats_fields {
+            stats.set(key.clone(), value);
+        }
+        stats.write_to(path)
+    }
+}
+
+/// The deepest entry, the number of favored entries and the slowest execution in a corpus
+#[cfg(feature = "std")]
+struct CorpusSummary {
+    max_depth: u64,
+    favored: usize,
+    slowest_exec: Duration,
+}
+
+/// Summarizes the metadata of all entries in the `corpus`
+#[cfg(feature = "std")]
+fn corpus_summary<C>(corpus: &C) -> Result<CorpusSummary, Error>
+where
+    C: Corpus,
+{
+    let mut summary = CorpusSummary {
+        max_depth: 0,
+        favored: 0,
+        slowest_exec: Duration::ZERO,
+    };
+    for id in corpus.ids() {
+        let testcase = corpus.get(id)?.borrow();
+        if let Ok(meta) = testcase.metadata::<SchedulerTestcaseMetadata>() {
+            summary.max_depth = summary.max_depth.max(meta.depth());
+        }
+        if testcase.has_metadata::<IsFavoredMetadata>() {
+            summary.favored += 1;
+        }
+        if let Some(exec_time) = testcase.exec_time() {
+            summary.slowest_exec = summary.slowest_exec.max(*exec_time);
+        }
+    }
+    Ok(summary)
+}
+
 impl<E, EM, Z> Default for AflStatsStage<E, EM, Z>
 where
     E: UsesState,
@@ -173,13 +742,81 @@ where
     #[must_use]
     fn default() -> Self {
         Self {
-            has_fuzzed_size: 0,
-            is_favored_size: 0,
             own_finds_size: 0,
             imported_size: 0,
             last_report_time: current_time(),
             stats_report_interval: Duration::from_secs(15),
+            #[cfg(feature = "std")]
+            plot_data: None,
+            #[cfg(feature = "std")]
+            fuzzer_stats: None,
+            #[cfg(feature = "std")]
+            fuzzer_stats_fields: vec![],
+            #[cfg(feature = "std")]
+            finds: FindTracker::default(),
+            size_histogram: None,
             phantom: PhantomData,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::stages::stats::CorpusSizeHistogram;
+
+    #[test]
+    fn test_corpus_size_histogram() {
+        let mut histogram = CorpusSizeHistogram::default();
+        for len in [0, 63, 64, 300, 100_000] {
+            histogram.add(len);
+        }
+        assert_eq!(histogram.counts(), &[2, 1, 1, 0, 0, 1]);
+        assert_eq!(
+            format!("{histogram}"),
+            "<64B: 2, 64-256B: 1, 256-1024B: 1, 1024-4096B: 0, 4096-16384B: 0, >=16384B: 1"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_afl_fuzzer_stats() {
+        use alloc::string::ToString;
+
+        use crate::{stages::stats::AflFuzzerStats, state::AflppStats};
+
+        let mut stats = AflFuzzerStats::default();
+        stats.set("start_time", 1_700_000_000);
+        stats.set("execs_done", 1);
+        stats.set("saved_crashes", 2);
+        stats.set("afl_banner", "libafl");
+        stats.set("execs_done", 1_234_567);
+        assert_eq!(stats.fields().len(), 4);
+        assert_eq!(stats.get("execs_done"), Some("1234567"));
+
+        let written = stats.to_string();
+        assert!(written.starts_with("start_time        : 1700000000\n"));
+        let parsed = AflppStats::parse(&written).unwrap();
+        assert_eq!(parsed.execs_done, 1_234_567);
+        assert_eq!(parsed.saved_crashes, 2);
+    }
+
+    /// Checks the format and the keys against the `fuzzer_stats` of a real `AFL++` 4.09c run
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_afl_fuzzer_stats_aflpp_fixture() {
+        use alloc::{string::ToString, vec::Vec};
+
+        use crate::stages::stats::{AflFuzzerStats, AFLPP_FUZZER_STATS_KEYS};
+
+        let fixture = include_str!("../../test_data/aflpp_fuzzer_stats");
+
+        let mut stats = AflFuzzerStats::default();
+        for line in fixture.lines() {
+            let (key, value) = line.split_once(" : ").unwrap();
+            stats.set(key.trim_end().to_string(), value);
+        }
+        let keys: Vec<&str> = stats.fields().iter().map(|(key, _)| key.as_ref()).collect();
+        assert_eq!(keys, AFLPP_FUZZER_STATS_KEYS);
+        assert_eq!(stats.to_string(), fixture);
+    }
+}
@@ -0,0 +1,288 @@
+//! The [`StopOnObjectiveStage`] stops the whole fuzzing campaign as soon as the first objective was found,
+//! e.g., to gate pull requests in CI on a short fuzzing run.
+
+use alloc::{boxed::Box, string::ToString};
+use core::marker::PhantomData;
+
+use libafl_bolts::impl_serdeany;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    events::{CustomBufEventResult, Event, EventFirer, EventRestarter, HasCustomBufHandlers},
+    executors::ExitKind,
+    stages::Stage,
+    state::{HasSolutions, UsesState},
+    Error, HasMetadata,
+};
+
+/// The tag of the [`Event::CustomBuf`] telling all clients to stop
+pub const STOP_ON_OBJECTIVE_TAG: &str = "libafl_stop_on_objective";
+
+/// Exit status if the first objective was a crash
+pub const STOP_ON_OBJECTIVE_EXIT_CRASH: i32 = 90;
+/// Exit status if the first objective was a timeout
+pub const STOP_ON_OBJECTIVE_EXIT_TIMEOUT: i32 = 91;
+/// Exit status if the first objective was an out of memory condition
+pub const STOP_ON_OBJECTIVE_EXIT_OOM: i32 = 92;
+/// Exit status for any other objective
+pub const STOP_ON_OBJECTIVE_EXIT_OTHER: i32 = 93;
+
+/// Returns `true` if the given exit status was produced by a [`StopOnObjectiveStage`]
+#[must_use]
+pub fn is_stop_on_objective_exit_code(status: i32) -> bool {
+    (STOP_ON_OBJECTIVE_EXIT_CRASH..=STOP_ON_OBJECTIVE_EXIT_OTHER).contains(&status)
+}
+
+/// Maps the [`ExitKind`] of an objective to the exit status of the fuzzer
+#[must_use]
+pub fn stop_on_objective_exit_code(exit_kind: Option<&ExitKind>) -> i32 {
+    match exit_kind {
+        Some(ExitKind::Crash) => STOP_ON_OBJECTIVE_EXIT_CRASH,
+        Some(ExitKind::Timeout) => STOP_ON_OBJECTIVE_EXIT_TIMEOUT,
+        Some(ExitKind::Oom) => STOP_ON_OBJECTIVE_EXIT_OOM,
+        _ => STOP_ON_OBJECTIVE_EXIT_OTHER,
+    }
+}
+
+/// Metadata set when another client of the fleet requested to stop
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StopRequestedMetadata {
+    /// The exit status to stop with
+    pub exit_code: i32,
+}
+
+impl_serdeany!(StopRequestedMetadata);
+
+/// The number of solutions when the [`StopOnObjectiveStage`] first ran,
+/// so that only objectives found afterwards stop the fuzzer
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StopOnObjectiveMetadata {
+    /// The number of solutions, e.g., kept from a previous campaign
+    pub solutions: usize,
+}
+
+impl_serdeany!(StopOnObjectiveMetadata);
+
+/// The exit status for the first objective found since the [`StopOnObjectiveStage`] first ran, if any.
+/// The first run only records the number of solutions in the [`StopOnObjectiveMetadata`].
+fn new_objective_exit_code<S>(state: &mut S) -> Result<Option<i32>, Error>
+where
+    S: HasSolutions + HasMetadata,
+{
+    let count = state.solutions().count();
+    let Ok(start) = state
+        .metadata::<StopOnObjectiveMetadata>()
+        .map(|meta| meta.solutions)
+    else {
+        state.add_metadata(StopOnObjectiveMetadata { solutions: count });
+        return Ok(None);
+    };
+    if count <= start {
+        return Ok(None);
+    }
+    let testcase = state
+        .solutions()
+        .get(state.solutions().nth(start))?
+        .borrow();
+    Ok(Some(stop_on_objective_exit_code(
+        testcase.metadata::<ExitKind>().ok(),
+    )))
+}
+
+/// A stage that exits the fuzzer with a status encoding the objective type
+/// (see [`stop_on_objective_exit_code`]) once the first objective was found.
+///
+/// Solutions already in the corpus when the stage first runs, e.g., from a previous campaign,
+/// do not count. Put it first among the stages, so that it starts counting before any mutation;
+/// an objective found in one iteration then stops the fuzzer at the start of the next.
+/// Before exiting, the stage broadcasts an [`Event::CustomBuf`] tagged [`STOP_ON_OBJECTIVE_TAG`];
+/// clients that called [`StopOnObjectiveStage::register`] on their event manager stop as well,
+/// so that the first crash anywhere halts the whole fleet.
+#[derive(Debug, Clone)]
+pub struct StopOnObjectiveStage<EM, Z> {
+    phantom: PhantomData<(EM, Z)>,
+}
+
+impl<EM, Z> UsesState for StopOnObjectiveStage<EM, Z>
+where
+    EM: UsesState,
+{
+    type State = EM::State;
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for StopOnObjectiveStage<EM, Z>
+where
+    E: UsesState<State = Self::State>,
+    EM: EventFirer + EventRestarter,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasSolutions + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        if let Ok(requested) = state.metadata::<StopRequestedMetadata>() {
+            let exit_code = requested.exit_code;
+            log::info!("Another client found an objective, stopping with status {exit_code}");
+            manager.send_exiting()?;
+            std::process::exit(exit_code);
+        }
+
+        let Some(exit_code) = new_objective_exit_code(state)? else {
+            return Ok(());
+        };
+
+        log::info!("Found a new objective, stopping with status {exit_code}");
+        manager.fire(
+            state,
+            Event::CustomBuf {
+                buf: exit_code.to_le_bytes().to_vec(),
+                tag: STOP_ON_OBJECTIVE_TAG.to_string(),
+            },
+        )?;
+        manager.send_exiting()?;
+        std::process::exit(exit_code);
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(())
+    }
+}
+
+impl<EM, Z> StopOnObjectiveStage<EM, Z> {
+    /// Creates a new [`StopOnObjectiveStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<EM, Z> StopOnObjectiveStage<EM, Z>
+where
+    EM: HasCustomBufHandlers,
+    EM::State: HasMetadata,
+{
+    /// Makes this client stop as well when any other client of the fleet finds an objective.
+    pub fn register(manager: &mut EM) {
+        manager.add_custom_buf_handler(Box::new(|state, tag, buf| {
+            if tag != STOP_ON_OBJECTIVE_TAG {
+                return Ok(CustomBufEventResult::Next);
+            }
+            let exit_code = buf
+                .try_into()
+                .map_or(STOP_ON_OBJECTIVE_EXIT_OTHER, i32::from_le_bytes);
+            state.add_metadata(StopRequestedMetadata { exit_code });
+            Ok(CustomBufEventResult::Handled)
+        }));
+    }
+}
+
+impl<EM, Z> Default for StopOnObjectiveStage<EM, Z> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{test::NopExecutor, ExitKind},
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        stages::{
+            stop_on_objective::{
+                is_stop_on_objective_exit_code, new_objective_exit_code,
+                stop_on_objective_exit_code, STOP_ON_OBJECTIVE_EXIT_CRASH,
+                STOP_ON_OBJECTIVE_EXIT_OOM, STOP_ON_OBJECTIVE_EXIT_OTHER,
+                STOP_ON_OBJECTIVE_EXIT_TIMEOUT,
+            },
+            Stage, StopOnObjectiveStage,
+        },
+        state::{test::test_std_state, HasSolutions, StdState},
+        HasMetadata,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    fn add_solution(state: &mut TestState, exit_kind: ExitKind) {
+        let mut testcase = Testcase::new(BytesInput::new(vec![0]));
+        testcase.add_metadata(exit_kind);
+        state.solutions_mut().add(testcase).unwrap();
+    }
+
+    #[test]
+    fn test_stop_on_objective_exit_code() {
+        for (exit_kind, exit_code) in [
+            (Some(&ExitKind::Crash), STOP_ON_OBJECTIVE_EXIT_CRASH),
+            (Some(&ExitKind::Timeout), STOP_ON_OBJECTIVE_EXIT_TIMEOUT),
+            (Some(&ExitKind::Oom), STOP_ON_OBJECTIVE_EXIT_OOM),
+            (Some(&ExitKind::Ok), STOP_ON_OBJECTIVE_EXIT_OTHER),
+            (None, STOP_ON_OBJECTIVE_EXIT_OTHER),
+        ] {
+            assert_eq!(stop_on_objective_exit_code(exit_kind), exit_code);
+            assert!(is_stop_on_objective_exit_code(exit_code));
+        }
+        for status in [
+            0,
+            1,
+            STOP_ON_OBJECTIVE_EXIT_CRASH - 1,
+            STOP_ON_OBJECTIVE_EXIT_OTHER + 1,
+        ] {
+            assert!(!is_stop_on_objective_exit_code(status));
+        }
+    }
+
+    #[test]
+    fn test_stop_on_objective_new_objectives_only() {
+        let mut state: TestState = test_std_state();
+        add_solution(&mut state, ExitKind::Crash);
+
+        // The solution of a previous campaign does not stop the fuzzer
+        let mut stage = StopOnObjectiveStage::new();
+        for _ in 0..2 {
+            stage
+                .perform(
+                    &mut NopFuzzer::new(),
+                    &mut NopExecutor::new(),
+                    &mut state,
+                    &mut NopEventManager::new(),
+                )
+                .unwrap();
+        }
+
+        // The first new objective decides the exit status
+        add_solution(&mut state, ExitKind::Timeout);
+        add_solution(&mut state, ExitKind::Crash);
+        assert_eq!(
+            new_objective_exit_code(&mut state).unwrap(),
+            Some(STOP_ON_OBJECTIVE_EXIT_TIMEOUT)
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! The [`TargetRotationStage`] switches a [`RotatingExecutor`] to its next target,
+//! so that all targets are fuzzed in turn.
+
+use core::marker::PhantomData;
+
+use crate::{
+    executors::{HasObservers, RotatingExecutor},
+    stages::Stage,
+    state::UsesState,
+    Error,
+};
+
+/// A stage switching the [`RotatingExecutor`] to its next target every `every` runs,
+/// so every `every` scheduled corpus entries if it is the first stage.
+/// The target only changes between executions, so the observers stay consistent.
+#[derive(Debug, Clone)]
+pub struct TargetRotationStage<EM, Z> {
+    every: usize,
+    runs: usize,
+    phantom: PhantomData<(EM, Z)>,
+}
+
+impl<EM, Z> TargetRotationStage<EM, Z> {
+    /// Creates a new [`TargetRotationStage`], rotating on every run
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_interval(1)
+    }
+
+    /// Creates a new [`TargetRotationStage`], rotating every `every` runs
+    #[must_use]
+    pub fn with_interval(every: usize) -> Self {
+        Self {
+            every: every.max(1),
+            runs: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<EM, Z> Default for TargetRotationStage<EM, Z> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<EM, Z> UsesState for TargetRotationStage<EM, Z>
+where
+    EM: UsesState,
+{
+    type State = EM::State;
+}
+
+impl<E, EM, Z> Stage<RotatingExecutor<E>, EM, Z> for TargetRotationStage<EM, Z>
+where
+    E: HasObservers<State = Self::State>,
+    EM: UsesState,
+    Z: UsesState<State = Self::State>,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        executor: &mut RotatingExecutor<E>,
+        _state: &mut Self::State,
+        _manager: &mut EM,
+    ) -> Result<(), Error> {
+        self.runs += 1;
+        if self.runs % self.every == 0 {
+            let target = executor.rotate();
+            log::debug!("Rotating to target {target}");
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::tuples::tuple_list;
+
+    use crate::{
+        events::NopEventManager,
+        executors::{test::NopExecutor, RotatingExecutor, WithObservers},
+        fuzzer::test::NopFuzzer,
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        stages::{Stage, TargetRotationStage},
+        state::NopState,
+    };
+
+    fn target(
+        name: &'static str,
+    ) -> WithObservers<NopExecutor<NopState<BytesInput>>, (StdMapObserver<'static, u8, false>, ())>
+    {
+        WithObservers::new(
+            NopExecutor::new(),
+            tuple_list!(StdMapObserver::owned(name, vec![0_u8; 4])),
+        )
+    }
+
+    #[test]
+    fn test_target_rotation_interval() {
+        let mut executor = RotatingExecutor::new(vec![
+            target("edges_0"),
+            target("edges_1"),
+            target("edges_2"),
+        ])
+        .unwrap();
+        let mut state = NopState::<BytesInput>::new();
+        let mut stage = TargetRotationStage::with_interval(2);
+        let mut targets = vec![];
+        for _ in 0..6 {
+            stage
+                .perform(
+                    &mut NopFuzzer::new(),
+                    &mut executor,
+                    &mut state,
+                    &mut NopEventManager::new(),
+                )
+                .unwrap();
+            targets.push(executor.current());
+        }
+        assert_eq!(targets, [0, 1, 1, 2, 2, 0]);
+
+        // An interval of zero rotates on every run
+        let mut stage = TargetRotationStage::with_interval(0);
+        for expected in [1, 2] {
+            stage
+                .perform(
+                    &mut NopFuzzer::new(),
+                    &mut executor,
+                    &mut state,
+                    &mut NopEventManager::new(),
+                )
+                .unwrap();
+            assert_eq!(executor.current(), expected);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! The [`ToggleableStage`] wraps a stage that can be enabled or disabled at runtime,
+//! e.g., to turn cmplog or deterministic stages on and off in a running campaign.
+
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use hashbrown::HashMap;
+use libafl_bolts::{impl_serdeany, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{CustomBufEventResult, Event, EventFirer, HasCustomBufHandlers},
+    stages::Stage,
+    state::UsesState,
+    Error, HasMetadata,
+};
+
+/// The tag of the [`Event::CustomBuf`] toggling a stage
+pub const STAGE_TOGGLE_TAG: &str = "libafl_stage_toggle";
+
+/// The stages enabled or disabled at runtime, overriding the defaults of their [`ToggleableStage`]s
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StageToggleMetadata {
+    toggles: HashMap<String, bool>,
+}
+
+impl_serdeany!(StageToggleMetadata);
+
+impl StageToggleMetadata {
+    /// If the stage with the given name was enabled or disabled at runtime, `None` if it was never toggled
+    #[must_use]
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.toggles.get(name).copied()
+    }
+
+    /// Enables or disables the stage with the given name
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        self.toggles.insert(name.to_string(), enabled);
+    }
+}
+
+/// Enables or disables the [`ToggleableStage`] with the given name in this client.
+pub fn set_stage_enabled<S>(state: &mut S, name: &str, enabled: bool)
+where
+    S: HasMetadata,
+{
+    state
+        .metadata_or_insert_with(StageToggleMetadata::default)
+        .set_enabled(name, enabled);
+}
+
+/// Enables or disables the [`ToggleableStage`] with the given name in this client,
+/// and broadcasts an [`Event::CustomBuf`] tagged [`STAGE_TOGGLE_TAG`] so that all clients
+/// that called [`register_stage_toggles`] on their event manager follow.
+pub fn toggle_stage<EM>(
+    manager: &mut EM,
+    state: &mut EM::State,
+    name: &str,
+    enabled: bool,
+) -> Result<(), Error>
+where
+    EM: EventFirer,
+    EM::State: HasMetadata,
+{
+    set_stage_enabled(state, name, enabled);
+    let mut buf = Vec::with_capacity(name.len() + 1);
+    buf.push(u8::from(enabled));
+    buf.extend_from_slice(name.as_bytes());
+    manager.fire(
+        state,
+        Event::CustomBuf {
+            buf,
+            tag: STAGE_TOGGLE_TAG.to_string(),
+        },
+    )
+}
+
+/// Makes this client honor the stage toggles sent by [`toggle_stage`].
+pub fn register_stage_toggles<EM>(manager: &mut EM)
+where
+    EM: HasCustomBufHandlers,
+    EM::State: HasMetadata,
+{
+    manager.add_custom_buf_handler(Box::new(|state, tag, buf| {
+        if tag != STAGE_TOGGLE_TAG {
+            return Ok(CustomBufEventResult::Next);
+        }
+        let Some((enabled, name)) = buf.split_first() else {
+            return Err(Error::illegal_argument("Empty stage toggle event"));
+        };
+        let name = core::str::from_utf8(name)
+            .map_err(|err| Error::illegal_argument(format!("Invalid stage name: {err}")))?;
+        log::info!(
+            "Stage {name} {} at runtime",
+            if *enabled == 0 { "disabled" } else { "enabled" }
+        );
+        set_stage_enabled(state, name, *enabled != 0);
+        Ok(CustomBufEventResult::Handled)
+    }));
+}
+
+/// A stage that only runs the wrapped stage while it is enabled.
+/// Whether it is enabled is checked at the start of each [`Stage::perform`], from the
+/// [`StageToggleMetadata`] in the state, falling back to the default given on creation.
+///
+/// Toggle it by name with [`set_stage_enabled`] or, for all clients, with [`toggle_stage`].
+#[derive(Debug, Clone)]
+pub struct ToggleableStage<ST> {
+    name: Cow<'static, str>,
+    stage: ST,
+    enabled_by_default: bool,
+}
+
+impl<ST> ToggleableStage<ST> {
+    /// Creates a new [`ToggleableStage`], enabled unless toggled otherwise
+    #[must_use]
+    pub fn new(name: &str, stage: ST) -> Self {
+        Self {
+            name: Cow::Owned(name.to_string()),
+            stage,
+            enabled_by_default: true,
+        }
+    }
+
+    /// Keeps the stage disabled until it gets enabled at runtime
+    #[must_use]
+    pub fn disabled(mut self) -> Self {
+        self.enabled_by_default = false;
+        self
+    }
+
+    /// The wrapped stage
+    pub fn inner(&self) -> &ST {
+        &self.stage
+    }
+
+    /// The wrapped stage (mutable)
+    pub fn inner_mut(&mut self) -> &mut ST {
+        &mut self.stage
+    }
+
+    /// If the stage is currently enabled
+    pub fn is_enabled<S>(&self, state: &S) -> bool
+    where
+        S: HasMetadata,
+    {
+        state
+            .metadata::<StageToggleMetadata>()
+            .ok()
+            .and_then(|meta| meta.is_enabled(&self.name))
+            .unwrap_or(self.enabled_by_default)
+    }
+}
+
+impl<ST> Named for ToggleableStage<ST> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<ST> UsesState for ToggleableStage<ST>
+where
+    ST: UsesState,
+{
+    type State = ST::State;
+}
+
+impl<E, EM, ST, Z> Stage<E, EM, Z> for ToggleableStage<ST>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    ST: Stage<E, EM, Z>,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasMetadata,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        if self.is_enabled(state) {
+            self.stage.perform(fuzzer, executor, state, manager)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn restart_progress_should_run(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        self.stage.restart_progress_should_run(state)
+    }
+
+    #[inline]
+    fn clear_restart_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        self.stage.clear_restart_progress(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::serdeany::SerdeAnyMap;
+
+    use crate::{
+        stages::toggle::{set_stage_enabled, StageToggleMetadata, ToggleableStage},
+        HasMetadata,
+    };
+
+    struct MetadataOnly(SerdeAnyMap);
+
+    impl HasMetadata for MetadataOnly {
+        fn metadata_map(&self) -> &SerdeAnyMap {
+            &self.0
+        }
+
+        fn metadata_map_mut(&mut self) -> &mut SerdeAnyMap {
+            &mut self.0
+        }
+    }
+
+    #[test]
+    fn test_stage_toggle() {
+        let mut state = MetadataOnly(SerdeAnyMap::new());
+        let cmplog = ToggleableStage::new("cmplog", ());
+        let deterministic = ToggleableStage::new("deterministic", ()).disabled();
+        assert!(cmplog.is_enabled(&state));
+        assert!(!deterministic.is_enabled(&state));
+
+        set_stage_enabled(&mut state, "cmplog", false);
+        set_stage_enabled(&mut state, "deterministic", true);
+        assert!(!cmplog.is_enabled(&state));
+        assert!(deterministic.is_enabled(&state));
+        assert_eq!(
+            state
+                .metadata::<StageToggleMetadata>()
+                .unwrap()
+                .is_enabled("other"),
+            None
+        );
+    }
+}
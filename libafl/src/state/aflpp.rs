@@ -0,0 +1,232 @@
+//! Parsing of the output directory of `AFL++`, to resume fuzzing from its queue, crashes and stats,
+//! see [`crate::state::StdState::resume_from_aflpp`].
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::time::Duration;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::impl_serdeany;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// The approximate stats of an `AFL++` run, as read from its `fuzzer_stats` file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AflppStats {
+    /// When the run started, since the unix epoch
+    pub start_time: Duration,
+    /// When the stats were last written, since the unix epoch
+    pub last_update: Duration,
+    /// The number of executions
+    pub execs_done: u64,
+    /// The number of completed queue cycles
+    pub cycles_done: u64,
+    /// The number of entries in the queue
+    pub corpus_count: u64,
+    /// The number of saved crashes
+    pub saved_crashes: u64,
+    /// The number of saved hangs
+    pub saved_hangs: u64,
+    /// The version of `AFL++` that wrote the stats
+    pub afl_version: String,
+}
+
+impl AflppStats {
+    /// Parses the `key : value` lines of a `fuzzer_stats` file. Unknown keys are ignored,
+    /// missing ones are left at their default.
+    pub fn parse(stats: &str) -> Result<Self, Error> {
+        let parse_num = |key: &str, value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| Error::illegal_argument(format!("Invalid {key} {value:?}")))
+        };
+        let mut parsed = Self::default();
+        for line in stats.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "start_time" => parsed.start_time = Duration::from_secs(parse_num(key, value)?),
+                "last_update" => parsed.last_update = Duration::from_secs(parse_num(key, value)?),
+                "execs_done" => parsed.execs_done = parse_num(key, value)?,
+                "cycles_done" => parsed.cycles_done = parse_num(key, value)?,
+                "corpus_count" | "paths_total" => parsed.corpus_count = parse_num(key, value)?,
+                // `AFL++` before 4.0 calls them unique crashes and hangs
+                "saved_crashes" | "unique_crashes" => {
+                    parsed.saved_crashes = parse_num(key, value)?;
+                }
+                "saved_hangs" | "unique_hangs" => parsed.saved_hangs = parse_num(key, value)?,
+                "afl_version" => parsed.afl_version = value.to_string(),
+                _ => {}
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Reads and parses a `fuzzer_stats` file
+    pub fn from_file<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+}
+
+/// The metadata `AFL++` encodes in the filename of queue and crash entries,
+/// such as `id:000012,src:000003+000007,time:1234,execs:5678,op:splice,rep:2,+cov`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct AflppEntryMetadata {
+    /// The id of the entry
+    pub id: u64,
+    /// The ids of the entries it was derived from, two for splicing
+    pub src: Vec<u64>,
+    /// The original filename of an initial seed
+    pub orig: Option<String>,
+    /// The signal the target crashed with, for crashes
+    pub sig: Option<i32>,
+    /// When it was found, relative to the start of the run
+    pub time: Option<Duration>,
+    /// The executions when it was found
+    pub execs: Option<u64>,
+    /// The mutation operator that produced it
+    pub op: Option<String>,
+    /// If it covered new edges, rather than only new hit counts
+    pub new_cov: bool,
+}
+
+impl_serdeany!(AflppEntryMetadata);
+
+impl AflppEntryMetadata {
+    /// Parses an `AFL++` entry filename, `None` if it does not start with an `id`
+    #[must_use]
+    pub fn parse_filename(filename: &str) -> Option<Self> {
+        let mut fields = filename.split(',');
+        let id = fields.next()?.strip_prefix("id:")?.parse().ok()?;
+        let mut meta = Self {
+            id,
+            ..Self::default()
+        };
+        for field in fields {
+            if field == "+cov" {
+                meta.new_cov = true;
+                continue;
+            }
+            let Some((key, value)) = field.split_once(':') else {
+                continue;
+            };
+            match key {
+                "src" => meta.src = value.split('+').filter_map(|id| id.parse().ok()).collect(),
+                "orig" => meta.orig = Some(value.to_string()),
+                "sig" => meta.sig = value.parse().ok(),
+                "time" => meta.time = value.parse().ok().map(Duration::from_millis),
+                "execs" => meta.execs = value.parse().ok(),
+                "op" => meta.op = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Some(meta)
+    }
+}
+
+/// The instance directory of an `AFL++` output directory: `out_dir` itself if it holds a `queue`,
+/// else its `default` instance, as created by `afl-fuzz -o out_dir` without `-M` or `-S`
+pub fn aflpp_instance_dir(out_dir: &Path) -> Result<PathBuf, Error> {
+    [out_dir.to_path_buf(), out_dir.join("default")]
+        .into_iter()
+        .find(|dir| dir.join("queue").is_dir())
+        .ok_or_else(|| {
+            Error::illegal_argument(format!(
+                "{} is not an AFL++ output directory, it has no queue",
+                out_dir.display()
+            ))
+        })
+}
+
+/// The entry files in `dir`, sorted by name, so by id. Hidden files, such as the `.state`
+/// directory, the `README.txt` of the crashes, and directories are skipped.
+pub fn aflpp_entries(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut entries = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name == "README.txt" || !entry.file_type()?.is_file() {
+            continue;
+        }
+        entries.push(entry.path());
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::state::aflpp::{AflppEntryMetadata, AflppStats};
+
+    #[test]
+    fn test_parse_aflpp_stats() {
+        let stats = AflppStats::parse(
+            "start_time        : 1700000000\n\
+             last_update       : 1700003600\n\
+             run_time          : 3600\n\
+             fuzzer_pid        : 4242\n\
+             cycles_done       : 3\n\
+             execs_done        : 1234567\n\
+             execs_per_sec     : 342.93\n\
+             corpus_count      : 421\n\
+             saved_crashes     : 2\n\
+             saved_hangs       : 1\n\
+             afl_version       : ++4.21c\n\
+             command_line      : afl-fuzz -i in -o out -- ./target @@\n",
+        )
+        .unwrap();
+        assert_eq!(stats.start_time, Duration::from_secs(1_700_000_000));
+        assert_eq!(stats.execs_done, 1_234_567);
+        assert_eq!(stats.cycles_done, 3);
+        assert_eq!(stats.corpus_count, 421);
+        assert_eq!(stats.saved_crashes, 2);
+        assert_eq!(stats.afl_version, "++4.21c");
+        assert!(AflppStats::parse("execs_done : many").is_err());
+    }
+
+    #[test]
+    fn test_parse_aflpp_filename() {
+        let meta = AflppEntryMetadata::parse_filename(
+            "id:000012,src:000003+000007,time:1234,execs:5678,op:splice,rep:2,+cov",
+        )
+        .unwrap();
+        assert_eq!(meta.id, 12);
+        assert_eq!(meta.src, [3, 7]);
+        assert_eq!(meta.time, Some(Duration::from_millis(1234)));
+        assert_eq!(meta.execs, Some(5678));
+        assert_eq!(meta.op.as_deref(), Some("splice"));
+        assert!(meta.new_cov);
+
+        let seed =
+            AflppEntryMetadata::parse_filename("id:000000,time:0,execs:0,orig:seed.bin").unwrap();
+        assert_eq!(seed.orig.as_deref(), Some("seed.bin"));
+
+        let crash = AflppEntryMetadata::parse_filename(
+            "id:000001,sig:11,src:000012,time:99,execs:100,op:havoc,rep:4",
+        )
+        .unwrap();
+        assert_eq!(crash.sig, Some(11));
+        assert!(AflppEntryMetadata::parse_filename("seed.bin").is_none());
+    }
+}
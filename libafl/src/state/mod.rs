@@ -1,6 +1,9 @@
 //! The fuzzer, and state are the core pieces of every good fuzzer
 
 #[cfg(feature = "std")]
+use alloc::borrow::Cow;
+#[cfg(feature = "tar")]
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::{
     borrow::BorrowMut,
@@ -14,7 +17,14 @@ use std::{
     fs,
     path::{Path, PathBuf},
 };
+#[cfg(feature = "tar")]
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+};
 
+#[cfg(feature = "std")]
+use aflpp::{aflpp_entries, aflpp_instance_dir};
 #[cfg(feature = "std")]
 use libafl_bolts::core_affinity::{CoreId, Cores};
 use libafl_bolts::{
@@ -23,6 +33,10 @@ use libafl_bolts::{
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+#[cfg(feature = "std")]
+pub mod aflpp;
+#[cfg(feature = "std")]
+pub use aflpp::{AflppEntryMetadata, AflppStats};
 mod stack;
 pub use stack::StageStack;
 
@@ -30,10 +44,12 @@ pub use stack::StageStack;
 use crate::monitors::ClientPerfMonitor;
 #[cfg(feature = "scalability_introspection")]
 use crate::monitors::ScalabilityMonitor;
+#[cfg(feature = "std")]
+use crate::monitors::{AggregatorOps, UserStats, UserStatsValue};
 use crate::{
     corpus::{Corpus, CorpusId, HasCurrentCorpusId, HasTestcase, Testcase},
     events::{Event, EventFirer, LogSeverity},
-    feedbacks::Feedback,
+    feedbacks::{coverage_snapshot::coverage_ranking, Feedback, SeedLoadingMetadata},
     fuzzer::{Evaluator, ExecuteInputResult},
     generators::Generator,
     inputs::{Input, UsesInput},
@@ -206,6 +222,43 @@ pub struct LoadConfig<'a, I, S, Z> {
     loader: &'a mut dyn FnMut(&mut Z, &mut S, &Path) -> Result<I, Error>,
     /// Error if Input leads to a Solution.
     exit_on_solution: bool,
+    /// Drop uninteresting inputs, instead of adding them as disabled
+    dedup: bool,
+    /// The number of inputs dropped as uninteresting
+    deduplicated: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a, I, S, Z> LoadConfig<'a, I, S, Z> {
+    /// Loads the inputs with `loader`, adding uninteresting inputs as disabled
+    /// and continuing on solutions
+    fn new(loader: &'a mut dyn FnMut(&mut Z, &mut S, &Path) -> Result<I, Error>) -> Self {
+        Self {
+            forced: false,
+            loader,
+            exit_on_solution: false,
+            dedup: false,
+            deduplicated: 0,
+        }
+    }
+
+    /// Loads inputs even if they are deemed uninteresting
+    fn forced(mut self) -> Self {
+        self.forced = true;
+        self
+    }
+
+    /// Errors if an input leads to a solution
+    fn exit_on_solution(mut self) -> Self {
+        self.exit_on_solution = true;
+        self
+    }
+
+    /// Drops uninteresting inputs, instead of adding them as disabled
+    fn dedup(mut self) -> Self {
+        self.dedup = true;
+        self
+    }
 }
 
 #[cfg(feature = "std")]
@@ -256,6 +309,9 @@ pub struct StdState<I, C, R, SC> {
     /// If inputs have been processed for multicore loading
     /// relevant only for `load_initial_inputs_multicore`
     multicore_inputs_processed: Option<bool>,
+    #[cfg(feature = "std")]
+    /// The seed to shuffle the initial inputs with before loading them, if any
+    initial_inputs_shuffle_seed: Option<u64>,
     /// The last time we reported progress (if available/used).
     /// This information is used by fuzzer `maybe_report_progress`.
     last_report_time: Option<Duration>,
@@ -620,6 +676,46 @@ where
         }
     }
 
+    /// Loads the initial inputs in a pseudo-random order derived from `seed`, or in directory order for `None`.
+    ///
+    /// The same seed and the same set of files always give the same order,
+    /// which helps to reproduce bugs depending on the load order, e.g., in deduplication or calibration.
+    /// This only affects the order initial inputs are loaded in, not the randomness of the fuzzing itself.
+    /// Call it before any of the `load_initial_inputs` methods.
+    pub fn set_initial_inputs_shuffle_seed(&mut self, seed: Option<u64>) {
+        self.initial_inputs_shuffle_seed = seed;
+    }
+
+    /// The seed the initial inputs are shuffled with, if any
+    #[must_use]
+    pub fn initial_inputs_shuffle_seed(&self) -> Option<u64> {
+        self.initial_inputs_shuffle_seed
+    }
+
+    /// Expands all remaining initial inputs and shuffles them, if a shuffle seed is set.
+    fn shuffle_initial_files(&mut self) -> Result<(), Error> {
+        let Some(seed) = self.initial_inputs_shuffle_seed else {
+            return Ok(());
+        };
+        let mut files = vec![];
+        loop {
+            match self.next_file() {
+                Ok(path) => files.push(path),
+                Err(Error::IteratorEnd(_, _)) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        // Sort first, so the order does not depend on the order the file system lists the directories in
+        files.sort();
+        let mut rand = StdRand::with_seed(seed);
+        for i in (1..files.len()).rev() {
+            files.swap(i, rand.below(i + 1));
+        }
+        log::info!("Shuffled {} initial inputs with seed {seed}", files.len());
+        self.remaining_initial_files = Some(files);
+        Ok(())
+    }
+
     /// Resets the state of initial files.
     fn reset_initial_files_state(&mut self) {
         self.remaining_initial_files = None;
@@ -642,6 +738,7 @@ where
             })?;
             self.dont_reenter = Some(files.clone());
             self.remaining_initial_files = Some(files);
+            self.shuffle_initial_files()?;
         }
         Ok(())
     }
@@ -669,6 +766,7 @@ where
             }
         } else {
             self.remaining_initial_files = Some(file_list.to_vec());
+            self.shuffle_initial_files()?;
         }
 
         self.continue_loading_initial_inputs_custom(fuzzer, executor, manager, load_config)
@@ -695,21 +793,25 @@ where
         } else {
             let (res, _) = fuzzer.evaluate_input(self, executor, manager, input.clone())?;
             if res == ExecuteInputResult::None {
-                fuzzer.add_disabled_input(self, input)?;
-                log::warn!("input {:?} was not interesting, adding as disabled.", &path);
+                if config.dedup {
+                    config.deduplicated += 1;
+                    log::debug!("input {:?} adds no new coverage, dropping it.", &path);
+                } else {
+                    fuzzer.add_disabled_input(self, input)?;
+                    log::warn!("input {:?} was not interesting, adding as disabled.", &path);
+                }
             }
             Ok(res)
         }
     }
-    /// Loads initial inputs from the passed-in `in_dirs`.
-    /// This method takes a list of files and a `LoadConfig`
-    /// which specifies the special handling of initial inputs
-    fn continue_loading_initial_inputs_custom<E, EM, Z>(
+
+    /// Loads the remaining initial files
+    fn load_remaining_initial_files<E, EM, Z>(
         &mut self,
         fuzzer: &mut Z,
         executor: &mut E,
         manager: &mut EM,
-        mut config: LoadConfig<I, Self, Z>,
+        config: &mut LoadConfig<I, Self, Z>,
     ) -> Result<(), Error>
     where
         E: UsesState<State = Self>,
@@ -719,7 +821,7 @@ where
         loop {
             match self.next_file() {
                 Ok(path) => {
-                    let res = self.load_file(&path, manager, fuzzer, executor, &mut config)?;
+                    let res = self.load_file(&path, manager, fuzzer, executor, config)?;
                     if config.exit_on_solution && matches!(res, ExecuteInputResult::Solution) {
                         return Err(Error::invalid_corpus(format!(
                             "Input {} resulted in a solution.",
@@ -727,10 +829,31 @@ where
                         )));
                     }
                 }
-                Err(Error::IteratorEnd(_, _)) => break,
+                Err(Error::IteratorEnd(_, _)) => return Ok(()),
                 Err(e) => return Err(e),
             }
         }
+    }
+
+    /// Loads initial inputs from the passed-in `in_dirs`.
+    /// This method takes a list of files and a `LoadConfig`
+    /// which specifies the special handling of initial inputs
+    fn continue_loading_initial_inputs_custom<E, EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        mut config: LoadConfig<I, Self, Z>,
+    ) -> Result<(), Error>
+    where
+        E: UsesState<State = Self>,
+        EM: EventFirer<State = Self>,
+        Z: Evaluator<E, EM, State = Self>,
+    {
+        self.set_loading_seeds(true);
+        let res = self.load_remaining_initial_files(fuzzer, executor, manager, &mut config);
+        self.set_loading_seeds(false);
+        res?;
 
         manager.fire(
             self,
@@ -740,6 +863,30 @@ where
                 phantom: PhantomData::<I>,
             },
         )?;
+        if config.dedup {
+            manager.fire(
+                self,
+                Event::Log {
+                    severity_level: LogSeverity::Info,
+                    message: format!(
+                        "Dropped {} initial inputs without new coverage.",
+                        config.deduplicated
+                    ),
+                    phantom: PhantomData::<I>,
+                },
+            )?;
+            manager.fire(
+                self,
+                Event::UpdateUserStats {
+                    name: Cow::Borrowed("seeds_deduplicated"),
+                    value: UserStats::new(
+                        UserStatsValue::Number(config.deduplicated as u64),
+                        AggregatorOps::Sum,
+                    ),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
         Ok(())
     }
 
@@ -764,11 +911,7 @@ where
             executor,
             manager,
             file_list,
-            LoadConfig {
-                loader: &mut |_, _, path| I::from_file(path),
-                forced: false,
-                exit_on_solution: false,
-            },
+            LoadConfig::new(&mut |_, _, path| I::from_file(path)),
         )
     }
 
@@ -792,11 +935,7 @@ where
             fuzzer,
             executor,
             manager,
-            LoadConfig {
-                loader: &mut |_, _, path| I::from_file(path),
-                forced: true,
-                exit_on_solution: false,
-            },
+            LoadConfig::new(&mut |_, _, path| I::from_file(path)).forced(),
         )
     }
     /// Loads initial inputs from the passed-in `in_dirs`.
@@ -819,11 +958,7 @@ where
             executor,
             manager,
             file_list,
-            LoadConfig {
-                loader: &mut |_, _, path| I::from_file(path),
-                forced: true,
-                exit_on_solution: false,
-            },
+            LoadConfig::new(&mut |_, _, path| I::from_file(path)).forced(),
         )
     }
 
@@ -845,11 +980,32 @@ where
             fuzzer,
             executor,
             manager,
-            LoadConfig {
-                loader: &mut |_, _, path| I::from_file(path),
-                forced: false,
-                exit_on_solution: false,
-            },
+            LoadConfig::new(&mut |_, _, path| I::from_file(path)),
+        )
+    }
+
+    /// Loads initial inputs from the passed-in `in_dirs`, deduplicating them by coverage:
+    /// each input is run once and only kept if the feedback deems it interesting, i.e., if it adds
+    /// new coverage relative to the inputs loaded before. Redundant inputs are dropped instead of
+    /// being added as disabled, and their number is reported as the `seeds_deduplicated` user stat.
+    pub fn load_initial_inputs_dedup<E, EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        in_dirs: &[PathBuf],
+    ) -> Result<(), Error>
+    where
+        E: UsesState<State = Self>,
+        EM: EventFirer<State = Self>,
+        Z: Evaluator<E, EM, State = Self>,
+    {
+        self.canonicalize_input_dirs(in_dirs)?;
+        self.continue_loading_initial_inputs_custom(
+            fuzzer,
+            executor,
+            manager,
+            LoadConfig::new(&mut |_, _, path| I::from_file(path)).dedup(),
         )
     }
 
@@ -872,14 +1028,199 @@ where
             fuzzer,
             executor,
             manager,
-            LoadConfig {
-                loader: &mut |_, _, path| I::from_file(path),
-                forced: false,
-                exit_on_solution: true,
-            },
+            LoadConfig::new(&mut |_, _, path| I::from_file(path)).exit_on_solution(),
         )
     }
 
+    /// Loads initial inputs from a tar archive, optionally gzip or zstd compressed.
+    /// The archive is streamed, nested directories are flattened and hidden or empty entries are skipped.
+    /// Each entry goes through the same evaluation path as inputs loaded from a directory,
+    /// using a single scratch file in the temp directory for [`Input::from_file`].
+    #[cfg(feature = "tar")]
+    pub fn load_initial_inputs_from_archive<E, EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        archive: &Path,
+    ) -> Result<(), Error>
+    where
+        E: UsesState<State = Self>,
+        EM: EventFirer<State = Self>,
+        Z: Evaluator<E, EM, State = Self>,
+    {
+        let mut reader = BufReader::new(File::open(archive)?);
+        let (is_gzip, is_zstd) = {
+            let magic = reader.fill_buf()?;
+            (
+                magic.starts_with(&[0x1f, 0x8b]),
+                magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]),
+            )
+        };
+        let reader: Box<dyn Read> = if is_gzip {
+            Box::new(flate2::read::GzDecoder::new(reader))
+        } else if is_zstd {
+            #[cfg(feature = "zstd")]
+            {
+                Box::new(zstd::stream::read::Decoder::with_buffer(reader)?)
+            }
+            #[cfg(not(feature = "zstd"))]
+            {
+                return Err(Error::unsupported(format!(
+                    "Archive {} is zstd compressed, enable the `zstd` feature to load it",
+                    archive.display()
+                )));
+            }
+        } else {
+            Box::new(reader)
+        };
+
+        let scratch =
+            std::env::temp_dir().join(format!(".libafl_archive_entry.{}", std::process::id()));
+        let mut config = LoadConfig::new(&mut |_, _, path| I::from_file(path));
+
+        self.set_loading_seeds(true);
+        let load_entries = || -> Result<(), Error> {
+            let mut bytes = vec![];
+            for entry in tar::Archive::new(reader).entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() || entry.size() == 0 {
+                    continue;
+                }
+                let path = entry.path()?.into_owned();
+                if path
+                    .file_name()
+                    .map_or(true, |name| name.to_string_lossy().starts_with('.'))
+                {
+                    continue;
+                }
+
+                bytes.clear();
+                entry.read_to_end(&mut bytes)?;
+                fs::write(&scratch, &bytes)?;
+                log::info!("Loading archive entry {:?} ...", &path);
+                self.load_file(&scratch, manager, fuzzer, executor, &mut config)?;
+            }
+            Ok(())
+        };
+        let res = load_entries();
+        self.set_loading_seeds(false);
+        drop(fs::remove_file(&scratch));
+        res?;
+
+        manager.fire(
+            self,
+            Event::Log {
+                severity_level: LogSeverity::Debug,
+                message: format!("Loaded {} initial testcases.", self.corpus().count()), // get corpus count
+                phantom: PhantomData::<I>,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Exports a snapshot of all (enabled) corpus entries to a tar archive, without stopping the campaign.
+    /// The archive is zstd compressed if `archive` ends in `.zst` or `.tzst`, gzip compressed
+    /// if it ends in `.gz` or `.tgz`, and uncompressed otherwise.
+    /// Entries keep the filenames of their testcases, if they have any.
+    /// The ids are collected upfront and the archive is written to a temporary file first,
+    /// synced to disk and renamed, so readers only ever observe a complete, consistent snapshot.
+    /// Returns the number of exported entries.
+    #[cfg(feature = "tar")]
+    pub fn export_corpus_to_archive(&self, archive: &Path) -> Result<usize, Error> {
+        let ids: Vec<CorpusId> = self.corpus().ids().collect();
+        let extension = archive
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let mut tmp_name = archive.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp = PathBuf::from(tmp_name);
+
+        let export = || -> Result<usize, Error> {
+            let file = File::create(&tmp)?;
+            match extension.as_str() {
+                "zst" | "tzst" => {
+                    #[cfg(feature = "zstd")]
+                    {
+                        let mut builder =
+                            tar::Builder::new(zstd::stream::write::Encoder::new(file, 0)?);
+                        let count = self.append_corpus_entries(&mut builder, &ids)?;
+                        builder.into_inner()?.finish()?.sync_all()?;
+                        Ok(count)
+                    }
+                    #[cfg(not(feature = "zstd"))]
+                    {
+                        drop(file);
+                        Err(Error::unsupported(format!(
+                            "Cannot export to {}, enable the `zstd` feature for zstd compressed archives",
+                            archive.display()
+                        )))
+                    }
+                }
+                "gz" | "tgz" => {
+                    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+                        file,
+                        flate2::Compression::default(),
+                    ));
+                    let count = self.append_corpus_entries(&mut builder, &ids)?;
+                    builder.into_inner()?.finish()?.sync_all()?;
+                    Ok(count)
+                }
+                _ => {
+                    let mut builder = tar::Builder::new(file);
+                    let count = self.append_corpus_entries(&mut builder, &ids)?;
+                    builder.into_inner()?.sync_all()?;
+                    Ok(count)
+                }
+            }
+        };
+        match export() {
+            Ok(count) => {
+                fs::rename(&tmp, archive)?;
+                log::info!("Exported {count} corpus entries to {}", archive.display());
+                Ok(count)
+            }
+            Err(err) => {
+                drop(fs::remove_file(&tmp));
+                Err(err)
+            }
+        }
+    }
+
+    /// Appends the inputs of the given corpus ids to the archive `builder`,
+    /// each serialized in memory as [`Input::to_file`] would write it.
+    #[cfg(feature = "tar")]
+    fn append_corpus_entries<W>(
+        &self,
+        builder: &mut tar::Builder<W>,
+        ids: &[CorpusId],
+    ) -> Result<usize, Error>
+    where
+        W: Write,
+    {
+        let mut count = 0;
+        for &id in ids {
+            let input = self.corpus().cloned_input_for_id(id)?;
+            let name = self
+                .corpus()
+                .get(id)?
+                .borrow()
+                .filename()
+                .clone()
+                .unwrap_or_else(|| input.generate_name(id.0));
+            let bytes = input.to_file_bytes()?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(libafl_bolts::current_time().as_secs());
+            builder.append_data(&mut header, &name, bytes.as_slice())?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
     fn calculate_corpus_size(&mut self) -> Result<usize, Error> {
         let mut count: usize = 0;
         loop {
@@ -914,11 +1255,7 @@ where
                 fuzzer,
                 executor,
                 manager,
-                LoadConfig {
-                    loader: &mut |_, _, path| I::from_file(path),
-                    forced: false,
-                    exit_on_solution: false,
-                },
+                LoadConfig::new(&mut |_, _, path| I::from_file(path)),
             )?;
         } else {
             self.canonicalize_input_dirs(in_dirs)?;
@@ -981,6 +1318,151 @@ where
         }
         Ok(())
     }
+
+    /// Loads a deterministic partition of the initial inputs from the passed-in `in_dirs`,
+    /// so that parallel clients do not all load (and calibrate) the same seeds.
+    ///
+    /// All initial files are sorted by path, and the client with index `client_index` loads
+    /// each file whose position modulo `num_clients` equals its index.
+    /// With at least as many seeds as clients, every seed is thus loaded by exactly one client,
+    /// independent of the order the file system lists the directories in.
+    /// With fewer seeds than clients, the surplus clients additionally load one seed each,
+    /// so that no client starts with an empty corpus: these seeds are loaded by several clients.
+    pub fn load_initial_inputs_partitioned<E, EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        in_dirs: &[PathBuf],
+        client_index: usize,
+        num_clients: usize,
+    ) -> Result<(), Error>
+    where
+        E: UsesState<State = Self>,
+        EM: EventFirer<State = Self>,
+        Z: Evaluator<E, EM, State = Self>,
+    {
+        if client_index >= num_clients {
+            return Err(Error::illegal_argument(format!(
+                "Client index {client_index} is out of range for {num_clients} clients"
+            )));
+        }
+        if !self.multicore_inputs_processed.unwrap_or(false) {
+            self.canonicalize_input_dirs(in_dirs)?;
+            let mut files = vec![];
+            loop {
+                match self.next_file() {
+                    Ok(path) => files.push(path),
+                    Err(Error::IteratorEnd(_, _)) => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            files.sort();
+            let partition = partition_initial_files(files, client_index, num_clients);
+            log::info!(
+                "client {client_index} of {num_clients} loads {} initial inputs",
+                partition.len()
+            );
+            self.reset_initial_files_state();
+            self.remaining_initial_files = Some(partition);
+            self.multicore_inputs_processed = Some(true);
+        }
+        self.continue_loading_initial_inputs_custom(
+            fuzzer,
+            executor,
+            manager,
+            LoadConfig::new(&mut |_, _, path| I::from_file(path)),
+        )
+    }
+
+    /// Resumes from the output directory of an `AFL++` run, for users migrating from `AFL++`.
+    /// `out_dir` is the directory passed to `afl-fuzz -o`, or one of its instance directories.
+    ///
+    /// All entries of its `queue` are added to the corpus, even if they are not considered
+    /// `interesting`, with the [`AflppEntryMetadata`] parsed from their filename.
+    /// The `crashes` are added to the solutions without running them again.
+    /// If there is a `fuzzer_stats` file, the executions of the `AFL++` run are added to the
+    /// executions and its start time is adopted. Returns the parsed stats, if any.
+    pub fn resume_from_aflpp<E, EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        out_dir: &Path,
+    ) -> Result<Option<AflppStats>, Error>
+    where
+        E: UsesState<State = Self>,
+        EM: EventFirer<State = Self>,
+        Z: Evaluator<E, EM, State = Self>,
+    {
+        let instance_dir = aflpp_instance_dir(out_dir)?;
+        let parse_entry = |path: &Path| {
+            path.file_name()
+                .and_then(|name| AflppEntryMetadata::parse_filename(&name.to_string_lossy()))
+        };
+
+        let queue = aflpp_entries(&instance_dir.join("queue"))?;
+        for path in &queue {
+            let input = I::from_file(path)?;
+            let id = fuzzer.add_input(self, executor, manager, input)?;
+            if let Some(meta) = parse_entry(path) {
+                self.corpus().get(id)?.borrow_mut().add_metadata(meta);
+            }
+        }
+
+        let crashes = aflpp_entries(&instance_dir.join("crashes"))?;
+        for path in &crashes {
+            let mut testcase = Testcase::new(I::from_file(path)?);
+            if let Some(meta) = parse_entry(path) {
+                testcase.add_metadata(meta);
+            }
+            self.solutions_mut().add(testcase)?;
+        }
+
+        let stats_file = instance_dir.join("fuzzer_stats");
+        let stats = if stats_file.is_file() {
+            let stats = AflppStats::from_file(&stats_file)?;
+            *self.executions_mut() += stats.execs_done;
+            if stats.start_time > Duration::ZERO {
+                *self.start_time_mut() = stats.start_time;
+            }
+            Some(stats)
+        } else {
+            None
+        };
+
+        log::info!(
+            "Resumed from the AFL++ output in {}: {} queue entries, {} crashes",
+            instance_dir.display(),
+            queue.len(),
+            crashes.len()
+        );
+        Ok(stats)
+    }
+}
+
+/// Selects the files at positions equal to `client_index` modulo `num_clients`.
+/// If that leaves nothing, picks a single file, so every client gets at least one seed,
+/// even though that file is then also selected for another client.
+#[cfg(feature = "std")]
+fn partition_initial_files(
+    files: Vec<PathBuf>,
+    client_index: usize,
+    num_clients: usize,
+) -> Vec<PathBuf> {
+    if files.is_empty() {
+        return files;
+    }
+    if client_index >= files.len() {
+        let fallback = client_index % files.len();
+        return files.into_iter().skip(fallback).take(1).collect();
+    }
+    files
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| i % num_clients == client_index)
+        .map(|(_, path)| path)
+        .collect()
 }
 
 impl<C, I, R, SC> StdState<I, C, R, SC>
@@ -990,6 +1472,14 @@ where
     R: Rand,
     SC: Corpus<Input = <Self as UsesInput>::Input>,
 {
+    /// Marks the state as loading the initial inputs, or as done loading them,
+    /// if a [`crate::feedbacks::SeedFeedback`] added the [`SeedLoadingMetadata`]
+    fn set_loading_seeds(&mut self, loading: bool) {
+        if let Ok(meta) = self.metadata_mut::<SeedLoadingMetadata>() {
+            meta.set_loading(loading);
+        }
+    }
+
     fn generate_initial_internal<G, E, EM, Z>(
         &mut self,
         fuzzer: &mut Z,
@@ -1006,18 +1496,25 @@ where
         Z: Evaluator<E, EM, State = Self>,
     {
         let mut added = 0;
-        for _ in 0..num {
-            let input = generator.generate(self)?;
-            if forced {
-                let _: CorpusId = fuzzer.add_input(self, executor, manager, input)?;
-                added += 1;
-            } else {
-                let (res, _) = fuzzer.evaluate_input(self, executor, manager, input)?;
-                if res != ExecuteInputResult::None {
+        self.set_loading_seeds(true);
+        let mut generate = || -> Result<(), Error> {
+            for _ in 0..num {
+                let input = generator.generate(self)?;
+                if forced {
+                    let _: CorpusId = fuzzer.add_input(self, executor, manager, input)?;
                     added += 1;
+                } else {
+                    let (res, _) = fuzzer.evaluate_input(self, executor, manager, input)?;
+                    if res != ExecuteInputResult::None {
+                        added += 1;
+                    }
                 }
             }
-        }
+            Ok(())
+        };
+        let res = generate();
+        self.set_loading_seeds(false);
+        res?;
         manager.fire(
             self,
             Event::Log {
@@ -1101,11 +1598,20 @@ where
             phantom: PhantomData,
             #[cfg(feature = "std")]
             multicore_inputs_processed: None,
+            #[cfg(feature = "std")]
+            initial_inputs_shuffle_seed: None,
         };
         feedback.init_state(&mut state)?;
         objective.init_state(&mut state)?;
         Ok(state)
     }
+
+    /// Ranks the corpus entries by the number of map entries only they cover, the most important entries first.
+    /// Relies on the snapshots of a [`crate::feedbacks::CoverageSnapshotFeedback`] in the feedback chain,
+    /// see [`coverage_ranking`].
+    pub fn corpus_coverage_ranking(&self) -> Result<Vec<(CorpusId, usize)>, Error> {
+        coverage_ranking(self.corpus())
+    }
 }
 
 #[cfg(feature = "introspection")]
@@ -1265,9 +1771,26 @@ impl<I> HasScalabilityMonitor for NopState<I> {
 
 #[cfg(test)]
 pub mod test {
+    #[cfg(feature = "std")]
+    use std::{fs, path::PathBuf};
+
     use libafl_bolts::rands::StdRand;
+    #[cfg(feature = "std")]
+    use libafl_bolts::tuples::tuple_list;
 
     use super::StdState;
+    #[cfg(feature = "std")]
+    use crate::{
+        corpus::Corpus,
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        feedbacks::{ConstFeedback, SeedFeedback, SeedLoadingMetadata, SeedPhasePolicy},
+        fuzzer::Evaluator,
+        inputs::BytesInput,
+        schedulers::QueueScheduler,
+        state::HasCorpus,
+        HasMetadata, StdFuzzer,
+    };
     use crate::{corpus::InMemoryCorpus, inputs::Input};
 
     #[must_use]
@@ -1282,4 +1805,296 @@ pub mod test {
         )
         .expect("couldn't instantiate the test state")
     }
+
+    /// Writes `inputs` as files into a fresh directory named after `name`
+    #[cfg(feature = "std")]
+    fn write_initial_inputs(name: &str, inputs: &[&[u8]]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("libafl_{name}.{}", std::process::id()));
+        drop(fs::remove_dir_all(&dir));
+        fs::create_dir_all(&dir).unwrap();
+        for (idx, input) in inputs.iter().enumerate() {
+            fs::write(dir.join(format!("input_{idx}")), input).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore)]
+    fn test_seed_loading_metadata() {
+        let dir = write_initial_inputs("test_seed_loading", &[b"a", b"b"]);
+
+        // Keeps every input while loading the seeds, none afterwards
+        let mut feedback = SeedFeedback::new(ConstFeedback::new(true), SeedPhasePolicy::SeedsOnly);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        state
+            .load_initial_inputs(&mut fuzzer, &mut executor, &mut mgr, &[dir.clone()])
+            .unwrap();
+        assert_eq!(state.corpus().count(), 2);
+        assert!(!state.metadata::<SeedLoadingMetadata>().unwrap().loading());
+
+        fuzzer
+            .evaluate_input(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(b"c".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(state.corpus().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore)]
+    fn test_load_initial_inputs_dedup() {
+        use crate::{executors::test::MapExecutor, feedbacks::MaxMapFeedback};
+
+        // `a` and `i` both cover map entry 1, `b` covers entry 2
+        let dir = write_initial_inputs("test_load_dedup", &[b"a", b"i", b"b"]);
+
+        for dedup in [true, false] {
+            let mut executor = MapExecutor::new(false);
+            let mut feedback = MaxMapFeedback::new(executor.map_observer());
+            let mut objective = ConstFeedback::new(false);
+            let mut state = StdState::new(
+                StdRand::with_seed(0),
+                InMemoryCorpus::<BytesInput>::new(),
+                InMemoryCorpus::new(),
+                &mut feedback,
+                &mut objective,
+            )
+            .unwrap();
+            let mut mgr = NopEventManager::new();
+            let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+
+            if dedup {
+                state
+                    .load_initial_inputs_dedup(&mut fuzzer, &mut executor, &mut mgr, &[dir.clone()])
+                    .unwrap();
+            } else {
+                state
+                    .load_initial_inputs(&mut fuzzer, &mut executor, &mut mgr, &[dir.clone()])
+                    .unwrap();
+            }
+            // the redundant input is dropped, instead of being added as disabled
+            assert_eq!(state.corpus().count(), 2);
+            assert_eq!(state.corpus().count_disabled(), usize::from(!dedup));
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", unix))]
+    #[cfg_attr(miri, ignore)]
+    fn test_resume_from_aflpp() {
+        use core::time::Duration;
+
+        use crate::{
+            executors::test::MapExecutor,
+            feedbacks::MaxMapFeedback,
+            state::{AflppEntryMetadata, HasExecutions, HasSolutions, HasStartTime},
+        };
+
+        // the layout `afl-fuzz -o out` leaves behind, with its bookkeeping files
+        let out = std::env::temp_dir().join(format!("libafl_aflpp_out.{}", std::process::id()));
+        drop(fs::remove_dir_all(&out));
+        let instance = out.join("default");
+        for dir in ["queue/.state/auto_extras", "crashes", "hangs"] {
+            fs::create_dir_all(instance.join(dir)).unwrap();
+        }
+        let files: [(&str, &[u8]); 7] = [
+            ("queue/id:000000,time:0,execs:0,orig:seed.bin", b"seed"),
+            (
+                "queue/id:000001,src:000000,time:12,execs:34,op:havoc,rep:2,+cov",
+                b"seed!",
+            ),
+            ("queue/.state/auto_extras/auto_000000", b"token"),
+            (
+                "crashes/README.txt",
+                b"Command line used to find this crash",
+            ),
+            (
+                "crashes/id:000000,sig:11,src:000001,time:99,execs:100,op:havoc,rep:4",
+                b"boom",
+            ),
+            (
+                "fuzzer_stats",
+                b"start_time        : 1700000000\nexecs_done        : 1234567\n",
+            ),
+            ("cmdline", b"./target\n@@\n"),
+        ];
+        for (path, contents) in files {
+            fs::write(instance.join(path), contents).unwrap();
+        }
+
+        let mut executor = MapExecutor::new(false);
+        let mut feedback = MaxMapFeedback::new(executor.map_observer());
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+
+        // the output directory itself resolves to its `default` instance
+        let stats = state
+            .resume_from_aflpp(&mut fuzzer, &mut executor, &mut mgr, &out)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.execs_done, 1_234_567);
+        assert!(*state.executions() >= 1_234_567);
+        assert_eq!(*state.start_time(), Duration::from_secs(1_700_000_000));
+
+        // only the entries are adopted, not the hidden state or the readme
+        assert_eq!(state.corpus().count(), 2);
+        let first = state.corpus().first().unwrap();
+        let meta = state
+            .corpus()
+            .get(first)
+            .unwrap()
+            .borrow()
+            .metadata::<AflppEntryMetadata>()
+            .unwrap()
+            .clone();
+        assert_eq!(meta.orig.as_deref(), Some("seed.bin"));
+        assert_eq!(state.solutions().count(), 1);
+        let crash = state.solutions().first().unwrap();
+        let crash = state.solutions().get(crash).unwrap().borrow();
+        assert_eq!(
+            crash.metadata::<AflppEntryMetadata>().unwrap().sig,
+            Some(11)
+        );
+        drop(crash);
+
+        // a directory without a queue is rejected
+        assert!(state
+            .resume_from_aflpp(
+                &mut fuzzer,
+                &mut executor,
+                &mut mgr,
+                &instance.join("crashes")
+            )
+            .is_err());
+
+        fs::remove_dir_all(out).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_partition_initial_files() {
+        use alloc::vec::Vec;
+
+        use super::partition_initial_files;
+
+        let files = |count: usize| -> Vec<PathBuf> {
+            (0..count)
+                .map(|idx| PathBuf::from(format!("input_{idx}")))
+                .collect()
+        };
+
+        // more files than clients: every file goes to exactly one client
+        let mut loaded = Vec::new();
+        for client_index in 0..3 {
+            let partition = partition_initial_files(files(7), client_index, 3);
+            assert!(!partition.is_empty());
+            loaded.extend(partition);
+        }
+        loaded.sort();
+        assert_eq!(loaded, files(7));
+
+        // fewer files than clients: the surplus clients get one file each, shared with others
+        let partitions = (0..5)
+            .map(|client_index| partition_initial_files(files(2), client_index, 5))
+            .collect::<Vec<_>>();
+        assert_eq!(partitions[0], files(2)[..1]);
+        assert_eq!(partitions[1], files(2)[1..]);
+        for partition in &partitions[2..] {
+            assert_eq!(partition.len(), 1);
+        }
+
+        assert!(partition_initial_files(Vec::new(), 0, 2).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "tar")]
+    #[cfg_attr(miri, ignore)]
+    fn test_export_corpus_to_archive() {
+        use alloc::{boxed::Box, string::String, vec::Vec};
+        use std::{fs::File, io::Read};
+
+        use crate::corpus::Testcase;
+
+        let mut state = test_std_state::<BytesInput>();
+        let named = Testcase::with_filename(BytesInput::new(b"a".to_vec()), "named".into());
+        let unnamed = BytesInput::new(b"bc".to_vec());
+        state.corpus_mut().add(named).unwrap();
+        let unnamed_id = state
+            .corpus_mut()
+            .add(Testcase::new(unnamed.clone()))
+            .unwrap();
+
+        let dir = write_initial_inputs("test_export_corpus", &[]);
+        for name in ["corpus.tar", "corpus.tar.gz"] {
+            let archive = dir.join(name);
+            assert_eq!(state.export_corpus_to_archive(&archive).unwrap(), 2);
+            assert!(!dir.join(format!("{name}.tmp")).exists());
+
+            let file = File::open(&archive).unwrap();
+            let reader: Box<dyn Read> = if name.ends_with(".gz") {
+                Box::new(flate2::read::GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+            let mut entries: Vec<(String, Vec<u8>)> = tar::Archive::new(reader)
+                .entries()
+                .unwrap()
+                .map(|entry| {
+                    let mut entry = entry.unwrap();
+                    let path = entry.path().unwrap().to_string_lossy().into_owned();
+                    let mut bytes = vec![];
+                    entry.read_to_end(&mut bytes).unwrap();
+                    (path, bytes)
+                })
+                .collect();
+            entries.sort();
+
+            let mut expected = vec![
+                ("named".into(), b"a".to_vec()),
+                (unnamed.generate_name(unnamed_id.0), b"bc".to_vec()),
+            ];
+            expected.sort();
+            assert_eq!(entries, expected);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1013,6 +1013,26 @@ where
         }
     }
 
+    /// The number of full pages the broker did not read yet.
+    /// Pages the broker already read are freed on the next page allocation.
+    #[must_use]
+    pub fn pending_unread_pages(&self) -> usize {
+        let (_, old_pages) = self.out_shmems.split_last().unwrap();
+        old_pages
+            .iter()
+            .filter(|map| unsafe {
+                (*map.page()).receivers_joined_count.load(Ordering::Relaxed) == 0
+            })
+            .count()
+    }
+
+    /// If the broker does not keep up with the messages of this sender.
+    /// Sending on while saturated will eventually make the sender give up, see `LLMP_CFG_MAX_PENDING_UNREAD_PAGES`.
+    #[must_use]
+    pub fn is_saturated(&self) -> bool {
+        self.pending_unread_pages() + 1 >= LLMP_CFG_MAX_PENDING_UNREAD_PAGES
+    }
+
     /// For debug purposes: Mark save to unmap, even though it might not have been read by a receiver yet.
     /// # Safety
     /// If this method is called, the page may be unmapped before it is read by any receiver.
@@ -3120,6 +3140,12 @@ where
         self.sender.safe_to_unmap()
     }
 
+    /// If the broker does not keep up with the messages of this client, see [`LlmpSender::is_saturated`]
+    #[must_use]
+    pub fn is_saturated(&self) -> bool {
+        self.sender.is_saturated()
+    }
+
     /// For debug purposes: mark the client as save to unmap, even though it might not have been read.
     ///
     /// # Safety
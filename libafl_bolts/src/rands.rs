@@ -39,6 +39,14 @@ fn random_seed_from_random_state() -> u64 {
     RandomState::new().build_hasher().finish()
 }
 
+/// Derives the seed of one of several random streams from a shared `base` seed, e.g., for each fuzzer client
+/// from the core it runs on. The seeds are reproducible for the same `base`, and distinct for different `stream`s.
+#[must_use]
+pub fn derive_seed(base: u64, stream: u64) -> u64 {
+    let mut x = base ^ stream.wrapping_mul(0x9e3779b97f4a7c15);
+    splitmix64(&mut x)
+}
+
 // https://prng.di.unimi.it/splitmix64.c
 fn splitmix64(x: &mut u64) -> u64 {
     *x = x.wrapping_add(0x9e3779b97f4a7c15);
@@ -469,7 +477,7 @@ impl XkcdRand {
 #[cfg(test)]
 mod tests {
     use crate::rands::{
-        Rand, RomuDuoJrRand, RomuTrioRand, Sfc64Rand, StdRand, XorShift64Rand,
+        derive_seed, Rand, RomuDuoJrRand, RomuTrioRand, Sfc64Rand, StdRand, XorShift64Rand,
         Xoshiro256PlusPlusRand,
     };
 
@@ -492,6 +500,13 @@ mod tests {
         test_single_rand(&mut Sfc64Rand::with_seed(0));
     }
 
+    #[test]
+    fn test_derive_seed() {
+        assert_eq!(derive_seed(1337, 3), derive_seed(1337, 3));
+        assert_ne!(derive_seed(1337, 3), derive_seed(1337, 4));
+        assert_ne!(derive_seed(1337, 3), derive_seed(1338, 3));
+    }
+
     #[test]
     fn test_romutrio_golden() {
         // https://github.com/ziglang/zig/blob/130fb5cb0fb9039e79450c9db58d6590c5bee3b3/lib/std/Random/RomuTrio.zig#L75-L95
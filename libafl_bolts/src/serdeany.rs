@@ -57,6 +57,10 @@ pub trait SerdeAny: Any + erased_serde::Serialize + Debug {
     fn as_any_mut(&mut self) -> &mut dyn Any;
     /// returns this as boxed Any trait
     fn as_any_boxed(self: Box<Self>) -> Box<dyn Any>;
+    /// returns the name of the concrete type, for debugging
+    fn type_name(&self) -> &'static str {
+        core::any::type_name::<Self>()
+    }
 }
 
 /// Wrap a type for serialization
@@ -402,6 +406,12 @@ pub mod serdeany_registry {
             self.map.is_empty()
         }
 
+        /// Iterates over all elements in this map, in no particular order.
+        /// Use [`crate::serdeany::SerdeAny::type_name`] to tell them apart.
+        pub fn values(&self) -> impl Iterator<Item = &dyn crate::serdeany::SerdeAny> {
+            self.map.values().map(AsRef::as_ref)
+        }
+
         /// Returns if the map contains the given type.
         #[must_use]
         #[inline]
@@ -10,8 +10,8 @@ use libafl::{
     corpus::{CachedOnDiskCorpus, Corpus, OnDiskCorpus},
     events::{launcher::Launcher, EventConfig, EventRestarter, LlmpRestartingEventManager},
     executors::{ExitKind, ShadowExecutor},
-    feedback_or, feedback_or_fast,
-    feedbacks::{CrashFeedback, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
+    feedback_and_fast, feedback_or, feedback_or_fast,
+    feedbacks::{ConstFeedback, CrashFeedback, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
     fuzzer::{Fuzzer, StdFuzzer},
     generators::RandBytesGenerator,
     inputs::{BytesInput, HasTargetBytes},
@@ -83,6 +83,11 @@ where
     /// Fuzz `iterations` number of times, instead of indefinitely; implies use of `fuzz_loop_for`
     #[builder(default = None)]
     iterations: Option<u64>,
+    /// Only keep solutions covering map entries that no previous solution covered.
+    /// The objective keeps its own coverage history, independent of the corpus, so a crash in an edge
+    /// that non-crashing inputs already reached is still saved, while repeats of known crashes are not.
+    #[builder(default = false)]
+    objective_coverage: bool,
 }
 
 impl<'a, H> Debug for QemuBytesCoverageSugar<'a, H>
@@ -109,6 +114,7 @@ where
                 },
             )
             .field("iterations", &self.iterations)
+            .field("objective_coverage", &self.objective_coverage)
             .finish()
     }
 }
@@ -181,7 +187,14 @@ where
             );
 
             // A feedback to choose if an input is a solution or not
-            let mut objective = feedback_or_fast!(CrashFeedback::new(), TimeoutFeedback::new());
+            // With `objective_coverage`, solutions also need to cover new entries of the objective's own map history
+            let mut objective = feedback_and_fast!(
+                feedback_or_fast!(CrashFeedback::new(), TimeoutFeedback::new()),
+                feedback_or_fast!(
+                    ConstFeedback::new(!self.objective_coverage),
+                    MaxMapFeedback::with_name("edges_objective", &edges_observer)
+                )
+            );
 
             // If not restarting, create a State from scratch
             let mut state = state.unwrap_or_else(|| {
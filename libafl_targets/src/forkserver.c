@@ -26,6 +26,7 @@
 #define SHMEM_FUZZ_HDR_SIZE 4
 #define SHM_ENV_VAR "__AFL_SHM_ID"
 #define SHM_FUZZ_ENV_VAR "__AFL_SHM_FUZZ_ID"
+#define FEATURES_SHM_ENV_VAR "__LIBAFL_FEATURES_SHM_ID"
 #define DEFAULT_PERMISSION 0600
 
 /* Reporting errors */
@@ -73,6 +74,11 @@ uint8_t        *__afl_fuzz_ptr;
 static uint32_t __afl_fuzz_len_local;
 uint32_t       *__afl_fuzz_len = &__afl_fuzz_len_local;
 
+/* The map of harness-defined features, empty until __libafl_map_features_shm
+   found one */
+uint8_t *__libafl_features_ptr;
+size_t   __libafl_features_size;
+
 int already_initialized_shm;
 int already_initialized_forkserver;
 
@@ -214,6 +220,60 @@ static void map_input_shared_memory() {
   }
 }
 
+/* Features map setup. The map is optional: without the env vars, features
+   are silently dropped. */
+
+void __libafl_map_features_shm(void) {
+  if (__libafl_features_ptr) return;
+
+  char *id_str = getenv(FEATURES_SHM_ENV_VAR);
+  char *size_str = getenv(FEATURES_SHM_ENV_VAR "_SIZE");
+
+  if (!id_str || !size_str) return;
+
+  size_t   size = strtoul(size_str, NULL, 10);
+  uint8_t *map = NULL;
+
+  if (!size) return;
+
+#ifdef USEMMAP
+  int shm_fd = shm_open(id_str, O_RDWR, DEFAULT_PERMISSION);
+  if (shm_fd == -1) {
+    fprintf(stderr, "shm_open() failed for features\n");
+    send_forkserver_error(FS_ERROR_SHM_OPEN);
+    exit(1);
+  }
+
+  map =
+      (uint8_t *)mmap(0, size, PROT_READ | PROT_WRITE, MAP_SHARED, shm_fd, 0);
+
+  close(shm_fd);
+
+  if (map == MAP_FAILED) { map = NULL; }
+#else
+  uint32_t shm_id = atoi(id_str);
+  map = (uint8_t *)shmat(shm_id, NULL, 0);
+#endif
+
+  if (!map || map == (void *)-1) {
+    perror("Could not access features shared memory");
+    send_forkserver_error(FS_ERROR_SHMAT);
+    exit(1);
+  }
+
+  __libafl_features_ptr = map;
+  __libafl_features_size = size;
+}
+
+/* Records a hit of the harness-defined feature, folded into the map size. */
+
+void __libafl_feature_hit(uint32_t feature) {
+  if (!__libafl_features_size) return;
+
+  uint8_t *entry = &__libafl_features_ptr[feature % __libafl_features_size];
+  if (*entry != 0xff) { (*entry)++; }
+}
+
 /* Fork server logic. */
 
 void __afl_start_forkserver(void) {
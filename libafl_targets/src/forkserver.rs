@@ -3,6 +3,10 @@
 extern "C" {
     /// Map a shared memory region for the edge coverage map.
     fn __afl_map_shm();
+    /// Map the shared memory region for the harness-defined features map, if passed.
+    fn __libafl_map_features_shm();
+    /// Record a hit of a harness-defined feature.
+    fn __libafl_feature_hit(feature: u32);
     /// Start the forkserver.
     fn __afl_start_forkserver();
 }
@@ -16,6 +20,28 @@ pub fn map_shared_memory() {
     unsafe { __afl_map_shm() }
 }
 
+/// Map the shared memory region for the harness-defined features map,
+/// passed by `ForkserverExecutorBuilder::features_map` in `__LIBAFL_FEATURES_SHM_ID`.
+/// Without that env var, all features are dropped.
+///
+/// # Note
+///
+/// The function's logic is written in C and this code is a wrapper.
+pub fn map_features_shared_memory() {
+    unsafe { __libafl_map_features_shm() }
+}
+
+/// Record a hit of the harness-defined `feature`, such as a compared value or an app-specific
+/// counter. Features are folded into the size of the map, and their hitcounts saturate.
+/// Call [`map_features_shared_memory`] before.
+///
+/// # Note
+///
+/// The function's logic is written in C and this code is a wrapper.
+pub fn feature_hit(feature: u32) {
+    unsafe { __libafl_feature_hit(feature) }
+}
+
 /// Start the forkserver from this point. Any shared memory must be created before.
 ///
 /// # Note
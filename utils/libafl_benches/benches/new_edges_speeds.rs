@@ -0,0 +1,108 @@
+//! Compare the overhead of the `NewEdgesMapObserver` on a full in-process execution
+//! to the same executor observing the plain hitcounts map
+
+use core::ptr::addr_of_mut;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libafl::{
+    corpus::InMemoryCorpus,
+    events::NopEventManager,
+    executors::{inprocess::InProcessExecutor, ExitKind},
+    feedbacks::ConstFeedback,
+    fuzzer::{ExecutesInput, StdFuzzer},
+    inputs::BytesInput,
+    observers::{HitcountsMapObserver, NewEdgesMapObserver, StdMapObserver},
+    schedulers::QueueScheduler,
+    state::StdState,
+};
+use libafl_bolts::{
+    rands::{Rand, StdRand},
+    tuples::tuple_list,
+};
+
+const MAP_SIZE: usize = 1 << 16;
+/// The number of entries covered per run, as for a typical target
+const COVERED: usize = 1000;
+
+/// The coverage map the harness writes to
+static mut MAP: [u8; MAP_SIZE] = [0; MAP_SIZE];
+
+/// A fresh state, fuzzer and event manager, with feedbacks that do not look at the map
+macro_rules! setup {
+    ($state:ident, $fuzzer:ident, $mgr:ident) => {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut $state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut $fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut $mgr = NopEventManager::new();
+    };
+}
+
+fn map_observer() -> HitcountsMapObserver<StdMapObserver<'static, u8, false>> {
+    HitcountsMapObserver::new(unsafe {
+        StdMapObserver::from_mut_ptr("map", addr_of_mut!(MAP).cast(), MAP_SIZE)
+    })
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut rand = StdRand::with_seed(0);
+    let covered: Vec<usize> = (0..COVERED).map(|_| rand.below(MAP_SIZE)).collect();
+    let input = BytesInput::new(vec![0]);
+
+    // The same target for both executors, covering the same entries in every run
+    let mut harness = |_input: &BytesInput| {
+        let map = unsafe { &mut *addr_of_mut!(MAP) };
+        for idx in &covered {
+            map[*idx] = map[*idx].wrapping_add(1);
+        }
+        ExitKind::Ok
+    };
+
+    {
+        setup!(state, fuzzer, mgr);
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(map_observer()),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+        c.bench_function("hitcounts_execution", |b| {
+            b.iter(|| {
+                fuzzer
+                    .execute_input(&mut state, &mut executor, &mut mgr, black_box(&input))
+                    .unwrap()
+            });
+        });
+    }
+
+    {
+        setup!(state, fuzzer, mgr);
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(NewEdgesMapObserver::new(map_observer())),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+        c.bench_function("new_edges_execution", |b| {
+            b.iter(|| {
+                fuzzer
+                    .execute_input(&mut state, &mut executor, &mut mgr, black_box(&input))
+                    .unwrap()
+            });
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);